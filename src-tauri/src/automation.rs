@@ -0,0 +1,123 @@
+use chrono::{DateTime, Utc};
+use log::{error, info};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::AppState;
+
+// Internal events an automation trigger can fire on
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum TriggerEvent {
+    CheckIn,
+    CheckOut,
+    IdleWarning, // The pre-checkout prompt is shown
+    ApiFailure, // The first failed API request after working ones
+}
+
+impl TriggerEvent {
+    fn label(&self) -> &'static str {
+        match self {
+            TriggerEvent::CheckIn => "check-in",
+            TriggerEvent::CheckOut => "check-out",
+            TriggerEvent::IdleWarning => "idle-warning",
+            TriggerEvent::ApiFailure => "api-failure",
+        }
+    }
+}
+
+// Webhook URLs of Zapier, IFTTT, n8n and the like, posted to on each event,
+// separately from the main API. Events without a URL fire nothing.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default, JsonSchema)]
+#[serde(default)]
+pub struct AutomationTriggers {
+    pub check_in: Option<String>,
+    pub check_out: Option<String>,
+    pub idle_warning: Option<String>,
+    pub api_failure: Option<String>,
+}
+
+impl AutomationTriggers {
+    pub fn url(&self, event: TriggerEvent) -> Option<&str> {
+        let url = match event {
+            TriggerEvent::CheckIn => &self.check_in,
+            TriggerEvent::CheckOut => &self.check_out,
+            TriggerEvent::IdleWarning => &self.idle_warning,
+            TriggerEvent::ApiFailure => &self.api_failure,
+        };
+        url.as_deref().map(str::trim).filter(|url| !url.is_empty())
+    }
+
+    // Each configured URL with the name of its field, for validation
+    pub fn urls(&self) -> Vec<(&'static str, &str)> {
+        [
+            ("automation_triggers.check_in", TriggerEvent::CheckIn),
+            ("automation_triggers.check_out", TriggerEvent::CheckOut),
+            ("automation_triggers.idle_warning", TriggerEvent::IdleWarning),
+            ("automation_triggers.api_failure", TriggerEvent::ApiFailure),
+        ]
+        .into_iter()
+        .filter_map(|(field, event)| self.url(event).map(|url| (field, url)))
+        .collect()
+    }
+}
+
+// A flat body, since automation platforms map top-level fields most easily.
+// `detail` says more about the event: the cause of a status change, the
+// seconds left before an idle check-out or the API error.
+pub fn body(event: TriggerEvent, username: &str, device_name: &str, status: &str, detail: &str, now: DateTime<Utc>) -> Value {
+    json!({
+        "event": event.label(),
+        "timestamp": now.to_rfc3339(),
+        "username": username,
+        "device_name": device_name,
+        "status": status,
+        "detail": detail,
+    })
+}
+
+// Post the event to its webhook in the background, if one is configured
+pub fn fire(state: &AppState, event: TriggerEvent, detail: &str) {
+    let settings = state.settings.lock().unwrap().clone();
+    let Some(url) = settings.automation_triggers.url(event).map(str::to_string) else {
+        return;
+    };
+
+    let status = state.status.lock().unwrap().label();
+    let body = body(event, &settings.username, &settings.device_name, status, detail, Utc::now());
+    tauri::async_runtime::spawn(async move {
+        let result = reqwest::Client::new().post(&url).json(&body).send().await;
+        match result {
+            Ok(response) if response.status().is_success() => info!("Fired the {} automation trigger", event.label()),
+            Ok(response) => error!("The {} automation trigger failed with status {}", event.label(), response.status()),
+            Err(err) => error!("Failed to fire the {} automation trigger: {}", event.label(), err.without_url()),
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_configured_events_fire() {
+        let triggers = AutomationTriggers {
+            check_in: Some(" https://hooks.zapier.com/hooks/catch/1/abc ".to_string()),
+            check_out: Some(String::new()),
+            ..AutomationTriggers::default()
+        };
+        assert_eq!(triggers.url(TriggerEvent::CheckIn), Some("https://hooks.zapier.com/hooks/catch/1/abc"));
+        assert_eq!(triggers.url(TriggerEvent::CheckOut), None);
+        assert_eq!(triggers.urls(), vec![("automation_triggers.check_in", "https://hooks.zapier.com/hooks/catch/1/abc")]);
+    }
+
+    #[test]
+    fn test_body_is_flat() {
+        let now = DateTime::parse_from_rfc3339("2024-05-06T07:00:00Z").unwrap().with_timezone(&Utc);
+        let body = body(TriggerEvent::IdleWarning, "alice", "laptop", "checked-in", "60", now);
+        assert_eq!(body["event"], json!("idle-warning"));
+        assert_eq!(body["timestamp"], json!("2024-05-06T07:00:00+00:00"));
+        assert!(body.as_object().unwrap().values().all(|value| value.is_string()));
+    }
+}
@@ -0,0 +1,109 @@
+use serde::{Deserialize, Serialize};
+
+use crate::cli;
+use crate::profiles::DEFAULT_PROFILE;
+
+// Whether a profile starts at login, and with which launch arguments. Each
+// enabled profile gets its own login item, which starts the app with
+// `--profile <name>` followed by `args`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct AutostartEntry {
+    pub profile: String,
+    pub enabled: bool,
+    pub args: Vec<String>, // Extra launch arguments, e.g. `--headless`, without `--profile`
+}
+
+// Autostart choices of every profile, see `AUTOSTART_FILENAME`
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct Autostart {
+    pub entries: Vec<AutostartEntry>,
+}
+
+impl Autostart {
+    pub fn find(&self, profile: &str) -> Option<&AutostartEntry> {
+        self.entries.iter().find(|entry| entry.profile == profile)
+    }
+
+    // Add an entry or replace the one for the same profile
+    pub fn set(&mut self, entry: AutostartEntry) {
+        match self.entries.iter_mut().find(|existing| existing.profile == entry.profile) {
+            Some(existing) => *existing = entry,
+            None => self.entries.push(entry),
+        }
+    }
+
+    pub fn rename(&mut self, profile: &str, new_name: &str) {
+        if let Some(entry) = self.entries.iter_mut().find(|entry| entry.profile == profile) {
+            entry.profile = new_name.to_string();
+        }
+    }
+
+    pub fn remove(&mut self, profile: &str) -> Option<AutostartEntry> {
+        let index = self.entries.iter().position(|entry| entry.profile == profile)?;
+        Some(self.entries.remove(index))
+    }
+}
+
+// Launch arguments for a profile's login item, checked like the command line.
+// A `--profile` in `args` must name the same profile and is dropped, since it
+// is added when the item is registered.
+pub fn check_args(profile: &str, args: &[String]) -> Result<Vec<String>, String> {
+    let parsed = cli::parse(args)?;
+    if parsed.profile.as_deref().is_some_and(|name| name != profile) {
+        return Err(format!("Autostart arguments for '{}' can't start another profile", profile));
+    }
+
+    let mut checked = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--profile" {
+            args.next();
+        } else if !arg.starts_with("--profile=") {
+            checked.push(arg.clone());
+        }
+    }
+    Ok(checked)
+}
+
+// Arguments the login item starts the app with
+pub fn launch_args(entry: &AutostartEntry) -> Vec<String> {
+    let mut args = vec!["--profile".to_string(), entry.profile.clone()];
+    args.extend(entry.args.iter().cloned());
+    args
+}
+
+// Name of a profile's login item. The default profile keeps the app's own
+// name, so the item registered before profiles had their own is reused.
+pub fn item_name(app_name: &str, profile: &str) -> String {
+    if profile == DEFAULT_PROFILE {
+        app_name.to_string()
+    } else {
+        format!("{} ({})", app_name, profile)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn test_check_args_strips_own_profile() {
+        assert_eq!(check_args("work", &args(&["--headless", "--profile", "work"])), Ok(args(&["--headless"])));
+        assert_eq!(check_args("work", &args(&["--profile=work", "--check-in"])), Ok(args(&["--check-in"])));
+        assert!(check_args("work", &args(&["--profile", "home"])).is_err());
+        assert!(check_args("work", &args(&["--minimized"])).is_err());
+    }
+
+    #[test]
+    fn test_launch_args_name_the_profile() {
+        let entry = AutostartEntry { profile: "work".to_string(), enabled: true, args: args(&["--headless"]) };
+        assert_eq!(launch_args(&entry), args(&["--profile", "work", "--headless"]));
+        assert_eq!(item_name("remodance", "work"), "remodance (work)");
+        assert_eq!(item_name("remodance", DEFAULT_PROFILE), "remodance");
+    }
+}
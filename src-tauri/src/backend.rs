@@ -0,0 +1,157 @@
+use std::future::Future;
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use reqwest::header::HeaderMap;
+use reqwest::StatusCode;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+// Where attendance events are sent, see `Settings::backend`
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum BackendKind {
+    #[default]
+    Webhook, // The event payload as is, posted to `api_endpoint`
+    Erpnext,
+    Odoo,
+    Bamboohr,
+    Personio,
+}
+
+// Why an event wasn't delivered. Events are queued while the backend is
+// unreachable and dropped when it rejects them.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SendError {
+    Unreachable(String),
+    Rejected(String),
+}
+
+impl From<SendError> for String {
+    fn from(err: SendError) -> Self {
+        match err {
+            SendError::Unreachable(message) | SendError::Rejected(message) => message,
+        }
+    }
+}
+
+// What a backend answered, read in full so adapters can interpret it
+#[derive(Debug, Clone)]
+pub struct Reply {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: String,
+}
+
+// Send a request to a backend. Failing to get an answer means it's unreachable.
+pub async fn request(request: reqwest::RequestBuilder) -> Result<Reply, SendError> {
+    let response = request.send().await.map_err(|e| SendError::Unreachable(format!("Failed to send request: {}", e)))?;
+    let status = response.status();
+    let headers = response.headers().clone();
+    let body = response.text().await.map_err(|e| SendError::Unreachable(format!("Failed to read response: {}", e)))?;
+    Ok(Reply { status, headers, body })
+}
+
+// A system attendance events are sent to, each in its own way. Sending goes
+// through the same steps for all of them, see `deliver`.
+pub trait BackendAdapter {
+    // What an event becomes for this backend
+    type Event: Send;
+    // Whatever requests need to be accepted, e.g. a token
+    type Session: Send + Sync;
+
+    fn name(&self) -> &'static str;
+    // The event to send, none for events the backend has no use for
+    fn build(&self, event_type: &str, payload: &Value) -> Result<Option<Self::Event>, String>;
+    fn authenticate(&self, client: &reqwest::Client) -> impl Future<Output = Result<Self::Session, SendError>> + Send;
+    // Send the event, returning the reply that decides whether it was
+    // recorded. None when there was nothing left to record.
+    fn submit(&self, client: &reqwest::Client, session: &Self::Session, event: Self::Event) -> impl Future<Output = Result<Option<Reply>, SendError>> + Send;
+    fn interpret(&self, reply: &Reply) -> Result<(), SendError>;
+}
+
+// Record one event in a backend
+pub async fn deliver<B: BackendAdapter + Sync>(backend: &B, event_type: &str, payload: &Value) -> Result<(), SendError> {
+    let Some(event) = backend.build(event_type, payload).map_err(SendError::Rejected)? else {
+        return Ok(());
+    };
+    let client = reqwest::Client::new();
+    let session = backend.authenticate(&client).await?;
+    match backend.submit(&client, &session, event).await? {
+        Some(reply) => backend.interpret(&reply),
+        None => Ok(()),
+    }
+}
+
+// Whether an event starts or ends time at work, for HR systems that only
+// know clock-ins and clock-outs. Breaks end and restart work. Other events
+// aren't sent to them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Clock {
+    In,
+    Out,
+}
+
+pub fn clock(event_type: &str) -> Option<Clock> {
+    match event_type {
+        "check-in" | "break-end" => Some(Clock::In),
+        "check-out" | "break-start" => Some(Clock::Out),
+        _ => None,
+    }
+}
+
+// Local time of an event from its payload's `date` and `time`, so queued
+// events keep the time they happened at
+pub fn event_time(payload: &Value) -> Result<NaiveDateTime, String> {
+    let data = &payload["payload"];
+    let (Some(date), Some(time)) = (data["date"].as_str(), data["time"].as_str()) else {
+        return Err("The event has no date and time".to_string());
+    };
+    NaiveDateTime::parse_from_str(&format!("{} {}", date, time), "%Y-%m-%d %H:%M:%S")
+        .map_err(|err| format!("Invalid event time '{} {}': {}", date, time, err))
+}
+
+// When an event happened, from the payload's UTC `timestamp`
+pub fn event_utc(payload: &Value) -> Result<DateTime<Utc>, String> {
+    let timestamp = payload["timestamp"].as_str().ok_or("The event has no timestamp")?;
+    DateTime::parse_from_rfc3339(timestamp)
+        .map(|at| at.with_timezone(&Utc))
+        .map_err(|err| format!("Invalid event timestamp '{}': {}", timestamp, err))
+}
+
+// The employee an event is for, `Settings::user_id` when it was created
+pub fn employee(payload: &Value) -> Result<&str, String> {
+    payload["user_id"].as_str().filter(|id| !id.is_empty()).ok_or_else(|| "The event has no employee".to_string())
+}
+
+pub fn device_name(payload: &Value) -> &str {
+    payload["payload"]["device_name"].as_str().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_event_fields() {
+        let payload = json!({
+            "event_type": "check-in",
+            "user_id": "HR-EMP-00042",
+            "payload": { "time": "08:59:30", "date": "2024-05-06", "device_id": "abc", "device_name": "laptop" },
+            "timestamp": "2024-05-06T06:59:30.123+00:00",
+        });
+        assert_eq!(event_time(&payload).unwrap().to_string(), "2024-05-06 08:59:30");
+        assert_eq!(event_utc(&payload).unwrap().to_rfc3339(), "2024-05-06T06:59:30.123+00:00");
+        assert_eq!(employee(&payload), Ok("HR-EMP-00042"));
+        assert_eq!(device_name(&payload), "laptop");
+        assert!(event_time(&json!({ "payload": {} })).is_err());
+    }
+
+    #[test]
+    fn test_breaks_clock_out_and_back_in() {
+        assert_eq!(clock("break-start"), Some(Clock::Out));
+        assert_eq!(clock("break-end"), Some(Clock::In));
+        assert_eq!(clock("out-of-office"), None);
+    }
+}
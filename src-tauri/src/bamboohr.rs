@@ -0,0 +1,124 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::backend::{self, BackendAdapter, Clock, Reply, SendError};
+
+// A BambooHR account with time tracking, where events become clock-in and
+// clock-out entries of the employee's timesheet. `Settings::user_id` must be
+// the numeric employee ID and `Settings::timezone` set, since BambooHR needs
+// the zone of each entry. The API key is kept with the other secrets, see
+// `Secrets::bamboohr_api_key`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default, JsonSchema)]
+#[serde(default)]
+pub struct BambooHrSettings {
+    pub subdomain: String, // "acme" for acme.bamboohr.com
+}
+
+pub struct BambooHr<'a> {
+    pub settings: &'a BambooHrSettings,
+    pub api_key: Option<&'a str>,
+    pub timezone: Option<&'a str>,
+}
+
+pub fn employee_id(payload: &Value) -> Result<u64, String> {
+    let employee = backend::employee(payload)?;
+    employee.trim().parse().map_err(|_| format!("'{}' is not a BambooHR employee ID, set the employee ID to the number of the employee", employee))
+}
+
+// Path and body of the timesheet entry for an event, none for events
+// BambooHR has no use for. The device is noted on clock-ins.
+pub fn clock_entry(event_type: &str, payload: &Value, timezone: &str) -> Result<Option<(String, Value)>, String> {
+    let Some(clock) = backend::clock(event_type) else {
+        return Ok(None);
+    };
+    let employee_id = employee_id(payload)?;
+    let time = backend::event_time(payload)?;
+    let date = time.format("%Y-%m-%d").to_string();
+    let hour = time.format("%H:%M").to_string();
+    Ok(Some(match clock {
+        Clock::In => (
+            format!("time_tracking/employees/{}/clock_in", employee_id),
+            json!({ "date": date, "start": hour, "timezone": timezone, "note": backend::device_name(payload) }),
+        ),
+        Clock::Out => (
+            format!("time_tracking/employees/{}/clock_out", employee_id),
+            json!({ "date": date, "end": hour, "timezone": timezone }),
+        ),
+    }))
+}
+
+// The subdomain as entered, tolerating the full host or URL
+pub fn company_domain(subdomain: &str) -> &str {
+    let subdomain = subdomain.trim().trim_start_matches("https://").trim_start_matches("http://");
+    subdomain.split('.').next().unwrap_or_default()
+}
+
+impl<'a> BackendAdapter for BambooHr<'a> {
+    // Path and body of the timesheet entry
+    type Event = (String, Value);
+    // The API key, sent as the basic auth user
+    type Session = &'a str;
+
+    fn name(&self) -> &'static str {
+        "BambooHR"
+    }
+
+    fn build(&self, event_type: &str, payload: &Value) -> Result<Option<Self::Event>, String> {
+        if backend::clock(event_type).is_none() {
+            return Ok(None);
+        }
+        let timezone = self.timezone.ok_or("Set the timezone to send events to BambooHR")?;
+        clock_entry(event_type, payload, timezone)
+    }
+
+    async fn authenticate(&self, _client: &reqwest::Client) -> Result<&'a str, SendError> {
+        self.api_key.ok_or_else(|| SendError::Rejected("No BambooHR API key is set".to_string()))
+    }
+
+    async fn submit(&self, client: &reqwest::Client, api_key: &&'a str, (path, entry): Self::Event) -> Result<Option<Reply>, SendError> {
+        let url = format!("https://api.bamboohr.com/api/gateway.php/{}/v1/{}", company_domain(&self.settings.subdomain), path);
+        let request = client.post(url).basic_auth(api_key, Some("x")).header("Accept", "application/json").json(&entry);
+        backend::request(request).await.map(Some)
+    }
+
+    fn interpret(&self, reply: &Reply) -> Result<(), SendError> {
+        if reply.status.is_success() {
+            return Ok(());
+        }
+        // BambooHR explains errors in a header rather than the body
+        let message = reply.headers.get("X-BambooHR-Error-Message").and_then(|value| value.to_str().ok());
+        Err(SendError::Rejected(match message {
+            Some(message) => format!("BambooHR rejected the entry with status {}: {}", reply.status, message),
+            None => format!("BambooHR request failed with status {}", reply.status),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clock_entry() {
+        let payload = json!({
+            "user_id": "42",
+            "payload": { "time": "08:59:30", "date": "2024-05-06", "device_id": "abc", "device_name": "laptop" },
+        });
+        let (path, entry) = clock_entry("check-in", &payload, "Europe/Berlin").unwrap().unwrap();
+        assert_eq!(path, "time_tracking/employees/42/clock_in");
+        assert_eq!(entry, json!({ "date": "2024-05-06", "start": "08:59", "timezone": "Europe/Berlin", "note": "laptop" }));
+
+        let (path, entry) = clock_entry("break-start", &payload, "Europe/Berlin").unwrap().unwrap();
+        assert_eq!(path, "time_tracking/employees/42/clock_out");
+        assert_eq!(entry["end"], json!("08:59"));
+        assert_eq!(clock_entry("pause", &payload, "Europe/Berlin").unwrap(), None);
+        assert!(clock_entry("check-in", &json!({ "user_id": "alice" }), "UTC").is_err());
+    }
+
+    #[test]
+    fn test_company_domain() {
+        assert_eq!(company_domain("acme"), "acme");
+        assert_eq!(company_domain(" https://acme.bamboohr.com/ "), "acme");
+    }
+}
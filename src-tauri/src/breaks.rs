@@ -0,0 +1,273 @@
+use chrono::{DateTime, Duration, NaiveTime, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::history::{BreakPeriod, MicroBreak};
+
+// Break kind recorded for idle periods inside the lunch window
+pub const LUNCH_BREAK: &str = "lunch";
+
+// Break kind recorded for short idle periods outside the lunch window
+pub const IDLE_BREAK: &str = "idle";
+
+// Break kind recorded locally for paused sessions
+pub const PAUSE_BREAK: &str = "pause";
+
+// Time after a micro-break suggestion in which resting still counts as taking it
+pub const MICRO_BREAK_GRACE_SECS: i64 = 120;
+
+// Daily window in which longer idle periods count as a lunch break
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+pub struct LunchWindow {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+    pub min_idle_mins: u64, // Idle time after which a lunch break is recorded
+}
+
+impl LunchWindow {
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        time >= self.start && time < self.end
+    }
+}
+
+impl Default for LunchWindow {
+    fn default() -> Self {
+        Self {
+            start: NaiveTime::from_hms_opt(12, 0, 0).unwrap(),
+            end: NaiveTime::from_hms_opt(14, 0, 0).unwrap(),
+            min_idle_mins: 20,
+        }
+    }
+}
+
+// A kind of break the user can start, with an optional maximum length
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+pub struct BreakType {
+    pub name: String,
+    pub max_mins: Option<u64>, // Notify when a break of this type runs longer
+}
+
+impl BreakType {
+    pub fn new(name: &str, max_mins: Option<u64>) -> Self {
+        Self {
+            name: name.to_string(),
+            max_mins,
+        }
+    }
+}
+
+// Break types offered until the user configures their own
+pub fn default_break_types() -> Vec<BreakType> {
+    vec![
+        BreakType::new(LUNCH_BREAK, Some(60)),
+        BreakType::new("coffee", Some(15)),
+        BreakType::new("meeting", None),
+        BreakType::new("errand", Some(30)),
+    ]
+}
+
+// Look up a break type by name
+pub fn find_break_type<'a>(types: &'a [BreakType], name: &str) -> Option<&'a BreakType> {
+    types.iter().find(|break_type| break_type.name == name)
+}
+
+// Breaks of one type started in a period
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct BreakTypeSummary {
+    pub kind: String,
+    pub count: usize,
+    pub total_mins: i64,
+}
+
+// Count and length of breaks per type for breaks started inside [from, to),
+// counting a running break up to `now`
+pub fn totals_by_type(breaks: &[BreakPeriod], from: DateTime<Utc>, to: DateTime<Utc>, now: DateTime<Utc>) -> Vec<BreakTypeSummary> {
+    let mut totals: Vec<BreakTypeSummary> = Vec::new();
+
+    for period in breaks.iter().filter(|p| p.start >= from && p.start < to) {
+        let mins = (period.end.unwrap_or(now) - period.start).num_minutes();
+        match totals.iter_mut().find(|total| total.kind == period.kind) {
+            Some(total) => {
+                total.count += 1;
+                total.total_mins += mins;
+            }
+            None => totals.push(BreakTypeSummary {
+                kind: period.kind.clone(),
+                count: 1,
+                total_mins: mins,
+            }),
+        }
+    }
+
+    totals
+}
+
+// Consumption of the daily break allowance. Break time over the allowance
+// is deducted from worked time by policy.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct BreakBudget {
+    pub allowance_mins: u64,
+    pub used_mins: i64,
+    pub remaining_mins: i64,
+    pub exceeded_mins: i64,
+}
+
+impl BreakBudget {
+    pub fn new(allowance_mins: u64, used_mins: i64) -> Self {
+        let remaining_mins = allowance_mins as i64 - used_mins;
+        Self {
+            allowance_mins,
+            used_mins,
+            remaining_mins: remaining_mins.max(0),
+            exceeded_mins: (-remaining_mins).max(0),
+        }
+    }
+
+    pub fn exceeded(&self) -> bool {
+        self.exceeded_mins > 0
+    }
+}
+
+// How many suggested micro-breaks were taken
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+pub struct MicroBreakStats {
+    pub suggested: usize,
+    pub taken: usize,
+}
+
+// Micro-break compliance for suggestions made inside [from, to)
+pub fn micro_break_stats(micro_breaks: &[MicroBreak], from: DateTime<Utc>, to: DateTime<Utc>) -> MicroBreakStats {
+    micro_breaks
+        .iter()
+        .filter(|m| m.suggested_at >= from && m.suggested_at < to)
+        .fold(MicroBreakStats::default(), |mut stats, micro_break| {
+            stats.suggested += 1;
+            if micro_break.taken_at.is_some() {
+                stats.taken += 1;
+            }
+            stats
+        })
+}
+
+// State of the recurring break reminder. Taking a break starts a new work
+// stretch, which resets the cadence and any snooze.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReminderCadence {
+    pub last_reminded: Option<DateTime<Utc>>,
+    pub snoozed_until: Option<DateTime<Utc>>,
+}
+
+impl ReminderCadence {
+    // When the next reminder is due for a stretch of work started at `working_since`
+    pub fn next_due(&self, working_since: DateTime<Utc>, interval: Duration) -> DateTime<Utc> {
+        let reminded = self.last_reminded.filter(|last| *last >= working_since);
+        match (reminded, self.snoozed_until) {
+            (Some(_), Some(until)) => until,
+            (Some(last), None) => last + interval,
+            (None, _) => working_since + interval,
+        }
+    }
+
+    pub fn reminded(&mut self, at: DateTime<Utc>) {
+        self.last_reminded = Some(at);
+        self.snoozed_until = None;
+    }
+
+    pub fn snooze(&mut self, until: DateTime<Utc>) {
+        self.snoozed_until = Some(until);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::History;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_lunch_window_contains() {
+        let window = LunchWindow::default();
+        assert!(window.contains(NaiveTime::from_hms_opt(12, 0, 0).unwrap()));
+        assert!(window.contains(NaiveTime::from_hms_opt(13, 59, 59).unwrap()));
+        assert!(!window.contains(NaiveTime::from_hms_opt(14, 0, 0).unwrap()));
+        assert!(!window.contains(NaiveTime::from_hms_opt(11, 30, 0).unwrap()));
+    }
+
+    #[test]
+    fn test_totals_by_type() {
+        let mut history = History::default();
+        history.start_break("coffee", at("2025-03-10T10:00:00Z"));
+        history.start_break("lunch", at("2025-03-10T10:10:00Z"));
+        history.start_break("coffee", at("2025-03-10T11:00:00Z"));
+        history.start_session(at("2025-03-10T11:20:00Z"));
+        history.start_break("coffee", at("2025-03-11T10:00:00Z"));
+
+        let totals = totals_by_type(
+            &history.breaks,
+            at("2025-03-10T00:00:00Z"),
+            at("2025-03-11T00:00:00Z"),
+            at("2025-03-11T12:00:00Z"),
+        );
+
+        assert_eq!(
+            totals,
+            vec![
+                BreakTypeSummary { kind: "coffee".to_string(), count: 2, total_mins: 30 },
+                BreakTypeSummary { kind: "lunch".to_string(), count: 1, total_mins: 50 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_default_break_types_include_lunch() {
+        let types = default_break_types();
+        assert_eq!(find_break_type(&types, LUNCH_BREAK).and_then(|t| t.max_mins), Some(60));
+        assert!(find_break_type(&types, "nap").is_none());
+    }
+
+    #[test]
+    fn test_reminder_cadence() {
+        let interval = Duration::minutes(90);
+        let mut cadence = ReminderCadence::default();
+        let working_since = at("2025-03-10T09:00:00Z");
+
+        assert_eq!(cadence.next_due(working_since, interval), at("2025-03-10T10:30:00Z"));
+
+        cadence.reminded(at("2025-03-10T10:30:00Z"));
+        assert_eq!(cadence.next_due(working_since, interval), at("2025-03-10T12:00:00Z"));
+
+        cadence.snooze(at("2025-03-10T10:40:00Z"));
+        assert_eq!(cadence.next_due(working_since, interval), at("2025-03-10T10:40:00Z"));
+
+        // A break starts a new stretch and drops the snooze
+        let after_break = at("2025-03-10T10:35:00Z");
+        assert_eq!(cadence.next_due(after_break, interval), at("2025-03-10T12:05:00Z"));
+    }
+
+    #[test]
+    fn test_micro_break_stats() {
+        let mut history = History::default();
+        history.record_micro_break(at("2025-03-10T09:20:00Z"));
+        history.take_micro_break(at("2025-03-10T09:20:00Z"), at("2025-03-10T09:20:30Z"));
+        history.record_micro_break(at("2025-03-10T09:40:00Z"));
+        history.record_micro_break(at("2025-03-11T09:20:00Z"));
+
+        let stats = micro_break_stats(&history.micro_breaks, at("2025-03-10T00:00:00Z"), at("2025-03-11T00:00:00Z"));
+        assert_eq!(stats, MicroBreakStats { suggested: 2, taken: 1 });
+    }
+
+    #[test]
+    fn test_break_budget() {
+        let within = BreakBudget::new(60, 45);
+        assert_eq!(within.remaining_mins, 15);
+        assert!(!within.exceeded());
+
+        let over = BreakBudget::new(60, 75);
+        assert_eq!(over.remaining_mins, 0);
+        assert_eq!(over.exceeded_mins, 15);
+        assert!(over.exceeded());
+    }
+}
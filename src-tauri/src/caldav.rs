@@ -0,0 +1,135 @@
+use chrono::{DateTime, Duration, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::history::Session;
+
+// A CalDAV calendar, e.g. on Nextcloud or Fastmail, finished sessions are
+// published to as events. The password, usually an app password, is kept
+// with the other secrets, see `Secrets::caldav_password`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+#[serde(default)]
+pub struct CalDavSettings {
+    pub enabled: bool,
+    pub calendar_url: String, // The calendar collection, e.g. https://cloud.example.com/remote.php/dav/calendars/alice/work/
+    pub username: String,
+    pub event_title: String,
+}
+
+impl Default for CalDavSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            calendar_url: String::new(),
+            username: String::new(),
+            event_title: "Work".to_string(),
+        }
+    }
+}
+
+// Sessions that ended after `published_through`, oldest first. The first
+// publish only covers the last day, earlier history isn't backfilled.
+pub fn sessions_to_publish(sessions: &[Session], published_through: Option<DateTime<Utc>>, now: DateTime<Utc>) -> Vec<&Session> {
+    let after = published_through.unwrap_or(now - Duration::days(1));
+    let mut finished: Vec<&Session> = sessions.iter().filter(|session| session.end.is_some_and(|end| end > after)).collect();
+    finished.sort_by_key(|session| session.end);
+    finished
+}
+
+// Stable per session and device, so publishing again replaces the event
+pub fn event_uid(session: &Session, device_id: &str) -> String {
+    format!("{}-{}@remodance", session.start.timestamp(), device_id)
+}
+
+// Where the event of `uid` lives in the calendar
+pub fn event_url(calendar_url: &str, uid: &str) -> Result<Url, String> {
+    let mut url = Url::parse(calendar_url.trim()).map_err(|err| format!("Invalid calendar URL: {}", err))?;
+    url.path_segments_mut()
+        .map_err(|_| "Invalid calendar URL".to_string())?
+        .pop_if_empty()
+        .push(&format!("{}.ics", uid));
+    Ok(url)
+}
+
+// Text values escaped as RFC 5545 requires
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace(';', "\\;").replace(',', "\\,").replace('\n', "\\n")
+}
+
+fn ics_time(at: DateTime<Utc>) -> String {
+    at.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+// A calendar holding the one event for a finished session
+pub fn event_ics(uid: &str, start: DateTime<Utc>, end: DateTime<Utc>, title: &str, device_name: &str, now: DateTime<Utc>) -> String {
+    [
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//Remodance//Attendance//EN".to_string(),
+        "BEGIN:VEVENT".to_string(),
+        format!("UID:{}", uid),
+        format!("DTSTAMP:{}", ics_time(now)),
+        format!("DTSTART:{}", ics_time(start)),
+        format!("DTEND:{}", ics_time(end)),
+        format!("SUMMARY:{}", escape(title)),
+        format!("DESCRIPTION:{}", escape(device_name)),
+        "TRANSP:OPAQUE".to_string(),
+        "END:VEVENT".to_string(),
+        "END:VCALENDAR".to_string(),
+    ]
+    .join("\r\n")
+        + "\r\n"
+}
+
+// Create or replace the event of a finished session
+pub async fn publish(settings: &CalDavSettings, password: Option<&str>, session: &Session, device_id: &str, device_name: &str) -> Result<(), String> {
+    let end = session.end.ok_or("The session is still running")?;
+    let uid = event_uid(session, device_id);
+    let ics = event_ics(&uid, session.start, end, &settings.event_title, device_name, Utc::now());
+    let response = reqwest::Client::new()
+        .put(event_url(&settings.calendar_url, &uid)?)
+        .basic_auth(&settings.username, password)
+        .header("Content-Type", "text/calendar; charset=utf-8")
+        .body(ics)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send request: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("CalDAV request failed with status {}", response.status()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_sessions_to_publish() {
+        let now = at("2024-05-06T18:00:00Z");
+        let sessions = vec![
+            Session { start: at("2024-05-04T08:00:00Z"), end: Some(at("2024-05-04T16:00:00Z")) },
+            Session { start: at("2024-05-06T08:00:00Z"), end: Some(at("2024-05-06T12:00:00Z")) },
+            Session { start: at("2024-05-06T13:00:00Z"), end: None },
+        ];
+        assert_eq!(sessions_to_publish(&sessions, None, now), vec![&sessions[1]]);
+        assert!(sessions_to_publish(&sessions, Some(at("2024-05-06T12:00:00Z")), now).is_empty());
+        assert_eq!(sessions_to_publish(&sessions, Some(at("2024-05-01T00:00:00Z")), now).len(), 2);
+    }
+
+    #[test]
+    fn test_event() {
+        let url = event_url("https://cloud.example.com/remote.php/dav/calendars/alice/work/", "1714982400-abc@remodance").unwrap();
+        assert_eq!(url.as_str(), "https://cloud.example.com/remote.php/dav/calendars/alice/work/1714982400-abc@remodance.ics");
+
+        let ics = event_ics("u1", at("2024-05-06T08:00:00Z"), at("2024-05-06T12:30:00Z"), "Work", "laptop, home", at("2024-05-06T18:00:00Z"));
+        assert!(ics.contains("\r\nDTSTART:20240506T080000Z\r\nDTEND:20240506T123000Z\r\n"));
+        assert!(ics.contains("\r\nDESCRIPTION:laptop\\, home\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+    }
+}
@@ -0,0 +1,164 @@
+// Conferencing apps, by the process names they run under. Browsers stand in
+// for Google Meet and other calls in a web page.
+const CALL_APPS: [(&str, &[&str]); 6] = [
+    ("zoom", &["zoom", "zoom.us", "cpthost", "zoomus"]),
+    ("teams", &["teams", "ms-teams", "msteams"]),
+    ("webex", &["webex", "ciscowebexstart", "webexhost"]),
+    ("slack", &["slack"]),
+    ("discord", &["discord"]),
+    ("browser", &["chrome", "chromium", "google-chrome", "firefox", "msedge", "safari", "brave", "opera", "vivaldi"]),
+];
+
+// Processes that only run while their app is in a call, for platforms that
+// don't tell which apps use the microphone. Zoom starts its meeting host next
+// to the client when a meeting begins.
+const IN_CALL_PROCESSES: [(&str, &str); 1] = [("cpthost", "zoom")];
+
+// Lowercase process name without a path or `.exe`
+fn normalize(name: &str) -> String {
+    let name = name.rsplit(['/', '\\', '#']).next().unwrap_or(name).to_lowercase();
+    name.strip_suffix(".exe").map(str::to_string).unwrap_or(name)
+}
+
+// Conferencing app a process belongs to
+fn call_app(process: &str) -> Option<&'static str> {
+    let process = normalize(process);
+    CALL_APPS
+        .iter()
+        .find(|(_, names)| names.iter().any(|name| process == *name || process.starts_with(&format!("{}_", name))))
+        .map(|(app, _)| *app)
+}
+
+// The app in a call. Where the platform tells which processes record from
+// the microphone, a conferencing app or browser among them is in a call.
+// Otherwise only in-call helper processes count, so an open but idle Zoom
+// window doesn't.
+pub fn detect(mic_users: Option<&[String]>, processes: &[String]) -> Option<&'static str> {
+    match mic_users {
+        Some(users) => users.iter().find_map(|user| call_app(user)),
+        None => processes.iter().find_map(|process| {
+            let process = normalize(process);
+            IN_CALL_PROCESSES.iter().find(|(name, _)| process == *name).map(|(_, app)| *app)
+        }),
+    }
+}
+
+// Binaries recording audio, from `pactl list source-outputs`
+#[cfg(any(not(any(target_os = "macos", target_os = "windows")), test))]
+pub fn parse_source_outputs(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("application.process.binary = "))
+        .map(|binary| binary.trim_matches('"').to_string())
+        .collect()
+}
+
+// Apps using the microphone right now, from `reg query` of the microphone
+// consent store. An app is recording while its `LastUsedTimeStop` is zero.
+#[cfg(any(target_os = "windows", test))]
+pub fn parse_consent_store(output: &str) -> Vec<String> {
+    let mut users = Vec::new();
+    let mut key = None;
+    for line in output.lines() {
+        let line = line.trim();
+        if line.starts_with("HKEY_") {
+            key = Some(line);
+        } else if let Some(stop) = line.strip_prefix("LastUsedTimeStop") {
+            let recording = stop.split_whitespace().last() == Some("0x0");
+            if let (true, Some(key)) = (recording, key) {
+                users.push(key.rsplit('\\').next().unwrap_or(key).to_string());
+            }
+        }
+    }
+    users
+}
+
+#[cfg(target_os = "macos")]
+fn mic_users() -> Option<Vec<String>> {
+    None
+}
+
+#[cfg(target_os = "macos")]
+fn processes() -> Vec<String> {
+    std::process::Command::new("ps")
+        .args(["-axco", "comm="])
+        .output()
+        .map(|output| String::from_utf8_lossy(&output.stdout).lines().map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+#[cfg(target_os = "windows")]
+fn mic_users() -> Option<Vec<String>> {
+    let key = "HKCU\\Software\\Microsoft\\Windows\\CurrentVersion\\CapabilityAccessManager\\ConsentStore\\microphone";
+    let output = std::process::Command::new("reg").args(["query", key, "/s"]).output().ok()?;
+    output.status.success().then(|| parse_consent_store(&String::from_utf8_lossy(&output.stdout)))
+}
+
+// Not needed, the consent store answers on every supported Windows version
+#[cfg(target_os = "windows")]
+fn processes() -> Vec<String> {
+    Vec::new()
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn mic_users() -> Option<Vec<String>> {
+    let output = std::process::Command::new("pactl").args(["list", "source-outputs"]).output().ok()?;
+    output.status.success().then(|| parse_source_outputs(&String::from_utf8_lossy(&output.stdout)))
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn processes() -> Vec<String> {
+    std::fs::read_dir("/proc")
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|entry| std::fs::read_to_string(entry.path().join("comm")).ok())
+                .map(|comm| comm.trim().to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// The conferencing app in a call right now, if any
+pub fn current() -> Option<&'static str> {
+    match mic_users() {
+        Some(users) => detect(Some(&users), &[]),
+        None => detect(None, &processes()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn test_detect_call_apps() {
+        assert_eq!(detect(Some(&names(&["pipewire", "zoom"])), &[]), Some("zoom"));
+        assert_eq!(detect(Some(&names(&["C:#Program Files#Google#Chrome#Application#chrome.exe"])), &[]), Some("browser"));
+        assert_eq!(detect(Some(&names(&["MSTeams_8wekyb3d8bbwe"])), &[]), Some("teams"));
+        assert_eq!(detect(Some(&names(&["obs"])), &names(&["CptHost"])), None);
+        assert_eq!(detect(None, &names(&["zoom.us", "CptHost"])), Some("zoom"));
+        assert_eq!(detect(None, &names(&["zoom.us"])), None);
+    }
+
+    #[test]
+    fn test_parse_microphone_users() {
+        let pactl = "Source Output #42\n\tProperties:\n\t\tapplication.name = \"ZOOM VoiceEngine\"\n\t\tapplication.process.binary = \"zoom\"\n";
+        assert_eq!(parse_source_outputs(pactl), names(&["zoom"]));
+
+        let reg = "\
+HKEY_CURRENT_USER\\Software\\Microsoft\\Windows\\CurrentVersion\\CapabilityAccessManager\\ConsentStore\\microphone\\NonPackaged\\C:#Program Files#Zoom#bin#Zoom.exe
+    LastUsedTimeStart    REG_QWORD    0x1da9f0c2b3e4a10
+    LastUsedTimeStop    REG_QWORD    0x0
+
+HKEY_CURRENT_USER\\Software\\Microsoft\\Windows\\CurrentVersion\\CapabilityAccessManager\\ConsentStore\\microphone\\NonPackaged\\C:#Windows#explorer.exe
+    LastUsedTimeStart    REG_QWORD    0x1da9f0c2b3e4a10
+    LastUsedTimeStop    REG_QWORD    0x1da9f0c2b3e4a20
+";
+        assert_eq!(parse_consent_store(reg), names(&["C:#Program Files#Zoom#bin#Zoom.exe"]));
+    }
+}
@@ -0,0 +1,149 @@
+use std::path::Path;
+
+use chrono::Local;
+use serde::de::DeserializeOwned;
+use tauri::AppHandle;
+
+use crate::breaks;
+use crate::history::History;
+use crate::profiles::{self, Profiles};
+use crate::queue::PendingQueue;
+use crate::AppState;
+
+// Headless commands, e.g. `remodance --check-in` from a script or cron job.
+// A running instance receives them through the single-instance plugin and
+// logs the result, otherwise the app starts without its window, runs the
+// command, prints the result and exits. `--status` only reads from disk.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CliCommand {
+    CheckIn,
+    CheckOut,
+    Status,
+    FlushQueue,
+}
+
+// Flags given at launch
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LaunchArgs {
+    pub command: Option<CliCommand>,
+    pub headless: bool, // Run with only the tray icon, see `Settings::headless`
+    pub profile: Option<String>, // Switch to this profile before anything else, `--profile <name>`
+}
+
+// Parse the launch arguments (without the program name). Other arguments,
+// such as deep links, are left alone.
+pub fn parse<I, S>(args: I) -> Result<LaunchArgs, String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let mut launch_args = LaunchArgs::default();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let arg = arg.as_ref();
+        if !arg.starts_with("--") {
+            continue;
+        }
+        if arg == "--headless" {
+            launch_args.headless = true;
+            continue;
+        }
+        if arg == "--profile" || arg.starts_with("--profile=") {
+            let name = match arg.strip_prefix("--profile=") {
+                Some(name) => name.to_string(),
+                None => args.next().map(|name| name.as_ref().to_string()).ok_or("--profile needs a profile name")?,
+            };
+            launch_args.profile = Some(profiles::check_name(&name)?.to_string());
+            continue;
+        }
+
+        let parsed = match arg {
+            "--check-in" => CliCommand::CheckIn,
+            "--check-out" => CliCommand::CheckOut,
+            "--status" => CliCommand::Status,
+            "--flush-queue" => CliCommand::FlushQueue,
+            _ => return Err(format!("Unknown option '{}'", arg)),
+        };
+        if launch_args.command.replace(parsed).is_some() {
+            return Err("Only one command can be given at a time".to_string());
+        }
+    }
+
+    Ok(launch_args)
+}
+
+// Run a command inside the app, returning the line to report back
+pub async fn run(app_handle: &AppHandle, state: &AppState, command: CliCommand) -> Result<String, String> {
+    match command {
+        CliCommand::CheckIn => crate::set_attendance(app_handle, state, "check-in").await.map(|_| "Checked in".to_string()),
+        CliCommand::CheckOut => crate::set_attendance(app_handle, state, "check-out").await.map(|_| "Checked out".to_string()),
+        CliCommand::FlushQueue => crate::flush_pending_events(app_handle, state)
+            .await
+            .map(|sent| format!("Sent {} queued event(s)", sent)),
+        CliCommand::Status => Ok(status_line(&state.history.lock().unwrap(), state.pending.lock().unwrap().len())),
+    }
+}
+
+// Status as recorded in local history, which the running instance keeps current
+pub fn status_line(history: &History, queued: usize) -> String {
+    let status = match (history.current_break(), history.current_session()) {
+        (Some(period), _) if period.kind == breaks::PAUSE_BREAK => format!("paused since {}", period.start.with_timezone(&Local).format("%H:%M")),
+        (Some(period), _) => format!("on {} break since {}", period.kind, period.start.with_timezone(&Local).format("%H:%M")),
+        (None, Some(session)) => format!("checked in since {}", session.start.with_timezone(&Local).format("%H:%M")),
+        (None, None) => "checked out".to_string(),
+    };
+
+    format!("{}, {} event(s) queued", status, queued)
+}
+
+// Print the status without starting the app. Reads the stores straight from
+// the app data directory so it also works while another instance is running.
+pub fn print_status(data_dir: &Path) {
+    let profiles: Profiles = read_store(data_dir, crate::PROFILES_FILENAME, "profiles");
+    let history_file = profiles::history_filename(profiles.active.as_deref());
+    let history: History = read_store(data_dir, &history_file, "history");
+    let pending: PendingQueue = read_store(data_dir, crate::PENDING_FILENAME, "pending");
+    println!("{}", status_line(&history, pending.len()));
+}
+
+// Read one key of a store file, falling back to the default when it is missing or unreadable
+fn read_store<T: DeserializeOwned + Default>(data_dir: &Path, file: &str, key: &str) -> T {
+    std::fs::read_to_string(data_dir.join(file))
+        .ok()
+        .and_then(|contents| serde_json::from_str::<serde_json::Value>(&contents).ok())
+        .and_then(|mut store| serde_json::from_value(store[key].take()).ok())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ignores_other_arguments() {
+        assert_eq!(parse(["remodance://settings"]), Ok(LaunchArgs::default()));
+        assert_eq!(parse(["--check-in"]).map(|args| args.command), Ok(Some(CliCommand::CheckIn)));
+        assert_eq!(
+            parse(["remodance://settings", "--headless", "--flush-queue"]),
+            Ok(LaunchArgs { command: Some(CliCommand::FlushQueue), headless: true, profile: None })
+        );
+        assert_eq!(parse(["--profile", "work", "--headless"]).map(|args| args.profile), Ok(Some("work".to_string())));
+        assert_eq!(parse(["--profile=home"]).map(|args| args.profile), Ok(Some("home".to_string())));
+        assert!(parse(["--profile"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_rejects_unknown_and_repeated_commands() {
+        assert!(parse(["--check-inn"]).is_err());
+        assert!(parse(["--check-in", "--check-out"]).is_err());
+    }
+
+    #[test]
+    fn test_status_line_from_history() {
+        let mut history = History::default();
+        assert_eq!(status_line(&history, 0), "checked out, 0 event(s) queued");
+
+        history.start_session(chrono::Utc::now());
+        assert!(status_line(&history, 2).starts_with("checked in since"));
+    }
+}
@@ -0,0 +1,125 @@
+use chrono::{DateTime, Datelike, Duration, FixedOffset, Local, LocalResult, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+
+// Parse an IANA timezone name such as "Europe/Berlin"
+pub fn parse_timezone(name: &str) -> Result<Tz, String> {
+    name.parse::<Tz>()
+        .map_err(|_| format!("Unknown timezone '{}'", name))
+}
+
+// Current time in the configured timezone, falling back to the system
+// timezone when none (or an invalid one) is configured
+pub fn now(timezone: Option<&str>) -> DateTime<FixedOffset> {
+    local(timezone, Utc::now())
+}
+
+// An instant as wall-clock time in the configured timezone
+pub fn local(timezone: Option<&str>, at: DateTime<Utc>) -> DateTime<FixedOffset> {
+    match timezone.map(parse_timezone) {
+        Some(Ok(tz)) => at.with_timezone(&tz).fixed_offset(),
+        _ => at.with_timezone(&Local).fixed_offset(),
+    }
+}
+
+// Current calendar date in the configured timezone
+pub fn today(timezone: Option<&str>) -> NaiveDate {
+    date_of(timezone, Utc::now())
+}
+
+// Calendar date of an instant in the configured timezone
+pub fn date_of(timezone: Option<&str>, at: DateTime<Utc>) -> NaiveDate {
+    match timezone.map(parse_timezone) {
+        Some(Ok(tz)) => at.with_timezone(&tz).date_naive(),
+        _ => at.with_timezone(&Local).date_naive(),
+    }
+}
+
+// Resolve a wall-clock time on a date in the configured timezone. Times that
+// fall into a DST gap are moved forward past the gap, and ambiguous times
+// (the repeated hour when clocks go back) resolve to the first occurrence.
+pub fn resolve(timezone: Option<&str>, date: NaiveDate, time: NaiveTime) -> DateTime<FixedOffset> {
+    let naive = date.and_time(time);
+    match timezone.map(parse_timezone) {
+        Some(Ok(tz)) => resolve_in(&tz, naive),
+        _ => resolve_in(&Local, naive),
+    }
+}
+
+fn resolve_in<T: TimeZone>(tz: &T, naive: NaiveDateTime) -> DateTime<FixedOffset> {
+    match tz.from_local_datetime(&naive) {
+        LocalResult::Single(dt) => dt.fixed_offset(),
+        LocalResult::Ambiguous(earliest, _) => earliest.fixed_offset(),
+        LocalResult::None => resolve_in(tz, naive + Duration::hours(1)),
+    }
+}
+
+// UTC bounds of a calendar day in the configured timezone. Days are not
+// always 24 hours long around DST transitions.
+pub fn day_bounds(timezone: Option<&str>, date: NaiveDate) -> (DateTime<Utc>, DateTime<Utc>) {
+    let start = resolve(timezone, date, NaiveTime::MIN).with_timezone(&Utc);
+    let end = resolve(timezone, date + Duration::days(1), NaiveTime::MIN).with_timezone(&Utc);
+    (start, end)
+}
+
+// First day (Monday) of the week containing the date
+pub fn week_start(date: NaiveDate) -> NaiveDate {
+    date - Duration::days(date.weekday().num_days_from_monday() as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_now_uses_configured_timezone() {
+        // Tokyo has no DST, so the offset is always +09:00
+        let now = now(Some("Asia/Tokyo"));
+        assert_eq!(now.offset().local_minus_utc(), 9 * 3600);
+    }
+
+    #[test]
+    fn test_today_differs_across_date_line() {
+        // UTC+14 and UTC-11 are 25 hours apart, so the dates never match
+        let east = today(Some("Pacific/Kiritimati"));
+        let west = today(Some("Pacific/Pago_Pago"));
+        assert!(east > west);
+    }
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn time(s: &str) -> NaiveTime {
+        NaiveTime::parse_from_str(s, "%H:%M").unwrap()
+    }
+
+    #[test]
+    fn test_resolve_skips_dst_gap() {
+        let resolved = resolve(Some("Europe/Berlin"), date("2025-03-30"), time("02:30"));
+        assert_eq!(resolved.to_rfc3339(), "2025-03-30T03:30:00+02:00");
+    }
+
+    #[test]
+    fn test_resolve_ambiguous_time_uses_first_occurrence() {
+        let resolved = resolve(Some("Europe/Berlin"), date("2025-10-26"), time("02:30"));
+        assert_eq!(resolved.to_rfc3339(), "2025-10-26T02:30:00+02:00");
+    }
+
+    #[test]
+    fn test_day_bounds_across_dst_change() {
+        let (start, end) = day_bounds(Some("Europe/Berlin"), date("2025-03-30"));
+        assert_eq!(end - start, Duration::hours(23));
+    }
+
+    #[test]
+    fn test_week_start_is_monday() {
+        assert_eq!(week_start(date("2025-03-30")), date("2025-03-24"));
+        assert_eq!(week_start(date("2025-03-24")), date("2025-03-24"));
+    }
+
+    #[test]
+    fn test_parse_timezone_rejects_unknown_names() {
+        assert!(parse_timezone("America/New_York").is_ok());
+        assert!(parse_timezone("Mars/Olympus_Mons").is_err());
+    }
+}
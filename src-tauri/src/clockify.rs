@@ -0,0 +1,144 @@
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::time_tracker::{TimeEntry, TimeTracker};
+
+const API_URL: &str = "https://api.clockify.me/api/v1";
+
+// Where Clockify entries go. The API key is kept with the other secrets, see
+// `Secrets::clockify_api_key`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+#[serde(default)]
+pub struct ClockifySettings {
+    pub workspace_id: Option<String>, // Workspace entries are created in
+    pub project_id: Option<String>, // Project of the entries, none when unset
+    pub description: String,
+}
+
+impl Default for ClockifySettings {
+    fn default() -> Self {
+        Self {
+            workspace_id: None,
+            project_id: None,
+            description: "Work".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct User {
+    id: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Entry {
+    id: String,
+    workspace_id: String,
+    #[serde(default)]
+    description: String,
+    project_id: Option<String>,
+}
+
+pub struct Clockify<'a> {
+    pub settings: &'a ClockifySettings,
+    pub api_key: &'a str,
+}
+
+pub fn start_body(settings: &ClockifySettings, now: DateTime<Utc>) -> Value {
+    json!({
+        "start": now.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        "description": settings.description,
+        "projectId": settings.project_id,
+    })
+}
+
+// The running entry, from the user's in-progress entries. Clockify only tags
+// with tags created beforehand, so entries with the configured description
+// and project count as started by the app.
+pub fn parse_running(body: &str, settings: &ClockifySettings) -> Result<Option<TimeEntry>, String> {
+    let entries: Vec<Entry> = serde_json::from_str(body).map_err(|err| format!("Unexpected Clockify response: {}", err))?;
+    Ok(entries.into_iter().next().map(|entry| TimeEntry {
+        started_by_app: entry.description == settings.description && entry.project_id == settings.project_id,
+        id: entry.id,
+        workspace_id: entry.workspace_id,
+    }))
+}
+
+impl Clockify<'_> {
+    fn workspace_id(&self) -> Result<&str, String> {
+        self.settings.workspace_id.as_deref().ok_or_else(|| "No Clockify workspace is configured".to_string())
+    }
+
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<String, String> {
+        let response = request
+            .header("X-Api-Key", self.api_key)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to send request: {}", e))?;
+        if !response.status().is_success() {
+            return Err(format!("Clockify request failed with status {}", response.status()));
+        }
+        response.text().await.map_err(|e| format!("Failed to read response: {}", e))
+    }
+
+    // ID of the user the API key belongs to
+    async fn user_id(&self) -> Result<String, String> {
+        let body = self.send(reqwest::Client::new().get(format!("{}/user", API_URL))).await?;
+        let user: User = serde_json::from_str(&body).map_err(|err| format!("Unexpected Clockify response: {}", err))?;
+        Ok(user.id)
+    }
+}
+
+impl TimeTracker for Clockify<'_> {
+    fn name(&self) -> &'static str {
+        "Clockify"
+    }
+
+    async fn running(&self) -> Result<Option<TimeEntry>, String> {
+        let url = format!("{}/workspaces/{}/user/{}/time-entries?in-progress=true", API_URL, self.workspace_id()?, self.user_id().await?);
+        let body = self.send(reqwest::Client::new().get(url)).await?;
+        parse_running(&body, self.settings)
+    }
+
+    async fn start(&self, now: DateTime<Utc>) -> Result<(), String> {
+        let url = format!("{}/workspaces/{}/time-entries", API_URL, self.workspace_id()?);
+        self.send(reqwest::Client::new().post(url).json(&start_body(self.settings, now))).await?;
+        Ok(())
+    }
+
+    // Clockify stops whichever entry of the user is running
+    async fn stop(&self, entry: &TimeEntry, now: DateTime<Utc>) -> Result<(), String> {
+        let url = format!("{}/workspaces/{}/user/{}/time-entries", API_URL, entry.workspace_id, self.user_id().await?);
+        let body = json!({ "end": now.to_rfc3339_opts(chrono::SecondsFormat::Secs, true) });
+        self.send(reqwest::Client::new().patch(url).json(&body)).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_running_recognizes_own_entries() {
+        let settings = ClockifySettings { project_id: Some("p1".to_string()), ..ClockifySettings::default() };
+        let body = r#"[{"id": "e1", "workspaceId": "w1", "description": "Work", "projectId": "p1", "timeInterval": {"start": "2024-05-06T09:00:00Z", "end": null}}]"#;
+        let running = parse_running(body, &settings).unwrap().unwrap();
+        assert_eq!((running.id.as_str(), running.started_by_app), ("e1", true));
+
+        let other = r#"[{"id": "e2", "workspaceId": "w1", "description": "Support ticket", "projectId": "p1"}]"#;
+        assert!(!parse_running(other, &settings).unwrap().unwrap().started_by_app);
+        assert_eq!(parse_running("[]", &settings).unwrap(), None);
+    }
+
+    #[test]
+    fn test_start_body() {
+        let now = DateTime::parse_from_rfc3339("2024-05-06T09:00:00Z").unwrap().with_timezone(&Utc);
+        let body = start_body(&ClockifySettings::default(), now);
+        assert_eq!(body["start"], json!("2024-05-06T09:00:00Z"));
+        assert_eq!(body["projectId"], Value::Null);
+    }
+}
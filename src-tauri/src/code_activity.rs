@@ -0,0 +1,149 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use url::Url;
+
+// Where the commit and pull request activity is read from
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum CodeHost {
+    #[default]
+    Github,
+    Gitlab,
+}
+
+// Recent pushes and pull requests of the user's own account, counted as
+// activity while this machine is idle, e.g. when working on a second
+// computer. The token is kept with the other secrets, see
+// `Secrets::code_activity_token`; without one only public activity is seen.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default, JsonSchema)]
+#[serde(default)]
+pub struct CodeActivitySettings {
+    pub enabled: bool,
+    pub host: CodeHost,
+    pub api_url: Option<String>, // For GitHub Enterprise or self-hosted GitLab, e.g. https://gitlab.example.com
+    pub username: String, // Login on the host
+}
+
+// Where the user's recent events are listed
+pub fn events_url(settings: &CodeActivitySettings) -> Result<Url, String> {
+    let base = match (settings.host, settings.api_url.as_deref().map(str::trim)) {
+        (_, Some(api_url)) if !api_url.is_empty() => api_url,
+        (CodeHost::Github, _) => "https://api.github.com",
+        (CodeHost::Gitlab, _) => "https://gitlab.com",
+    };
+    let path = match settings.host {
+        CodeHost::Github => vec!["users", settings.username.trim(), "events"],
+        CodeHost::Gitlab => vec!["api", "v4", "users", settings.username.trim(), "events"],
+    };
+    let mut url = Url::parse(base).map_err(|err| format!("Invalid API URL: {}", err))?;
+    url.path_segments_mut()
+        .map_err(|_| "Invalid API URL".to_string())?
+        .pop_if_empty()
+        .extend(path);
+    Ok(url)
+}
+
+// Whether an event is the user's own commit or pull request work, as
+// opposed to e.g. starring a repository
+fn is_code_event(host: CodeHost, event: &Value) -> bool {
+    match host {
+        CodeHost::Github => matches!(
+            event["type"].as_str(),
+            Some("PushEvent" | "PullRequestEvent" | "PullRequestReviewEvent" | "PullRequestReviewCommentEvent" | "CommitCommentEvent")
+        ),
+        CodeHost::Gitlab => {
+            event["action_name"].as_str().is_some_and(|action| action.starts_with("pushed"))
+                || matches!(event["target_type"].as_str(), Some("MergeRequest" | "DiffNote"))
+        }
+    }
+}
+
+// Time of the latest commit or pull request event in a listing
+pub fn latest_activity(host: CodeHost, events: &Value) -> Option<DateTime<Utc>> {
+    events
+        .as_array()?
+        .iter()
+        .filter(|event| is_code_event(host, event))
+        .filter_map(|event| event["created_at"].as_str())
+        .filter_map(|created_at| DateTime::parse_from_rfc3339(created_at).ok())
+        .map(|created_at| created_at.with_timezone(&Utc))
+        .max()
+}
+
+// The idle time with the latest code activity counted as input on this
+// machine
+pub fn idle_with_activity(idle: Duration, last_activity: Option<DateTime<Utc>>, now: DateTime<Utc>) -> Duration {
+    match last_activity.and_then(|at| (now - at).to_std().ok()) {
+        Some(since) if since < idle => since,
+        _ => idle,
+    }
+}
+
+// Fetch the time of the user's latest commit or pull request event
+pub async fn fetch_latest(settings: &CodeActivitySettings, token: Option<&str>) -> Result<Option<DateTime<Utc>>, String> {
+    // GitHub refuses requests without a user agent
+    let request = reqwest::Client::new()
+        .get(events_url(settings)?)
+        .header("User-Agent", "remodance");
+    let request = match (settings.host, token) {
+        (CodeHost::Github, Some(token)) => request.bearer_auth(token).header("Accept", "application/vnd.github+json"),
+        (CodeHost::Github, None) => request.header("Accept", "application/vnd.github+json"),
+        (CodeHost::Gitlab, Some(token)) => request.header("PRIVATE-TOKEN", token),
+        (CodeHost::Gitlab, None) => request,
+    };
+    let response = request.send().await.map_err(|e| format!("Failed to send request: {}", e))?;
+
+    if !response.status().is_success() {
+        return Err(format!("Activity request failed with status {}", response.status()));
+    }
+    let events: Value = response.json().await.map_err(|e| format!("Failed to read the activity: {}", e))?;
+    Ok(latest_activity(settings.host, &events))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_events_url() {
+        let github = CodeActivitySettings { username: "alice".to_string(), ..Default::default() };
+        assert_eq!(events_url(&github).unwrap().as_str(), "https://api.github.com/users/alice/events");
+        let gitlab = CodeActivitySettings {
+            host: CodeHost::Gitlab,
+            api_url: Some("https://gitlab.example.com/".to_string()),
+            username: "alice".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(events_url(&gitlab).unwrap().as_str(), "https://gitlab.example.com/api/v4/users/alice/events");
+    }
+
+    #[test]
+    fn test_latest_activity() {
+        let github = json!([
+            { "type": "WatchEvent", "created_at": "2024-05-06T12:00:00Z" },
+            { "type": "PushEvent", "created_at": "2024-05-06T10:30:00Z" },
+            { "type": "PullRequestEvent", "created_at": "2024-05-06T09:00:00Z" },
+        ]);
+        assert_eq!(latest_activity(CodeHost::Github, &github).unwrap().to_rfc3339(), "2024-05-06T10:30:00+00:00");
+        let gitlab = json!([
+            { "action_name": "joined", "target_type": null, "created_at": "2024-05-06T12:00:00.000Z" },
+            { "action_name": "pushed to", "target_type": null, "created_at": "2024-05-06T11:00:00.000Z" },
+        ]);
+        assert_eq!(latest_activity(CodeHost::Gitlab, &gitlab).unwrap().to_rfc3339(), "2024-05-06T11:00:00+00:00");
+        assert_eq!(latest_activity(CodeHost::Github, &json!({ "message": "Not Found" })), None);
+    }
+
+    #[test]
+    fn test_idle_with_activity() {
+        let now = DateTime::parse_from_rfc3339("2024-05-06T12:00:00Z").unwrap().with_timezone(&Utc);
+        let pushed = Some(now - chrono::Duration::minutes(2));
+        assert_eq!(idle_with_activity(Duration::from_secs(3600), pushed, now), Duration::from_secs(120));
+        assert_eq!(idle_with_activity(Duration::from_secs(30), pushed, now), Duration::from_secs(30));
+        assert_eq!(idle_with_activity(Duration::from_secs(30), None, now), Duration::from_secs(30));
+    }
+}
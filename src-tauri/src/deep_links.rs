@@ -0,0 +1,107 @@
+use std::sync::Arc;
+
+use log::{error, info};
+use tauri::{AppHandle, Emitter, Manager, State};
+use url::Url;
+
+use crate::AppState;
+
+pub const SCHEME: &str = "remodance";
+
+// What a `remodance://` link asks the app to do
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeepLinkAction {
+    CheckIn { note: Option<String> },
+    CheckOut { note: Option<String> },
+    StartBreak { kind: String },
+    EndBreak,
+    OpenSettings,
+}
+
+// Parse a link such as `remodance://check-in?note=standup` or `remodance://break?kind=coffee`
+pub fn parse(link: &str) -> Result<DeepLinkAction, String> {
+    let url = Url::parse(link).map_err(|err| format!("Invalid link '{}': {}", link, err))?;
+    if url.scheme() != SCHEME {
+        return Err(format!("Unsupported link scheme '{}'", url.scheme()));
+    }
+
+    let param = |name: &str| {
+        url.query_pairs()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.into_owned())
+            .filter(|value| !value.is_empty())
+    };
+
+    match url.host_str().unwrap_or_default() {
+        "check-in" => Ok(DeepLinkAction::CheckIn { note: param("note") }),
+        "check-out" => Ok(DeepLinkAction::CheckOut { note: param("note") }),
+        "break" => param("kind")
+            .map(|kind| DeepLinkAction::StartBreak { kind })
+            .ok_or_else(|| "Break links need a 'kind' parameter".to_string()),
+        "end-break" => Ok(DeepLinkAction::EndBreak),
+        "settings" => Ok(DeepLinkAction::OpenSettings),
+        action => Err(format!("Unknown link action '{}'", action)),
+    }
+}
+
+// Perform the action of an opened link
+pub fn handle(app_handle: &AppHandle, link: &str) {
+    let action = match parse(link) {
+        Ok(action) => action,
+        Err(err) => {
+            error!("Ignoring deep link: {}", err);
+            return;
+        }
+    };
+    info!("Deep link opened: {:?}", action);
+
+    if action == DeepLinkAction::OpenSettings {
+        crate::tray::show_main_window(app_handle);
+        let _ = app_handle.emit("open_settings", ());
+        return;
+    }
+
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        let state: State<'_, Arc<AppState>> = app_handle.state();
+        let result = match action {
+            DeepLinkAction::CheckIn { note } => crate::set_attendance_with_note(&app_handle, &state, "check-in", note).await,
+            DeepLinkAction::CheckOut { note } => crate::set_attendance_with_note(&app_handle, &state, "check-out", note).await,
+            DeepLinkAction::StartBreak { kind } => crate::take_break(&app_handle, &state, &kind).await,
+            DeepLinkAction::EndBreak => crate::return_from_break(&app_handle, &state).await,
+            DeepLinkAction::OpenSettings => Ok(()),
+        };
+
+        if let Err(err) = result {
+            error!("Deep link failed: {}", err);
+        }
+        crate::tray::refresh(&app_handle, &state);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_actions_and_parameters() {
+        assert_eq!(
+            parse("remodance://check-in?note=stand%20up"),
+            Ok(DeepLinkAction::CheckIn { note: Some("stand up".to_string()) })
+        );
+        assert_eq!(parse("remodance://check-out"), Ok(DeepLinkAction::CheckOut { note: None }));
+        assert_eq!(
+            parse("remodance://break?kind=coffee"),
+            Ok(DeepLinkAction::StartBreak { kind: "coffee".to_string() })
+        );
+        assert_eq!(parse("remodance://settings"), Ok(DeepLinkAction::OpenSettings));
+    }
+
+    #[test]
+    fn test_parse_rejects_unknown_links() {
+        assert!(parse("remodance://break").is_err());
+        assert!(parse("remodance://delete-everything").is_err());
+        assert!(parse("https://check-in").is_err());
+        assert!(parse("not a link").is_err());
+    }
+}
@@ -0,0 +1,30 @@
+// Debugging commands. They bypass the normal attendance flow, so they are
+// rejected unless `developer_mode` is on.
+pub const COMMANDS: [&str; 3] = [
+    "simulate_idle", // Pretend the user has been idle for a while
+    "dump_state",    // Return the in-memory state as JSON
+    "force_send",    // Post an event without changing the status
+];
+
+// Refuse a developer command outside developer mode
+pub fn require(developer_mode: bool, command: &str) -> Result<(), String> {
+    if !COMMANDS.contains(&command) {
+        return Err(format!("'{}' is not a registered developer command", command));
+    }
+    if !developer_mode {
+        return Err(format!("'{}' is only available in developer mode", command));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_require_developer_mode() {
+        assert!(require(true, "dump_state").is_ok());
+        assert!(require(false, "dump_state").unwrap_err().contains("developer mode"));
+        assert!(require(true, "delete_everything").is_err());
+    }
+}
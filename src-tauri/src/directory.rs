@@ -0,0 +1,62 @@
+use serde::Deserialize;
+
+// Placeholder in `directory_url` replaced by the username
+const USERNAME_PLACEHOLDER: &str = "{username}";
+
+// Directory response, e.g. `{"employee_id": "E-1042"}`
+#[derive(Debug, Deserialize)]
+struct DirectoryEntry {
+    employee_id: String,
+}
+
+// URL to look a username up at. The username goes where `{username}` is,
+// or into a `username` query parameter when the URL has no placeholder.
+pub fn lookup_url(template: &str, username: &str) -> Result<String, String> {
+    let url = if template.contains(USERNAME_PLACEHOLDER) {
+        url::Url::parse(&template.replace(USERNAME_PLACEHOLDER, &percent_encode(username)))
+    } else {
+        url::Url::parse_with_params(template, [("username", username)])
+    };
+    url.map(String::from).map_err(|err| format!("Invalid directory URL: {}", err))
+}
+
+// Encode everything but unreserved characters, so the username is safe in a path or query
+fn percent_encode(text: &str) -> String {
+    text.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (byte as char).to_string(),
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+// The employee ID in a directory response
+pub fn parse_response(body: &str) -> Result<String, String> {
+    let entry: DirectoryEntry = serde_json::from_str(body).map_err(|err| format!("Invalid directory response: {}", err))?;
+    let employee_id = entry.employee_id.trim();
+    if employee_id.is_empty() {
+        return Err("The directory has no employee ID for this user".to_string());
+    }
+    Ok(employee_id.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup_url_and_response() {
+        assert_eq!(
+            lookup_url("https://hr.example/people/{username}", "j doe").unwrap(),
+            "https://hr.example/people/j%20doe"
+        );
+        assert_eq!(
+            lookup_url("https://hr.example/lookup?format=json", "jdoe").unwrap(),
+            "https://hr.example/lookup?format=json&username=jdoe"
+        );
+
+        assert_eq!(parse_response(r#"{"employee_id": " E-1042 "}"#), Ok("E-1042".to_string()));
+        assert!(parse_response(r#"{"employee_id": ""}"#).is_err());
+        assert!(parse_response("not json").is_err());
+    }
+}
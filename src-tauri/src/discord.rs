@@ -0,0 +1,131 @@
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+use discord_rich_presence::activity::{Activity, Timestamps};
+use discord_rich_presence::{DiscordIpc, DiscordIpcClient};
+use log::{debug, info};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State};
+
+use crate::history::History;
+use crate::i18n::t;
+use crate::{AppState, AttendanceStatus};
+
+// A Discord activity showing "Working" with the time since check-in, shown
+// while checked in. Needs Discord running on the same machine.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+#[serde(default)]
+pub struct DiscordSettings {
+    pub enabled: bool,
+    pub application_id: Option<String>, // Discord application named after what should show, e.g. "Remodance"
+    pub show_breaks: bool, // Keep the activity during breaks, as "On a break"
+}
+
+impl Default for DiscordSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            application_id: None,
+            show_breaks: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Presence {
+    pub details: String,
+    pub since: DateTime<Utc>, // Discord counts the elapsed time up from here
+}
+
+// The activity for a status, none when it should be cleared
+pub fn presence(settings: &DiscordSettings, status: &AttendanceStatus, history: &History) -> Option<Presence> {
+    match status {
+        AttendanceStatus::CheckedIn => history.current_session().map(|session| Presence {
+            details: t("discord.working"),
+            since: session.start,
+        }),
+        AttendanceStatus::OnBreak if settings.show_breaks => history.current_break().map(|current| Presence {
+            details: t("status.on_break"),
+            since: current.start,
+        }),
+        _ => None,
+    }
+}
+
+// The open connection and the application it was opened for. Discord drops the
+// activity when the connection closes, so it is kept open while enabled.
+static CLIENT: Mutex<Option<(String, DiscordIpcClient)>> = Mutex::new(None);
+
+fn apply(client: &mut Option<(String, DiscordIpcClient)>, application_id: Option<&str>, presence: Option<Presence>) -> Result<(), String> {
+    if client.as_ref().is_some_and(|(id, _)| Some(id.as_str()) != application_id) {
+        if let Some((_, mut old)) = client.take() {
+            let _ = old.close();
+        }
+    }
+    let Some(application_id) = application_id else {
+        return Ok(());
+    };
+    if client.is_none() {
+        if presence.is_none() {
+            return Ok(());
+        }
+        let mut connected = DiscordIpcClient::new(application_id);
+        connected.connect().map_err(|err| format!("Failed to connect to Discord: {}", err))?;
+        info!("Connected to Discord");
+        *client = Some((application_id.to_string(), connected));
+    }
+
+    let (_, connected) = client.as_mut().unwrap();
+    match presence {
+        Some(presence) => {
+            let activity = Activity::new().details(presence.details).timestamps(Timestamps::new().start(presence.since.timestamp()));
+            connected.set_activity(activity)
+        }
+        None => connected.clear_activity(),
+    }
+    .map_err(|err| format!("Failed to update the Discord activity: {}", err))
+}
+
+// Follow the status in Discord, in the background. Does nothing until enabled,
+// and retries on the next update while Discord isn't running.
+pub fn update_presence(app_handle: AppHandle) {
+    tauri::async_runtime::spawn_blocking(move || {
+        let state: State<'_, std::sync::Arc<AppState>> = app_handle.state();
+        let settings = state.settings.lock().unwrap().discord.clone();
+        let status = state.status.lock().unwrap().clone();
+        let presence = presence(&settings, &status, &state.history.lock().unwrap());
+        let application_id = settings.application_id.as_deref().filter(|_| settings.enabled);
+
+        let mut client = CLIENT.lock().unwrap();
+        if let Err(err) = apply(&mut client, application_id, presence) {
+            debug!("Discord activity not updated: {}", err);
+            *client = None;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::history::{BreakPeriod, Session};
+
+    fn at(value: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(value).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_presence_follows_status() {
+        let settings = DiscordSettings::default();
+        let mut history = History::default();
+        history.sessions.push(Session { start: at("2024-05-06T07:00:00Z"), end: None });
+        let working = presence(&settings, &AttendanceStatus::CheckedIn, &history).unwrap();
+        assert_eq!(working.since, at("2024-05-06T07:00:00Z"));
+        assert_eq!(presence(&settings, &AttendanceStatus::CheckedOut, &history), None);
+
+        history.breaks.push(BreakPeriod { kind: "lunch".to_string(), start: at("2024-05-06T09:00:00Z"), end: None });
+        assert_eq!(presence(&settings, &AttendanceStatus::OnBreak, &history).map(|on_break| on_break.since), Some(at("2024-05-06T09:00:00Z")));
+        let hidden = DiscordSettings { show_breaks: false, ..settings };
+        assert_eq!(presence(&hidden, &AttendanceStatus::OnBreak, &history), None);
+    }
+}
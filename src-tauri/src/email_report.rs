@@ -0,0 +1,160 @@
+use chrono::{Duration, NaiveDate};
+use lettre::message::header::ContentType;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::clock;
+use crate::i18n::tf;
+use crate::overtime::OvertimePeriod;
+use crate::summary::PeriodSummary;
+
+// How the connection to the mail server is secured
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum SmtpSecurity {
+    StartTls, // Usually port 587
+    Tls, // Usually port 465
+    None, // Only for relays on the local network
+}
+
+// An attendance summary emailed after each day or week, for managers
+// without a dashboard. The SMTP password is kept with the other secrets,
+// see `Secrets::smtp_password`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+#[serde(default)]
+pub struct EmailReportSettings {
+    pub enabled: bool,
+    pub period: OvertimePeriod,
+    pub smtp_host: String,
+    pub smtp_port: u16,
+    pub security: SmtpSecurity,
+    pub username: Option<String>, // No authentication when unset
+    pub from: String,
+    pub recipients: Vec<String>,
+}
+
+impl Default for EmailReportSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            period: OvertimePeriod::Day,
+            smtp_host: String::new(),
+            smtp_port: 587,
+            security: SmtpSecurity::StartTls,
+            username: None,
+            from: String::new(),
+            recipients: Vec::new(),
+        }
+    }
+}
+
+// First day of the last finished day or week before `today`
+pub fn last_period(period: OvertimePeriod, today: NaiveDate) -> NaiveDate {
+    match period {
+        OvertimePeriod::Day => today - Duration::days(1),
+        OvertimePeriod::Week => clock::week_start(today) - Duration::days(7),
+    }
+}
+
+// The period to report on, unless it was already sent. Only the last
+// finished period is sent, missed ones aren't caught up on.
+pub fn due(period: OvertimePeriod, sent_through: Option<NaiveDate>, today: NaiveDate) -> Option<NaiveDate> {
+    let start = last_period(period, today);
+    sent_through.is_none_or(|sent| sent < start).then_some(start)
+}
+
+fn hours_and_mins(mins: i64) -> String {
+    format!("{}h {:02}m", mins / 60, mins % 60)
+}
+
+// Subject and plain-text body of a summary
+pub fn render(summary: &PeriodSummary, name: &str) -> (String, String) {
+    let subject = match summary.period {
+        OvertimePeriod::Day => tf("email.subject.day", &[("name", &name), ("date", &summary.start)]),
+        OvertimePeriod::Week => tf("email.subject.week", &[("name", &name), ("date", &summary.start)]),
+    };
+    let mut body = vec![
+        tf("email.worked", &[("time", &hours_and_mins(summary.worked_mins))]),
+        tf("email.breaks", &[("count", &summary.break_count), ("time", &hours_and_mins(summary.break_mins))]),
+    ];
+    for total in &summary.breaks_by_type {
+        body.push(format!("  {}: {} × {}", total.kind, total.count, hours_and_mins(total.total_mins)));
+    }
+    body.push(tf("email.longest_stretch", &[("time", &hours_and_mins(summary.longest_stretch_mins))]));
+    if !summary.holidays.is_empty() {
+        let names: Vec<String> = summary.holidays.iter().map(|holiday| format!("{} ({})", holiday.name, holiday.date)).collect();
+        body.push(tf("email.holidays", &[("names", &names.join(", "))]));
+    }
+    (subject, body.join("\n") + "\n")
+}
+
+fn mailbox(address: &str) -> Result<Mailbox, String> {
+    address.trim().parse().map_err(|err| format!("Invalid email address '{}': {}", address, err))
+}
+
+pub async fn send(settings: &EmailReportSettings, password: Option<&str>, subject: &str, body: String) -> Result<(), String> {
+    let mut message = Message::builder().from(mailbox(&settings.from)?).subject(subject).header(ContentType::TEXT_PLAIN);
+    for recipient in &settings.recipients {
+        message = message.to(mailbox(recipient)?);
+    }
+    let message = message.body(body).map_err(|err| format!("Failed to build the email: {}", err))?;
+
+    let host = settings.smtp_host.trim();
+    let transport = match settings.security {
+        SmtpSecurity::StartTls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host),
+        SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(host),
+        SmtpSecurity::None => Ok(AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host)),
+    }
+    .map_err(|err| format!("Invalid mail server: {}", err))?
+    .port(settings.smtp_port);
+    let transport = match &settings.username {
+        Some(username) => transport.credentials(Credentials::new(username.clone(), password.unwrap_or_default().to_string())),
+        None => transport,
+    };
+
+    transport.build().send(message).await.map_err(|err| format!("Failed to send the email: {}", err))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::breaks::BreakTypeSummary;
+    use crate::holidays::{Holiday, HolidaySource};
+
+    #[test]
+    fn test_due_sends_each_period_once() {
+        let monday = NaiveDate::from_ymd_opt(2024, 5, 13).unwrap();
+        let sunday = monday - Duration::days(1);
+        assert_eq!(due(OvertimePeriod::Day, None, monday), Some(sunday));
+        assert_eq!(due(OvertimePeriod::Day, Some(sunday), monday), None);
+
+        let wednesday = monday + Duration::days(2);
+        let last_week = monday - Duration::days(7);
+        assert_eq!(due(OvertimePeriod::Week, Some(last_week - Duration::days(7)), wednesday), Some(last_week));
+        assert_eq!(due(OvertimePeriod::Week, Some(last_week), wednesday), None);
+    }
+
+    #[test]
+    fn test_render_summary() {
+        let summary = PeriodSummary {
+            period: OvertimePeriod::Day,
+            start: NaiveDate::from_ymd_opt(2024, 5, 13).unwrap(),
+            worked_mins: 455,
+            break_count: 2,
+            break_mins: 45,
+            breaks_by_type: vec![BreakTypeSummary { kind: "lunch".to_string(), count: 1, total_mins: 30 }],
+            longest_stretch_mins: 190,
+            holidays: vec![Holiday { date: NaiveDate::from_ymd_opt(2024, 5, 13).unwrap(), name: "Whit Monday".to_string(), source: HolidaySource::Feed }],
+        };
+
+        let (subject, body) = render(&summary, "alice");
+        assert_eq!(subject, "Attendance of alice on 2024-05-13");
+        assert!(body.contains("7h 35m"));
+        assert!(body.contains("lunch: 1 × 0h 30m"));
+        assert!(body.contains("Whit Monday (2024-05-13)"));
+    }
+}
@@ -0,0 +1,57 @@
+use serde_json::{Map, Value};
+
+use crate::policy::Policy;
+use crate::Settings;
+
+const PREFIX: &str = "REMODANCE_";
+
+// Settings given as `REMODANCE_<FIELD>` environment variables, e.g.
+// `REMODANCE_API_ENDPOINT` or `REMODANCE_IDLE_TIMEOUT_SECS=300`. Values are read
+// as JSON unless the setting is text, so numbers, booleans and objects work.
+pub fn read<I: IntoIterator<Item = (String, String)>>(vars: I) -> Map<String, Value> {
+    let defaults = serde_json::to_value(Settings::default()).unwrap_or_default();
+
+    vars.into_iter()
+        .filter_map(|(name, raw)| {
+            let key = name.strip_prefix(PREFIX)?.to_ascii_lowercase();
+            let value = match defaults.get(&key) {
+                Some(Value::String(_)) => Value::String(raw),
+                _ => serde_json::from_str(&raw).unwrap_or(Value::String(raw)),
+            };
+            Some((key, value))
+        })
+        .collect()
+}
+
+// Settings with the environment overrides applied. Unlike the policy they
+// don't lock anything, they only win over the stored values at startup.
+pub fn apply<I: IntoIterator<Item = (String, String)>>(settings: &Settings, vars: I) -> Settings {
+    let values = read(vars);
+    if values.is_empty() {
+        return settings.clone();
+    }
+    Policy::new("environment", values).enforce(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overrides_parse_by_setting_type() {
+        let vars = [
+            ("REMODANCE_API_ENDPOINT", "https://kiosk.example/attendance"),
+            ("REMODANCE_USERNAME", "1042"),
+            ("REMODANCE_IDLE_TIMEOUT_MINS", "3"),
+            ("REMODANCE_TIMEZONE", "Europe/Berlin"),
+            ("HOME", "/root"),
+        ]
+        .map(|(name, value)| (name.to_string(), value.to_string()));
+
+        let settings = apply(&Settings::default(), vars);
+        assert_eq!(settings.api_endpoint, "https://kiosk.example/attendance");
+        assert_eq!(settings.username, "1042");
+        assert_eq!(settings.idle_timeout_secs, 180);
+        assert_eq!(settings.timezone.as_deref(), Some("Europe/Berlin"));
+    }
+}
@@ -0,0 +1,103 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::backend::{self, BackendAdapter, Clock, Reply, SendError};
+
+// An ERPNext or Frappe HR site events are recorded on as Employee Checkins.
+// `Settings::user_id` must be the Employee ID, e.g. "HR-EMP-00042", and the
+// app's timezone the site's. The API secret of the key is kept with the
+// other secrets, see `Secrets::erpnext_api_secret`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default, JsonSchema)]
+#[serde(default)]
+pub struct ErpNextSettings {
+    pub url: String, // Site URL, e.g. https://erp.example.com
+    pub api_key: Option<String>, // API key of the user the checkins are created as
+}
+
+// The Employee Checkin for an event, none for events ERPNext has no use for
+pub fn checkin(event_type: &str, payload: &Value) -> Result<Option<Value>, String> {
+    let Some(clock) = backend::clock(event_type) else {
+        return Ok(None);
+    };
+    Ok(Some(json!({
+        "employee": backend::employee(payload)?,
+        "log_type": if clock == Clock::In { "IN" } else { "OUT" },
+        "time": backend::event_time(payload)?.format("%Y-%m-%d %H:%M:%S").to_string(),
+        "device_id": backend::device_name(payload),
+    })))
+}
+
+// Frappe error responses carry the exception, e.g.
+// `"exception": "frappe.exceptions.LinkValidationError: Could not find Employee: E-1"`
+pub fn error_message(body: &str) -> Option<String> {
+    let body: Value = serde_json::from_str(body).ok()?;
+    let exception = body["exception"].as_str().or(body["exc_type"].as_str())?;
+    Some(exception.split_once(": ").map_or(exception, |(_, message)| message).to_string())
+}
+
+pub struct ErpNext<'a> {
+    pub settings: &'a ErpNextSettings,
+    pub api_secret: Option<&'a str>,
+}
+
+impl BackendAdapter for ErpNext<'_> {
+    type Event = Value;
+    type Session = String;
+
+    fn name(&self) -> &'static str {
+        "ERPNext"
+    }
+
+    fn build(&self, event_type: &str, payload: &Value) -> Result<Option<Value>, String> {
+        checkin(event_type, payload)
+    }
+
+    // The Authorization header of an API key and secret
+    async fn authenticate(&self, _client: &reqwest::Client) -> Result<String, SendError> {
+        let api_key = self.settings.api_key.as_deref().ok_or_else(|| SendError::Rejected("No ERPNext API key is configured".to_string()))?;
+        let api_secret = self.api_secret.ok_or_else(|| SendError::Rejected("No ERPNext API secret is set".to_string()))?;
+        Ok(format!("token {}:{}", api_key, api_secret))
+    }
+
+    async fn submit(&self, client: &reqwest::Client, authorization: &String, checkin: Value) -> Result<Option<Reply>, SendError> {
+        let url = format!("{}/api/resource/Employee%20Checkin", self.settings.url.trim().trim_end_matches('/'));
+        backend::request(client.post(url).header("Authorization", authorization).json(&checkin)).await.map(Some)
+    }
+
+    fn interpret(&self, reply: &Reply) -> Result<(), SendError> {
+        if reply.status.is_success() {
+            return Ok(());
+        }
+        Err(SendError::Rejected(match error_message(&reply.body) {
+            Some(message) => format!("ERPNext rejected the checkin with status {}: {}", reply.status, message),
+            None => format!("ERPNext request failed with status {}", reply.status),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checkin_fields() {
+        let payload = json!({
+            "event_type": "break-start",
+            "user_id": "HR-EMP-00042",
+            "payload": { "time": "12:00:05", "date": "2024-05-06", "device_id": "abc", "device_name": "laptop", "type": "lunch" },
+        });
+        assert_eq!(
+            checkin("break-start", &payload).unwrap(),
+            Some(json!({ "employee": "HR-EMP-00042", "log_type": "OUT", "time": "2024-05-06 12:00:05", "device_id": "laptop" }))
+        );
+        assert_eq!(checkin("out-of-office", &payload).unwrap(), None);
+    }
+
+    #[test]
+    fn test_error_message() {
+        let body = r#"{"exception": "frappe.exceptions.LinkValidationError: Could not find Employee: HR-EMP-1", "exc_type": "LinkValidationError"}"#;
+        assert_eq!(error_message(body), Some("Could not find Employee: HR-EMP-1".to_string()));
+        assert_eq!(error_message("<html>"), None);
+    }
+}
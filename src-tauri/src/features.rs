@@ -0,0 +1,70 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+// Experimental subsystems that ship disabled until a deployment turns them on
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Feature {
+    NewIdleBackend, // Platform idle detection replacing the polling monitor
+    EventBatching, // Send queued events in one request instead of one by one
+}
+
+pub const ALL: [Feature; 2] = [Feature::NewIdleBackend, Feature::EventBatching];
+
+impl Feature {
+    // Name used in `Settings::feature_flags`
+    pub fn name(self) -> &'static str {
+        match self {
+            Feature::NewIdleBackend => "new-idle-backend",
+            Feature::EventBatching => "event-batching",
+        }
+    }
+
+    fn default_enabled(self) -> bool {
+        match self {
+            Feature::NewIdleBackend | Feature::EventBatching => false,
+        }
+    }
+}
+
+// A flag as returned by `get_feature_flags`
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct FeatureFlag {
+    pub feature: Feature,
+    pub enabled: bool,
+    pub overridden: bool, // Set in the settings rather than the built-in default
+}
+
+// Whether a feature is on, given the overrides from the settings. Names of
+// features this version doesn't know are ignored, so newer remote settings
+// don't break older clients.
+pub fn enabled(overrides: &BTreeMap<String, bool>, feature: Feature) -> bool {
+    overrides.get(feature.name()).copied().unwrap_or(feature.default_enabled())
+}
+
+pub fn flags(overrides: &BTreeMap<String, bool>) -> Vec<FeatureFlag> {
+    ALL.iter()
+        .map(|&feature| FeatureFlag {
+            feature,
+            enabled: enabled(overrides, feature),
+            overridden: overrides.contains_key(feature.name()),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overrides_win_over_defaults() {
+        let overrides = BTreeMap::from([("event-batching".to_string(), true), ("from-the-future".to_string(), true)]);
+        assert!(enabled(&overrides, Feature::EventBatching));
+        assert!(!enabled(&overrides, Feature::NewIdleBackend));
+
+        let flags = flags(&overrides);
+        assert_eq!(flags.len(), ALL.len());
+        assert!(flags.iter().any(|flag| flag.feature == Feature::EventBatching && flag.overridden));
+    }
+}
@@ -0,0 +1,240 @@
+use chrono::{DateTime, Utc};
+use log::info;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use tauri_plugin_opener::OpenerExt;
+use url::Url;
+
+use crate::meetings::{CalendarProvider, Meeting};
+use crate::oauth::{self, Provider};
+use crate::secrets;
+
+// Keychain name of the Google sign-in, see `secrets::load_token`
+pub const KEYCHAIN_NAME: &str = "google-calendar";
+// `Meeting::source` of meetings from Google Calendar
+pub const SOURCE: &str = "google";
+
+const EVENTS_URL: &str = "https://www.googleapis.com/calendar/v3/calendars";
+const SCOPE: &str = "https://www.googleapis.com/auth/calendar.events.readonly";
+const MAX_EVENTS: &str = "250";
+
+// Google Calendar as a source of meetings. `client_id` and `client_secret`
+// come from a "Desktop app" OAuth client in the organization's Google Cloud
+// project; Google doesn't treat the secret of such clients as confidential.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+#[serde(default)]
+pub struct GoogleCalendarSettings {
+    pub enabled: bool,
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+    pub calendar_id: String, // "primary" for the user's own calendar
+    pub tag_heartbeats: bool, // Send `in_meeting` with `activity_update`
+}
+
+impl Default for GoogleCalendarSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            client_id: None,
+            client_secret: None,
+            calendar_id: "primary".to_string(),
+            tag_heartbeats: false,
+        }
+    }
+}
+
+// Whether Google Calendar is set up, for the settings screen
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct GoogleCalendarStatus {
+    pub enabled: bool,
+    pub signed_in: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct EventList {
+    #[serde(default)]
+    items: Vec<Event>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Event {
+    #[serde(default)]
+    summary: String,
+    #[serde(default)]
+    status: String,
+    transparency: Option<String>,
+    event_type: Option<String>,
+    start: EventTime,
+    end: EventTime,
+    #[serde(default)]
+    attendees: Vec<Attendee>,
+}
+
+// All-day events only have a `date`, they are never meetings
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EventTime {
+    date_time: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Attendee {
+    #[serde(rename = "self", default)]
+    is_self: bool,
+    #[serde(default)]
+    response_status: String,
+}
+
+pub fn provider(settings: &GoogleCalendarSettings) -> Result<Provider, String> {
+    Ok(Provider {
+        device_code_url: "https://oauth2.googleapis.com/device/code".to_string(),
+        // Offline access with consent is what makes Google return a refresh token
+        authorize_url: "https://accounts.google.com/o/oauth2/v2/auth?access_type=offline&prompt=consent".to_string(),
+        token_url: "https://oauth2.googleapis.com/token".to_string(),
+        client_id: settings.client_id.clone().ok_or("No Google client ID is configured")?,
+        client_secret: settings.client_secret.clone(),
+        scope: SCOPE.to_string(),
+    })
+}
+
+pub fn events_url(calendar_id: &str, from: DateTime<Utc>, to: DateTime<Utc>) -> Result<Url, String> {
+    let mut url = Url::parse(EVENTS_URL).map_err(|err| format!("Invalid calendar URL: {}", err))?;
+    url.path_segments_mut()
+        .map_err(|_| "Invalid calendar URL".to_string())?
+        .extend([calendar_id, "events"]);
+    url.query_pairs_mut()
+        .append_pair("timeMin", &from.to_rfc3339())
+        .append_pair("timeMax", &to.to_rfc3339())
+        .append_pair("singleEvents", "true")
+        .append_pair("orderBy", "startTime")
+        .append_pair("maxResults", MAX_EVENTS);
+    Ok(url)
+}
+
+// Meetings the user accepted, from an events list. Events without other
+// attendees, tentative or declined invitations, events marked free, and
+// focus time or out-of-office blocks are left out.
+pub fn parse_events(body: &str) -> Result<Vec<Meeting>, String> {
+    let list: EventList = serde_json::from_str(body).map_err(|err| format!("Unexpected calendar response: {}", err))?;
+
+    Ok(list
+        .items
+        .into_iter()
+        .filter(|event| event.status != "cancelled")
+        .filter(|event| event.transparency.as_deref() != Some("transparent"))
+        .filter(|event| event.event_type.as_deref().is_none_or(|kind| kind == "default"))
+        .filter(|event| event.attendees.iter().any(|attendee| attendee.is_self && attendee.response_status == "accepted"))
+        .filter_map(|event| {
+            Some(Meeting {
+                source: SOURCE,
+                title: event.summary,
+                start: event.start.date_time?,
+                end: event.end.date_time?,
+            })
+        })
+        .collect())
+}
+
+pub fn signed_in() -> bool {
+    matches!(secrets::load_token(KEYCHAIN_NAME), Ok(Some(_)))
+}
+
+pub fn status(enabled: bool) -> GoogleCalendarStatus {
+    GoogleCalendarStatus { enabled, signed_in: signed_in() }
+}
+
+// Sign in through the browser. Google doesn't allow calendar access with a
+// device code, so the sign-in page redirects back to this machine instead.
+pub async fn sign_in(app_handle: &AppHandle, settings: &GoogleCalendarSettings) -> Result<(), String> {
+    let provider = provider(settings)?;
+    let token = oauth::sign_in_with_browser(&provider, |url| {
+        app_handle.opener().open_url(url, None::<&str>).map_err(|err| format!("Failed to open the browser: {}", err))
+    })
+    .await?;
+    secrets::save_token(KEYCHAIN_NAME, Some(&token))?;
+    info!("Signed in to Google Calendar");
+    Ok(())
+}
+
+pub fn sign_out() -> Result<(), String> {
+    secrets::save_token(KEYCHAIN_NAME, None)?;
+    info!("Signed out of Google Calendar");
+    Ok(())
+}
+
+impl CalendarProvider for GoogleCalendarSettings {
+    fn source(&self) -> &'static str {
+        SOURCE
+    }
+
+    fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn tag_heartbeats(&self) -> bool {
+        self.tag_heartbeats
+    }
+
+    fn signed_in(&self) -> bool {
+        signed_in()
+    }
+
+    async fn fetch_meetings(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> Result<Vec<Meeting>, String> {
+        let token = secrets::load_token(KEYCHAIN_NAME)?.ok_or("Not signed in to Google Calendar")?;
+        let token = if token.expired(Utc::now()) {
+            let refreshed = oauth::refresh(&provider(self)?, &token).await?;
+            secrets::save_token(KEYCHAIN_NAME, Some(&refreshed))?;
+            refreshed
+        } else {
+            token
+        };
+
+        let response = reqwest::Client::new()
+            .get(events_url(&self.calendar_id, from, to)?)
+            .bearer_auth(&token.access_token)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to send request: {}", e))?;
+        if !response.status().is_success() {
+            return Err(format!("Google Calendar request failed with status {}", response.status()));
+        }
+        let body = response.text().await.map_err(|e| format!("Failed to read response: {}", e))?;
+        parse_events(&body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_accepted_meetings_count() {
+        let body = r#"{"items": [
+            {"summary": "Standup", "status": "confirmed", "start": {"dateTime": "2024-05-06T09:00:00+02:00"}, "end": {"dateTime": "2024-05-06T09:15:00+02:00"},
+             "attendees": [{"email": "me@example.com", "self": true, "responseStatus": "accepted"}, {"email": "a@example.com", "responseStatus": "accepted"}]},
+            {"summary": "Maybe", "status": "confirmed", "start": {"dateTime": "2024-05-06T10:00:00Z"}, "end": {"dateTime": "2024-05-06T11:00:00Z"},
+             "attendees": [{"self": true, "responseStatus": "tentative"}]},
+            {"summary": "Dentist", "status": "confirmed", "start": {"dateTime": "2024-05-06T12:00:00Z"}, "end": {"dateTime": "2024-05-06T13:00:00Z"}},
+            {"summary": "Focus", "status": "confirmed", "eventType": "focusTime", "start": {"dateTime": "2024-05-06T13:00:00Z"}, "end": {"dateTime": "2024-05-06T15:00:00Z"},
+             "attendees": [{"self": true, "responseStatus": "accepted"}]},
+            {"summary": "Offsite", "status": "confirmed", "start": {"date": "2024-05-07"}, "end": {"date": "2024-05-08"},
+             "attendees": [{"self": true, "responseStatus": "accepted"}]}
+        ]}"#;
+
+        let meetings = parse_events(body).unwrap();
+        assert_eq!(meetings.len(), 1);
+        assert_eq!(meetings[0].title, "Standup");
+        assert_eq!(meetings[0].start.to_rfc3339(), "2024-05-06T07:00:00+00:00");
+    }
+
+    #[test]
+    fn test_events_url_encodes_calendar() {
+        let from = DateTime::parse_from_rfc3339("2024-05-06T00:00:00Z").unwrap().with_timezone(&Utc);
+        let url = events_url("team@group.calendar.google.com", from, from + chrono::Duration::days(1)).unwrap();
+        assert!(url.path().ends_with("/calendars/team@group.calendar.google.com/events"));
+        assert!(url.query().unwrap().contains("singleEvents=true"));
+    }
+}
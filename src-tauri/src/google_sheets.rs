@@ -0,0 +1,189 @@
+use chrono::{Duration, NaiveDate, Utc};
+use log::info;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tauri::AppHandle;
+use tauri_plugin_opener::OpenerExt;
+use url::Url;
+
+use crate::clock;
+use crate::history::Session;
+use crate::oauth::{self, Provider};
+use crate::secrets;
+
+// Keychain name of the Google Sheets sign-in, see `secrets::load_token`
+pub const KEYCHAIN_NAME: &str = "google-sheets";
+
+const SHEETS_URL: &str = "https://sheets.googleapis.com/v4/spreadsheets";
+const SCOPE: &str = "https://www.googleapis.com/auth/spreadsheets";
+
+// A shared Google Sheet each finished day's sessions are appended to, one row
+// per session: date, name, device, check-in, check-out and hours. The OAuth
+// client is a "Desktop app" client, as for Google Calendar.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+#[serde(default)]
+pub struct GoogleSheetsSettings {
+    pub enabled: bool,
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+    pub spreadsheet_id: Option<String>, // The long ID in the sheet's URL
+    pub sheet: String, // Tab the rows are appended to
+}
+
+impl Default for GoogleSheetsSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            client_id: None,
+            client_secret: None,
+            spreadsheet_id: None,
+            sheet: "Attendance".to_string(),
+        }
+    }
+}
+
+// Whether the export is set up, for the settings screen
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct GoogleSheetsStatus {
+    pub enabled: bool,
+    pub signed_in: bool,
+    pub exported_through: Option<NaiveDate>, // Last day appended to the sheet
+}
+
+pub fn provider(settings: &GoogleSheetsSettings) -> Result<Provider, String> {
+    Ok(Provider {
+        device_code_url: "https://oauth2.googleapis.com/device/code".to_string(),
+        // Offline access with consent is what makes Google return a refresh token
+        authorize_url: "https://accounts.google.com/o/oauth2/v2/auth?access_type=offline&prompt=consent".to_string(),
+        token_url: "https://oauth2.googleapis.com/token".to_string(),
+        client_id: settings.client_id.clone().ok_or("No Google client ID is configured")?,
+        client_secret: settings.client_secret.clone(),
+        scope: SCOPE.to_string(),
+    })
+}
+
+pub fn append_url(spreadsheet_id: &str, sheet: &str) -> Result<Url, String> {
+    let mut url = Url::parse(SHEETS_URL).map_err(|err| format!("Invalid sheet URL: {}", err))?;
+    url.path_segments_mut()
+        .map_err(|_| "Invalid sheet URL".to_string())?
+        .extend([spreadsheet_id, "values", &format!("'{}'!A:F:append", sheet.replace('\'', "''"))]);
+    url.query_pairs_mut()
+        .append_pair("valueInputOption", "USER_ENTERED")
+        .append_pair("insertDataOption", "INSERT_ROWS");
+    Ok(url)
+}
+
+// Finished days after `exported_through` and before `today`. The first export
+// only covers yesterday, earlier history isn't backfilled.
+pub fn days_to_export(exported_through: Option<NaiveDate>, today: NaiveDate) -> Vec<NaiveDate> {
+    let first = exported_through.map_or(today - Duration::days(1), |date| date + Duration::days(1));
+    first.iter_days().take_while(|date| *date < today).collect()
+}
+
+// Rows for the sessions that started on `date`. Sessions still running are
+// left out.
+pub fn rows(sessions: &[Session], date: NaiveDate, timezone: Option<&str>, username: &str, device_name: &str) -> Vec<Value> {
+    sessions
+        .iter()
+        .filter(|session| clock::date_of(timezone, session.start) == date)
+        .filter_map(|session| {
+            let end = session.end?;
+            let hours = (end - session.start).num_seconds() as f64 / 3600.0;
+            Some(json!([
+                date.to_string(),
+                username,
+                device_name,
+                clock::local(timezone, session.start).format("%H:%M").to_string(),
+                clock::local(timezone, end).format("%H:%M").to_string(),
+                (hours * 100.0).round() / 100.0,
+            ]))
+        })
+        .collect()
+}
+
+pub fn signed_in() -> bool {
+    matches!(secrets::load_token(KEYCHAIN_NAME), Ok(Some(_)))
+}
+
+// Sign in through the browser, as for Google Calendar
+pub async fn sign_in(app_handle: &AppHandle, settings: &GoogleSheetsSettings) -> Result<(), String> {
+    let provider = provider(settings)?;
+    let token = oauth::sign_in_with_browser(&provider, |url| {
+        app_handle.opener().open_url(url, None::<&str>).map_err(|err| format!("Failed to open the browser: {}", err))
+    })
+    .await?;
+    secrets::save_token(KEYCHAIN_NAME, Some(&token))?;
+    info!("Signed in to Google Sheets");
+    Ok(())
+}
+
+pub fn sign_out() -> Result<(), String> {
+    secrets::save_token(KEYCHAIN_NAME, None)?;
+    info!("Signed out of Google Sheets");
+    Ok(())
+}
+
+// Append rows below the sheet's last row
+pub async fn append(settings: &GoogleSheetsSettings, rows: Vec<Value>) -> Result<(), String> {
+    let spreadsheet_id = settings.spreadsheet_id.as_deref().ok_or("No spreadsheet is configured")?;
+    let token = secrets::load_token(KEYCHAIN_NAME)?.ok_or("Not signed in to Google Sheets")?;
+    let token = if token.expired(Utc::now()) {
+        let refreshed = oauth::refresh(&provider(settings)?, &token).await?;
+        secrets::save_token(KEYCHAIN_NAME, Some(&refreshed))?;
+        refreshed
+    } else {
+        token
+    };
+
+    let response = reqwest::Client::new()
+        .post(append_url(spreadsheet_id, &settings.sheet)?)
+        .bearer_auth(&token.access_token)
+        .json(&json!({ "values": rows }))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send request: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("Google Sheets request failed with status {}", response.status()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{DateTime, Utc};
+
+    fn at(value: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(value).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_rows_for_finished_sessions() {
+        let date = NaiveDate::from_ymd_opt(2024, 5, 6).unwrap();
+        let sessions = vec![
+            Session { start: at("2024-05-06T07:00:00Z"), end: Some(at("2024-05-06T11:30:00Z")) },
+            Session { start: at("2024-05-06T12:15:00Z"), end: None },
+            Session { start: at("2024-05-07T07:00:00Z"), end: Some(at("2024-05-07T08:00:00Z")) },
+        ];
+
+        let rows = rows(&sessions, date, Some("Europe/Berlin"), "alice", "laptop");
+        assert_eq!(rows, vec![json!(["2024-05-06", "alice", "laptop", "09:00", "13:30", 4.5])]);
+    }
+
+    #[test]
+    fn test_days_to_export() {
+        let today = NaiveDate::from_ymd_opt(2024, 5, 8).unwrap();
+        assert_eq!(days_to_export(None, today), vec![NaiveDate::from_ymd_opt(2024, 5, 7).unwrap()]);
+        assert_eq!(days_to_export(Some(today - Duration::days(3)), today).len(), 2);
+        assert!(days_to_export(Some(today - Duration::days(1)), today).is_empty());
+    }
+
+    #[test]
+    fn test_append_url_quotes_the_sheet() {
+        let url = append_url("abc123", "Team's hours").unwrap();
+        assert!(url.path().starts_with("/v4/spreadsheets/abc123/values/"));
+        assert!(url.path().ends_with(":append"));
+        assert!(url.query().unwrap().contains("valueInputOption=USER_ENTERED"));
+    }
+}
@@ -0,0 +1,161 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::clock;
+use crate::time_tracker::{TimeEntry, TimeTracker};
+
+const API_URL: &str = "https://api.harvestapp.com/v2";
+// Harvest rejects requests without a user agent naming the app
+const USER_AGENT: &str = "Remodance (https://github.com/rashidpathiyil/remodance)";
+
+// Where Harvest timers go. The personal access token is kept with the other
+// secrets, see `Secrets::harvest_token`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+#[serde(default)]
+pub struct HarvestSettings {
+    pub account_id: Option<String>, // Sent as `Harvest-Account-Id`
+    pub project_id: Option<u64>,
+    pub task_id: Option<u64>, // Task of the project the hours are billed to
+    pub notes: String,
+}
+
+impl Default for HarvestSettings {
+    fn default() -> Self {
+        Self {
+            account_id: None,
+            project_id: None,
+            task_id: None,
+            notes: "Work".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct User {
+    id: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct EntryList {
+    #[serde(default)]
+    time_entries: Vec<Entry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Entry {
+    id: u64,
+    #[serde(default)]
+    notes: Option<String>,
+    project: Reference,
+    task: Reference,
+}
+
+#[derive(Debug, Deserialize)]
+struct Reference {
+    id: u64,
+}
+
+pub struct Harvest<'a> {
+    pub settings: &'a HarvestSettings,
+    pub token: &'a str,
+    pub timezone: Option<&'a str>, // The day hours are booked on follows the settings' timezone
+}
+
+// A running timer booked on `spent_date`, as Harvest starts one when no hours
+// are given
+pub fn start_body(settings: &HarvestSettings, spent_date: NaiveDate) -> Result<Value, String> {
+    Ok(json!({
+        "project_id": settings.project_id.ok_or("No Harvest project is configured")?,
+        "task_id": settings.task_id.ok_or("No Harvest task is configured")?,
+        "spent_date": spent_date.to_string(),
+        "notes": settings.notes,
+    }))
+}
+
+// The user's running timer. Harvest has no tags, so timers on the configured
+// project and task with the configured notes count as started by the app.
+pub fn parse_running(body: &str, settings: &HarvestSettings) -> Result<Option<TimeEntry>, String> {
+    let list: EntryList = serde_json::from_str(body).map_err(|err| format!("Unexpected Harvest response: {}", err))?;
+    Ok(list.time_entries.into_iter().next().map(|entry| TimeEntry {
+        started_by_app: entry.notes.as_deref() == Some(settings.notes.as_str())
+            && Some(entry.project.id) == settings.project_id
+            && Some(entry.task.id) == settings.task_id,
+        id: entry.id.to_string(),
+        workspace_id: settings.account_id.clone().unwrap_or_default(),
+    }))
+}
+
+impl Harvest<'_> {
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<String, String> {
+        let account_id = self.settings.account_id.as_deref().ok_or("No Harvest account is configured")?;
+        let response = request
+            .bearer_auth(self.token)
+            .header("Harvest-Account-Id", account_id)
+            .header(reqwest::header::USER_AGENT, USER_AGENT)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to send request: {}", e))?;
+        if !response.status().is_success() {
+            return Err(format!("Harvest request failed with status {}", response.status()));
+        }
+        response.text().await.map_err(|e| format!("Failed to read response: {}", e))
+    }
+}
+
+impl TimeTracker for Harvest<'_> {
+    fn name(&self) -> &'static str {
+        "Harvest"
+    }
+
+    async fn running(&self) -> Result<Option<TimeEntry>, String> {
+        let body = self.send(reqwest::Client::new().get(format!("{}/users/me", API_URL))).await?;
+        let user: User = serde_json::from_str(&body).map_err(|err| format!("Unexpected Harvest response: {}", err))?;
+
+        let url = format!("{}/time_entries?is_running=true&user_id={}", API_URL, user.id);
+        let body = self.send(reqwest::Client::new().get(url)).await?;
+        parse_running(&body, self.settings)
+    }
+
+    async fn start(&self, now: DateTime<Utc>) -> Result<(), String> {
+        let body = start_body(self.settings, clock::date_of(self.timezone, now))?;
+        self.send(reqwest::Client::new().post(format!("{}/time_entries", API_URL)).json(&body)).await?;
+        Ok(())
+    }
+
+    async fn stop(&self, entry: &TimeEntry, _now: DateTime<Utc>) -> Result<(), String> {
+        let url = format!("{}/time_entries/{}/stop", API_URL, entry.id);
+        self.send(reqwest::Client::new().patch(url)).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings() -> HarvestSettings {
+        HarvestSettings { account_id: Some("123".to_string()), project_id: Some(14), task_id: Some(8), ..HarvestSettings::default() }
+    }
+
+    #[test]
+    fn test_parse_running_recognizes_own_timers() {
+        let body = r#"{"time_entries": [{"id": 636709355, "notes": "Work", "is_running": true, "project": {"id": 14, "name": "Acme"}, "task": {"id": 8, "name": "Development"}}]}"#;
+        let running = parse_running(body, &settings()).unwrap().unwrap();
+        assert_eq!((running.id.as_str(), running.started_by_app), ("636709355", true));
+
+        let other_task = r#"{"time_entries": [{"id": 1, "notes": "Work", "project": {"id": 14}, "task": {"id": 9}}]}"#;
+        assert!(!parse_running(other_task, &settings()).unwrap().unwrap().started_by_app);
+        assert_eq!(parse_running(r#"{"time_entries": []}"#, &settings()).unwrap(), None);
+    }
+
+    #[test]
+    fn test_start_body_needs_project_and_task() {
+        let date = NaiveDate::from_ymd_opt(2024, 5, 6).unwrap();
+        let body = start_body(&settings(), date).unwrap();
+        assert_eq!(body["spent_date"], json!("2024-05-06"));
+        assert_eq!(body["task_id"], json!(8));
+        assert!(start_body(&HarvestSettings::default(), date).is_err());
+    }
+}
@@ -0,0 +1,280 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+// How long local history is kept
+const HISTORY_RETENTION_DAYS: i64 = 90;
+
+// A checked-in period. `end` is empty while the session is running.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Session {
+    pub start: DateTime<Utc>,
+    pub end: Option<DateTime<Utc>>,
+}
+
+impl Session {
+    // Time of this session that falls inside [from, to)
+    pub fn overlap(&self, from: DateTime<Utc>, to: DateTime<Utc>, now: DateTime<Utc>) -> Duration {
+        let start = self.start.max(from);
+        let end = self.end.unwrap_or(now).min(to);
+
+        if end > start {
+            end - start
+        } else {
+            Duration::zero()
+        }
+    }
+}
+
+// A break taken between sessions. `end` is empty while the break is running.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct BreakPeriod {
+    pub kind: String,
+    pub start: DateTime<Utc>,
+    pub end: Option<DateTime<Utc>>,
+}
+
+// A minimum-break reminder and whether the user took the break afterwards
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct BreakReminder {
+    pub session_start: DateTime<Utc>,
+    pub reminded_at: DateTime<Utc>,
+    pub taken_at: Option<DateTime<Utc>>,
+}
+
+// A suggested eye/stretch break and when the user took it, if they did
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct MicroBreak {
+    pub suggested_at: DateTime<Utc>,
+    pub taken_at: Option<DateTime<Utc>>,
+}
+
+// Something kept for later review: an overridden automatic action or a settings change
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct AuditEntry {
+    pub at: DateTime<Utc>,
+    pub action: String,
+    pub detail: String,
+}
+
+// Local attendance history used for summaries and limits
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct History {
+    pub sessions: Vec<Session>,
+    pub breaks: Vec<BreakPeriod>,
+    pub break_reminders: Vec<BreakReminder>,
+    pub micro_breaks: Vec<MicroBreak>,
+    pub audit: Vec<AuditEntry>,
+}
+
+impl History {
+    // Open a new session unless one is already running
+    pub fn start_session(&mut self, at: DateTime<Utc>) {
+        if self.current_session().is_some() {
+            return;
+        }
+
+        self.end_break(at);
+        self.sessions.push(Session { start: at, end: None });
+        self.prune(at);
+    }
+
+    // Close the running session, if any
+    pub fn end_session(&mut self, at: DateTime<Utc>) {
+        if let Some(session) = self.sessions.iter_mut().rev().find(|s| s.end.is_none()) {
+            session.end = Some(at.max(session.start));
+        }
+
+        // Leaving the session counts as taking the suggested break
+        for reminder in self.break_reminders.iter_mut().filter(|r| r.taken_at.is_none()) {
+            reminder.taken_at = Some(at);
+        }
+    }
+
+    // End the running session and start a break at the same time
+    pub fn start_break(&mut self, kind: &str, at: DateTime<Utc>) {
+        self.end_session(at);
+        self.end_break(at);
+        self.breaks.push(BreakPeriod {
+            kind: kind.to_string(),
+            start: at,
+            end: None,
+        });
+    }
+
+    // Close the running break, if any
+    pub fn end_break(&mut self, at: DateTime<Utc>) {
+        if let Some(current) = self.breaks.iter_mut().rev().find(|b| b.end.is_none()) {
+            current.end = Some(at.max(current.start));
+        }
+    }
+
+    // Drop the running break, leaving its time unaccounted as after a check-out
+    pub fn cancel_break(&mut self) {
+        if self.current_break().is_some() {
+            self.breaks.pop();
+        }
+    }
+
+    // Record that a break was suggested during the running session
+    pub fn record_break_reminder(&mut self, at: DateTime<Utc>) {
+        let Some(session_start) = self.current_session().map(|session| session.start) else {
+            return;
+        };
+
+        self.break_reminders.push(BreakReminder {
+            session_start,
+            reminded_at: at,
+            taken_at: None,
+        });
+    }
+
+    // Whether a break was already suggested during the running session
+    pub fn break_reminded(&self) -> bool {
+        self.current_session().is_some_and(|session| {
+            self.break_reminders.iter().any(|r| r.session_start == session.start)
+        })
+    }
+
+    pub fn record_micro_break(&mut self, at: DateTime<Utc>) {
+        self.micro_breaks.push(MicroBreak {
+            suggested_at: at,
+            taken_at: None,
+        });
+    }
+
+    // Mark the latest micro-break as taken if it was suggested after `since`.
+    // Returns false when there is no such pending micro-break.
+    pub fn take_micro_break(&mut self, since: DateTime<Utc>, at: DateTime<Utc>) -> bool {
+        match self.micro_breaks.last_mut() {
+            Some(micro_break) if micro_break.taken_at.is_none() && micro_break.suggested_at >= since => {
+                micro_break.taken_at = Some(at);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn record_audit(&mut self, at: DateTime<Utc>, action: &str, detail: &str) {
+        self.audit.push(AuditEntry {
+            at,
+            action: action.to_string(),
+            detail: detail.to_string(),
+        });
+    }
+
+    pub fn current_session(&self) -> Option<&Session> {
+        self.sessions.last().filter(|session| session.end.is_none())
+    }
+
+    pub fn current_break(&self) -> Option<&BreakPeriod> {
+        self.breaks.last().filter(|period| period.end.is_none())
+    }
+
+    // Total worked time inside [from, to), counting a running session up to `now`
+    pub fn worked_between(&self, from: DateTime<Utc>, to: DateTime<Utc>, now: DateTime<Utc>) -> Duration {
+        self.sessions
+            .iter()
+            .map(|session| session.overlap(from, to, now))
+            .fold(Duration::zero(), |total, worked| total + worked)
+    }
+
+    // Drop sessions older than the retention period
+    fn prune(&mut self, now: DateTime<Utc>) {
+        let cutoff = now - Duration::days(HISTORY_RETENTION_DAYS);
+        self.sessions.retain(|session| session.end.is_none_or(|end| end >= cutoff));
+        self.breaks.retain(|period| period.end.is_none_or(|end| end >= cutoff));
+        self.break_reminders.retain(|reminder| reminder.reminded_at >= cutoff);
+        self.micro_breaks.retain(|micro_break| micro_break.suggested_at >= cutoff);
+        self.audit.retain(|entry| entry.at >= cutoff);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_sessions_open_and_close() {
+        let mut history = History::default();
+        history.start_session(at("2025-03-10T09:00:00Z"));
+        history.start_session(at("2025-03-10T09:30:00Z"));
+        assert_eq!(history.sessions.len(), 1);
+
+        history.end_session(at("2025-03-10T12:00:00Z"));
+        assert!(history.current_session().is_none());
+        assert_eq!(history.sessions[0].end, Some(at("2025-03-10T12:00:00Z")));
+    }
+
+    #[test]
+    fn test_break_reminder_marked_taken_on_session_end() {
+        let mut history = History::default();
+        history.start_session(at("2025-03-10T08:00:00Z"));
+        assert!(!history.break_reminded());
+
+        history.record_break_reminder(at("2025-03-10T14:00:00Z"));
+        assert!(history.break_reminded());
+
+        history.end_session(at("2025-03-10T14:20:00Z"));
+        assert_eq!(history.break_reminders[0].taken_at, Some(at("2025-03-10T14:20:00Z")));
+    }
+
+    #[test]
+    fn test_micro_break_taken_only_while_pending() {
+        let mut history = History::default();
+        history.record_micro_break(at("2025-03-10T09:20:00Z"));
+
+        assert!(!history.take_micro_break(at("2025-03-10T09:21:00Z"), at("2025-03-10T09:23:00Z")));
+        assert!(history.take_micro_break(at("2025-03-10T09:19:00Z"), at("2025-03-10T09:20:30Z")));
+        assert!(!history.take_micro_break(at("2025-03-10T09:19:00Z"), at("2025-03-10T09:20:40Z")));
+        assert_eq!(history.micro_breaks[0].taken_at, Some(at("2025-03-10T09:20:30Z")));
+    }
+
+    #[test]
+    fn test_break_splits_sessions() {
+        let mut history = History::default();
+        history.start_session(at("2025-03-10T09:00:00Z"));
+        history.start_break("lunch", at("2025-03-10T12:00:00Z"));
+        assert!(history.current_session().is_none());
+        assert_eq!(history.current_break().map(|b| b.kind.as_str()), Some("lunch"));
+        history.start_session(at("2025-03-10T12:45:00Z"));
+
+        assert!(history.current_break().is_none());
+
+        assert_eq!(history.sessions.len(), 2);
+        assert_eq!(history.sessions[0].end, Some(at("2025-03-10T12:00:00Z")));
+        assert_eq!(history.breaks[0].kind, "lunch");
+        assert_eq!(history.breaks[0].end, Some(at("2025-03-10T12:45:00Z")));
+    }
+
+    #[test]
+    fn test_cancelled_break_leaves_session_ended() {
+        let mut history = History::default();
+        history.start_session(at("2025-03-10T09:00:00Z"));
+        history.start_break("idle", at("2025-03-10T10:00:00Z"));
+        history.cancel_break();
+
+        assert!(history.breaks.is_empty());
+        assert_eq!(history.sessions[0].end, Some(at("2025-03-10T10:00:00Z")));
+    }
+
+    #[test]
+    fn test_worked_between_clips_to_range() {
+        let mut history = History::default();
+        history.start_session(at("2025-03-10T22:00:00Z"));
+        history.end_session(at("2025-03-11T02:00:00Z"));
+        history.start_session(at("2025-03-11T09:00:00Z"));
+
+        let worked = history.worked_between(
+            at("2025-03-11T00:00:00Z"),
+            at("2025-03-12T00:00:00Z"),
+            at("2025-03-11T10:30:00Z"),
+        );
+
+        assert_eq!(worked, Duration::minutes(210));
+    }
+}
@@ -0,0 +1,293 @@
+use chrono::{Duration, NaiveDate};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+// Public holiday feed (https://date.nager.at), queried by year and country code
+const HOLIDAY_FEED_URL: &str = "https://date.nager.at/api/v3/PublicHolidays";
+
+// Where a holiday entry came from
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum HolidaySource {
+    Manual,
+    Feed,
+    Calendar, // Synced from a subscribed ICS calendar
+}
+
+// An ICS calendar whose matching all-day events become non-working days
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+pub struct CalendarSubscription {
+    pub url: String,
+    // Event titles must contain one of these (case-insensitive); empty matches all
+    #[serde(default)]
+    pub keywords: Vec<String>,
+}
+
+impl CalendarSubscription {
+    pub fn matches(&self, title: &str) -> bool {
+        let title = title.to_lowercase();
+        self.keywords.is_empty() || self.keywords.iter().any(|k| title.contains(&k.to_lowercase()))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+pub struct Holiday {
+    pub date: NaiveDate,
+    pub name: String,
+    pub source: HolidaySource,
+}
+
+// Entry returned by the public holiday feed
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FeedHoliday {
+    date: NaiveDate,
+    local_name: String,
+    #[serde(default)]
+    global: bool,
+    #[serde(default)]
+    counties: Option<Vec<String>>,
+}
+
+// Check whether the given date is in the holiday list
+pub fn is_holiday(holidays: &[Holiday], date: NaiveDate) -> bool {
+    holidays.iter().any(|holiday| holiday.date == date)
+}
+
+// Merge imported holidays into the existing list. Manual entries always win
+// over imported ones for the same date.
+pub fn merge_holidays(holidays: &mut Vec<Holiday>, imported: Vec<Holiday>) -> usize {
+    let mut added = 0;
+
+    for holiday in imported {
+        if let Some(existing) = holidays.iter_mut().find(|h| h.date == holiday.date) {
+            if existing.source != HolidaySource::Manual {
+                *existing = holiday;
+            }
+            continue;
+        }
+
+        holidays.push(holiday);
+        added += 1;
+    }
+
+    holidays.sort_by_key(|holiday| holiday.date);
+    added
+}
+
+// Replace the days synced from calendars with `synced`. Dates that already
+// have a manual or imported holiday keep it, so a later sync can't remove them.
+pub fn sync_calendar_days(holidays: &mut Vec<Holiday>, synced: Vec<Holiday>) {
+    holidays.retain(|h| h.source != HolidaySource::Calendar);
+
+    for day in synced {
+        if !is_holiday(holidays, day.date) {
+            holidays.push(day);
+        }
+    }
+
+    holidays.sort_by_key(|holiday| holiday.date);
+}
+
+// Build the feed URL for a country (ISO 3166-1 alpha-2) and year
+pub fn feed_url(country: &str, year: i32) -> String {
+    format!("{}/{}/{}", HOLIDAY_FEED_URL, year, country.to_uppercase())
+}
+
+// Parse the JSON returned by the holiday feed. When a region (e.g. "DE-BY") is
+// given, regional holidays for other regions are skipped.
+pub fn parse_feed_json(json: &str, region: Option<&str>) -> Result<Vec<Holiday>, String> {
+    let entries: Vec<FeedHoliday> = serde_json::from_str(json)
+        .map_err(|e| format!("Failed to parse holiday feed: {}", e))?;
+
+    let holidays = entries
+        .into_iter()
+        .filter(|entry| {
+            if entry.global {
+                return true;
+            }
+            match (region, &entry.counties) {
+                (Some(region), Some(counties)) => counties.iter().any(|c| c.eq_ignore_ascii_case(region)),
+                (None, _) => true,
+                (Some(_), None) => true,
+            }
+        })
+        .map(|entry| Holiday {
+            date: entry.date,
+            name: entry.local_name,
+            source: HolidaySource::Feed,
+        })
+        .collect();
+
+    Ok(holidays)
+}
+
+// Parse all-day events from an ICS calendar as holidays. Multi-day events
+// produce one entry per day.
+pub fn parse_ics(ics: &str) -> Result<Vec<Holiday>, String> {
+    let mut holidays = Vec::new();
+    let mut in_event = false;
+    let mut date: Option<NaiveDate> = None;
+    let mut end_date: Option<NaiveDate> = None;
+    let mut summary: Option<String> = None;
+
+    for line in unfold_ics_lines(ics) {
+        if line == "BEGIN:VEVENT" {
+            in_event = true;
+            date = None;
+            end_date = None;
+            summary = None;
+        } else if line == "END:VEVENT" {
+            if let Some(start) = date.take() {
+                // DTEND of an all-day event is exclusive
+                let last = end_date.take().map(|end| end - Duration::days(1)).unwrap_or(start).max(start);
+                let name = summary.take().unwrap_or_else(|| "Holiday".to_string());
+
+                let mut day = start;
+                while day <= last {
+                    holidays.push(Holiday {
+                        date: day,
+                        name: name.clone(),
+                        source: HolidaySource::Feed,
+                    });
+                    day += Duration::days(1);
+                }
+            }
+            in_event = false;
+        } else if in_event {
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let name = key.split(';').next().unwrap_or(key);
+
+            match name {
+                // Only date values (all-day events) count as holidays
+                "DTSTART" if value.len() == 8 => {
+                    date = NaiveDate::parse_from_str(value, "%Y%m%d").ok();
+                }
+                "DTEND" if value.len() == 8 => {
+                    end_date = NaiveDate::parse_from_str(value, "%Y%m%d").ok();
+                }
+                "SUMMARY" => summary = Some(value.replace("\\,", ",").replace("\\;", ";")),
+                _ => {}
+            }
+        }
+    }
+
+    if holidays.is_empty() && !ics.contains("BEGIN:VCALENDAR") {
+        return Err("Not a valid ICS calendar".to_string());
+    }
+
+    Ok(holidays)
+}
+
+// ICS folds long lines by starting continuation lines with whitespace
+fn unfold_ics_lines(ics: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+
+    for raw in ics.lines() {
+        let raw = raw.trim_end_matches('\r');
+        if raw.starts_with(' ') || raw.starts_with('\t') {
+            if let Some(last) = lines.last_mut() {
+                last.push_str(&raw[1..]);
+                continue;
+            }
+        }
+        lines.push(raw.to_string());
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_parse_feed_json_filters_region() {
+        let json = r#"[
+            {"date":"2025-01-01","localName":"Neujahr","name":"New Year's Day","global":true,"counties":null},
+            {"date":"2025-01-06","localName":"Heilige Drei Könige","name":"Epiphany","global":false,"counties":["DE-BW","DE-BY"]},
+            {"date":"2025-03-08","localName":"Frauentag","name":"Women's Day","global":false,"counties":["DE-BE"]}
+        ]"#;
+
+        let holidays = parse_feed_json(json, Some("DE-BY")).unwrap();
+        let dates: Vec<NaiveDate> = holidays.iter().map(|h| h.date).collect();
+        assert_eq!(dates, vec![date("2025-01-01"), date("2025-01-06")]);
+    }
+
+    #[test]
+    fn test_parse_ics_all_day_events() {
+        let ics = "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nDTSTART;VALUE=DATE:20251225\r\nSUMMARY:Christmas\r\n  Day\r\nEND:VEVENT\r\nBEGIN:VEVENT\r\nDTSTART:20251226T090000Z\r\nSUMMARY:Meeting\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n";
+
+        let holidays = parse_ics(ics).unwrap();
+        assert_eq!(holidays.len(), 1);
+        assert_eq!(holidays[0].date, date("2025-12-25"));
+        assert_eq!(holidays[0].name, "Christmas Day");
+    }
+
+    #[test]
+    fn test_parse_ics_expands_multi_day_events() {
+        let ics = "BEGIN:VCALENDAR\nBEGIN:VEVENT\nDTSTART;VALUE=DATE:20250714\nDTEND;VALUE=DATE:20250717\nSUMMARY:PTO Alice\nEND:VEVENT\nEND:VCALENDAR\n";
+
+        let holidays = parse_ics(ics).unwrap();
+        let dates: Vec<NaiveDate> = holidays.iter().map(|h| h.date).collect();
+        assert_eq!(dates, vec![date("2025-07-14"), date("2025-07-15"), date("2025-07-16")]);
+    }
+
+    #[test]
+    fn test_subscription_keyword_matching() {
+        let subscription = CalendarSubscription {
+            url: "https://example.com/team.ics".to_string(),
+            keywords: vec!["PTO".to_string(), "Public Holiday".to_string()],
+        };
+
+        assert!(subscription.matches("pto – Alice"));
+        assert!(subscription.matches("Public holiday: Labour Day"));
+        assert!(!subscription.matches("Team offsite"));
+    }
+
+    #[test]
+    fn test_merge_keeps_manual_entries() {
+        let mut holidays = vec![Holiday {
+            date: date("2025-12-25"),
+            name: "Family day".to_string(),
+            source: HolidaySource::Manual,
+        }];
+        let imported = vec![
+            Holiday { date: date("2025-12-25"), name: "Christmas".to_string(), source: HolidaySource::Feed },
+            Holiday { date: date("2025-01-01"), name: "New Year".to_string(), source: HolidaySource::Feed },
+        ];
+
+        let added = merge_holidays(&mut holidays, imported);
+
+        assert_eq!(added, 1);
+        assert_eq!(holidays[0].date, date("2025-01-01"));
+        assert_eq!(holidays[1].name, "Family day");
+        assert!(is_holiday(&holidays, date("2025-01-01")));
+    }
+
+    #[test]
+    fn test_calendar_sync_keeps_feed_holidays() {
+        let mut holidays = vec![Holiday { date: date("2025-05-01"), name: "Labour Day".to_string(), source: HolidaySource::Feed }];
+        let synced = || vec![
+            Holiday { date: date("2025-05-01"), name: "Office closed".to_string(), source: HolidaySource::Calendar },
+            Holiday { date: date("2025-05-02"), name: "Bridge day".to_string(), source: HolidaySource::Calendar },
+        ];
+
+        sync_calendar_days(&mut holidays, synced());
+        sync_calendar_days(&mut holidays, synced());
+
+        assert_eq!(holidays.len(), 2);
+        assert_eq!(holidays[0].name, "Labour Day");
+        assert_eq!(holidays[1].source, HolidaySource::Calendar);
+
+        sync_calendar_days(&mut holidays, Vec::new());
+        assert_eq!(holidays.len(), 1);
+        assert_eq!(holidays[0].source, HolidaySource::Feed);
+    }
+}
@@ -0,0 +1,150 @@
+use log::{error, info};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tauri::{AppHandle, Manager, State};
+
+use crate::mqtt::{self, MqttMessage};
+use crate::secrets;
+use crate::{AppState, AttendanceStatus};
+
+const STATUSES: [AttendanceStatus; 4] = [
+    AttendanceStatus::CheckedIn,
+    AttendanceStatus::OnBreak,
+    AttendanceStatus::Paused,
+    AttendanceStatus::CheckedOut,
+];
+
+// The attendance status published as Home Assistant entities through the
+// broker in `Settings::mqtt`, found by Home Assistant through MQTT discovery
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+#[serde(default)]
+pub struct HomeAssistantSettings {
+    pub enabled: bool,
+    pub discovery_prefix: String,
+    pub node_id: Option<String>, // Derived from the username when unset
+}
+
+impl Default for HomeAssistantSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            discovery_prefix: "homeassistant".to_string(),
+            node_id: None,
+        }
+    }
+}
+
+// Home Assistant only allows letters, digits, `_` and `-` in node IDs
+pub fn node_id(settings: &HomeAssistantSettings, username: &str) -> String {
+    let name = settings.node_id.as_deref().unwrap_or(username);
+    let id: String = name
+        .trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' })
+        .collect();
+    if id.is_empty() { "remodance".to_string() } else { id }
+}
+
+pub fn state_topic(node_id: &str) -> String {
+    format!("remodance/{}/status", node_id)
+}
+
+// Discovery configs for an enum sensor with the status and a binary sensor
+// that is on while working, plus the retained status itself
+pub fn messages(settings: &HomeAssistantSettings, username: &str, device_name: &str, status: &AttendanceStatus) -> Vec<MqttMessage> {
+    let node = node_id(settings, username);
+    let state_topic = state_topic(&node);
+    let prefix = settings.discovery_prefix.trim().trim_end_matches('/');
+    let device = json!({
+        "identifiers": [format!("remodance_{}", node)],
+        "name": format!("Remodance {} ({})", username, device_name),
+        "manufacturer": "Remodance",
+    });
+
+    let sensor = json!({
+        "name": "Attendance",
+        "unique_id": format!("{}_attendance", node),
+        "state_topic": state_topic,
+        "device_class": "enum",
+        "options": STATUSES.map(|status| status.label()),
+        "icon": "mdi:briefcase-clock",
+        "device": device,
+    });
+    let working = json!({
+        "name": "Working",
+        "unique_id": format!("{}_working", node),
+        "state_topic": state_topic,
+        "value_template": format!("{{{{ 'ON' if value == '{}' else 'OFF' }}}}", AttendanceStatus::CheckedIn.label()),
+        "icon": "mdi:laptop",
+        "device": device,
+    });
+
+    let config = |component: &str, object: &str, payload: Value| MqttMessage {
+        topic: format!("{}/{}/{}/{}/config", prefix, component, node, object),
+        payload: payload.to_string(),
+        retain: true,
+    };
+    vec![
+        config("sensor", "attendance", sensor),
+        config("binary_sensor", "working", working),
+        MqttMessage { topic: state_topic.clone(), payload: status.label().to_string(), retain: true },
+    ]
+}
+
+// Statuses are published one at a time, so the retained one is the latest
+static PUBLISHING: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+// Publish the current status, in the background. Does nothing until enabled.
+pub fn publish_status(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let state: State<'_, std::sync::Arc<AppState>> = app_handle.state();
+        let settings = state.settings.lock().unwrap().clone();
+        if !settings.home_assistant.enabled {
+            return;
+        }
+        let password = secrets::current(&state).unwrap_or_default().mqtt_password;
+
+        let _publishing = PUBLISHING.lock().await;
+        let status = state.status.lock().unwrap().clone();
+        let messages = messages(&settings.home_assistant, &settings.username, &settings.device_name, &status);
+        match mqtt::publish(&settings.mqtt, password.as_deref(), messages).await {
+            Ok(()) => info!("Published '{}' to Home Assistant", status.label()),
+            Err(err) => error!("Failed to publish to Home Assistant: {}", err),
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_node_id_is_sanitized() {
+        let settings = HomeAssistantSettings::default();
+        assert_eq!(node_id(&settings, "Alice.Smith"), "alice_smith");
+        assert_eq!(node_id(&settings, " "), "remodance");
+        let custom = HomeAssistantSettings { node_id: Some("desk-1".to_string()), ..settings };
+        assert_eq!(node_id(&custom, "alice"), "desk-1");
+    }
+
+    #[test]
+    fn test_messages_announce_and_publish_the_status() {
+        let messages = messages(&HomeAssistantSettings::default(), "alice", "laptop", &AttendanceStatus::OnBreak);
+        let topics: Vec<&str> = messages.iter().map(|message| message.topic.as_str()).collect();
+        assert_eq!(topics, vec![
+            "homeassistant/sensor/alice/attendance/config",
+            "homeassistant/binary_sensor/alice/working/config",
+            "remodance/alice/status",
+        ]);
+        assert!(messages.iter().all(|message| message.retain));
+        assert_eq!(messages[2].payload, "on-break");
+
+        let sensor: Value = serde_json::from_str(&messages[0].payload).unwrap();
+        assert_eq!(sensor["state_topic"], json!("remodance/alice/status"));
+        assert_eq!(sensor["options"], json!(["checked-in", "on-break", "paused", "checked-out"]));
+        let working: Value = serde_json::from_str(&messages[1].payload).unwrap();
+        assert_eq!(working["value_template"], json!("{{ 'ON' if value == 'checked-in' else 'OFF' }}"));
+    }
+}
@@ -0,0 +1,170 @@
+use std::str::FromStr;
+use std::sync::Arc;
+
+use log::{error, info, warn};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+
+use crate::{AppState, AttendanceStatus};
+
+// Global keyboard shortcuts, active even when the window is hidden. Unset bindings are disabled.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+#[serde(default)]
+pub struct HotkeySettings {
+    pub check_in: Option<String>,
+    pub check_out: Option<String>,
+    pub toggle_break: Option<String>, // Starts the first break type, or ends the running break
+}
+
+impl Default for HotkeySettings {
+    fn default() -> Self {
+        Self {
+            check_in: Some("Ctrl+Alt+I".to_string()),
+            check_out: Some("Ctrl+Alt+O".to_string()),
+            toggle_break: Some("Ctrl+Alt+B".to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum HotkeyAction {
+    CheckIn,
+    CheckOut,
+    ToggleBreak,
+}
+
+// A binding that could not be registered, reported back to the settings screen
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct HotkeyConflict {
+    pub action: HotkeyAction,
+    pub shortcut: String,
+    pub reason: String,
+}
+
+impl HotkeySettings {
+    fn bindings(&self) -> Vec<(HotkeyAction, &str)> {
+        [
+            (HotkeyAction::CheckIn, &self.check_in),
+            (HotkeyAction::CheckOut, &self.check_out),
+            (HotkeyAction::ToggleBreak, &self.toggle_break),
+        ]
+        .into_iter()
+        .filter_map(|(action, shortcut)| shortcut.as_deref().map(|shortcut| (action, shortcut)))
+        .collect()
+    }
+
+    // Parse every binding, reporting invalid ones and ones that repeat an
+    // earlier binding. Only the first of two identical shortcuts is kept.
+    pub fn resolve(&self) -> (Vec<(HotkeyAction, Shortcut)>, Vec<HotkeyConflict>) {
+        let mut resolved: Vec<(HotkeyAction, Shortcut)> = Vec::new();
+        let mut conflicts = Vec::new();
+
+        for (action, text) in self.bindings() {
+            let conflict = |reason: String| HotkeyConflict { action, shortcut: text.to_string(), reason };
+
+            match Shortcut::from_str(text) {
+                Err(err) => conflicts.push(conflict(format!("Invalid shortcut: {}", err))),
+                Ok(shortcut) => match resolved.iter().find(|(_, existing)| *existing == shortcut) {
+                    Some((other, _)) => conflicts.push(conflict(format!("Already used for {:?}", other))),
+                    None => resolved.push((action, shortcut)),
+                },
+            }
+        }
+
+        (resolved, conflicts)
+    }
+}
+
+// Replace the registered shortcuts with the configured ones. Shortcuts held by
+// another application fail to register and are returned as conflicts.
+pub fn register(app_handle: &AppHandle, hotkeys: &HotkeySettings) -> Vec<HotkeyConflict> {
+    let global_shortcut = app_handle.global_shortcut();
+    if let Err(err) = global_shortcut.unregister_all() {
+        error!("Failed to unregister global shortcuts: {}", err);
+    }
+
+    let (resolved, mut conflicts) = hotkeys.resolve();
+    for (action, shortcut) in resolved {
+        let result = global_shortcut.on_shortcut(shortcut, move |app_handle, _, event| {
+            if event.state == ShortcutState::Pressed {
+                run_action(app_handle, action);
+            }
+        });
+
+        if let Err(err) = result {
+            conflicts.push(HotkeyConflict {
+                action,
+                shortcut: shortcut.into_string(),
+                reason: format!("Could not register: {}", err),
+            });
+        }
+    }
+
+    for conflict in &conflicts {
+        warn!("Hotkey {:?} ({}) not active: {}", conflict.action, conflict.shortcut, conflict.reason);
+    }
+    conflicts
+}
+
+fn run_action(app_handle: &AppHandle, action: HotkeyAction) {
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        let state: State<'_, Arc<AppState>> = app_handle.state();
+        info!("Hotkey pressed: {:?}", action);
+
+        let result = match action {
+            HotkeyAction::CheckIn => crate::set_attendance(&app_handle, &state, "check-in").await,
+            HotkeyAction::CheckOut => crate::set_attendance(&app_handle, &state, "check-out").await,
+            HotkeyAction::ToggleBreak => {
+                let status = state.status.lock().unwrap().clone();
+                if status == AttendanceStatus::OnBreak {
+                    crate::return_from_break(&app_handle, &state).await
+                } else {
+                    let kind = state.settings.lock().unwrap().break_types.first().map(|t| t.name.clone());
+                    match kind {
+                        Some(kind) => crate::take_break(&app_handle, &state, &kind).await,
+                        None => Err("No break types configured".to_string()),
+                    }
+                }
+            }
+        };
+
+        if let Err(err) = result {
+            error!("Hotkey {:?} failed: {}", action, err);
+        }
+        crate::tray::refresh(&app_handle, &state);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_reports_duplicate_and_invalid_bindings() {
+        let hotkeys = HotkeySettings {
+            check_in: Some("Ctrl+Alt+I".to_string()),
+            check_out: Some("alt+control+i".to_string()),
+            toggle_break: Some("Ctrl+Nope".to_string()),
+        };
+
+        let (resolved, conflicts) = hotkeys.resolve();
+        assert_eq!(resolved.len(), 1);
+        assert_eq!(resolved[0].0, HotkeyAction::CheckIn);
+
+        let actions: Vec<HotkeyAction> = conflicts.iter().map(|c| c.action).collect();
+        assert_eq!(actions, vec![HotkeyAction::CheckOut, HotkeyAction::ToggleBreak]);
+    }
+
+    #[test]
+    fn test_unset_bindings_are_skipped() {
+        let hotkeys = HotkeySettings { toggle_break: None, ..HotkeySettings::default() };
+
+        let (resolved, conflicts) = hotkeys.resolve();
+        assert_eq!(resolved.len(), 2);
+        assert!(conflicts.is_empty());
+    }
+}
@@ -0,0 +1,79 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::RwLock;
+
+use once_cell::sync::Lazy;
+
+pub const DEFAULT_LOCALE: &str = "en";
+
+// Locales with bundled translations in `locales/`
+pub const LOCALES: [&str; 2] = ["en", "de"];
+
+static CATALOGS: Lazy<HashMap<&'static str, HashMap<String, String>>> = Lazy::new(|| {
+    [("en", include_str!("../locales/en.json")), ("de", include_str!("../locales/de.json"))]
+        .into_iter()
+        .map(|(locale, json)| (locale, serde_json::from_str(json).expect("bundled translations are valid JSON")))
+        .collect()
+});
+
+// Locale used for backend text, set from the settings
+static CURRENT: RwLock<&'static str> = RwLock::new(DEFAULT_LOCALE);
+
+// The bundled locale for a language tag such as "de-AT" or "de_DE.UTF-8"
+pub fn supported(tag: &str) -> Option<&'static str> {
+    let language = tag.split(['-', '_', '.']).next()?.to_ascii_lowercase();
+    LOCALES.into_iter().find(|locale| *locale == language)
+}
+
+// Use the configured locale, or the system language when unset, falling back to English
+pub fn set_locale(setting: Option<&str>) {
+    let locale = setting
+        .and_then(supported)
+        .or_else(|| sys_locale::get_locale().as_deref().and_then(supported))
+        .unwrap_or(DEFAULT_LOCALE);
+    *CURRENT.write().unwrap() = locale;
+}
+
+pub fn current() -> &'static str {
+    *CURRENT.read().unwrap()
+}
+
+// User-facing text for a key in the current locale
+pub fn t(key: &str) -> String {
+    translate(current(), key, &[])
+}
+
+// Like `t`, filling in `{name}` placeholders
+pub fn tf(key: &str, args: &[(&str, &dyn Display)]) -> String {
+    translate(current(), key, args)
+}
+
+// English stands in for keys a translation lacks, and the key for unknown keys
+fn translate(locale: &str, key: &str, args: &[(&str, &dyn Display)]) -> String {
+    let text = [locale, DEFAULT_LOCALE]
+        .iter()
+        .find_map(|locale| CATALOGS.get(locale)?.get(key))
+        .map(String::as_str)
+        .unwrap_or(key);
+    args.iter().fold(text.to_string(), |text, (name, value)| text.replace(&format!("{{{}}}", name), &value.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_translations_are_complete_and_filled_in() {
+        let english = &CATALOGS[DEFAULT_LOCALE];
+        for locale in LOCALES {
+            let mut missing: Vec<_> = english.keys().filter(|key| !CATALOGS[locale].contains_key(*key)).collect();
+            missing.sort();
+            assert!(missing.is_empty(), "{} lacks {:?}", locale, missing);
+        }
+
+        assert_eq!(supported("de_DE.UTF-8"), Some("de"));
+        assert_eq!(supported("fr-FR"), None);
+        assert_eq!(translate("de", "tray.countdown", &[("secs", &45)]), "Auschecken in 45 s");
+        assert_eq!(translate("en", "no.such.key", &[]), "no.such.key");
+    }
+}
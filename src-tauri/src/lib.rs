@@ -1,52 +1,561 @@
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use tauri::{AppHandle, Emitter, Manager, State};
+use tauri::{AppHandle, Emitter, Manager, RunEvent, State, WebviewWindow, WebviewWindowBuilder, WindowEvent};
 use std::sync::{Arc, Mutex};
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime};
 use tokio::time;
 use user_idle::UserIdle;
-use chrono::{Utc, Local};
-use serde_json;
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveTime, Utc};
 use log::{info, error, debug};
-use reqwest;
+use i18n::{t, tf};
+use auto_launch::{AutoLaunch, AutoLaunchBuilder};
 use tauri_plugin_store::StoreBuilder;
+use tauri_plugin_deep_link::DeepLinkExt;
+
+mod automation;
+mod autostart;
+mod backend;
+mod bamboohr;
+mod breaks;
+mod caldav;
+mod calls;
+mod cli;
+mod clockify;
+mod code_activity;
+mod clock;
+mod deep_links;
+mod developer;
+mod email_report;
+mod erpnext;
+mod directory;
+mod discord;
+mod features;
+mod google_calendar;
+mod google_sheets;
+mod env_overrides;
+mod harvest;
+mod history;
+mod home_assistant;
+mod holidays;
+mod hotkeys;
+mod meetings;
+mod i18n;
+mod local_api;
+mod matrix;
+mod migrations;
+mod mqtt;
+mod notifications;
+mod oauth;
+mod odoo;
+mod outlook_calendar;
+mod onboarding;
+mod overtime;
+mod personio;
+mod plugins;
+mod policy;
+mod preview;
+mod profiles;
+mod push;
+mod provisioning;
+mod prompts;
+mod queue;
+mod remote_config;
+mod schedule;
+mod secrets;
+mod settings_audit;
+mod settings_file;
+mod settings_pin;
+mod settings_schema;
+mod sounds;
+mod store;
+mod store_crypto;
+mod summary;
+mod telegram;
+mod telemetry;
+mod teams;
+mod time_tracker;
+mod toggl;
+mod transitions;
+mod tray;
+mod updates;
+mod validation;
+mod webhook;
+
+use autostart::{Autostart, AutostartEntry};
+use breaks::{BreakType, BreakTypeSummary, LunchWindow, MicroBreakStats, ReminderCadence};
+use history::{AuditEntry, BreakReminder, History};
+use features::FeatureFlag;
+use google_calendar::{GoogleCalendarSettings, GoogleCalendarStatus};
+use google_sheets::{GoogleSheetsSettings, GoogleSheetsStatus};
+use holidays::{CalendarSubscription, Holiday, HolidaySource};
+use hotkeys::{HotkeyConflict, HotkeySettings};
+use meetings::{CalendarProvider, Meeting};
+use outlook_calendar::{OutlookCalendarSettings, OutlookCalendarStatus};
+use notifications::{NotificationKind, NotificationSettings};
+use cli::CliCommand;
+use onboarding::{Onboarding, OnboardingState, OnboardingStep};
+use odoo::{Odoo, OdooSettings};
+use personio::{Personio, PersonioSettings};
+use plugins::PluginHook;
+use profiles::{ProfileList, Profiles};
+use push::PushSettings;
+use queue::{PendingEvent, PendingQueue};
+use overtime::{OvertimePeriod, OvertimeWarning};
+use policy::Policy;
+use preview::Subsystem;
+use prompts::Prompt;
+use secrets::{SecretStatus, Secrets};
+use settings_audit::{Change, ChangeSource};
+use schedule::{DayOverride, ScheduleTemplate, TimeWindow, WeeklySchedule, WorkBlock};
+use sounds::{SoundCue, SoundSettings};
+use store_crypto::{EncryptionMode, Envelope, StoreKey};
+use summary::{BlockSummary, PeriodSummary, TodaySummary};
+use teams::{SignInPrompt, TeamsSettings, TeamsStatus};
+use telegram::TelegramSettings;
+use telemetry::TelemetrySettings;
+use automation::{AutomationTriggers, TriggerEvent};
+use backend::{BackendAdapter, BackendKind, SendError};
+use bamboohr::{BambooHr, BambooHrSettings};
+use caldav::CalDavSettings;
+use clockify::ClockifySettings;
+use code_activity::CodeActivitySettings;
+use discord::DiscordSettings;
+use email_report::EmailReportSettings;
+use erpnext::{ErpNext, ErpNextSettings};
+use harvest::HarvestSettings;
+use home_assistant::HomeAssistantSettings;
+use local_api::LocalApiSettings;
+use matrix::MatrixSettings;
+use mqtt::MqttSettings;
+use time_tracker::TimeTrackerKind;
+use toggl::TogglSettings;
+use updates::{UpdateChannel, UpdateInfo};
+use validation::FieldError;
+use webhook::{Webhook, ORG_ID_HEADER};
+use transitions::{ScheduleContext, Transition};
 
 // Constants
+const ACTIVE_IDLE_SECS: u64 = 60; // Idle time below which the machine counts as in use
 const SETTINGS_FILENAME: &str = "settings.json";
+const HISTORY_FILENAME: &str = "history.json";
+const PENDING_FILENAME: &str = "pending.json";
+const PROFILES_FILENAME: &str = "profiles.json";
+const ONBOARDING_FILENAME: &str = "onboarding.json";
+const AUTOSTART_FILENAME: &str = "autostart.json";
+const PIN_FILENAME: &str = "settings-pin.json";
+const DEVICE_FILENAME: &str = "device.json";
+const EXPORTS_FILENAME: &str = "exports.json";
+const REMOTE_CONFIG_FILENAME: &str = "remote-config.json";
+const SHEETS_EXPORT_KEY: &str = "google_sheets";
+const EMAIL_REPORT_KEY: &str = "email_report";
+const MATRIX_SUMMARY_KEY: &str = "matrix_summary";
+const CALDAV_EXPORT_KEY: &str = "caldav";
+const CALENDAR_SYNC_INTERVAL_SECS: u64 = 6 * 60 * 60;
+const REMOTE_CONFIG_INTERVAL_SECS: u64 = 60 * 60;
+const MEETING_SYNC_SECS: u64 = 5 * 60;
+const CODE_ACTIVITY_SECS: u64 = 5 * 60;
+const SHEETS_EXPORT_SECS: u64 = 60 * 60;
+const EMAIL_REPORT_SECS: u64 = 60 * 60;
+const MATRIX_SUMMARY_SECS: u64 = 60 * 60;
+const CALDAV_PUBLISH_SECS: u64 = 5 * 60;
+const MEETING_WINDOW_HOURS: i64 = 12; // Meetings are fetched this far before and after now
+const TELEMETRY_EXPORT_SECS: u64 = 10;
+const TELEGRAM_RETRY_SECS: u64 = 60; // Wait after a failed poll or while the bot is off
+const LOCAL_API_RETRY_SECS: u64 = 30; // Wait while the local API is off or its port is taken
+const DISCORD_PRESENCE_SECS: u64 = 60; // Reconnects to Discord after it was started or restarted
+const CALL_CHECK_SECS: u64 = 30; // How often running calls are looked for
+const QUEUE_RETRY_SECS: u64 = 60;
+const PROBE_TIMEOUT_SECS: u64 = 5;
+const SETTINGS_WATCH_SECS: u64 = 2; // How often settings.json is checked for outside edits
+const MAIN_WINDOW: &str = "main";
+const UPDATE_CHECK_INTERVAL_SECS: u64 = 24 * 60 * 60;
+const ACTIVITY_UPDATE_SECS: u64 = 60;
+const MICRO_BREAK_CHANNEL: &str = "micro-breaks"; // Notification channel for eye/stretch nudges
 
 // Attendance status
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
 enum AttendanceStatus {
     CheckedIn,
+    #[default]
     CheckedOut,
+    OnBreak,
+    Paused, // Local-only interruption, the API still sees the user as checked in
 }
 
-impl Default for AttendanceStatus {
-    fn default() -> Self {
-        Self::CheckedOut
+impl AttendanceStatus {
+    // Event for entering this status. Pauses are never sent to the API.
+    fn event_type(&self) -> &'static str {
+        match self {
+            AttendanceStatus::CheckedIn => "check-in",
+            AttendanceStatus::CheckedOut => "check-out",
+            AttendanceStatus::OnBreak => "break-start",
+            AttendanceStatus::Paused => "pause",
+        }
     }
+    
+    // Status name used by `get_attendance_status` and event payloads
+    fn label(&self) -> &'static str {
+        match self {
+            AttendanceStatus::CheckedIn => "checked-in",
+            AttendanceStatus::CheckedOut => "checked-out",
+            AttendanceStatus::OnBreak => "on-break",
+            AttendanceStatus::Paused => "paused",
+        }
+    }
+}
+
+// Whether a status change was made by the app or by the user
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+enum ChangeCause {
+    Auto,
+    Manual,
+}
+
+// Body of the `attendance_changed` event
+#[derive(Debug, Serialize, Clone)]
+struct AttendanceChanged {
+    status: &'static str,
+    event_type: String,
+    cause: ChangeCause,
+    timestamp: DateTime<Utc>,
+    session_secs: Option<i64>, // Length of the running session, or of the one that just ended
+}
+
+// Body of the `activity_update` event
+#[derive(Debug, Serialize, Clone)]
+struct ActivityUpdate {
+    idle_secs: u64,
+    timestamp: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    in_meeting: Option<bool>, // Only sent when a connected calendar has `tag_heartbeats` on
+}
+
+// Outcome of the last API request
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+enum ApiHealth {
+    Ok,
+    Failing, // Reachable, but the last request was rejected
+    Offline,
+}
+
+// Everything the main window shows at a glance, returned by `get_dashboard`
+#[derive(Debug, Serialize, Clone)]
+struct Dashboard {
+    status: &'static str,
+    session_start: Option<DateTime<Utc>>,
+    today: TodaySummary,
+    queued_events: usize,
+    api_health: ApiHealth,
+    next_transition: Option<Transition>,
+}
+
+// What saving settings would do, returned by `preview_settings`
+#[derive(Debug, Serialize, Clone)]
+struct SettingsPreview {
+    errors: Vec<FieldError>,
+    changes: Vec<Change>,
+    restarts: Vec<Subsystem>,
+    connection: Option<ConnectionCheck>, // Not tried when the endpoint is invalid
+}
+
+// Result of probing the API endpoint
+#[derive(Debug, Serialize, Clone, PartialEq)]
+struct ConnectionCheck {
+    reachable: bool,
+    error: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(default)]
 struct Settings {
+    backend: BackendKind, // Where events go, `api_endpoint` only applies to the webhook
     api_endpoint: String,
     username: String,
+    employee_id: Option<String>, // HR identifier sent as `user_id`, the username is sent when unset
+    directory_url: Option<String>, // Looked up once for `employee_id` when it is unset, see `directory`
     device_name: String,
-    idle_timeout_mins: u64,
+    org_id: Option<String>, // Organization this client reports to, sent with every event and as `X-Remodance-Org-Id`
+    locale: Option<String>, // Language of notifications and the tray, e.g. "de", the system language when unset
+    feature_flags: std::collections::BTreeMap<String, bool>, // Overrides of the built-in defaults in `features`, by name
+    idle_timeout_secs: u64, // Time without input after which the user counts as away
     auto_mode: bool,
     developer_mode: bool,
+    holidays: Vec<Holiday>,
+    holiday_country: Option<String>, // ISO country code used for holiday imports
+    holiday_region: Option<String>,  // Optional subdivision, e.g. "DE-BY"
+    calendar_subscriptions: Vec<CalendarSubscription>, // ICS calendars providing non-working days
+    vacation: Option<Vacation>,
+    timezone: Option<String>, // IANA timezone name, system timezone when unset
+    overtime_daily_mins: Option<u64>,  // Daily worked-time target, no alerts when unset
+    overtime_weekly_mins: Option<u64>, // Weekly worked-time target, no alerts when unset
+    max_daily_mins: Option<u64>,       // Hard daily limit after which the user is checked out
+    break_reminder_after_mins: Option<u64>, // Continuous work after which a break is suggested
+    lunch_window: Option<LunchWindow>,      // Idle periods in this window are recorded as lunch breaks
+    end_of_day_reminder: Option<NaiveTime>, // Remind to check out when still checked in after this time
+    schedule: WeeklySchedule,
+    morning_reminder: bool, // Remind to check in when active after the scheduled start
+    quiet_hours: Option<TimeWindow>, // No automatic check-ins or reminders in this window
+    follow_schedule: bool, // Auto check-in only inside scheduled blocks, auto check-out at block end
+    schedule_templates: Vec<ScheduleTemplate>,
+    active_template: Option<String>, // Template used instead of `schedule` when set
+    day_overrides: Vec<DayOverride>, // One-off working hours for single dates
+    break_types: Vec<BreakType>, // Break kinds that can be started with `start_break`
+    break_reminder_interval_mins: Option<u64>, // Suggest a break this often while checked in
+    break_reminder_snooze_mins: u64,
+    micro_break_interval_mins: Option<u64>, // Eye/stretch nudges while checked in, off when unset
+    micro_break_secs: u64, // Rest time that counts as taking a micro-break
+    idle_break_max_mins: Option<u64>, // Idle periods past the idle timeout but shorter than this are breaks
+    break_budget_mins: Option<u64>, // Daily break allowance, break time over it is deducted
+    notifications: NotificationSettings,
+    headless: bool, // Start with only the tray icon, the window is created when opened from the tray
+    update_channel: UpdateChannel,
+    checkout_warning_secs: u64, // Ask whether the user is still there this long before an idle check-out, 0 disables
+    hotkeys: HotkeySettings,
+    launch_behavior: LaunchBehavior,
+    sounds: SoundSettings,
+    status_line_format: String, // See `tray::DEFAULT_STATUS_LINE_FORMAT`
+    close_behavior: CloseBehavior,
+    block_end_warning_mins: u64, // Warn this long before checking out at the end of a scheduled block, 0 disables
+    config_url: Option<String>, // Signed settings bundle fetched periodically, see `remote_config`
+    config_public_key: Option<String>, // Base64 Ed25519 key the bundle must be signed with, trusted only from the policy
+    probe_endpoint_on_save: bool, // Try a changed API endpoint when saving and warn if it doesn't answer
+    teams: TeamsSettings, // Set the Teams presence from the attendance status
+    google_calendar: GoogleCalendarSettings, // Accepted meetings keep the user checked in while idle
+    outlook_calendar: OutlookCalendarSettings, // The same for Microsoft 365 and Exchange calendars
+    call_detection: bool, // Calls in Zoom, Teams, Meet and other conferencing apps count as meetings
+    time_tracker: Option<TimeTrackerKind>, // Tracker sessions are mirrored into, none when unset
+    toggl: TogglSettings,
+    clockify: ClockifySettings,
+    harvest: HarvestSettings,
+    google_sheets: GoogleSheetsSettings, // Append each finished day's sessions to a Google Sheet
+    email_report: EmailReportSettings, // Email a summary after each day or week
+    push: PushSettings, // Critical events pushed to the phone through ntfy, Pushover or Gotify
+    mqtt: MqttSettings, // Broker for everything published over MQTT
+    home_assistant: HomeAssistantSettings, // Publish the status to Home Assistant over MQTT
+    discord: DiscordSettings, // Show a "Working" activity in Discord while checked in
+    telegram: TelegramSettings, // Alerts and remote commands through a Telegram bot
+    automation_triggers: AutomationTriggers, // Webhooks of automation platforms fired on internal events
+    telemetry: TelemetrySettings, // OTLP export of logs and spans
+    erpnext: ErpNextSettings, // Used when `backend` is ERPNext
+    odoo: OdooSettings, // Used when `backend` is Odoo
+    bamboohr: BambooHrSettings, // Used when `backend` is BambooHR
+    personio: PersonioSettings, // Used when `backend` is Personio
+    plugins: Vec<PluginHook>, // Run on every status change
+    local_api: LocalApiSettings,
+    matrix: MatrixSettings,
+    caldav: CalDavSettings, // Calendar finished sessions are published to
+    code_activity: CodeActivitySettings, // Pushes and pull requests counted as activity
+}
+
+// Part of the settings restored by `reset_settings`
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+enum ResetScope {
+    All,
+    Connection, // Endpoint, identity and the settings bundle source
+    Schedule, // Working hours, templates, overrides, quiet hours and the lunch window
+    Notifications, // Notifications, sounds and reminders, including break and micro-break ones
+}
+
+// What the window's close button does
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+enum CloseBehavior {
+    #[default]
+    Ask, // Quit, asking whether to check out first while checked in
+    HideToTray, // Keep running in the tray with the monitor active
+    Quit, // Check out and quit
+}
+
+// What happens when the app starts while checked out
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+enum LaunchBehavior {
+    #[default]
+    Nothing,
+    AutoCheckIn,
+    Prompt, // Emit `launch_prompt` so the frontend can ask "Start your work day?"
+}
+
+// Out-of-office range during which monitoring is disabled
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+struct Vacation {
+    from: NaiveDate,
+    to: NaiveDate,
+    notify_api: bool, // Post a single out-of-office event when the range starts
+    #[serde(default)]
+    announced: bool,
+}
+
+impl Vacation {
+    fn contains(&self, date: NaiveDate) -> bool {
+        date >= self.from && date <= self.to
+    }
 }
 
 impl Default for Settings {
     fn default() -> Self {
         Self {
+            backend: BackendKind::Webhook,
             api_endpoint: "https://example.com/attendance".to_string(),
             username: whoami::username(),
+            employee_id: None,
+            directory_url: None,
             device_name: whoami::fallible::hostname().unwrap_or_else(|_| "unknown".to_string()),
-            idle_timeout_mins: 10,
+            org_id: None,
+            locale: None,
+            feature_flags: std::collections::BTreeMap::new(),
+            idle_timeout_secs: 10 * 60,
             auto_mode: true,
             developer_mode: false,
+            holidays: Vec::new(),
+            holiday_country: None,
+            holiday_region: None,
+            calendar_subscriptions: Vec::new(),
+            vacation: None,
+            timezone: None,
+            overtime_daily_mins: Some(8 * 60),
+            overtime_weekly_mins: Some(40 * 60),
+            max_daily_mins: None,
+            break_reminder_after_mins: Some(6 * 60),
+            lunch_window: None,
+            end_of_day_reminder: Some(NaiveTime::from_hms_opt(18, 0, 0).unwrap()),
+            schedule: WeeklySchedule::default(),
+            morning_reminder: true,
+            quiet_hours: None,
+            follow_schedule: false,
+            schedule_templates: Vec::new(),
+            active_template: None,
+            day_overrides: Vec::new(),
+            break_types: breaks::default_break_types(),
+            break_reminder_interval_mins: Some(90),
+            break_reminder_snooze_mins: 10,
+            micro_break_interval_mins: None,
+            micro_break_secs: 20,
+            idle_break_max_mins: None,
+            break_budget_mins: None,
+            notifications: NotificationSettings::default(),
+            headless: false,
+            update_channel: UpdateChannel::default(),
+            checkout_warning_secs: 60,
+            hotkeys: HotkeySettings::default(),
+            launch_behavior: LaunchBehavior::default(),
+            sounds: SoundSettings::default(),
+            status_line_format: tray::DEFAULT_STATUS_LINE_FORMAT.to_string(),
+            close_behavior: CloseBehavior::default(),
+            block_end_warning_mins: 5,
+            config_url: None,
+            config_public_key: None,
+            probe_endpoint_on_save: true,
+            teams: TeamsSettings::default(),
+            google_calendar: GoogleCalendarSettings::default(),
+            outlook_calendar: OutlookCalendarSettings::default(),
+            call_detection: false,
+            time_tracker: None,
+            toggl: TogglSettings::default(),
+            clockify: ClockifySettings::default(),
+            harvest: HarvestSettings::default(),
+            google_sheets: GoogleSheetsSettings::default(),
+            email_report: EmailReportSettings::default(),
+            push: PushSettings::default(),
+            mqtt: MqttSettings::default(),
+            home_assistant: HomeAssistantSettings::default(),
+            discord: DiscordSettings::default(),
+            telegram: TelegramSettings::default(),
+            automation_triggers: AutomationTriggers::default(),
+            telemetry: TelemetrySettings::default(),
+            erpnext: ErpNextSettings::default(),
+            odoo: OdooSettings::default(),
+            bamboohr: BambooHrSettings::default(),
+            personio: PersonioSettings::default(),
+            plugins: Vec::new(),
+            local_api: LocalApiSettings::default(),
+            matrix: MatrixSettings::default(),
+            caldav: CalDavSettings::default(),
+            code_activity: CodeActivitySettings::default(),
+        }
+    }
+}
+
+impl Settings {
+    // Who events are sent for
+    fn user_id(&self) -> String {
+        self.employee_id.clone().unwrap_or_else(|| self.username.clone())
+    }
+    
+    // Restore the defaults for one part of the settings
+    fn reset(&mut self, scope: ResetScope) {
+        let defaults = Settings::default();
+        match scope {
+            ResetScope::All => *self = defaults,
+            ResetScope::Connection => {
+                self.api_endpoint = defaults.api_endpoint;
+                self.username = defaults.username;
+                self.employee_id = defaults.employee_id;
+                self.directory_url = defaults.directory_url;
+                self.device_name = defaults.device_name;
+                self.org_id = defaults.org_id;
+                self.config_url = defaults.config_url;
+                self.config_public_key = defaults.config_public_key;
+            }
+            ResetScope::Schedule => {
+                self.schedule = defaults.schedule;
+                self.follow_schedule = defaults.follow_schedule;
+                self.schedule_templates = defaults.schedule_templates;
+                self.active_template = defaults.active_template;
+                self.day_overrides = defaults.day_overrides;
+                self.block_end_warning_mins = defaults.block_end_warning_mins;
+                self.quiet_hours = defaults.quiet_hours;
+                self.lunch_window = defaults.lunch_window;
+            }
+            ResetScope::Notifications => {
+                self.notifications = defaults.notifications;
+                self.sounds = defaults.sounds;
+                self.morning_reminder = defaults.morning_reminder;
+                self.end_of_day_reminder = defaults.end_of_day_reminder;
+                self.break_reminder_after_mins = defaults.break_reminder_after_mins;
+                self.break_reminder_interval_mins = defaults.break_reminder_interval_mins;
+                self.break_reminder_snooze_mins = defaults.break_reminder_snooze_mins;
+                self.micro_break_interval_mins = defaults.micro_break_interval_mins;
+                self.micro_break_secs = defaults.micro_break_secs;
+            }
+        }
+    }
+    
+    // The schedule in effect: the active template, or the custom schedule
+    fn effective_schedule(&self) -> &WeeklySchedule {
+        self.active_template
+            .as_deref()
+            .and_then(|name| schedule::find_template(&self.schedule_templates, name))
+            .map(|template| &template.schedule)
+            .unwrap_or(&self.schedule)
+    }
+    
+    // Working blocks on a date, honouring one-off overrides
+    fn blocks_on(&self, date: NaiveDate) -> &[WorkBlock] {
+        schedule::blocks_on(self.effective_schedule(), &self.day_overrides, date)
+    }
+    
+    // Inputs that decide what the app does on a given day
+    fn schedule_context(&self) -> ScheduleContext<'_> {
+        ScheduleContext {
+            schedule: self.effective_schedule(),
+            overrides: &self.day_overrides,
+            holidays: &self.holidays,
+            vacation: self.vacation.as_ref().map(|vacation| (vacation.from, vacation.to)),
+            follow_schedule: self.follow_schedule,
+            end_of_day_reminder: self.end_of_day_reminder,
+            timezone: self.timezone.as_deref(),
         }
     }
+    
+    // Whether `activity_update` says if the user is in a meeting
+    fn tags_heartbeats(&self) -> bool {
+        self.google_calendar.tag_heartbeats() || self.outlook_calendar.tag_heartbeats()
+    }
 }
 
 // Store application state
@@ -56,6 +565,80 @@ struct AppState {
     last_activity: Mutex<Instant>,
     settings: Mutex<Settings>,
     manual_checkout: Mutex<bool>, // Track if checkout was manual
+    manual_checkout_date: Mutex<Option<NaiveDate>>, // Day of the manual checkout, reset on the next day
+    history: Mutex<History>,
+    overtime_warned: Mutex<Vec<(OvertimePeriod, NaiveDate)>>, // Periods already warned about
+    daily_limit: Mutex<Option<DailyLimit>>, // Set when the daily hour limit was reached
+    reminders_sent: Mutex<Vec<(String, NaiveDate)>>, // Daily reminders already shown
+    active_prompts: Mutex<Vec<Prompt>>, // Prompts waiting for an answer
+    auto_break: Mutex<bool>, // The running break was started by the idle monitor and ends on activity
+    break_reminder: Mutex<ReminderCadence>,
+    do_not_disturb_until: Mutex<Option<DateTime<Utc>>>, // Reminders are held back until then (meetings, focus time)
+    api_offline: Mutex<bool>, // The last API request could not be sent
+    api_failing: Mutex<bool>, // The last API request failed, for whatever reason
+    outage_pushed: Mutex<bool>, // The current API outage was pushed to the phone
+    tray_view: Mutex<Option<tray::TrayView>>, // What the tray icon and menu currently show
+    checkout_countdown: Mutex<Option<u64>>, // Seconds left before the idle check-out while the pre-checkout prompt is showing
+    still_here_at: Mutex<Option<Instant>>, // "I'm still here" was answered, counts as activity
+    code_activity_at: Mutex<Option<DateTime<Utc>>>, // Latest push or pull request on the code host, see `code_activity`
+    simulated_idle_since: Mutex<Option<Instant>>, // Idle time reported instead of the system's, see `simulate_idle`
+    pending: Mutex<PendingQueue>, // Events waiting for the API to be reachable again
+    send_lock: tokio::sync::Mutex<()>, // Held while sending so queued events go out in order
+    activity_emitted_at: Mutex<Option<Instant>>, // Last `activity_update` event
+    meetings: Mutex<Vec<Meeting>>, // Accepted meetings around now from connected calendars
+    call: Mutex<Option<(Instant, Option<&'static str>)>>, // Last call detection and the app found in a call
+    profiles: Mutex<Profiles>,
+    autostart: Mutex<Autostart>, // Login items of each profile
+    held_notifications: Mutex<Vec<notifications::HeldNotification>>, // Held back during OS Do-Not-Disturb
+    onboarding: Mutex<Onboarding>, // First-run setup progress
+    block_end_warned: Mutex<Option<DateTime<Utc>>>, // Block-end check-out the user was last warned about
+    checkout_extended_until: Mutex<Option<DateTime<Utc>>>, // Block-end check-out postponed until then
+    secrets: Mutex<Option<Secrets>>, // API credentials, loaded from the keychain on first use
+    store_lock: Mutex<StoreLock>,
+    policy: Mutex<Policy>, // Managed settings, read once at startup
+    settings_modified: Mutex<Option<SystemTime>>, // settings.json modification time when last read or written here
+    settings_pin: Mutex<Option<String>>, // Hash of the PIN guarding settings changes
+    settings_unlocked: Mutex<bool>, // The PIN was entered since the app started
+    pin_attempts: Mutex<settings_pin::Attempts>, // Wrong PINs, locking out further attempts
+    device_id: Mutex<String>, // Persistent UUID identifying this install to the API, see `load_device_id`
+}
+
+// Whether settings.json is encrypted and can be read
+#[derive(Debug, Clone, Default)]
+enum StoreLock {
+    #[default]
+    Plain,
+    Unlocked(StoreKey),
+    Locked(String), // Encrypted and not readable yet, with the reason. Saving is refused so the file isn't overwritten.
+}
+
+// Settings for the settings screen, with the fields the managed policy locks
+#[derive(Debug, Serialize, Clone)]
+struct AppConfig {
+    #[serde(flatten)]
+    settings: Settings,
+    locked_fields: Vec<String>,
+}
+
+// Whether settings changes need the PIN, as shown in the settings screen
+#[derive(Debug, Serialize, Clone, PartialEq)]
+struct SettingsProtection {
+    pin_set: bool,
+    unlocked: bool,
+}
+
+// Settings store encryption as shown in the settings screen
+#[derive(Debug, Serialize, Clone, PartialEq)]
+struct EncryptionStatus {
+    mode: Option<EncryptionMode>,
+    locked: Option<String>, // Why the encrypted settings could not be read
+}
+
+// Day on which the daily hour limit was reached
+#[derive(Debug, Clone, PartialEq)]
+struct DailyLimit {
+    date: NaiveDate,
+    overridden: bool, // The user checked in manually after reaching the limit
 }
 
 impl Default for AppState {
@@ -65,6 +648,42 @@ impl Default for AppState {
             last_activity: Mutex::new(Instant::now()),
             settings: Mutex::new(Settings::default()),
             manual_checkout: Mutex::new(false),
+            manual_checkout_date: Mutex::new(None),
+            history: Mutex::new(History::default()),
+            overtime_warned: Mutex::new(Vec::new()),
+            daily_limit: Mutex::new(None),
+            reminders_sent: Mutex::new(Vec::new()),
+            active_prompts: Mutex::new(Vec::new()),
+            auto_break: Mutex::new(false),
+            break_reminder: Mutex::new(ReminderCadence::default()),
+            do_not_disturb_until: Mutex::new(None),
+            api_offline: Mutex::new(false),
+            api_failing: Mutex::new(false),
+            outage_pushed: Mutex::new(false),
+            tray_view: Mutex::new(None),
+            checkout_countdown: Mutex::new(None),
+            still_here_at: Mutex::new(None),
+            code_activity_at: Mutex::new(None),
+            simulated_idle_since: Mutex::new(None),
+            pending: Mutex::new(PendingQueue::default()),
+            send_lock: tokio::sync::Mutex::new(()),
+            activity_emitted_at: Mutex::new(None),
+            meetings: Mutex::new(Vec::new()),
+            call: Mutex::new(None),
+            profiles: Mutex::new(Profiles::default()),
+            autostart: Mutex::new(Autostart::default()),
+            held_notifications: Mutex::new(Vec::new()),
+            onboarding: Mutex::new(Onboarding::default()),
+            block_end_warned: Mutex::new(None),
+            checkout_extended_until: Mutex::new(None),
+            secrets: Mutex::new(None),
+            store_lock: Mutex::new(StoreLock::default()),
+            policy: Mutex::new(Policy::default()),
+            settings_modified: Mutex::new(None),
+            settings_pin: Mutex::new(None),
+            settings_unlocked: Mutex::new(false),
+            pin_attempts: Mutex::new(settings_pin::Attempts::default()),
+            device_id: Mutex::new(String::new()),
         }
     }
 }
@@ -73,6 +692,8 @@ impl Default for AppState {
 struct AttendancePayload {
     event_type: String,
     user_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    org_id: Option<String>,
     payload: AttendanceData,
     timestamp: String,
 }
@@ -82,13 +703,51 @@ struct AttendanceData {
     time: String,
     date: String,
     device_id: String,
+    device_name: String, // Friendly label, the hostname by default
     #[serde(skip_serializing_if = "Option::is_none")]
     config: Option<ConfigData>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    out_of_office: Option<OutOfOfficeData>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    note: Option<String>, // Free text attached to a manual check-in/check-out
+}
+
+// Body of `break-start` and `break-end` posts. Durations are in minutes:
+// `planned_mins` is the maximum length of the break type, if it has one, and
+// `actual_mins` is only sent with `break-end`.
+#[derive(Debug, Serialize, Deserialize)]
+struct BreakPayload {
+    event_type: String,
+    user_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    org_id: Option<String>,
+    payload: BreakData,
+    timestamp: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BreakData {
+    time: String,
+    date: String,
+    device_id: String,
+    device_name: String,
+    #[serde(rename = "type")]
+    break_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    planned_mins: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    actual_mins: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OutOfOfficeData {
+    from: String,
+    to: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 struct ConfigData {
-    idle_timeout_mins: u64,
+    idle_timeout_secs: u64,
     auto_mode: bool,
 }
 
@@ -112,327 +771,3452 @@ fn start_idle_monitor(app_handle: AppHandle) {
                 state.settings.lock().unwrap().clone()
             };
             
+            tray::refresh(&app_handle_clone, &state);
+            
+            // Dates are evaluated in the configured timezone
+            let today = clock::today(settings.timezone.as_deref());
+            
+            // Vacation disables monitoring, reminders and events until the
+            // range ends, whether auto mode is on or not
+            if let Some(vacation) = settings.vacation.clone() {
+                if handle_vacation(&app_handle_clone, &state, vacation, today).await {
+                    continue;
+                }
+            }
+            
+            // Worked time counts towards overtime whether sessions were started manually or not
+            check_overtime(&app_handle_clone, &state, &settings);
+            enforce_daily_limit(&app_handle_clone, &state, &settings).await;
+            enforce_block_end(&app_handle_clone, &state, &settings).await;
+            check_break_compliance(&app_handle_clone, &state, &settings);
+            check_break_length(&app_handle_clone, &state, &settings);
+            check_break_budget(&app_handle_clone, &state, &settings);
+            check_break_reminder(&app_handle_clone, &state, &settings);
+            check_micro_break(&app_handle_clone, &state, &settings);
+            check_end_of_day_reminder(&app_handle_clone, &state, &settings);
+            check_morning_reminder(&app_handle_clone, &state, &settings);
+            
             // Skip if auto-mode is disabled
             if !settings.auto_mode {
                 continue;
             }
             
-            // Get the idle time using the correct API
-            let idle_duration = match UserIdle::get_time() {
-                Ok(idle_info) => idle_info.duration(),
-                Err(e) => {
-                    error!("Failed to get idle time: {}", e);
+            // A manual checkout only blocks auto check-in for the rest of that day
+            reset_manual_checkout_if_new_day(&state, today);
+            
+            // No automatic attendance on holidays
+            if holidays::is_holiday(&settings.holidays, today) {
+                continue;
+            }
+            
+            let idle_duration = match idle_time(&state) {
+                Ok(idle_duration) => idle_duration,
+                Err(err) => {
+                    error!("{}", err);
                     continue;
                 }
             };
             
+            let idle_duration = idle_since_still_here(&state, idle_duration);
+            
+            // Pushes and pull requests from another computer count as activity too
+            let idle_duration = if settings.code_activity.enabled {
+                code_activity::idle_with_activity(idle_duration, *state.code_activity_at.lock().unwrap(), Utc::now())
+            } else {
+                idle_duration
+            };
+            
             // Get current status
             let current_status = {
                 state.status.lock().unwrap().clone()
             };
             
-            // Convert idle timeout to milliseconds
-            let idle_timeout = Duration::from_secs(settings.idle_timeout_mins * 60);
+            // Idle timeout from the settings, in seconds
+            let idle_timeout = Duration::from_secs(settings.idle_timeout_secs);
+            
+            // Inside the lunch window only longer idle periods end the session, as a lunch break
+            let now_time = clock::now(settings.timezone.as_deref()).time();
+            let lunch_window = settings.lunch_window.as_ref().filter(|window| window.contains(now_time));
+            let idle_timeout = match lunch_window {
+                Some(window) => idle_timeout.max(Duration::from_secs(window.min_idle_mins * 60)),
+                None => idle_timeout,
+            };
+            
+            // Call attendees rarely touch input devices, so idle time inside
+            // an accepted meeting or a running call doesn't end the session
+            let in_meeting = meetings::current(&state.meetings.lock().unwrap(), Utc::now()).is_some()
+                || (settings.call_detection && current_call(&state).is_some());
+            
+            // Only plain idle check-outs are announced, breaks are not
+            let checks_out = current_status == AttendanceStatus::CheckedIn
+                && !in_meeting
+                && lunch_window.is_none()
+                && settings.idle_break_max_mins.is_none();
+            warn_before_checkout(&app_handle_clone, &state, &settings, checks_out, idle_duration, idle_timeout);
             
             // Check if the user is idle
             if idle_duration >= idle_timeout {
                 if current_status == AttendanceStatus::CheckedIn {
-                    info!("User is idle for {} seconds. Automatically checking out", idle_duration.as_secs());
-                    
-                    // Update status in state
-                    {
-                        let mut status = state.status.lock().unwrap();
-                        *status = AttendanceStatus::CheckedOut;
-                    }
-                    
-                    // Create payload and send check-out event to the API
-                    let payload = create_attendance_payload("check-out", &settings);
-                    if let Err(err) = send_to_api("check-out", &payload, &settings).await {
-                        error!("Failed to send check-out event: {}", err);
+                    if in_meeting {
+                        debug!("User is idle for {} seconds during a meeting. Staying checked in", idle_duration.as_secs());
+                    } else if lunch_window.is_some() {
+                        info!("User is idle for {} seconds during the lunch window. Recording a lunch break", idle_duration.as_secs());
+                        auto_break(&app_handle_clone, &state, &settings, breaks::LUNCH_BREAK, idle_duration).await;
+                    } else if settings.idle_break_max_mins.is_some() {
+                        info!("User is idle for {} seconds. Recording a short break", idle_duration.as_secs());
+                        auto_break(&app_handle_clone, &state, &settings, breaks::IDLE_BREAK, idle_duration).await;
+                    } else {
+                        info!("User is idle for {} seconds. Automatically checking out", idle_duration.as_secs());
+                        let reason = format!("No activity for {} minutes.", idle_duration.as_secs() / 60);
+                        auto_transition(&app_handle_clone, &state, &settings, AttendanceStatus::CheckedOut, &reason).await;
                     }
-                    
-                    // Notify the frontend
-                    let _ = app_handle_clone.emit("attendance_changed", "check-out");
+                } else if current_status == AttendanceStatus::OnBreak && idle_break_expired(&state, &settings, idle_duration) {
+                    // Too long for a short break: check out from when the user went idle instead
+                    info!("User is idle for {} seconds. Converting the short break into a check-out", idle_duration.as_secs());
+                    record_history(&app_handle_clone, &state, |history| history.cancel_break());
+                    let reason = format!("Away for {} minutes, too long for a short break.", idle_duration.as_secs() / 60);
+                    auto_transition(&app_handle_clone, &state, &settings, AttendanceStatus::CheckedOut, &reason).await;
                 }
             } else {
                 // User is active
                 if current_status == AttendanceStatus::CheckedOut {
-                    // Check if the checkout was manual
-                    let was_manual_checkout = {
-                        let manual_checkout = state.manual_checkout.lock().unwrap();
-                        *manual_checkout
-                    };
-                    
-                    // Only auto check-in if the checkout wasn't manual
-                    if !was_manual_checkout {
+                    if may_auto_check_in(&state, &settings, clock::now(settings.timezone.as_deref())) {
                         info!("User activity detected after being idle. Automatically checking in");
-                        
-                        // Update status in state
-                        {
-                            let mut status = state.status.lock().unwrap();
-                            *status = AttendanceStatus::CheckedIn;
-                        }
-                        
-                        // Create payload and send check-in event to the API
-                        let payload = create_attendance_payload("check-in", &settings);
-                        if let Err(err) = send_to_api("check-in", &payload, &settings).await {
-                            error!("Failed to send check-in event: {}", err);
-                        }
-                        
-                        // Notify the frontend
-                        let _ = app_handle_clone.emit("attendance_changed", "check-in");
+                        auto_transition(&app_handle_clone, &state, &settings, AttendanceStatus::CheckedIn, "Activity detected after being away.").await;
+                    }
+                } else if current_status == AttendanceStatus::OnBreak && *state.auto_break.lock().unwrap() {
+                    // Breaks recorded by the idle monitor end when the user is back
+                    info!("User activity detected during an automatic break. Ending the break");
+                    if let Err(err) = leave_break(&app_handle_clone, &state, &settings).await {
+                        error!("Failed to send break-end event: {}", err);
                     }
                 }
                 
                 // Update last activity time
-                {
-                    let mut last_activity = state.last_activity.lock().unwrap();
-                    *last_activity = Instant::now();
-                    
-                    // Emit activity update event every 60 seconds
-                    let elapsed = last_activity.elapsed();
-                    if elapsed.as_secs() > 60 {
-                        debug!("Emitting activity update");
-                        let _ = app_handle_clone.emit("activity_update", "");
+                *state.last_activity.lock().unwrap() = Instant::now();
+                
+                // Emit activity update event every 60 seconds, none while the
+                // session is paused
+                let due = current_status != AttendanceStatus::Paused && {
+                    let mut emitted_at = state.activity_emitted_at.lock().unwrap();
+                    let due = emitted_at.is_none_or(|at| at.elapsed() >= Duration::from_secs(ACTIVITY_UPDATE_SECS));
+                    if due {
+                        *emitted_at = Some(Instant::now());
                     }
+                    due
+                };
+                if due {
+                    debug!("Emitting activity update");
+                    let _ = app_handle_clone.emit("activity_update", ActivityUpdate {
+                        idle_secs: idle_duration.as_secs(),
+                        timestamp: Utc::now(),
+                        in_meeting: settings.tags_heartbeats().then_some(in_meeting),
+                    });
                 }
             }
         }
     });
 }
 
-// Send attendance event to API
-async fn send_to_api(event_type: &str, payload: &AttendancePayload, settings: &Settings) -> Result<(), String> {
-    // Serialize the payload to JSON
-    let payload_str = match serde_json::to_string(payload) {
-        Ok(s) => s,
-        Err(e) => return Err(format!("Failed to serialize payload: {}", e))
-    };
-    
-    info!("Sending {} event to API: {}", event_type, payload_str);
+// Time since the last input, using the correct API, unless a developer is
+// simulating idle time
+fn idle_time(state: &AppState) -> Result<Duration, String> {
+    if let Some(since) = *state.simulated_idle_since.lock().unwrap() {
+        return Ok(since.elapsed());
+    }
+    UserIdle::get_time()
+        .map(|idle| idle.duration())
+        .map_err(|err| format!("Failed to get idle time: {}", err))
+}
+
+// Answering "I'm still here" counts as activity until the user is really active again
+fn idle_since_still_here(state: &AppState, idle_duration: Duration) -> Duration {
+    let mut still_here_at = state.still_here_at.lock().unwrap();
+    match *still_here_at {
+        Some(at) if at.elapsed() < idle_duration => at.elapsed(),
+        _ => {
+            *still_here_at = None;
+            idle_duration
+        }
+    }
+}
+
+// Apply vacation state for today. Returns true while the vacation is active,
+// clears it once the range has passed.
+async fn handle_vacation(app_handle: &AppHandle, state: &AppState, vacation: Vacation, today: NaiveDate) -> bool {
+    if today > vacation.to {
+        info!("Vacation ended on {}. Resuming normal behavior", vacation.to);
+        
+        // Let the idle monitor check in again after the vacation
+        *state.manual_checkout.lock().unwrap() = false;
+        
+        if let Err(err) = update_settings(app_handle, state, ChangeSource::App, |settings| settings.vacation = None).await {
+            error!("Failed to clear vacation: {}", err);
+        }
+        let _ = app_handle.emit("vacation_changed", "");
+        return false;
+    }
     
-    // Get API endpoint from settings
-    let api_endpoint = &settings.api_endpoint;
+    if !vacation.contains(today) {
+        return false;
+    }
     
-    // Send the actual HTTP request
-    let client = reqwest::Client::new();
-    let response = client.post(api_endpoint)
-        .header("Content-Type", "application/json")
-        .body(payload_str)
-        .send()
-        .await
-        .map_err(|e| format!("Failed to send request: {}", e))?;
+    // Vacation starts while checked in: check out, so the server doesn't
+    // see the session running for the whole vacation
+    let settings = state.settings.lock().unwrap().clone();
+    if *state.status.lock().unwrap() != AttendanceStatus::CheckedOut {
+        let reason = format!("Vacation until {}. Automatic attendance resumes afterwards.", vacation.to);
+        auto_transition(app_handle, state, &settings, AttendanceStatus::CheckedOut, &reason).await;
+    }
     
-    // Check if the request was successful
-    if !response.status().is_success() {
-        let status = response.status();
-        let error_text = response.text().await
-            .unwrap_or_else(|_| "Failed to get error details".to_string());
+    if vacation.notify_api && !vacation.announced {
+        let mut payload = create_attendance_payload("out-of-office", &state.device_id.lock().unwrap(), &settings);
+        payload.payload.out_of_office = Some(OutOfOfficeData {
+            from: vacation.from.format("%Y-%m-%d").to_string(),
+            to: vacation.to.format("%Y-%m-%d").to_string(),
+        });
+        
+        // Announced once: the offline queue delivers it if the API is
+        // unreachable, and a rejected event isn't sent again every second
+        let result = update_settings(app_handle, state, ChangeSource::App, |settings| {
+            if let Some(vacation) = settings.vacation.as_mut() {
+                vacation.announced = true;
+            }
+        }).await;
+        if let Err(err) = result {
+            error!("Failed to save vacation state: {}", err);
+        }
         
-        error!("API request failed with status {}: {}", status, error_text);
-        return Err(format!("API request failed with status {}", status));
+        if let Err(err) = send_to_api(app_handle, "out-of-office", &payload, &settings).await {
+            error!("Failed to send out-of-office event: {}", err);
+        }
     }
     
-    info!("Successfully sent {} event to API", event_type);
-    Ok(())
+    true
 }
 
-// Helper to load settings from disk
-async fn load_settings_from_store(app_handle: &AppHandle) -> Settings {
-    let store_path = std::path::PathBuf::from(SETTINGS_FILENAME);
+// Keep the idle monitor from checking in again for the rest of the day
+fn mark_manual_checkout(state: &AppState, today: NaiveDate) {
+    *state.manual_checkout.lock().unwrap() = true;
+    *state.manual_checkout_date.lock().unwrap() = Some(today);
+}
+
+// Clear the manual checkout flag once the day of the checkout has passed
+fn reset_manual_checkout_if_new_day(state: &AppState, today: NaiveDate) {
+    let checkout_date = *state.manual_checkout_date.lock().unwrap();
     
-    // Try to create and load the store
-    match StoreBuilder::new(app_handle, store_path).build() {
-        Ok(store) => {
-            if let Err(err) = store.reload() {
-                error!("Failed to load store: {}. Using defaults.", err);
-                return Settings::default();
-            }
-            
-            match store.get("settings") {
-                Some(settings_value) => {
-                    if let Ok(settings) = serde_json::from_value(settings_value.clone()) {
-                        info!("Loaded settings from disk");
-                        return settings;
-                    }
-                }
-                None => {
-                    info!("No settings found in store. Using defaults.");
-                }
-            }
-            Settings::default()
-        },
-        Err(err) => {
-            error!("Failed to create store: {}. Using defaults.", err);
-            Settings::default()
+    if let Some(date) = checkout_date {
+        if date != today {
+            debug!("New day since manual checkout on {}. Re-enabling auto check-in", date);
+            *state.manual_checkout.lock().unwrap() = false;
+            *state.manual_checkout_date.lock().unwrap() = None;
         }
     }
 }
 
-// Helper to save settings to disk
-async fn save_settings_to_store(app_handle: &AppHandle, settings: &Settings) -> Result<(), String> {
-    let store_path = std::path::PathBuf::from(SETTINGS_FILENAME);
-    
-    // Try to create and load the store
-    let store = match StoreBuilder::new(app_handle, store_path).build() {
-        Ok(store) => store,
-        Err(err) => return Err(format!("Failed to create store: {}", err)),
-    };
+// Whether the app may check the user in by itself at `now`, in the
+// configured timezone. Used by the idle monitor and the launch behavior.
+fn may_auto_check_in(state: &AppState, settings: &Settings, now: DateTime<FixedOffset>) -> bool {
+    let today = now.date_naive();
     
-    // Load existing data if possible (not crucial if it fails for a new store)
-    let _ = store.reload();
+    // No automatic attendance on vacation or holidays
+    if settings.vacation.as_ref().is_some_and(|vacation| vacation.contains(today)) || holidays::is_holiday(&settings.holidays, today) {
+        return false;
+    }
     
-    // Insert settings
-    store.set("settings".to_string(), serde_json::to_value(settings).unwrap());
+    // A manual check-out and the daily hour limit block auto check-in until the next day
+    if *state.manual_checkout.lock().unwrap() || daily_limit_blocks_check_in(state, today) {
+        return false;
+    }
     
-    // Save the store
-    if let Err(err) = store.save() {
-        return Err(format!("Failed to save store: {}", err));
+    // Activity during quiet hours never starts a session
+    if settings.quiet_hours.as_ref().is_some_and(|quiet_hours| quiet_hours.contains(now.time())) {
+        return false;
     }
     
-    info!("Saved settings to disk");
-    Ok(())
+    // When following the schedule, activity only counts inside a work block
+    !settings.follow_schedule || schedule::active_block(settings.blocks_on(today), now.time()).is_some()
 }
 
-// Send attendance event
-#[tauri::command]
-async fn send_attendance_event(event_type: String, app_handle: AppHandle, state: State<'_, Arc<AppState>>) -> Result<(), String> {
-    // Get settings
-    let settings = {
-        state.settings.lock().unwrap().clone()
-    };
+// Change status on behalf of the idle monitor: update state and history, send
+// the event to the API and notify the frontend and the user
+async fn auto_transition(app_handle: &AppHandle, state: &AppState, settings: &Settings, new_status: AttendanceStatus, reason: &str) {
+    let event_type = new_status.event_type();
     
     // Update status in state
     {
         let mut status = state.status.lock().unwrap();
-        *status = if event_type == "check-in" {
-            // If checking in manually, reset the manual checkout flag
-            let mut manual_checkout = state.manual_checkout.lock().unwrap();
-            *manual_checkout = false;
-            AttendanceStatus::CheckedIn
-        } else {
-            // Mark as manual checkout
-            let mut manual_checkout = state.manual_checkout.lock().unwrap();
-            *manual_checkout = true;
-            AttendanceStatus::CheckedOut
-        };
+        *status = new_status.clone();
     }
+    record_status_change(app_handle, state, &new_status);
     
-    // Create payload and send to API
-    let payload = create_attendance_payload(&event_type, &settings);
-    send_to_api(&event_type, &payload, &settings).await?;
+    // Create payload and send the event to the API
+    let payload = create_attendance_payload(event_type, &state.device_id.lock().unwrap(), settings);
+    if let Err(err) = send_to_api(app_handle, event_type, &payload, settings).await {
+        error!("Failed to send {} event: {}", event_type, err);
+    }
     
     // Notify the frontend
-    let _ = app_handle.emit("attendance_changed", &event_type);
+    emit_attendance_changed(app_handle, state, event_type, ChangeCause::Auto);
     
-    Ok(())
+    match new_status {
+        AttendanceStatus::CheckedIn => notifications::notify(app_handle, settings, NotificationKind::AutoCheckIn, &t("notify.auto_check_in.title"), reason),
+        _ => {
+            notifications::notify(app_handle, settings, NotificationKind::AutoCheckOut, &t("notify.auto_check_out.title"), reason);
+            sounds::play(settings, SoundCue::AutoCheckOut);
+            telegram::notify_auto_check_out(state, settings, reason);
+        }
+    }
 }
 
-// Get current attendance status
-#[tauri::command]
-fn get_attendance_status(state: State<'_, Arc<AppState>>) -> String {
-    let status = state.status.lock().unwrap();
-    match *status {
-        AttendanceStatus::CheckedIn => "checked-in".to_string(),
-        AttendanceStatus::CheckedOut => "checked-out".to_string(),
+// Ask whether the user is still there shortly before an idle check-out. The
+// prompt is withdrawn once the user is back or the check-out happened.
+fn warn_before_checkout(app_handle: &AppHandle, state: &AppState, settings: &Settings, checks_out: bool, idle_duration: Duration, idle_timeout: Duration) {
+    let warning = Duration::from_secs(settings.checkout_warning_secs);
+    let in_warning_window = checks_out
+        && !warning.is_zero()
+        && idle_duration < idle_timeout
+        && idle_duration + warning >= idle_timeout;
+    
+    let secs_left = in_warning_window.then(|| idle_timeout.saturating_sub(idle_duration).as_secs());
+    
+    let previous = {
+        let mut countdown = state.checkout_countdown.lock().unwrap();
+        std::mem::replace(&mut *countdown, secs_left)
+    };
+    if previous != secs_left {
+        tray::update_countdown(app_handle, state);
+        local_api::broadcast("countdown", serde_json::json!({ "secs_left": secs_left }));
+    }
+    
+    match (secs_left, previous) {
+        (Some(secs_left), None) => {
+            info!("Idle check-out in {} seconds. Asking whether the user is still there", secs_left);
+            show_prompt(app_handle, state, prompts::pre_checkout(secs_left));
+            sounds::play(settings, SoundCue::CheckoutWarning);
+            automation::fire(state, TriggerEvent::IdleWarning, &secs_left.to_string());
+        }
+        (None, Some(_)) => withdraw_prompt(app_handle, state, prompts::PRE_CHECKOUT_PROMPT),
+        _ => {}
     }
 }
 
-// Get app configuration
-#[tauri::command]
-fn get_app_config(state: State<'_, Arc<AppState>>) -> Settings {
-    state.settings.lock().unwrap().clone()
+// Start a break that began when the user went idle. The break ends on its
+// own when activity returns.
+async fn auto_break(app_handle: &AppHandle, state: &AppState, settings: &Settings, kind: &str, idle_duration: Duration) {
+    let break_start = Utc::now() - chrono::Duration::from_std(idle_duration).unwrap_or_default();
+    if let Err(err) = enter_break(app_handle, state, settings, kind, break_start, true).await {
+        error!("Failed to send break-start event: {}", err);
+    }
+    
+    notifications::notify(
+        app_handle,
+        settings,
+        NotificationKind::AutoCheckOut,
+        &t("notify.auto_break.title"),
+        &tf("notify.auto_break.body", &[("mins", &(idle_duration.as_secs() / 60)), ("kind", &kind)]),
+    );
 }
 
-// Get app version
-#[tauri::command]
-fn get_app_version() -> String {
-    env!("CARGO_PKG_VERSION").to_string()
+// Whether a short break recorded for idleness has run past its maximum length
+fn idle_break_expired(state: &AppState, settings: &Settings, idle_duration: Duration) -> bool {
+    let Some(max_mins) = settings.idle_break_max_mins else {
+        return false;
+    };
+    
+    *state.auto_break.lock().unwrap()
+        && idle_duration >= Duration::from_secs(max_mins * 60)
+        && state.history.lock().unwrap().current_break().is_some_and(|current| current.kind == breaks::IDLE_BREAK)
 }
 
-// Open settings window
-#[tauri::command]
-fn open_settings() -> Result<(), String> {
-    Ok(())
+// Put the session on break. Auto check-out is paused until the break ends.
+async fn enter_break(app_handle: &AppHandle, state: &AppState, settings: &Settings, kind: &str, at: DateTime<Utc>, automatic: bool) -> Result<(), String> {
+    *state.status.lock().unwrap() = AttendanceStatus::OnBreak;
+    *state.auto_break.lock().unwrap() = automatic;
+    record_history(app_handle, state, |history| history.start_break(kind, at));
+    
+    let event_type = AttendanceStatus::OnBreak.event_type();
+    let payload = create_break_payload(event_type, &state.device_id.lock().unwrap(), settings, kind, None);
+    let result = send_to_api(app_handle, event_type, &payload, settings).await;
+    
+    let cause = if automatic { ChangeCause::Auto } else { ChangeCause::Manual };
+    emit_attendance_changed(app_handle, state, event_type, cause);
+    result
 }
 
-// Save settings
-#[tauri::command]
-async fn save_settings(settings: Settings, app_handle: AppHandle, state: State<'_, Arc<AppState>>) -> Result<(), String> {
-    // Update in-memory settings
-    {
-        let mut settings_lock = state.settings.lock().unwrap();
-        *settings_lock = settings.clone();
-    }
+// End the running break and resume the session
+async fn leave_break(app_handle: &AppHandle, state: &AppState, settings: &Settings) -> Result<(), String> {
+    *state.status.lock().unwrap() = AttendanceStatus::CheckedIn;
+    let automatic = std::mem::replace(&mut *state.auto_break.lock().unwrap(), false);
     
-    // Save settings to disk
-    save_settings_to_store(&app_handle, &settings).await?;
+    let now = Utc::now();
+    let current = state.history.lock().unwrap().current_break().cloned();
+    record_history(app_handle, state, |history| history.start_session(now));
     
-    Ok(())
+    let (kind, actual_mins) = match &current {
+        Some(current) => (current.kind.as_str(), Some((now - current.start).num_minutes())),
+        None => ("", None),
+    };
+    let payload = create_break_payload("break-end", &state.device_id.lock().unwrap(), settings, kind, actual_mins);
+    let result = send_to_api(app_handle, "break-end", &payload, settings).await;
+    
+    let cause = if automatic { ChangeCause::Auto } else { ChangeCause::Manual };
+    emit_attendance_changed(app_handle, state, "break-end", cause);
+    result
 }
 
-// Configure auto launch
-fn configure_auto_launch(app: &tauri::App) -> Result<(), Box<dyn std::error::Error>> {
-    use tauri_plugin_autostart::ManagerExt;
+// Check out once today's worked time reaches the configured hard limit
+async fn enforce_daily_limit(app_handle: &AppHandle, state: &AppState, settings: &Settings) {
+    let Some(max_daily_mins) = settings.max_daily_mins else {
+        return;
+    };
+    
+    if *state.status.lock().unwrap() != AttendanceStatus::CheckedIn {
+        return;
+    }
+    
+    let today = clock::today(settings.timezone.as_deref());
     
-    let autostart_manager = app.autolaunch();
+    // A manual check-in after reaching the limit overrides it for the day
+    if let Some(limit) = state.daily_limit.lock().unwrap().as_ref() {
+        if limit.date == today && limit.overridden {
+            return;
+        }
+    }
     
-    // Enable auto-launch by default
-    if let Ok(false) = autostart_manager.is_enabled() {
-        info!("Enabling auto-launch at startup");
-        let _ = autostart_manager.enable();
+    let worked_mins = {
+        let history = state.history.lock().unwrap();
+        overtime::worked_mins_on(&history, settings.timezone.as_deref(), today, Utc::now())
+    };
+    if worked_mins < max_daily_mins as i64 {
+        return;
     }
     
-    Ok(())
+    info!("Daily limit of {} minutes reached. Automatically checking out", max_daily_mins);
+    *state.daily_limit.lock().unwrap() = Some(DailyLimit { date: today, overridden: false });
+    
+    let reason = format!(
+        "Daily limit reached: you have worked {}h {}m today. Automatic check-in resumes tomorrow.",
+        worked_mins / 60,
+        worked_mins % 60,
+    );
+    auto_transition(app_handle, state, settings, AttendanceStatus::CheckedOut, &reason).await;
+    let _ = app_handle.emit("daily_limit_reached", worked_mins);
 }
 
-// Check if auto-launch is enabled
-#[tauri::command]
-fn is_auto_launch_enabled(app_handle: AppHandle) -> Result<bool, String> {
-    use tauri_plugin_autostart::ManagerExt;
+// Suggest a break once the running session exceeds the configured duration
+fn check_break_compliance(app_handle: &AppHandle, state: &AppState, settings: &Settings) {
+    let Some(after_mins) = settings.break_reminder_after_mins else {
+        return;
+    };
+    
+    let history = {
+        let mut history = state.history.lock().unwrap();
+        let Some(session) = history.current_session() else {
+            return;
+        };
+        
+        let continuous_mins = (Utc::now() - session.start).num_minutes();
+        if continuous_mins < after_mins as i64 || history.break_reminded() {
+            return;
+        }
+        
+        history.record_break_reminder(Utc::now());
+        history.clone()
+    };
     
-    let autostart_manager = app_handle.autolaunch();
+    if let Err(err) = save_history_to_store(app_handle, &history) {
+        error!("Failed to save history: {}", err);
+    }
     
-    autostart_manager.is_enabled()
-        .map_err(|err| format!("Failed to check auto-launch status: {}", err))
+    info!("Continuously checked in for over {} minutes. Suggesting a break", after_mins);
+    notifications::notify(
+        app_handle,
+        settings,
+        NotificationKind::Reminder,
+        &t("prompt.break_reminder.title"),
+        &tf("notify.break_suggested.body", &[("hours", &(after_mins / 60)), ("mins", &(after_mins % 60))]),
+    );
+    sounds::play(settings, SoundCue::BreakReminder);
+    let _ = app_handle.emit("break_suggested", after_mins);
 }
 
-// Toggle auto-launch
-#[tauri::command]
-fn toggle_auto_launch(app_handle: AppHandle, enable: bool) -> Result<(), String> {
-    use tauri_plugin_autostart::ManagerExt;
+// Notify once when the running break gets longer than its type allows
+fn check_break_length(app_handle: &AppHandle, state: &AppState, settings: &Settings) {
+    if *state.status.lock().unwrap() != AttendanceStatus::OnBreak {
+        return;
+    }
     
-    let autostart_manager = app_handle.autolaunch();
+    let Some(current) = state.history.lock().unwrap().current_break().cloned() else {
+        return;
+    };
+    let Some(max_mins) = breaks::find_break_type(&settings.break_types, &current.kind).and_then(|t| t.max_mins) else {
+        return;
+    };
     
-    if enable {
-        autostart_manager.enable()
-            .map_err(|err| format!("Failed to enable auto-launch: {}", err))
-    } else {
-        autostart_manager.disable()
-            .map_err(|err| format!("Failed to disable auto-launch: {}", err))
+    if (Utc::now() - current.start).num_minutes() < max_mins as i64 {
+        return;
+    }
+    
+    let key = format!("break-over-{}", current.start.timestamp());
+    if !remind_once(state, &key, clock::today(settings.timezone.as_deref())) {
+        return;
     }
+    
+    info!("{} break is longer than {} minutes", current.kind, max_mins);
+    notifications::notify(
+        app_handle,
+        settings,
+        NotificationKind::Reminder,
+        &t("notify.break_exceeded.title"),
+        &tf("notify.break_exceeded.body", &[("kind", &current.kind), ("mins", &max_mins)]),
+    );
+    let _ = app_handle.emit("break_exceeded", &current.kind);
 }
 
-// Helper to create the current ISO timestamp
-fn iso_timestamp() -> String {
-    Utc::now().to_rfc3339()
+// Warn once a day when today's breaks exceed the daily allowance
+fn check_break_budget(app_handle: &AppHandle, state: &AppState, settings: &Settings) {
+    if settings.break_budget_mins.is_none() || *state.status.lock().unwrap() != AttendanceStatus::OnBreak {
+        return;
+    }
+    
+    let timezone = settings.timezone.as_deref();
+    let today = clock::today(timezone);
+    let Some(budget) = summary::today_summary(&state.history.lock().unwrap(), timezone, &settings.holidays, settings.break_budget_mins, today, Utc::now()).break_budget else {
+        return;
+    };
+    
+    if !budget.exceeded() || !remind_once(state, "break-budget", today) {
+        return;
+    }
+    
+    info!("Break allowance of {} minutes used up", budget.allowance_mins);
+    notifications::notify(
+        app_handle,
+        settings,
+        NotificationKind::Reminder,
+        &t("notify.break_budget.title"),
+        &tf("notify.break_budget.body", &[("mins", &budget.allowance_mins)]),
+    );
+    let _ = app_handle.emit("break_budget_exceeded", &budget);
 }
 
-// Format current time as HH:MM:SS
-fn format_current_time() -> String {
-    Local::now().format("%H:%M:%S").to_string()
-}
+// Suggest a break on a fixed cadence while checked in
+fn check_break_reminder(app_handle: &AppHandle, state: &AppState, settings: &Settings) {
+    let Some(interval_mins) = settings.break_reminder_interval_mins else {
+        return;
+    };
+    
+    if *state.status.lock().unwrap() != AttendanceStatus::CheckedIn {
+        return;
+    }
+    
+    let Some(working_since) = state.history.lock().unwrap().current_session().map(|session| session.start) else {
+        return;
+    };
+    
+    let now = Utc::now();
+    let due = state.break_reminder.lock().unwrap().next_due(working_since, chrono::Duration::minutes(interval_mins as i64));
+    if now < due || in_quiet_hours(settings) || do_not_disturb(state, now) {
+        return;
+    }
+    
+    state.break_reminder.lock().unwrap().reminded(now);
+    
+    let worked_mins = (now - working_since).num_minutes();
+    info!("Working for {} minutes. Suggesting a break", worked_mins);
+    show_prompt(app_handle, state, prompts::break_reminder(worked_mins, settings.break_reminder_snooze_mins));
+    sounds::play(settings, SoundCue::BreakReminder);
+}
+
+// Nudge for a short eye/stretch break and track whether the user rested afterwards
+fn check_micro_break(app_handle: &AppHandle, state: &AppState, settings: &Settings) {
+    let Some(interval_mins) = settings.micro_break_interval_mins else {
+        return;
+    };
+    let Ok(idle) = idle_time(state) else {
+        return;
+    };
+    let now = Utc::now();
+    
+    // Resting long enough shortly after a suggestion counts as taking it
+    if idle.as_secs() >= settings.micro_break_secs {
+        let since = now - chrono::Duration::seconds(breaks::MICRO_BREAK_GRACE_SECS);
+        let history = {
+            let mut history = state.history.lock().unwrap();
+            if !history.take_micro_break(since, now) {
+                return;
+            }
+            history.clone()
+        };
+        if let Err(err) = save_history_to_store(app_handle, &history) {
+            error!("Failed to save history: {}", err);
+        }
+        let _ = app_handle.emit("micro_break_taken", ());
+        return;
+    }
+    
+    if *state.status.lock().unwrap() != AttendanceStatus::CheckedIn {
+        return;
+    }
+    
+    let (working_since, last_suggested) = {
+        let history = state.history.lock().unwrap();
+        let Some(session) = history.current_session() else {
+            return;
+        };
+        (session.start, history.micro_breaks.last().map(|m| m.suggested_at))
+    };
+    
+    let due = last_suggested.map_or(working_since, |last| last.max(working_since))
+        + chrono::Duration::minutes(interval_mins as i64);
+    if now < due || in_quiet_hours(settings) || do_not_disturb(state, now) {
+        return;
+    }
+    
+    record_history(app_handle, state, |history| history.record_micro_break(now));
+    
+    debug!("Suggesting a micro-break");
+    notifications::notify_on_channel(
+        app_handle,
+        settings,
+        NotificationKind::Reminder,
+        Some(MICRO_BREAK_CHANNEL),
+        &t("notify.micro_break.title"),
+        &tf("notify.micro_break.body", &[("secs", &settings.micro_break_secs)]),
+    );
+    let _ = app_handle.emit("micro_break_suggested", settings.micro_break_secs);
+}
+
+// Whether reminders are currently held back by do-not-disturb
+fn do_not_disturb(state: &AppState, now: DateTime<Utc>) -> bool {
+    state.do_not_disturb_until.lock().unwrap().is_some_and(|until| now < until)
+}
+
+// Ask to check out when still checked in past the configured evening time
+fn check_end_of_day_reminder(app_handle: &AppHandle, state: &AppState, settings: &Settings) {
+    let Some(reminder_time) = settings.end_of_day_reminder else {
+        return;
+    };
+    
+    if *state.status.lock().unwrap() != AttendanceStatus::CheckedIn {
+        return;
+    }
+    
+    let timezone = settings.timezone.as_deref();
+    let today = clock::today(timezone);
+    if clock::now(timezone) < clock::resolve(timezone, today, reminder_time) {
+        return;
+    }
+    
+    if remind_once(state, prompts::END_OF_DAY_PROMPT, today) {
+        info!("Still checked in after {}. Reminding to check out", reminder_time);
+        show_prompt(app_handle, state, prompts::end_of_day());
+        if settings.push.forgot_check_out {
+            push_critical(state, t("prompt.end_of_day.title"), t("prompt.end_of_day.body"));
+        }
+    }
+}
+
+// Ask to check in when the machine is in use after the scheduled start time but
+// the idle monitor won't check in automatically
+fn check_morning_reminder(app_handle: &AppHandle, state: &AppState, settings: &Settings) {
+    if !settings.morning_reminder || *state.status.lock().unwrap() != AttendanceStatus::CheckedOut {
+        return;
+    }
+    
+    if in_quiet_hours(settings) {
+        return;
+    }
+    
+    let timezone = settings.timezone.as_deref();
+    let today = clock::today(timezone);
+    
+    // With auto mode on, activity checks in by itself unless blocked for the day
+    let auto_check_in_blocked = *state.manual_checkout.lock().unwrap() || daily_limit_blocks_check_in(state, today);
+    if settings.auto_mode && !auto_check_in_blocked {
+        return;
+    }
+    
+    let Some(start_time) = schedule::day_start(settings.blocks_on(today)) else {
+        return;
+    };
+    if clock::now(timezone) < clock::resolve(timezone, today, start_time) {
+        return;
+    }
+    
+    if holidays::is_holiday(&settings.holidays, today)
+        || settings.vacation.as_ref().is_some_and(|vacation| vacation.contains(today))
+    {
+        return;
+    }
+    
+    let is_active = idle_time(state)
+        .map(|idle| idle.as_secs() < ACTIVE_IDLE_SECS)
+        .unwrap_or(false);
+    
+    if is_active && remind_once(state, prompts::MORNING_PROMPT, today) {
+        info!("Active after scheduled start {} but not checked in. Reminding to check in", start_time);
+        show_prompt(app_handle, state, prompts::morning_check_in());
+    }
+}
+
+// Whether the current time falls into the configured quiet hours
+fn in_quiet_hours(settings: &Settings) -> bool {
+    settings.quiet_hours.as_ref().is_some_and(|quiet_hours| {
+        quiet_hours.contains(clock::now(settings.timezone.as_deref()).time())
+    })
+}
+
+// Mark a daily reminder as sent. Returns false if it was already sent today.
+fn remind_once(state: &AppState, key: &str, today: NaiveDate) -> bool {
+    let mut sent = state.reminders_sent.lock().unwrap();
+    if sent.iter().any(|(k, date)| k == key && *date == today) {
+        return false;
+    }
+    
+    sent.retain(|(k, _)| k != key);
+    sent.push((key.to_string(), today));
+    true
+}
+
+// Show a prompt as a native notification and hand its actions to the frontend
+fn show_prompt(app_handle: &AppHandle, state: &AppState, prompt: Prompt) {
+    let settings = state.settings.lock().unwrap().clone();
+    let kind = match prompt.id.as_str() {
+        prompts::PRE_CHECKOUT_PROMPT | prompts::BLOCK_END_PROMPT => NotificationKind::CheckoutWarning,
+        _ => NotificationKind::Reminder,
+    };
+    notifications::notify(app_handle, &settings, kind, &prompt.title, &prompt.body);
+    
+    {
+        let mut active_prompts = state.active_prompts.lock().unwrap();
+        active_prompts.retain(|p| p.id != prompt.id);
+        active_prompts.push(prompt.clone());
+    }
+    
+    let _ = app_handle.emit("action_prompt", &prompt);
+}
+
+// Remove a prompt that no longer applies
+fn withdraw_prompt(app_handle: &AppHandle, state: &AppState, prompt_id: &str) {
+    state.active_prompts.lock().unwrap().retain(|p| p.id != prompt_id);
+    let _ = app_handle.emit("prompt_withdrawn", prompt_id);
+}
+
+// Check out at the end of a scheduled block when the session started before it,
+// warning shortly before. "Extend by 1 hour" on the warning moves the check-out.
+async fn enforce_block_end(app_handle: &AppHandle, state: &AppState, settings: &Settings) {
+    if !settings.follow_schedule {
+        return;
+    }
+    
+    let Some(session_start) = state.history.lock().unwrap().current_session().map(|session| session.start) else {
+        return;
+    };
+    
+    let timezone = settings.timezone.as_deref();
+    let today = clock::today(timezone);
+    let now = clock::now(timezone);
+    
+    let extended_until = *state.checkout_extended_until.lock().unwrap();
+    let warning = chrono::Duration::minutes(settings.block_end_warning_mins as i64);
+    
+    for block in settings.blocks_on(today) {
+        let block_end = clock::resolve(timezone, today, block.end).with_timezone(&Utc);
+        if session_start >= block_end {
+            continue;
+        }
+        let check_out_at = extended_until.map_or(block_end, |until| until.max(block_end));
+        
+        if now < check_out_at {
+            let warned = *state.block_end_warned.lock().unwrap() == Some(check_out_at);
+            if !warning.is_zero() && now + warning >= check_out_at && !warned {
+                *state.block_end_warned.lock().unwrap() = Some(check_out_at);
+                info!("Scheduled check-out at {}. Warning the user", check_out_at);
+                show_prompt(app_handle, state, prompts::block_end(check_out_at.with_timezone(now.offset()).time()));
+            }
+            continue;
+        }
+        
+        let key = format!("block-end-{}", block.end.format("%H:%M"));
+        if remind_once(state, &key, today) {
+            info!("Scheduled block ended at {}. Automatically checking out", block.end);
+            withdraw_prompt(app_handle, state, prompts::BLOCK_END_PROMPT);
+            let reason = match extended_until {
+                Some(until) if until > block_end => format!("Your work day was extended until {}.", until.with_timezone(now.offset()).format("%H:%M")),
+                _ => format!("Your scheduled block ended at {}.", block.end.format("%H:%M")),
+            };
+            auto_transition(app_handle, state, settings, AttendanceStatus::CheckedOut, &reason).await;
+            return;
+        }
+    }
+}
+
+// Postpone the block-end check-out the user was warned about by an hour,
+// noting the override in the audit log
+fn extend_block_end(app_handle: &AppHandle, state: &AppState) -> Result<(), String> {
+    let check_out_at = state.block_end_warned.lock().unwrap()
+        .ok_or_else(|| "No scheduled check-out to extend".to_string())?;
+    let until = check_out_at + chrono::Duration::hours(1);
+    *state.checkout_extended_until.lock().unwrap() = Some(until);
+    
+    info!("Scheduled check-out extended from {} to {}", check_out_at, until);
+    record_history(app_handle, state, |history| {
+        history.record_audit(Utc::now(), "extend-check-out", &format!("Automatic check-out moved from {} to {}", check_out_at, until));
+    });
+    Ok(())
+}
+
+// Whether the daily limit was reached today and not overridden
+fn daily_limit_blocks_check_in(state: &AppState, today: NaiveDate) -> bool {
+    state.daily_limit.lock().unwrap()
+        .as_ref()
+        .is_some_and(|limit| limit.date == today && !limit.overridden)
+}
+
+// Record a status change in the local history and persist it
+fn record_status_change(app_handle: &AppHandle, state: &AppState, status: &AttendanceStatus) {
+    let now = Utc::now();
+    record_history(app_handle, state, |history| match status {
+        AttendanceStatus::CheckedIn => history.start_session(now),
+        AttendanceStatus::CheckedOut => {
+            history.end_session(now);
+            history.end_break(now);
+        }
+        // Breaks are recorded with their kind by `enter_break`, pauses by `pause_session`
+        AttendanceStatus::OnBreak | AttendanceStatus::Paused => {}
+    });
+}
+
+// Tell the frontend about a status change, with enough detail that it doesn't
+// need to query the state again
+fn emit_attendance_changed(app_handle: &AppHandle, state: &AppState, event_type: &str, cause: ChangeCause) {
+    let now = Utc::now();
+    let session_secs = state.history.lock().unwrap().sessions.last()
+        .map(|session| (session.end.unwrap_or(now) - session.start).num_seconds());
+    
+    let event = AttendanceChanged {
+        status: state.status.lock().unwrap().label(),
+        event_type: event_type.to_string(),
+        cause,
+        timestamp: now,
+        session_secs,
+    };
+    if let Ok(event) = serde_json::to_value(&event) {
+        local_api::broadcast("attendance_changed", event.clone());
+        plugins::fire(app_handle, state, event);
+    }
+    let _ = app_handle.emit("attendance_changed", event);
+    
+    let cause = match cause {
+        ChangeCause::Auto => "auto",
+        ChangeCause::Manual => "manual",
+    };
+    telemetry::Span::start("attendance.transition", false, &[("event.type", event_type), ("cause", cause)]).end(None);
+    match event_type {
+        "check-in" => automation::fire(state, TriggerEvent::CheckIn, cause),
+        "check-out" => automation::fire(state, TriggerEvent::CheckOut, cause),
+        _ => {}
+    }
+    matrix::post_attendance(state, event_type);
+    teams::update_presence(app_handle.clone());
+    time_tracker::follow_status(app_handle.clone());
+    home_assistant::publish_status(app_handle.clone());
+    discord::update_presence(app_handle.clone());
+}
+
+// Apply a change to the local history and persist it
+fn record_history<F: FnOnce(&mut History)>(app_handle: &AppHandle, state: &AppState, update: F) {
+    let history = {
+        let mut history = state.history.lock().unwrap();
+        update(&mut history);
+        history.clone()
+    };
+    
+    if let Err(err) = save_history_to_store(app_handle, &history) {
+        error!("Failed to save history: {}", err);
+    }
+}
+
+// Warn once per day/week when worked time exceeds the overtime thresholds
+fn check_overtime(app_handle: &AppHandle, state: &AppState, settings: &Settings) {
+    if settings.overtime_daily_mins.is_none() && settings.overtime_weekly_mins.is_none() {
+        return;
+    }
+    
+    let warnings = {
+        let history = state.history.lock().unwrap();
+        if history.current_session().is_none() {
+            return;
+        }
+        overtime::check(
+            &history,
+            &settings.schedule_context(),
+            settings.overtime_daily_mins,
+            settings.overtime_weekly_mins,
+            Utc::now(),
+        )
+    };
+    
+    for warning in warnings {
+        {
+            let mut warned = state.overtime_warned.lock().unwrap();
+            let key = (warning.period, warning.period_start);
+            if warned.contains(&key) {
+                continue;
+            }
+            warned.retain(|(period, _)| *period != warning.period);
+            warned.push(key);
+        }
+        
+        info!("Overtime threshold exceeded: {:?}", warning);
+        notify_overtime(app_handle, settings, &warning);
+        let _ = app_handle.emit("overtime_warning", &warning);
+    }
+}
+
+// Show a native notification for an overtime warning
+fn notify_overtime(app_handle: &AppHandle, settings: &Settings, warning: &OvertimeWarning) {
+    let key = match warning.period {
+        OvertimePeriod::Day => "notify.overtime.day",
+        OvertimePeriod::Week => "notify.overtime.week",
+    };
+    let body = tf(key, &[
+        ("hours", &(warning.worked_mins / 60)),
+        ("mins", &(warning.worked_mins % 60)),
+        ("target_hours", &(warning.threshold_mins / 60)),
+        ("target_mins", &(warning.threshold_mins % 60)),
+    ]);
+    
+    notifications::notify(app_handle, settings, NotificationKind::Reminder, &t("notify.overtime.title"), &body);
+}
+
+// Send attendance event to API. Events that cannot be delivered because the
+// API is unreachable are queued and sent once it is back, after anything queued
+// earlier. The user is told about the first failure after a successful
+// request, not about every retry.
+async fn send_to_api<P: Serialize>(app_handle: &AppHandle, event_type: &str, payload: &P, settings: &Settings) -> Result<(), String> {
+    let state: State<'_, Arc<AppState>> = app_handle.state();
+    let payload = serde_json::to_value(payload)
+        .map_err(|e| format!("Failed to serialize payload: {}", e))?;
+    
+    let (result, queued) = {
+        let _sending = state.send_lock.lock().await;
+        let result = match replay_pending_events(app_handle, &state, settings).await {
+            Ok(_) => post_to_api(&state, event_type, &payload, settings).await,
+            Err(err) => Err(err),
+        };
+        
+        let queued = result.is_err() && *state.api_offline.lock().unwrap();
+        if queued {
+            update_pending(app_handle, &state, |pending| {
+                let event = PendingEvent {
+                    event_type: event_type.to_string(),
+                    payload,
+                    queued_at: Utc::now(),
+                };
+                if let Some(dropped) = pending.push(event) {
+                    error!("Offline queue is full, dropped a {} event from {}", dropped.event_type, dropped.queued_at);
+                }
+            });
+        }
+        (result, queued)
+    };
+    
+    let first_failure = {
+        let mut api_failing = state.api_failing.lock().unwrap();
+        let was_failing = std::mem::replace(&mut *api_failing, result.is_err());
+        result.is_err() && !was_failing
+    };
+    match &result {
+        Err(err) if first_failure => {
+            notifications::notify(
+                app_handle,
+                settings,
+                NotificationKind::ApiFailure,
+                &t("notify.api_failure.title"),
+                &tf(
+                    if queued { "notify.api_failure.queued" } else { "notify.api_failure.failed" },
+                    &[("event", &event_type), ("error", err)],
+                ),
+            );
+            automation::fire(&state, TriggerEvent::ApiFailure, err);
+        }
+        _ => {}
+    }
+    
+    if queued {
+        info!("Queued {} event until the API is reachable", event_type);
+        return Ok(());
+    }
+    result
+}
+
+// Resend queued events now, returning how many were sent
+async fn flush_pending_events(app_handle: &AppHandle, state: &AppState) -> Result<usize, String> {
+    let settings = state.settings.lock().unwrap().clone();
+    let _sending = state.send_lock.lock().await;
+    replay_pending_events(app_handle, state, &settings).await
+}
+
+// Send queued events oldest first, stopping while the API is unreachable.
+// Events the API rejects are dropped so they don't hold up the rest.
+// Callers must hold `send_lock`.
+async fn replay_pending_events(app_handle: &AppHandle, state: &AppState, settings: &Settings) -> Result<usize, String> {
+    let mut sent = 0;
+    loop {
+        let next = state.pending.lock().unwrap().front().cloned();
+        let Some(event) = next else {
+            return Ok(sent);
+        };
+        
+        match post_to_api(state, &event.event_type, &event.payload, settings).await {
+            Ok(()) => sent += 1,
+            Err(err) if *state.api_offline.lock().unwrap() => return Err(err),
+            Err(err) => error!("Dropping queued {} event from {}: {}", event.event_type, event.queued_at, err),
+        }
+        update_pending(app_handle, state, |pending| {
+            pending.pop_front();
+        });
+    }
+}
+
+// Retry queued events in the background
+fn start_queue_retry(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let state: State<'_, Arc<AppState>> = app_handle.state();
+        let mut interval = time::interval(Duration::from_secs(QUEUE_RETRY_SECS));
+        
+        loop {
+            interval.tick().await;
+            
+            if state.pending.lock().unwrap().is_empty() {
+                *state.outage_pushed.lock().unwrap() = false;
+                continue;
+            }
+            match flush_pending_events(&app_handle, &state).await {
+                Ok(sent) => info!("Sent {} queued event(s)", sent),
+                Err(err) => {
+                    debug!("Queued events not sent yet: {}", err);
+                    check_api_outage(&state);
+                }
+            }
+        }
+    });
+}
+
+// Push once per outage when queued events have waited too long for the API
+fn check_api_outage(state: &AppState) {
+    let Some(after_mins) = state.settings.lock().unwrap().push.api_unreachable_mins else {
+        return;
+    };
+    let (oldest, count) = {
+        let pending = state.pending.lock().unwrap();
+        (pending.front().map(|event| event.queued_at), pending.len())
+    };
+    let Some(oldest) = oldest else {
+        return;
+    };
+    
+    let waited = Utc::now() - oldest;
+    if waited < chrono::Duration::minutes(after_mins as i64) || std::mem::replace(&mut *state.outage_pushed.lock().unwrap(), true) {
+        return;
+    }
+    let body = tf("push.api_unreachable.body", &[("mins", &waited.num_minutes()), ("count", &count)]);
+    push_critical(state, t("push.api_unreachable.title"), body);
+}
+
+// Push a critical event to the phone in the background, when a push service is set up
+fn push_critical(state: &AppState, title: String, message: String) {
+    let settings = state.settings.lock().unwrap().push.clone();
+    if settings.service.is_none() {
+        return;
+    }
+    
+    let token = secrets::current(state).unwrap_or_default().push_token;
+    tauri::async_runtime::spawn(async move {
+        if let Err(err) = push::send(&settings, token.as_deref(), &title, &message).await {
+            error!("Failed to push '{}': {}", title, err);
+        }
+    });
+}
+
+// Change the offline queue and persist it
+fn update_pending<F: FnOnce(&mut PendingQueue)>(app_handle: &AppHandle, state: &AppState, update: F) {
+    let pending = {
+        let mut pending = state.pending.lock().unwrap();
+        update(&mut pending);
+        pending.clone()
+    };
+    local_api::broadcast("queue", serde_json::json!({ "pending": pending.len() }));
+    
+    if let Err(err) = store::save_json(app_handle, PENDING_FILENAME, "pending", &pending) {
+        error!("Failed to save offline queue: {}", err);
+    }
+}
+
+// Send an event to the configured backend, in a span of its own
+async fn post_to_api(state: &AppState, event_type: &str, payload: &serde_json::Value, settings: &Settings) -> Result<(), String> {
+    let span = telemetry::Span::start("api.send", true, &[("event.type", event_type)]);
+    // Sent without credentials the event would be rejected and dropped, so
+    // it is queued like for an unreachable backend until the keychain answers
+    let secrets = match secrets::current(state) {
+        Ok(secrets) => secrets,
+        Err(err) => {
+            *state.api_offline.lock().unwrap() = true;
+            span.end(Some(&err));
+            return Err(err);
+        }
+    };
+    let result = match settings.backend {
+        BackendKind::Webhook => {
+            let webhook = Webhook {
+                endpoint: &settings.api_endpoint,
+                org_id: settings.org_id.as_deref(),
+                api_token: secrets.api_token.as_deref(),
+                hmac_key: secrets.hmac_key.as_deref(),
+                traceparent: telemetry::enabled().then(|| span.traceparent()),
+            };
+            submit_to_backend(state, &webhook, event_type, payload).await
+        }
+        BackendKind::Erpnext => {
+            let erpnext = ErpNext { settings: &settings.erpnext, api_secret: secrets.erpnext_api_secret.as_deref() };
+            submit_to_backend(state, &erpnext, event_type, payload).await
+        }
+        BackendKind::Odoo => {
+            let odoo = Odoo { settings: &settings.odoo, api_key: secrets.odoo_api_key.as_deref() };
+            submit_to_backend(state, &odoo, event_type, payload).await
+        }
+        BackendKind::Bamboohr => {
+            let bamboohr = BambooHr { settings: &settings.bamboohr, api_key: secrets.bamboohr_api_key.as_deref(), timezone: settings.timezone.as_deref() };
+            submit_to_backend(state, &bamboohr, event_type, payload).await
+        }
+        BackendKind::Personio => {
+            let personio = Personio { settings: &settings.personio, client_secret: secrets.personio_client_secret.as_deref() };
+            submit_to_backend(state, &personio, event_type, payload).await
+        }
+    };
+    span.end(result.as_ref().err());
+    result
+}
+
+// Send one event through a backend adapter. Only an unreachable backend
+// counts as offline, so the event is queued rather than dropped.
+async fn submit_to_backend<B: BackendAdapter + Sync>(state: &AppState, backend: &B, event_type: &str, payload: &serde_json::Value) -> Result<(), String> {
+    info!("Sending {} event to {}", event_type, backend.name());
+    let result = backend::deliver(backend, event_type, payload).await;
+    *state.api_offline.lock().unwrap() = matches!(result, Err(SendError::Unreachable(_)));
+    
+    if result.is_ok() {
+        info!("Successfully sent {} event to {}", event_type, backend.name());
+    }
+    result.map_err(String::from)
+}
+
+// Check that the endpoint answers and accepts the configured credentials,
+// without sending an event
+async fn probe_endpoint(state: &AppState, endpoint: &str, org_id: Option<&str>) -> ConnectionCheck {
+    let secrets = match secrets::current(state) {
+        Ok(secrets) => secrets,
+        Err(err) => return ConnectionCheck { reachable: false, error: Some(err) },
+    };
+    let client = match reqwest::Client::builder().timeout(Duration::from_secs(PROBE_TIMEOUT_SECS)).build() {
+        Ok(client) => client,
+        Err(err) => return ConnectionCheck { reachable: false, error: Some(format!("Failed to create HTTP client: {}", err)) },
+    };
+    let mut request = client.head(endpoint);
+    if let Some(token) = &secrets.api_token {
+        request = request.bearer_auth(token);
+    }
+    if let Some(org_id) = org_id {
+        request = request.header(ORG_ID_HEADER, org_id);
+    }
+    
+    let error = match request.send().await {
+        Err(err) => return ConnectionCheck { reachable: false, error: Some(format!("The API endpoint is not reachable: {}", err)) },
+        Ok(response) => match response.status() {
+            reqwest::StatusCode::UNAUTHORIZED | reqwest::StatusCode::FORBIDDEN => {
+                Some(format!("The API endpoint rejected the credentials ({})", response.status()))
+            }
+            reqwest::StatusCode::NOT_FOUND => Some("The API endpoint was not found (404), check the path".to_string()),
+            _ => None,
+        },
+    };
+    ConnectionCheck { reachable: true, error }
+}
+
+// Load settings at startup. On the first run, when nothing is stored yet,
+// they are seeded from a provisioning file if one is deployed.
+async fn load_or_provision_settings(app_handle: &AppHandle) -> Settings {
+    match read_settings_from_store(app_handle).await {
+        Ok(Some(settings)) => settings,
+        Ok(None) => {
+            let Some((path, values)) = provisioning::load() else {
+                info!("No settings found in store. Using defaults.");
+                return Settings::default();
+            };
+            
+            let settings = Policy::new("provisioning", values).enforce(&Settings::default());
+            info!("Provisioned settings from {}", path.display());
+            if let Err(err) = save_settings_to_store(app_handle, &settings).await {
+                error!("Failed to save provisioned settings: {}", err);
+            }
+            settings
+        }
+        Err(err) => {
+            error!("{}. Using defaults.", err);
+            Settings::default()
+        }
+    }
+}
+
+// Read the stored settings, migrating them when needed. `None` when nothing is stored.
+async fn read_settings_from_store(app_handle: &AppHandle) -> Result<Option<Settings>, String> {
+    let store_path = std::path::PathBuf::from(SETTINGS_FILENAME);
+    
+    // Try to create and load the store
+    let store = StoreBuilder::new(app_handle, store_path)
+        .build()
+        .map_err(|err| format!("Failed to create store: {}", err))?;
+    store.reload().map_err(|err| format!("Failed to load store: {}", err))?;
+    
+    let settings_value = match store.get("encrypted") {
+        Some(envelope) => open_encrypted_settings(app_handle, envelope)
+            .ok_or_else(|| "Settings are encrypted and locked".to_string())?,
+        None => match store.get("settings") {
+            Some(settings_value) => settings_value,
+            None => return Ok(None),
+        },
+    };
+    
+    // Settings saved before versioning have no schema version
+    let version = store.get("schema_version").and_then(|version| version.as_u64()).unwrap_or(0);
+    let (settings, migrated) = read_stored_settings(settings_value, version);
+    info!("Loaded settings from disk");
+    
+    if migrated {
+        info!("Migrated settings from schema {} to {}", version, migrations::SCHEMA_VERSION);
+        if let Err(err) = save_settings_to_store(app_handle, &settings).await {
+            error!("Failed to save migrated settings: {}", err);
+        }
+    }
+    Ok(Some(settings))
+}
+
+// Modification time of settings.json, if it exists
+fn settings_file_modified(app_handle: &AppHandle) -> Option<SystemTime> {
+    let path = app_handle.path().app_data_dir().ok()?.join(SETTINGS_FILENAME);
+    std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+// Watch settings.json for edits made outside the app, e.g. by a deployment
+// script, and apply them without a restart
+fn start_settings_watch(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let state: State<'_, Arc<AppState>> = app_handle.state();
+        *state.settings_modified.lock().unwrap() = settings_file_modified(&app_handle);
+        let mut interval = time::interval(Duration::from_secs(SETTINGS_WATCH_SECS));
+        
+        loop {
+            interval.tick().await;
+            
+            let modified = settings_file_modified(&app_handle);
+            if modified.is_none() || modified == *state.settings_modified.lock().unwrap() {
+                continue;
+            }
+            *state.settings_modified.lock().unwrap() = modified;
+            
+            // Encrypted settings can't be edited by hand, and reading them
+            // again would lock a store unlocked with a passphrase
+            if !matches!(*state.store_lock.lock().unwrap(), StoreLock::Plain) {
+                continue;
+            }
+            reload_settings(&app_handle, &state).await;
+        }
+    });
+}
+
+// Read settings.json again and make it current, with the same precedence as at startup
+async fn reload_settings(app_handle: &AppHandle, state: &AppState) {
+    // A half-written or emptied file keeps the current settings
+    let loaded_settings = match read_settings_from_store(app_handle).await {
+        Ok(Some(settings)) => settings,
+        Ok(None) => return,
+        Err(err) => {
+            error!("Failed to reload settings: {}", err);
+            return;
+        }
+    };
+    let settings = env_overrides::apply(&loaded_settings, std::env::vars());
+    let settings = state.policy.lock().unwrap().enforce(&settings);
+    
+    let (previous, hotkeys_changed) = {
+        let mut settings_lock = state.settings.lock().unwrap();
+        if serde_json::to_value(&*settings_lock).ok() == serde_json::to_value(&settings).ok() {
+            return;
+        }
+        let changed = settings_lock.hotkeys != settings.hotkeys;
+        let previous = std::mem::replace(&mut *settings_lock, settings.clone());
+        (previous, changed)
+    };
+    
+    info!("Reloaded settings changed outside the app");
+    audit_settings_change(app_handle, state, &previous, &settings, ChangeSource::File);
+    i18n::set_locale(settings.locale.as_deref());
+    if hotkeys_changed {
+        hotkeys::register(app_handle, &settings.hotkeys);
+    }
+    tray::refresh(app_handle, state);
+    let _ = app_handle.emit("settings_updated", &settings);
+}
+
+// Migrate stored settings and read them, keeping what still parses
+fn read_stored_settings(mut settings_value: serde_json::Value, version: u64) -> (Settings, bool) {
+    let migrated = migrations::migrate(&mut settings_value, version);
+    let (settings, dropped): (Settings, _) = migrations::read_lenient(&settings_value);
+    if !dropped.is_empty() {
+        error!("Could not read settings {}. Using defaults for them.", dropped.join(", "));
+    }
+    (settings, migrated)
+}
+
+// Decrypt settings stored encrypted with the machine key. Settings encrypted
+// with a passphrase, or that fail to decrypt, stay locked until
+// `unlock_settings_store`.
+fn open_encrypted_settings(app_handle: &AppHandle, envelope: serde_json::Value) -> Option<serde_json::Value> {
+    let state: State<'_, Arc<AppState>> = app_handle.state();
+    let opened = serde_json::from_value::<Envelope>(envelope)
+        .map_err(|err| format!("Encrypted settings are corrupted: {}", err))
+        .and_then(|envelope| match envelope.mode {
+            EncryptionMode::Machine => {
+                let key = StoreKey::for_envelope(&envelope, &store_crypto::machine_secret()?)?;
+                Ok((key.decrypt(&envelope)?, key))
+            }
+            EncryptionMode::Passphrase => Err("A passphrase is needed to open the settings".to_string()),
+        });
+    
+    match opened {
+        Ok((settings_value, key)) => {
+            *state.store_lock.lock().unwrap() = StoreLock::Unlocked(key);
+            Some(settings_value)
+        }
+        Err(reason) => {
+            error!("{}. Using defaults until the settings are unlocked.", reason);
+            *state.store_lock.lock().unwrap() = StoreLock::Locked(reason);
+            None
+        }
+    }
+}
+
+// Helper to load the local history from disk
+fn load_history_from_store(app_handle: &AppHandle) -> History {
+    load_history_file(app_handle, &active_history_filename(app_handle))
+}
+
+// History store file of the active profile
+fn active_history_filename(app_handle: &AppHandle) -> String {
+    let state: State<'_, Arc<AppState>> = app_handle.state();
+    let active = state.profiles.lock().unwrap().active.clone();
+    profiles::history_filename(active.as_deref())
+}
+
+fn load_history_file(app_handle: &AppHandle, filename: &str) -> History {
+    store::load_or_default(app_handle, filename, "history")
+}
+
+// Helper to save the local history to disk
+fn save_history_to_store(app_handle: &AppHandle, history: &History) -> Result<(), String> {
+    save_history_file(app_handle, &active_history_filename(app_handle), history)
+}
+
+fn save_history_file(app_handle: &AppHandle, filename: &str, history: &History) -> Result<(), String> {
+    store::save_json(app_handle, filename, "history", history)
+}
+
+// Helper to load the saved settings profiles from disk
+fn load_profiles_from_store(app_handle: &AppHandle) -> Profiles {
+    let stored = store::load_json::<serde_json::Value>(app_handle, PROFILES_FILENAME, "profiles").unwrap_or_else(|err| {
+        error!("{}. Starting without profiles.", err);
+        None
+    });
+    let Some(mut value) = stored else {
+        return Profiles::default();
+    };
+    
+    // Profiles aren't versioned, so bring each one's settings up to date
+    if let Some(profiles) = value.get_mut("profiles").and_then(|profiles| profiles.as_array_mut()) {
+        for profile in profiles {
+            if let Some(settings) = profile.get_mut("settings") {
+                migrations::upgrade_partial(settings);
+            }
+        }
+    }
+    serde_json::from_value(value).unwrap_or_else(|err| {
+        error!("Failed to parse profiles: {}. Starting without profiles.", err);
+        Profiles::default()
+    })
+}
+
+// Helper to load the autostart choices from disk. `None` when they were never saved.
+fn load_autostart_from_store(app_handle: &AppHandle) -> Option<Autostart> {
+    store::load_json(app_handle, AUTOSTART_FILENAME, "autostart").unwrap_or_else(|err| {
+        error!("{}. Starting without login items.", err);
+        Some(Autostart::default())
+    })
+}
+
+// Helper to load how far an export got, e.g. the last day appended to the
+// Google Sheet, by the export's key
+fn load_export_from_store<T: DeserializeOwned>(app_handle: &AppHandle, key: &str) -> Option<T> {
+    store::load_json(app_handle, EXPORTS_FILENAME, key).unwrap_or_else(|err| {
+        error!("{}", err);
+        None
+    })
+}
+
+// Helper to save how far an export got
+fn save_export_to_store<T: Serialize>(app_handle: &AppHandle, key: &str, through: T) -> Result<(), String> {
+    store::save_json(app_handle, EXPORTS_FILENAME, key, &through)
+}
+
+// Helper to load this install's device ID, creating and saving one on the
+// first run. It stays the same when the hostname changes.
+fn load_device_id(app_handle: &AppHandle) -> String {
+    let stored = store::load_json(app_handle, DEVICE_FILENAME, "device_id").unwrap_or_else(|err| {
+        error!("{}", err);
+        None
+    });
+    if let Some(device_id) = stored {
+        return device_id;
+    }
+    
+    let device_id = uuid::Uuid::new_v4().to_string();
+    info!("Created device ID {}", device_id);
+    if let Err(err) = store::save_json(app_handle, DEVICE_FILENAME, "device_id", &device_id) {
+        error!("{}. The device ID changes on the next start.", err);
+    }
+    device_id
+}
+
+// Refuse settings changes until the PIN was entered, when one is set
+fn require_settings_unlocked(state: &AppState) -> Result<(), String> {
+    if state.settings_pin.lock().unwrap().is_some() && !*state.settings_unlocked.lock().unwrap() {
+        return Err(t("error.settings_locked"));
+    }
+    Ok(())
+}
+
+// Refuse changes made by the user while the settings are locked. Changes
+// the app, the policy or the signed bundle make go through.
+fn require_change_allowed(state: &AppState, source: ChangeSource) -> Result<(), String> {
+    if source == ChangeSource::Ui {
+        require_settings_unlocked(state)?;
+    }
+    Ok(())
+}
+
+// Change the saved profiles in memory. Profiles hold whole settings and
+// become the current ones when switched to, so they are locked like them.
+fn edit_profiles<F: FnOnce(&mut Profiles) -> Result<(), String>>(state: &AppState, update: F) -> Result<Profiles, String> {
+    require_settings_unlocked(state)?;
+    let mut profiles = state.profiles.lock().unwrap();
+    let mut updated = profiles.clone();
+    update(&mut updated)?;
+    *profiles = updated.clone();
+    Ok(updated)
+}
+
+// Change the saved profiles and persist them
+fn update_profiles<F: FnOnce(&mut Profiles) -> Result<(), String>>(app_handle: &AppHandle, state: &AppState, update: F) -> Result<Profiles, String> {
+    let profiles = edit_profiles(state, update)?;
+    store::save_json(app_handle, PROFILES_FILENAME, "profiles", &profiles)?;
+    Ok(profiles)
+}
+
+// Helper to save settings to disk
+async fn save_settings_to_store(app_handle: &AppHandle, settings: &Settings) -> Result<(), String> {
+    let store_path = std::path::PathBuf::from(SETTINGS_FILENAME);
+    
+    // Try to create and load the store
+    let store = match StoreBuilder::new(app_handle, store_path).build() {
+        Ok(store) => store,
+        Err(err) => return Err(format!("Failed to create store: {}", err)),
+    };
+    
+    // Load existing data if possible (not crucial if it fails for a new store)
+    let _ = store.reload();
+    
+    // Insert settings, encrypted when store encryption is on
+    let state: State<'_, Arc<AppState>> = app_handle.state();
+    let store_lock = state.store_lock.lock().unwrap().clone();
+    let settings_value = serde_json::to_value(settings).unwrap();
+    match store_lock {
+        StoreLock::Plain => {
+            store.set("settings".to_string(), settings_value);
+            store.delete("encrypted");
+        }
+        StoreLock::Unlocked(key) => {
+            let envelope = key.encrypt(&settings_value)?;
+            store.set("encrypted".to_string(), serde_json::to_value(envelope).unwrap());
+            store.delete("settings");
+        }
+        StoreLock::Locked(reason) => return Err(format!("Settings are encrypted and locked: {}", reason)),
+    }
+    store.set("schema_version".to_string(), migrations::SCHEMA_VERSION);
+    
+    // Save the store
+    if let Err(err) = store.save() {
+        return Err(format!("Failed to save store: {}", err));
+    }
+    *state.settings_modified.lock().unwrap() = settings_file_modified(app_handle);
+    
+    info!("Saved settings to disk");
+    Ok(())
+}
+
+// Send attendance event
+#[tauri::command]
+async fn send_attendance_event(event_type: String, app_handle: AppHandle, state: State<'_, Arc<AppState>>) -> Result<(), String> {
+    set_attendance(&app_handle, &state, &event_type).await
+}
+
+// Apply a manual check-in/check-out, shared by commands and prompt actions
+async fn set_attendance(app_handle: &AppHandle, state: &AppState, event_type: &str) -> Result<(), String> {
+    set_attendance_with_note(app_handle, state, event_type, None).await
+}
+
+// Apply a manual check-in/check-out with an optional note for the API
+async fn set_attendance_with_note(app_handle: &AppHandle, state: &AppState, event_type: &str, note: Option<String>) -> Result<(), String> {
+    // Get settings
+    let settings = {
+        state.settings.lock().unwrap().clone()
+    };
+    
+    // Update status in state
+    let new_status = {
+        let mut status = state.status.lock().unwrap();
+        *status = if event_type == "check-in" {
+            // If checking in manually, reset the manual checkout flag
+            let mut manual_checkout = state.manual_checkout.lock().unwrap();
+            *manual_checkout = false;
+            *state.manual_checkout_date.lock().unwrap() = None;
+            
+            // A manual check-in overrides the daily hour limit for the rest of the day
+            if let Some(limit) = state.daily_limit.lock().unwrap().as_mut() {
+                limit.overridden = true;
+            }
+            AttendanceStatus::CheckedIn
+        } else {
+            mark_manual_checkout(state, clock::today(settings.timezone.as_deref()));
+            AttendanceStatus::CheckedOut
+        };
+        status.clone()
+    };
+    record_status_change(app_handle, state, &new_status);
+    
+    // Create payload and send to API
+    let mut payload = create_attendance_payload(event_type, &state.device_id.lock().unwrap(), &settings);
+    payload.payload.note = note;
+    send_to_api(app_handle, event_type, &payload, &settings).await?;
+    
+    // Notify the frontend
+    emit_attendance_changed(app_handle, state, event_type, ChangeCause::Manual);
+    
+    Ok(())
+}
+
+// Refuse a debugging command unless developer mode is on
+fn require_developer_mode(state: &AppState, command: &str) -> Result<(), String> {
+    developer::require(state.settings.lock().unwrap().developer_mode, command)
+}
+
+// Developer command: make the idle monitor see `secs` of idle time, growing
+// from now on, until called again without `secs`
+#[tauri::command]
+fn simulate_idle(secs: Option<u64>, state: State<'_, Arc<AppState>>) -> Result<(), String> {
+    require_developer_mode(&state, "simulate_idle")?;
+    let since = secs
+        .map(|secs| Instant::now().checked_sub(Duration::from_secs(secs)).ok_or("Idle time is too long to simulate"))
+        .transpose()?;
+    *state.simulated_idle_since.lock().unwrap() = since;
+    
+    info!("Simulated idle time: {:?}", secs);
+    Ok(())
+}
+
+// Developer command: the in-memory state as JSON. Secrets are left out.
+#[tauri::command]
+fn dump_state(state: State<'_, Arc<AppState>>) -> Result<serde_json::Value, String> {
+    require_developer_mode(&state, "dump_state")?;
+    
+    Ok(serde_json::json!({
+        "status": *state.status.lock().unwrap(),
+        "settings": *state.settings.lock().unwrap(),
+        "history": *state.history.lock().unwrap(),
+        "pending": *state.pending.lock().unwrap(),
+        "profiles": *state.profiles.lock().unwrap(),
+        "active_prompts": *state.active_prompts.lock().unwrap(),
+        "manual_checkout": *state.manual_checkout.lock().unwrap(),
+        "auto_break": *state.auto_break.lock().unwrap(),
+        "api_offline": *state.api_offline.lock().unwrap(),
+        "api_failing": *state.api_failing.lock().unwrap(),
+        "do_not_disturb_until": *state.do_not_disturb_until.lock().unwrap(),
+        "checkout_countdown": *state.checkout_countdown.lock().unwrap(),
+        "checkout_extended_until": *state.checkout_extended_until.lock().unwrap(),
+        "device_id": *state.device_id.lock().unwrap(),
+        "locale": i18n::current(),
+        "simulated_idle_secs": state.simulated_idle_since.lock().unwrap().map(|since| since.elapsed().as_secs()),
+    }))
+}
+
+// Developer command: post an event of any type with the current settings,
+// leaving the status and history alone
+#[tauri::command]
+async fn force_send(event_type: String, app_handle: AppHandle, state: State<'_, Arc<AppState>>) -> Result<(), String> {
+    require_developer_mode(&state, "force_send")?;
+    let settings = state.settings.lock().unwrap().clone();
+    let payload = create_attendance_payload(&event_type, &state.device_id.lock().unwrap(), &settings);
+    
+    info!("Force sending a {} event", event_type);
+    send_to_api(&app_handle, &event_type, &payload, &settings).await
+}
+
+// Get prompts that are waiting for an answer
+#[tauri::command]
+fn get_active_prompts(state: State<'_, Arc<AppState>>) -> Vec<Prompt> {
+    state.active_prompts.lock().unwrap().clone()
+}
+
+// Handle the action the user chose for a prompt
+#[tauri::command]
+async fn handle_prompt_action(prompt_id: String, action_id: String, app_handle: AppHandle, state: State<'_, Arc<AppState>>) -> Result<(), String> {
+    let prompt = take_prompt(&state, &prompt_id, &action_id)?;
+    
+    info!("Prompt '{}' answered with '{}'", prompt.id, action_id);
+    
+    match action_id.as_str() {
+        prompts::ACTION_CHECK_IN => set_attendance(&app_handle, &state, "check-in").await,
+        prompts::ACTION_CHECK_OUT => set_attendance(&app_handle, &state, "check-out").await,
+        prompts::ACTION_SNOOZE => {
+            let snooze_mins = state.settings.lock().unwrap().break_reminder_snooze_mins;
+            let until = Utc::now() + chrono::Duration::minutes(snooze_mins as i64);
+            state.break_reminder.lock().unwrap().snooze(until);
+            Ok(())
+        }
+        prompts::ACTION_STILL_HERE | prompts::ACTION_CONFIRM_CHECK_OUT => {
+            let settings = state.settings.lock().unwrap().clone();
+            if answer_checkout_warning(&state, &action_id, clock::today(settings.timezone.as_deref())) {
+                auto_transition(&app_handle, &state, &settings, AttendanceStatus::CheckedOut, "Checked out from the inactivity warning.").await;
+            }
+            Ok(())
+        }
+        prompts::ACTION_CHECK_OUT_AND_QUIT => {
+            check_out_and_quit(&app_handle, &state).await;
+            Ok(())
+        }
+        prompts::ACTION_QUIT => {
+            app_handle.exit(0);
+            Ok(())
+        }
+        prompts::ACTION_EXTEND => extend_block_end(&app_handle, &state),
+        prompts::ACTION_DISMISS => Ok(()),
+        _ => Err(format!("Unknown prompt action '{}'", action_id)),
+    }
+}
+
+// Remove an active prompt being answered with `action_id`
+fn take_prompt(state: &AppState, prompt_id: &str, action_id: &str) -> Result<Prompt, String> {
+    let mut active_prompts = state.active_prompts.lock().unwrap();
+    let index = active_prompts.iter()
+        .position(|p| p.id == prompt_id)
+        .ok_or_else(|| format!("No active prompt '{}'", prompt_id))?;
+    
+    if !active_prompts[index].has_action(action_id) {
+        return Err(format!("Prompt '{}' has no action '{}'", prompt_id, action_id));
+    }
+    Ok(active_prompts.remove(index))
+}
+
+// Take the answer to the inactivity warning. Returns whether to check out now.
+fn answer_checkout_warning(state: &AppState, action_id: &str, today: NaiveDate) -> bool {
+    match action_id {
+        // Counts as activity, so the pending idle check-out doesn't happen
+        prompts::ACTION_STILL_HERE => {
+            *state.still_here_at.lock().unwrap() = Some(Instant::now());
+            false
+        }
+        prompts::ACTION_CONFIRM_CHECK_OUT if *state.status.lock().unwrap() == AttendanceStatus::CheckedIn => {
+            // Clicking the warning is input too, which would check straight
+            // back in on the next tick unless this counts as a manual check-out
+            mark_manual_checkout(state, today);
+            true
+        }
+        _ => false,
+    }
+}
+
+// Get current attendance status
+#[tauri::command]
+fn get_attendance_status(state: State<'_, Arc<AppState>>) -> String {
+    state.status.lock().unwrap().label().to_string()
+}
+
+// Start a break of the given kind while checked in
+#[tauri::command]
+async fn start_break(kind: String, app_handle: AppHandle, state: State<'_, Arc<AppState>>) -> Result<(), String> {
+    take_break(&app_handle, &state, &kind).await
+}
+
+// Start a break on the user's request, shared by commands and the tray menu
+async fn take_break(app_handle: &AppHandle, state: &AppState, kind: &str) -> Result<(), String> {
+    let settings = state.settings.lock().unwrap().clone();
+    if breaks::find_break_type(&settings.break_types, kind).is_none() {
+        return Err(format!("Unknown break type '{}'", kind));
+    }
+    if *state.status.lock().unwrap() != AttendanceStatus::CheckedIn {
+        return Err("A break can only be started while checked in".to_string());
+    }
+    
+    enter_break(app_handle, state, &settings, kind, Utc::now(), false).await
+}
+
+// End the running break and resume the session
+#[tauri::command]
+async fn end_break(app_handle: AppHandle, state: State<'_, Arc<AppState>>) -> Result<(), String> {
+    return_from_break(&app_handle, &state).await
+}
+
+// End a break on the user's request, shared by commands and the tray menu
+async fn return_from_break(app_handle: &AppHandle, state: &AppState) -> Result<(), String> {
+    if *state.status.lock().unwrap() != AttendanceStatus::OnBreak {
+        return Err("Not on a break".to_string());
+    }
+    
+    let settings = state.settings.lock().unwrap().clone();
+    leave_break(app_handle, state, &settings).await
+}
+
+// Pause the running session for a short interruption. Worked time stops
+// counting locally but nothing is sent to the API.
+#[tauri::command]
+fn pause_session(app_handle: AppHandle, state: State<'_, Arc<AppState>>) -> Result<(), String> {
+    {
+        let mut status = state.status.lock().unwrap();
+        if *status != AttendanceStatus::CheckedIn {
+            return Err("Only a running session can be paused".to_string());
+        }
+        *status = AttendanceStatus::Paused;
+    }
+    
+    record_history(&app_handle, &state, |history| history.start_break(breaks::PAUSE_BREAK, Utc::now()));
+    
+    emit_attendance_changed(&app_handle, &state, AttendanceStatus::Paused.event_type(), ChangeCause::Manual);
+    Ok(())
+}
+
+// Resume a paused session
+#[tauri::command]
+fn resume_session(app_handle: AppHandle, state: State<'_, Arc<AppState>>) -> Result<(), String> {
+    {
+        let mut status = state.status.lock().unwrap();
+        if *status != AttendanceStatus::Paused {
+            return Err("The session is not paused".to_string());
+        }
+        *status = AttendanceStatus::CheckedIn;
+    }
+    
+    record_history(&app_handle, &state, |history| history.start_session(Utc::now()));
+    
+    emit_attendance_changed(&app_handle, &state, "resume", ChangeCause::Manual);
+    Ok(())
+}
+
+// Get worked time per scheduled block for a day (YYYY-MM-DD, default today)
+#[tauri::command]
+fn get_block_report(date: Option<String>, state: State<'_, Arc<AppState>>) -> Result<Vec<BlockSummary>, String> {
+    let settings = state.settings.lock().unwrap().clone();
+    let date = match date {
+        Some(date) => parse_date(&date)?,
+        None => clock::today(settings.timezone.as_deref()),
+    };
+    
+    let history = state.history.lock().unwrap();
+    Ok(summary::block_report(&history, settings.blocks_on(date), settings.timezone.as_deref(), date, Utc::now()))
+}
+
+// Get today's worked time, breaks and remaining break allowance
+#[tauri::command]
+fn get_today_summary(state: State<'_, Arc<AppState>>) -> TodaySummary {
+    let settings = state.settings.lock().unwrap().clone();
+    let timezone = settings.timezone.as_deref();
+    
+    let history = state.history.lock().unwrap();
+    summary::today_summary(&history, timezone, &settings.holidays, settings.break_budget_mins, clock::today(timezone), Utc::now())
+}
+
+// Get worked time and break statistics for the day or week containing a date (YYYY-MM-DD, default today)
+#[tauri::command]
+fn get_summary(period: OvertimePeriod, date: Option<String>, state: State<'_, Arc<AppState>>) -> Result<PeriodSummary, String> {
+    let settings = state.settings.lock().unwrap().clone();
+    let date = match date {
+        Some(date) => parse_date(&date)?,
+        None => clock::today(settings.timezone.as_deref()),
+    };
+    
+    let history = state.history.lock().unwrap();
+    Ok(summary::period_summary(&history, settings.timezone.as_deref(), &settings.holidays, period, date, Utc::now()))
+}
+
+// Get status, today's totals, queue and API state and the next automatic action in one call
+#[tauri::command]
+fn get_dashboard(state: State<'_, Arc<AppState>>) -> Dashboard {
+    let settings = state.settings.lock().unwrap().clone();
+    let timezone = settings.timezone.as_deref();
+    let now = Utc::now();
+    
+    let (session_start, today) = {
+        let history = state.history.lock().unwrap();
+        let session_start = history.current_session().map(|session| session.start);
+        (session_start, summary::today_summary(&history, timezone, &settings.holidays, settings.break_budget_mins, clock::today(timezone), now))
+    };
+    let api_health = match (*state.api_offline.lock().unwrap(), *state.api_failing.lock().unwrap()) {
+        (true, _) => ApiHealth::Offline,
+        (false, true) => ApiHealth::Failing,
+        (false, false) => ApiHealth::Ok,
+    };
+    
+    Dashboard {
+        status: state.status.lock().unwrap().label(),
+        session_start,
+        today,
+        queued_events: state.pending.lock().unwrap().len(),
+        api_health,
+        next_transition: settings.schedule_context().upcoming(clock::now(timezone), 1).into_iter().next(),
+    }
+}
+
+// Get the short status text for menu-bar titles and widgets
+#[tauri::command]
+fn get_status_line(state: State<'_, Arc<AppState>>) -> String {
+    tray::current_status_line(&state)
+}
+
+// Get the next automatic actions the app will take
+#[tauri::command]
+fn get_upcoming_transitions(count: usize, state: State<'_, Arc<AppState>>) -> Vec<Transition> {
+    let settings = state.settings.lock().unwrap().clone();
+    settings.schedule_context().upcoming(clock::now(settings.timezone.as_deref()), count)
+}
+
+// Get the one-off per-day schedule overrides
+#[tauri::command]
+fn get_day_overrides(state: State<'_, Arc<AppState>>) -> Vec<DayOverride> {
+    state.settings.lock().unwrap().day_overrides.clone()
+}
+
+// Override the working hours of a single date (YYYY-MM-DD). No blocks marks the day off.
+#[tauri::command]
+async fn set_day_override(date: String, blocks: Option<Vec<WorkBlock>>, app_handle: AppHandle, state: State<'_, Arc<AppState>>) -> Result<Vec<DayOverride>, String> {
+    let date = parse_date(&date)?;
+    let blocks = blocks.unwrap_or_default();
+    
+    if let Some(block) = blocks.iter().find(|block| block.end <= block.start) {
+        return Err(format!("Block {}–{} ends before it starts", block.start.format("%H:%M"), block.end.format("%H:%M")));
+    }
+    
+    let settings = update_settings(&app_handle, &state, ChangeSource::Ui, |settings| {
+        // Overrides for past days are no longer needed
+        let today = clock::today(settings.timezone.as_deref());
+        settings.day_overrides.retain(|o| o.date != date && o.date >= today);
+        settings.day_overrides.push(DayOverride { date, blocks });
+        settings.day_overrides.sort_by_key(|o| o.date);
+    }).await?;
+    
+    let _ = app_handle.emit("schedule_changed", settings.active_template.clone());
+    Ok(settings.day_overrides)
+}
+
+// Remove the override for a date, restoring the recurring schedule
+#[tauri::command]
+async fn clear_day_override(date: String, app_handle: AppHandle, state: State<'_, Arc<AppState>>) -> Result<Vec<DayOverride>, String> {
+    let date = parse_date(&date)?;
+    
+    let settings = update_settings(&app_handle, &state, ChangeSource::Ui, |settings| {
+        settings.day_overrides.retain(|o| o.date != date);
+    }).await?;
+    
+    let _ = app_handle.emit("schedule_changed", settings.active_template.clone());
+    Ok(settings.day_overrides)
+}
+
+// Get micro-break compliance for a day (YYYY-MM-DD, default today)
+#[tauri::command]
+fn get_micro_break_stats(date: Option<String>, state: State<'_, Arc<AppState>>) -> Result<MicroBreakStats, String> {
+    let settings = state.settings.lock().unwrap().clone();
+    let date = match date {
+        Some(date) => parse_date(&date)?,
+        None => clock::today(settings.timezone.as_deref()),
+    };
+    
+    let (from, to) = clock::day_bounds(settings.timezone.as_deref(), date);
+    let history = state.history.lock().unwrap();
+    Ok(breaks::micro_break_stats(&history.micro_breaks, from, to))
+}
+
+// Hold back reminders for the given number of minutes, or lift do-not-disturb with `None`
+#[tauri::command]
+fn set_do_not_disturb(mins: Option<u64>, app_handle: AppHandle, state: State<'_, Arc<AppState>>) -> Option<DateTime<Utc>> {
+    let until = mins.map(|mins| Utc::now() + chrono::Duration::minutes(mins as i64));
+    *state.do_not_disturb_until.lock().unwrap() = until;
+    
+    let _ = app_handle.emit("do_not_disturb_changed", until);
+    until
+}
+
+// Get the configured break types
+#[tauri::command]
+fn get_break_types(state: State<'_, Arc<AppState>>) -> Vec<BreakType> {
+    state.settings.lock().unwrap().break_types.clone()
+}
+
+// Create or replace a break type
+#[tauri::command]
+async fn save_break_type(break_type: BreakType, app_handle: AppHandle, state: State<'_, Arc<AppState>>) -> Result<Vec<BreakType>, String> {
+    if break_type.name.trim().is_empty() {
+        return Err("Break type name must not be empty".to_string());
+    }
+    
+    let settings = update_settings(&app_handle, &state, ChangeSource::Ui, |settings| {
+        match settings.break_types.iter_mut().find(|t| t.name == break_type.name) {
+            Some(existing) => *existing = break_type,
+            None => settings.break_types.push(break_type),
+        }
+    }).await?;
+    
+    Ok(settings.break_types)
+}
+
+// Delete a break type. Breaks already recorded keep their type.
+#[tauri::command]
+async fn delete_break_type(name: String, app_handle: AppHandle, state: State<'_, Arc<AppState>>) -> Result<Vec<BreakType>, String> {
+    let settings = update_settings(&app_handle, &state, ChangeSource::Ui, |settings| {
+        settings.break_types.retain(|t| t.name != name);
+    }).await?;
+    
+    Ok(settings.break_types)
+}
+
+// Get break count and length per type for a day (YYYY-MM-DD, default today)
+#[tauri::command]
+fn get_break_report(date: Option<String>, state: State<'_, Arc<AppState>>) -> Result<Vec<BreakTypeSummary>, String> {
+    let settings = state.settings.lock().unwrap().clone();
+    let date = match date {
+        Some(date) => parse_date(&date)?,
+        None => clock::today(settings.timezone.as_deref()),
+    };
+    
+    let (from, to) = clock::day_bounds(settings.timezone.as_deref(), date);
+    let history = state.history.lock().unwrap();
+    Ok(breaks::totals_by_type(&history.breaks, from, to, Utc::now()))
+}
+
+// Get the schedule templates
+#[tauri::command]
+fn get_schedule_templates(state: State<'_, Arc<AppState>>) -> Vec<ScheduleTemplate> {
+    state.settings.lock().unwrap().schedule_templates.clone()
+}
+
+// Create or replace a schedule template
+#[tauri::command]
+async fn save_schedule_template(template: ScheduleTemplate, app_handle: AppHandle, state: State<'_, Arc<AppState>>) -> Result<Vec<ScheduleTemplate>, String> {
+    if template.name.trim().is_empty() {
+        return Err("Template name must not be empty".to_string());
+    }
+    
+    let settings = update_settings(&app_handle, &state, ChangeSource::Ui, |settings| {
+        match settings.schedule_templates.iter_mut().find(|t| t.name == template.name) {
+            Some(existing) => *existing = template,
+            None => settings.schedule_templates.push(template),
+        }
+    }).await?;
+    
+    let _ = app_handle.emit("schedule_changed", settings.active_template.clone());
+    Ok(settings.schedule_templates)
+}
+
+// Delete a schedule template. Deleting the active template falls back to the custom schedule.
+#[tauri::command]
+async fn delete_schedule_template(name: String, app_handle: AppHandle, state: State<'_, Arc<AppState>>) -> Result<Vec<ScheduleTemplate>, String> {
+    let settings = update_settings(&app_handle, &state, ChangeSource::Ui, |settings| {
+        settings.schedule_templates.retain(|t| t.name != name);
+        if settings.active_template.as_deref() == Some(name.as_str()) {
+            settings.active_template = None;
+        }
+    }).await?;
+    
+    let _ = app_handle.emit("schedule_changed", settings.active_template.clone());
+    Ok(settings.schedule_templates)
+}
+
+// Switch the active schedule template, or back to the custom schedule with `None`
+#[tauri::command]
+async fn set_active_template(name: Option<String>, app_handle: AppHandle, state: State<'_, Arc<AppState>>) -> Result<(), String> {
+    if let Some(name) = &name {
+        let settings = state.settings.lock().unwrap().clone();
+        if schedule::find_template(&settings.schedule_templates, name).is_none() {
+            return Err(format!("Unknown schedule template '{}'", name));
+        }
+    }
+    
+    let active_template = name.clone();
+    update_settings(&app_handle, &state, ChangeSource::Ui, |settings| settings.active_template = active_template).await?;
+    
+    info!("Active schedule template: {}", name.as_deref().unwrap_or("custom"));
+    let _ = app_handle.emit("schedule_changed", name);
+    
+    Ok(())
+}
+
+// Get the minimum-break reminders and whether each break was taken
+#[tauri::command]
+fn get_break_compliance(state: State<'_, Arc<AppState>>) -> Vec<BreakReminder> {
+    state.history.lock().unwrap().break_reminders.clone()
+}
+
+// Get app configuration
+#[tauri::command]
+fn get_app_config(state: State<'_, Arc<AppState>>) -> AppConfig {
+    AppConfig {
+        settings: state.settings.lock().unwrap().clone(),
+        locked_fields: state.policy.lock().unwrap().locked_fields(),
+    }
+}
+
+// Get app version
+#[tauri::command]
+fn get_app_version() -> String {
+    env!("CARGO_PKG_VERSION").to_string()
+}
+
+// Open settings window
+#[tauri::command]
+fn open_settings() -> Result<(), String> {
+    Ok(())
+}
+
+// The current settings with the fields sent by the settings screen put on
+// top. The screen only sends the fields it shows, the others keep their
+// current values instead of falling back to their defaults.
+fn merge_settings(current: &Settings, changes: serde_json::Value) -> Result<Settings, String> {
+    let mut merged = serde_json::to_value(current).map_err(|err| format!("Failed to serialize settings: {}", err))?;
+    let (Some(fields), serde_json::Value::Object(changes)) = (merged.as_object_mut(), changes) else {
+        return Err("Settings must be an object".to_string());
+    };
+    fields.extend(changes);
+    serde_json::from_value(merged).map_err(|err| format!("Invalid settings: {}", err))
+}
+
+// Check settings without saving them, so the settings screen can highlight problems
+#[tauri::command]
+fn validate_settings(settings: serde_json::Value, state: State<'_, Arc<AppState>>) -> Result<Vec<FieldError>, String> {
+    let settings = merge_settings(&state.settings.lock().unwrap(), settings)?;
+    Ok(validation::validate(&settings))
+}
+
+// Save settings, refusing ones that don't pass validation. A changed
+// endpoint is probed afterwards, returning a warning if it doesn't answer.
+#[tauri::command]
+async fn save_settings(settings: serde_json::Value, app_handle: AppHandle, state: State<'_, Arc<AppState>>) -> Result<Option<String>, String> {
+    let settings = merge_settings(&state.settings.lock().unwrap(), settings)?;
+    let errors = validation::validate(&settings);
+    if !errors.is_empty() {
+        return Err(format!("Invalid settings: {}", validation::summary(&errors)));
+    }
+    
+    let endpoint_changed = state.settings.lock().unwrap().api_endpoint != settings.api_endpoint;
+    apply_settings(&app_handle, &state, ChangeSource::Ui, settings).await?;
+    
+    let settings = state.settings.lock().unwrap().clone();
+    if !endpoint_changed || !settings.probe_endpoint_on_save {
+        return Ok(None);
+    }
+    let check = probe_endpoint(&state, settings.api_endpoint.trim(), settings.org_id.as_deref()).await;
+    Ok(check.error.map(|error| format!("Settings saved, but the endpoint check failed: {}", error)))
+}
+
+// Validate settings, compare them with the current ones and test the
+// connection, without saving anything
+#[tauri::command]
+async fn preview_settings(settings: serde_json::Value, state: State<'_, Arc<AppState>>) -> Result<SettingsPreview, String> {
+    let current = state.settings.lock().unwrap().clone();
+    // Saving applies the policy, so the preview does too
+    let settings = state.policy.lock().unwrap().enforce(&merge_settings(&current, settings)?);
+    
+    let errors = validation::validate(&settings);
+    let changes = settings_audit::changes(
+        &serde_json::to_value(&current).map_err(|err| format!("Failed to serialize settings: {}", err))?,
+        &serde_json::to_value(&settings).map_err(|err| format!("Failed to serialize settings: {}", err))?,
+    );
+    let restarts = preview::restarts(&changes);
+    
+    let connection = if errors.iter().any(|error| error.field == "api_endpoint") {
+        None
+    } else {
+        Some(probe_endpoint(&state, settings.api_endpoint.trim(), settings.org_id.as_deref()).await)
+    };
+    
+    Ok(SettingsPreview { errors, changes, restarts, connection })
+}
+
+// Restore the default settings for one part of the settings and save them
+#[tauri::command]
+async fn reset_settings(scope: ResetScope, app_handle: AppHandle, state: State<'_, Arc<AppState>>) -> Result<Settings, String> {
+    let mut settings = state.settings.lock().unwrap().clone();
+    settings.reset(scope);
+    
+    apply_settings(&app_handle, &state, ChangeSource::Ui, settings).await?;
+    info!("Reset {:?} settings to defaults", scope);
+    
+    let settings = state.settings.lock().unwrap().clone();
+    let _ = app_handle.emit("settings_updated", &settings);
+    Ok(settings)
+}
+
+// Experimental features and whether they are on. Flags come from the
+// settings, so the remote settings bundle or the policy can turn them on.
+#[tauri::command]
+fn get_feature_flags(state: State<'_, Arc<AppState>>) -> Vec<FeatureFlag> {
+    features::flags(&state.settings.lock().unwrap().feature_flags)
+}
+
+// JSON Schema of the settings with the fields the managed policy locks, for
+// building the settings screen
+#[tauri::command]
+fn get_settings_schema(state: State<'_, Arc<AppState>>) -> serde_json::Value {
+    settings_schema::generate(&state.policy.lock().unwrap().locked_fields())
+}
+
+// Whether Teams presence is enabled and signed in
+#[tauri::command]
+fn get_teams_status(state: State<'_, Arc<AppState>>) -> TeamsStatus {
+    teams::status(&state)
+}
+
+// Start the Microsoft sign-in for Teams presence, returning the code to enter
+#[tauri::command]
+async fn teams_sign_in(app_handle: AppHandle, state: State<'_, Arc<AppState>>) -> Result<SignInPrompt, String> {
+    teams::sign_in(&app_handle, &state).await
+}
+
+// Forget the Microsoft sign-in
+#[tauri::command]
+fn teams_sign_out() -> Result<(), String> {
+    teams::sign_out()
+}
+
+// Whether Google Calendar is enabled and signed in
+#[tauri::command]
+fn get_google_calendar_status(state: State<'_, Arc<AppState>>) -> GoogleCalendarStatus {
+    google_calendar::status(state.settings.lock().unwrap().google_calendar.enabled)
+}
+
+// Sign in to Google Calendar in the browser, then load today's meetings
+#[tauri::command]
+async fn google_calendar_sign_in(app_handle: AppHandle, state: State<'_, Arc<AppState>>) -> Result<(), String> {
+    let settings = state.settings.lock().unwrap().google_calendar.clone();
+    google_calendar::sign_in(&app_handle, &settings).await?;
+    sync_meetings(&state).await
+}
+
+// Forget the Google sign-in and its meetings
+#[tauri::command]
+fn google_calendar_sign_out(state: State<'_, Arc<AppState>>) -> Result<(), String> {
+    google_calendar::sign_out()?;
+    meetings::replace(&mut state.meetings.lock().unwrap(), google_calendar::SOURCE, Vec::new());
+    Ok(())
+}
+
+// Whether the Outlook calendar is enabled and signed in
+#[tauri::command]
+fn get_outlook_calendar_status(state: State<'_, Arc<AppState>>) -> OutlookCalendarStatus {
+    outlook_calendar::status(state.settings.lock().unwrap().outlook_calendar.enabled)
+}
+
+// Start signing in to the Outlook calendar, returns the code to enter
+#[tauri::command]
+async fn outlook_calendar_sign_in(app_handle: AppHandle, state: State<'_, Arc<AppState>>) -> Result<SignInPrompt, String> {
+    let settings = state.settings.lock().unwrap().outlook_calendar.clone();
+    outlook_calendar::sign_in(&app_handle, &settings).await
+}
+
+// Forget the Outlook sign-in and its meetings
+#[tauri::command]
+fn outlook_calendar_sign_out(state: State<'_, Arc<AppState>>) -> Result<(), String> {
+    outlook_calendar::sign_out()?;
+    meetings::replace(&mut state.meetings.lock().unwrap(), outlook_calendar::SOURCE, Vec::new());
+    Ok(())
+}
+
+// The conferencing app in a call, looked for at most every `CALL_CHECK_SECS`
+fn current_call(state: &AppState) -> Option<&'static str> {
+    let mut call = state.call.lock().unwrap();
+    match *call {
+        Some((checked_at, app)) if checked_at.elapsed() < Duration::from_secs(CALL_CHECK_SECS) => app,
+        _ => {
+            let app = calls::current();
+            let was_in_call = call.is_some_and(|(_, previous)| previous.is_some());
+            match app {
+                Some(app) if !was_in_call => info!("Call detected in {}", app),
+                None if was_in_call => info!("Call ended"),
+                _ => {}
+            }
+            *call = Some((Instant::now(), app));
+            app
+        }
+    }
+}
+
+// Whether the Google Sheets export is enabled, signed in, and how far it got
+#[tauri::command]
+fn get_google_sheets_status(app_handle: AppHandle, state: State<'_, Arc<AppState>>) -> GoogleSheetsStatus {
+    GoogleSheetsStatus {
+        enabled: state.settings.lock().unwrap().google_sheets.enabled,
+        signed_in: google_sheets::signed_in(),
+        exported_through: load_export_from_store(&app_handle, SHEETS_EXPORT_KEY),
+    }
+}
+
+// Sign in to Google Sheets in the browser
+#[tauri::command]
+async fn google_sheets_sign_in(app_handle: AppHandle, state: State<'_, Arc<AppState>>) -> Result<(), String> {
+    let settings = state.settings.lock().unwrap().google_sheets.clone();
+    google_sheets::sign_in(&app_handle, &settings).await
+}
+
+// Forget the Google Sheets sign-in
+#[tauri::command]
+fn google_sheets_sign_out() -> Result<(), String> {
+    google_sheets::sign_out()
+}
+
+// Append the finished days not exported yet, returns the number of rows
+#[tauri::command]
+async fn export_to_google_sheets(app_handle: AppHandle, state: State<'_, Arc<AppState>>) -> Result<usize, String> {
+    export_to_sheets(&app_handle, &state).await
+}
+
+// Email the summary of the last finished day or week now, even if it was sent
+#[tauri::command]
+async fn send_email_report(app_handle: AppHandle, state: State<'_, Arc<AppState>>) -> Result<(), String> {
+    let settings = state.settings.lock().unwrap().clone();
+    let start = email_report::last_period(settings.email_report.period, clock::today(settings.timezone.as_deref()));
+    email_period_summary(&app_handle, &state, start).await
+}
+
+// Push a test message to check the push settings
+#[tauri::command]
+async fn send_test_push(state: State<'_, Arc<AppState>>) -> Result<(), String> {
+    let settings = state.settings.lock().unwrap().push.clone();
+    let token = secrets::current(&state)?.push_token;
+    push::send(&settings, token.as_deref(), &t("push.test.title"), &t("push.test.body")).await
+}
+
+// The conferencing app in a call, when call detection is on
+#[tauri::command]
+fn get_current_call(state: State<'_, Arc<AppState>>) -> Option<&'static str> {
+    let enabled = state.settings.lock().unwrap().call_detection;
+    if enabled {
+        current_call(&state)
+    } else {
+        None
+    }
+}
+
+// The accepted meeting running now, if any
+#[tauri::command]
+fn get_current_meeting(state: State<'_, Arc<AppState>>) -> Option<Meeting> {
+    meetings::current(&state.meetings.lock().unwrap(), Utc::now()).cloned()
+}
+
+// Language used for backend text, from the settings or the system
+#[tauri::command]
+fn get_locale() -> &'static str {
+    i18n::current()
+}
+
+// UUID sent as `device_id` with every event
+#[tauri::command]
+fn get_device_id(state: State<'_, Arc<AppState>>) -> String {
+    state.device_id.lock().unwrap().clone()
+}
+
+// Replace the device ID with a new one, e.g. after cloning a machine image.
+// The API sees the install as a new device afterwards.
+#[tauri::command]
+fn regenerate_device_id(app_handle: AppHandle, state: State<'_, Arc<AppState>>) -> Result<String, String> {
+    require_settings_unlocked(&state)?;
+    let device_id = uuid::Uuid::new_v4().to_string();
+    store::save_json(&app_handle, DEVICE_FILENAME, "device_id", &device_id)?;
+    
+    info!("Regenerated device ID {}", device_id);
+    *state.device_id.lock().unwrap() = device_id.clone();
+    Ok(device_id)
+}
+
+// Whether a PIN guards the settings and whether it was entered
+#[tauri::command]
+fn get_settings_protection(state: State<'_, Arc<AppState>>) -> SettingsProtection {
+    SettingsProtection {
+        pin_set: state.settings_pin.lock().unwrap().is_some(),
+        unlocked: *state.settings_unlocked.lock().unwrap(),
+    }
+}
+
+// Allow settings changes until the app quits or `lock_settings`
+#[tauri::command]
+async fn unlock_settings(pin: String, state: State<'_, Arc<AppState>>) -> Result<(), String> {
+    try_unlock_settings(&state, &pin, Instant::now())
+}
+
+// Check the PIN unless wrong ones locked out attempts. Attempts are checked
+// one at a time, so parallel guesses wait for the lockout too.
+fn try_unlock_settings(state: &AppState, pin: &str, now: Instant) -> Result<(), String> {
+    let pin_hash = state.settings_pin.lock().unwrap().clone();
+    let Some(pin_hash) = pin_hash else {
+        return Ok(());
+    };
+    
+    let mut attempts = state.pin_attempts.lock().unwrap();
+    if let Some(wait) = attempts.locked_for(now) {
+        return Err(tf("error.pin_locked", &[("secs", &wait.as_secs().max(1))]));
+    }
+    if !settings_pin::verify(pin, &pin_hash) {
+        attempts.failed(now);
+        return Err(t("error.wrong_pin"));
+    }
+    attempts.succeeded();
+    
+    *state.settings_unlocked.lock().unwrap() = true;
+    info!("Settings unlocked");
+    Ok(())
+}
+
+// Require the PIN again before the next settings change
+#[tauri::command]
+fn lock_settings(state: State<'_, Arc<AppState>>) {
+    *state.settings_unlocked.lock().unwrap() = false;
+}
+
+// Set, change or remove the PIN guarding settings changes
+#[tauri::command]
+fn set_settings_pin(pin: Option<String>, app_handle: AppHandle, state: State<'_, Arc<AppState>>) -> Result<(), String> {
+    require_settings_unlocked(&state)?;
+    let pin_hash = pin.as_deref().map(settings_pin::hash).transpose()?;
+    match &pin_hash {
+        Some(pin_hash) => store::save_json(&app_handle, PIN_FILENAME, "pin_hash", pin_hash)?,
+        None => store::delete_json(&app_handle, PIN_FILENAME, "pin_hash")?,
+    }
+    
+    // Whoever just set the PIN stays unlocked for the session
+    *state.settings_unlocked.lock().unwrap() = pin_hash.is_some();
+    *state.settings_pin.lock().unwrap() = pin_hash;
+    info!("Settings PIN {}", if pin.is_some() { "set" } else { "removed" });
+    Ok(())
+}
+
+// Whether settings.json is encrypted, and why it is locked if it is
+#[tauri::command]
+fn get_settings_encryption(state: State<'_, Arc<AppState>>) -> EncryptionStatus {
+    match &*state.store_lock.lock().unwrap() {
+        StoreLock::Plain => EncryptionStatus { mode: None, locked: None },
+        StoreLock::Unlocked(key) => EncryptionStatus { mode: Some(key.mode), locked: None },
+        StoreLock::Locked(reason) => EncryptionStatus { mode: None, locked: Some(reason.clone()) },
+    }
+}
+
+// Turn settings store encryption on or off and rewrite the store. A
+// passphrase is needed for `EncryptionMode::Passphrase`.
+#[tauri::command]
+async fn set_settings_encryption(mode: Option<EncryptionMode>, passphrase: Option<String>, app_handle: AppHandle, state: State<'_, Arc<AppState>>) -> Result<(), String> {
+    require_settings_unlocked(&state)?;
+    if let StoreLock::Locked(reason) = &*state.store_lock.lock().unwrap() {
+        return Err(format!("Unlock the settings first: {}", reason));
+    }
+    
+    let store_lock = match mode {
+        None => StoreLock::Plain,
+        Some(EncryptionMode::Machine) => StoreLock::Unlocked(StoreKey::new(EncryptionMode::Machine, &store_crypto::machine_secret()?)?),
+        Some(EncryptionMode::Passphrase) => {
+            let passphrase = passphrase.filter(|passphrase| !passphrase.is_empty())
+                .ok_or_else(|| "Enter a passphrase".to_string())?;
+            StoreLock::Unlocked(StoreKey::new(EncryptionMode::Passphrase, &passphrase)?)
+        }
+    };
+    *state.store_lock.lock().unwrap() = store_lock;
+    
+    let settings = state.settings.lock().unwrap().clone();
+    save_settings_to_store(&app_handle, &settings).await
+}
+
+// Open settings encrypted with a passphrase and start using them
+#[tauri::command]
+async fn unlock_settings_store(passphrase: String, app_handle: AppHandle, state: State<'_, Arc<AppState>>) -> Result<Settings, String> {
+    let store = StoreBuilder::new(&app_handle, std::path::PathBuf::from(SETTINGS_FILENAME))
+        .build()
+        .map_err(|err| format!("Failed to create store: {}", err))?;
+    let _ = store.reload();
+    
+    let envelope: Envelope = store.get("encrypted")
+        .ok_or_else(|| "The settings are not encrypted".to_string())
+        .and_then(|envelope| serde_json::from_value(envelope).map_err(|err| format!("Encrypted settings are corrupted: {}", err)))?;
+    let key = StoreKey::for_envelope(&envelope, &passphrase)?;
+    let settings_value = key.decrypt(&envelope)?;
+    
+    let version = store.get("schema_version").and_then(|version| version.as_u64()).unwrap_or(0);
+    let (settings, _) = read_stored_settings(settings_value, version);
+    *state.store_lock.lock().unwrap() = StoreLock::Unlocked(key);
+    
+    apply_settings(&app_handle, &state, ChangeSource::File, settings.clone()).await?;
+    info!("Unlocked encrypted settings");
+    Ok(settings)
+}
+
+// Which API credentials are stored in the keychain
+#[tauri::command]
+fn get_secret_status(state: State<'_, Arc<AppState>>) -> Result<SecretStatus, String> {
+    Ok(secrets::current(&state)?.status())
+}
+
+// Set or, with no value, remove one of the credentials stored in the
+// keychain, by its name in `Secrets`
+#[tauri::command]
+fn save_secret(field: String, value: Option<String>, state: State<'_, Arc<AppState>>) -> Result<SecretStatus, String> {
+    require_settings_unlocked(&state)?;
+    secrets::save_field(&state, &field, value)
+}
+
+// Write settings to a file that can be imported on another machine, with the
+// keychain secrets when `include_secrets` is set
+#[tauri::command]
+fn export_settings(path: String, include_secrets: bool, state: State<'_, Arc<AppState>>) -> Result<(), String> {
+    require_settings_unlocked(&state)?;
+    let settings = state.settings.lock().unwrap().clone();
+    let secrets = if include_secrets { Some(secrets::current(&state)?) } else { None };
+    let contents = settings_file::export(&settings, secrets.as_ref())?;
+    
+    std::fs::write(&path, contents).map_err(|err| format!("Failed to write {}: {}", path, err))?;
+    info!("Exported settings to {}", path);
+    Ok(())
+}
+
+// Replace the settings with the ones in an exported file
+#[tauri::command]
+async fn import_settings(path: String, app_handle: AppHandle, state: State<'_, Arc<AppState>>) -> Result<Settings, String> {
+    let contents = std::fs::read_to_string(&path).map_err(|err| format!("Failed to read {}: {}", path, err))?;
+    let current = state.settings.lock().unwrap().clone();
+    let (settings, secrets) = settings_file::import(&contents, &current)?;
+    
+    apply_settings(&app_handle, &state, ChangeSource::Ui, settings.clone()).await?;
+    // Secrets exported along replace the ones in the keychain
+    if let Some(secrets) = secrets {
+        secrets::save(&state, secrets)?;
+    }
+    info!("Imported settings from {}", path);
+    Ok(settings)
+}
+
+// Make validated settings the current ones and persist them
+async fn apply_settings(app_handle: &AppHandle, state: &AppState, source: ChangeSource, settings: Settings) -> Result<(), String> {
+    require_change_allowed(state, source)?;
+    
+    // Fields locked by the policy keep the policy's values
+    let settings = state.policy.lock().unwrap().enforce(&settings);
+    
+    // Update in-memory settings
+    let (previous, hotkeys_changed) = {
+        let mut settings_lock = state.settings.lock().unwrap();
+        let changed = settings_lock.hotkeys != settings.hotkeys;
+        let previous = std::mem::replace(&mut *settings_lock, settings.clone());
+        (previous, changed)
+    };
+    
+    // Save settings to disk
+    save_settings_to_store(app_handle, &settings).await?;
+    audit_settings_change(app_handle, state, &previous, &settings, source);
+    i18n::set_locale(settings.locale.as_deref());
+    
+    if hotkeys_changed {
+        hotkeys::register(app_handle, &settings.hotkeys);
+    }
+    tray::refresh(app_handle, state);
+    
+    Ok(())
+}
+
+// Update settings in memory and persist them
+async fn update_settings<F>(app_handle: &AppHandle, state: &AppState, source: ChangeSource, update: F) -> Result<Settings, String>
+where
+    F: FnOnce(&mut Settings),
+{
+    require_change_allowed(state, source)?;
+    
+    let (previous, settings) = {
+        let mut settings_lock = state.settings.lock().unwrap();
+        let previous = settings_lock.clone();
+        update(&mut settings_lock);
+        *settings_lock = state.policy.lock().unwrap().enforce(&settings_lock);
+        (previous, settings_lock.clone())
+    };
+    
+    save_settings_to_store(app_handle, &settings).await?;
+    audit_settings_change(app_handle, state, &previous, &settings, source);
+    i18n::set_locale(settings.locale.as_deref());
+    
+    Ok(settings)
+}
+
+// Note each changed setting in the audit log, with where the change came from
+fn audit_settings_change(app_handle: &AppHandle, state: &AppState, previous: &Settings, settings: &Settings, source: ChangeSource) {
+    let (Ok(previous), Ok(settings)) = (serde_json::to_value(previous), serde_json::to_value(settings)) else {
+        return;
+    };
+    let changes = settings_audit::changes(&previous, &settings);
+    if changes.is_empty() {
+        return;
+    }
+    
+    let user = whoami::username();
+    let now = Utc::now();
+    record_history(app_handle, state, |history| {
+        for change in &changes {
+            history.record_audit(now, settings_audit::ACTION, &change.describe(source, &user));
+        }
+    });
+}
+
+// Audit log entries, oldest first: settings changes and overridden automatic actions
+#[tauri::command]
+fn get_audit_log(state: State<'_, Arc<AppState>>) -> Vec<AuditEntry> {
+    state.history.lock().unwrap().audit.clone()
+}
+
+// Look for a newer version on the configured release channel
+#[tauri::command]
+async fn check_for_updates(app_handle: AppHandle, state: State<'_, Arc<AppState>>) -> Result<Option<UpdateInfo>, String> {
+    let channel = state.settings.lock().unwrap().update_channel;
+    updates::check(&app_handle, channel).await
+}
+
+// Download and install the newest version, emitting `update_progress` while
+// downloading. The app restarts into the new version when done.
+#[tauri::command]
+async fn install_update(app_handle: AppHandle, state: State<'_, Arc<AppState>>) -> Result<(), String> {
+    let channel = state.settings.lock().unwrap().update_channel;
+    updates::install(&app_handle, channel).await
+}
+
+// Resend events queued while the API was unreachable, returning how many were sent
+#[tauri::command]
+async fn flush_queue(app_handle: AppHandle, state: State<'_, Arc<AppState>>) -> Result<usize, String> {
+    flush_pending_events(&app_handle, &state).await
+}
+
+// Get the first-run setup progress
+#[tauri::command]
+fn get_onboarding_state(state: State<'_, Arc<AppState>>) -> OnboardingState {
+    state.onboarding.lock().unwrap().state()
+}
+
+// Mark a setup step as done and persist the progress
+#[tauri::command]
+fn complete_onboarding_step(step: OnboardingStep, app_handle: AppHandle, state: State<'_, Arc<AppState>>) -> Result<OnboardingState, String> {
+    let onboarding = {
+        let mut onboarding = state.onboarding.lock().unwrap();
+        if !onboarding.complete(step) {
+            return Ok(onboarding.state());
+        }
+        onboarding.clone()
+    };
+    
+    store::save_json(&app_handle, ONBOARDING_FILENAME, "onboarding", &onboarding)?;
+    Ok(onboarding.state())
+}
+
+// Get the saved profile names and the active one
+#[tauri::command]
+fn get_profiles(state: State<'_, Arc<AppState>>) -> ProfileList {
+    state.profiles.lock().unwrap().list()
+}
+
+// Add a profile with the given settings, or a copy of the current ones,
+// without switching to it. The current settings become the default profile
+// if no profile was active.
+#[tauri::command]
+fn create_profile(name: String, settings: Option<Settings>, app_handle: AppHandle, state: State<'_, Arc<AppState>>) -> Result<ProfileList, String> {
+    let name = profiles::check_name(&name)?;
+    let current = state.settings.lock().unwrap().clone();
+    let settings = settings.unwrap_or_else(|| current.clone());
+    let errors = validation::validate(&settings);
+    if !errors.is_empty() {
+        return Err(format!("Invalid settings: {}", validation::summary(&errors)));
+    }
+    
+    let profiles = update_profiles(&app_handle, &state, |profiles| {
+        if profiles.find(name).is_some() {
+            return Err(format!("A profile named '{}' already exists", name));
+        }
+        let active = profiles.active.get_or_insert_with(|| profiles::DEFAULT_PROFILE.to_string()).clone();
+        profiles.save(&active, current);
+        profiles.save(name, settings);
+        Ok(())
+    })?;
+    
+    tray::refresh(&app_handle, &state);
+    Ok(profiles.list())
+}
+
+// Get a profile's settings. The active profile's are the ones in use.
+#[tauri::command]
+fn get_profile(name: String, state: State<'_, Arc<AppState>>) -> Result<Settings, String> {
+    let profiles = state.profiles.lock().unwrap();
+    if profiles.active.as_deref() == Some(name.as_str()) {
+        return Ok(state.settings.lock().unwrap().clone());
+    }
+    profiles.find(&name)
+        .map(|profile| profile.settings.clone())
+        .ok_or_else(|| format!("No profile named '{}'", name))
+}
+
+// Replace a profile's settings, applying them right away if it is the active one
+#[tauri::command]
+async fn update_profile(name: String, settings: Settings, app_handle: AppHandle, state: State<'_, Arc<AppState>>) -> Result<(), String> {
+    let errors = validation::validate(&settings);
+    if !errors.is_empty() {
+        return Err(format!("Invalid settings: {}", validation::summary(&errors)));
+    }
+    
+    let active = state.profiles.lock().unwrap().active.clone();
+    if active.as_deref() == Some(name.as_str()) {
+        return apply_settings(&app_handle, &state, ChangeSource::Ui, settings).await;
+    }
+    update_profiles(&app_handle, &state, |profiles| {
+        if profiles.find(&name).is_none() {
+            return Err(format!("No profile named '{}'", name));
+        }
+        profiles.save(&name, settings);
+        Ok(())
+    })?;
+    Ok(())
+}
+
+// Rename a profile, moving its history along
+#[tauri::command]
+fn rename_profile(name: String, new_name: String, app_handle: AppHandle, state: State<'_, Arc<AppState>>) -> Result<ProfileList, String> {
+    let new_name = profiles::check_name(&new_name)?;
+    let (from, to) = (profiles::history_filename(Some(&name)), profiles::history_filename(Some(new_name)));
+    
+    let profiles = update_profiles(&app_handle, &state, |profiles| profiles.rename(&name, new_name))?;
+    if from != to {
+        let history = load_history_file(&app_handle, &from);
+        save_history_file(&app_handle, &to, &history)?;
+        store::delete_file(&app_handle, &from)?;
+    }
+    
+    // The login item is named after the profile and starts it by name
+    let entry = state.autostart.lock().unwrap().find(&name).cloned();
+    if let Some(entry) = entry.filter(|entry| entry.enabled) {
+        register_autostart(&app_handle, &AutostartEntry { enabled: false, ..entry.clone() })?;
+        register_autostart(&app_handle, &AutostartEntry { profile: new_name.to_string(), ..entry })?;
+    }
+    update_autostart(&app_handle, &state, |autostart| autostart.rename(&name, new_name))?;
+    
+    info!("Renamed profile '{}' to '{}'", name, new_name);
+    let _ = app_handle.emit("profile_changed", profiles.list());
+    tray::refresh(&app_handle, &state);
+    Ok(profiles.list())
+}
+
+// Delete a profile other than the active one, with its history
+#[tauri::command]
+fn delete_profile(name: String, app_handle: AppHandle, state: State<'_, Arc<AppState>>) -> Result<ProfileList, String> {
+    let profiles = update_profiles(&app_handle, &state, |profiles| profiles.remove(&name).map(|_| ()))?;
+    store::delete_file(&app_handle, &profiles::history_filename(Some(&name)))?;
+    
+    let entry = state.autostart.lock().unwrap().find(&name).cloned();
+    if let Some(entry) = entry.filter(|entry| entry.enabled) {
+        register_autostart(&app_handle, &AutostartEntry { enabled: false, ..entry })?;
+    }
+    update_autostart(&app_handle, &state, |autostart| {
+        autostart.remove(&name);
+    })?;
+    
+    info!("Deleted profile '{}'", name);
+    let _ = app_handle.emit("profile_changed", profiles.list());
+    tray::refresh(&app_handle, &state);
+    Ok(profiles.list())
+}
+
+// Switch to another saved profile
+#[tauri::command]
+async fn switch_profile(name: String, app_handle: AppHandle, state: State<'_, Arc<AppState>>) -> Result<(), String> {
+    change_profile(&app_handle, &state, &name).await
+}
+
+// Swap in another profile's settings, shared by commands and the tray menu.
+// A running session is checked out under the old profile first since it
+// belongs to that profile's API and user, and the monitor starts over as if
+// the app had just been launched.
+async fn change_profile(app_handle: &AppHandle, state: &AppState, name: &str) -> Result<(), String> {
+    // Refused before checking out, not halfway through the switch
+    require_settings_unlocked(state)?;
+    
+    let (target, previous) = {
+        let profiles = state.profiles.lock().unwrap();
+        if profiles.active.as_deref() == Some(name) {
+            return Ok(());
+        }
+        let target = profiles.find(name)
+            .ok_or_else(|| format!("No profile named '{}'", name))?
+            .settings.clone();
+        (target, profiles.active.clone())
+    };
+    
+    if *state.status.lock().unwrap() != AttendanceStatus::CheckedOut {
+        set_attendance(app_handle, state, "check-out").await?;
+    }
+    
+    // Keep changes made to the old profile while it was active
+    let current = state.settings.lock().unwrap().clone();
+    let profiles = update_profiles(app_handle, state, |profiles| {
+        profiles.save(previous.as_deref().unwrap_or(profiles::DEFAULT_PROFILE), current);
+        profiles.active = Some(name.to_string());
+        Ok(())
+    })?;
+    
+    // Each profile has its own history. The old one was closed by the check-out.
+    let history = load_history_from_store(app_handle);
+    *state.status.lock().unwrap() = if history.current_session().is_some() { AttendanceStatus::CheckedIn } else { AttendanceStatus::CheckedOut };
+    *state.history.lock().unwrap() = history;
+    
+    let settings = update_settings(app_handle, state, ChangeSource::Ui, |settings| *settings = target).await?;
+    reset_monitor_state(state);
+    hotkeys::register(app_handle, &settings.hotkeys);
+    
+    info!("Switched to profile '{}'", name);
+    let _ = app_handle.emit("profile_changed", profiles.list());
+    tray::refresh(app_handle, state);
+    Ok(())
+}
+
+// Forget what the idle monitor remembered about the current day and session
+fn reset_monitor_state(state: &AppState) {
+    *state.manual_checkout.lock().unwrap() = false;
+    *state.manual_checkout_date.lock().unwrap() = None;
+    *state.daily_limit.lock().unwrap() = None;
+    state.overtime_warned.lock().unwrap().clear();
+    state.reminders_sent.lock().unwrap().clear();
+    state.active_prompts.lock().unwrap().clear();
+    *state.auto_break.lock().unwrap() = false;
+    *state.break_reminder.lock().unwrap() = ReminderCadence::default();
+    *state.checkout_countdown.lock().unwrap() = None;
+    *state.block_end_warned.lock().unwrap() = None;
+    *state.checkout_extended_until.lock().unwrap() = None;
+    *state.still_here_at.lock().unwrap() = None;
+}
+
+// Get the global hotkey bindings
+#[tauri::command]
+fn get_hotkeys(state: State<'_, Arc<AppState>>) -> HotkeySettings {
+    state.settings.lock().unwrap().hotkeys.clone()
+}
+
+// Save the global hotkey bindings and re-register them. Returns the bindings
+// that are invalid, duplicated or already taken by another application.
+#[tauri::command]
+async fn save_hotkeys(hotkeys: HotkeySettings, app_handle: AppHandle, state: State<'_, Arc<AppState>>) -> Result<Vec<HotkeyConflict>, String> {
+    let settings = update_settings(&app_handle, &state, ChangeSource::Ui, |settings| {
+        settings.hotkeys = hotkeys;
+    }).await?;
+    
+    Ok(hotkeys::register(&app_handle, &settings.hotkeys))
+}
+
+// Get the holiday list
+#[tauri::command]
+fn get_holidays(state: State<'_, Arc<AppState>>) -> Vec<Holiday> {
+    state.settings.lock().unwrap().holidays.clone()
+}
+
+// Add a manual holiday entry (date as YYYY-MM-DD)
+#[tauri::command]
+async fn add_holiday(date: String, name: String, app_handle: AppHandle, state: State<'_, Arc<AppState>>) -> Result<Vec<Holiday>, String> {
+    let date = parse_date(&date)?;
+    
+    let holiday = Holiday {
+        date,
+        name,
+        source: HolidaySource::Manual,
+    };
+    
+    let settings = update_settings(&app_handle, &state, ChangeSource::Ui, |settings| {
+        settings.holidays.retain(|h| h.date != date);
+        settings.holidays.push(holiday);
+        settings.holidays.sort_by_key(|h| h.date);
+    }).await?;
+    
+    Ok(settings.holidays)
+}
+
+// Remove the holiday on the given date
+#[tauri::command]
+async fn remove_holiday(date: String, app_handle: AppHandle, state: State<'_, Arc<AppState>>) -> Result<Vec<Holiday>, String> {
+    let date = parse_date(&date)?;
+    
+    let settings = update_settings(&app_handle, &state, ChangeSource::Ui, |settings| {
+        settings.holidays.retain(|h| h.date != date);
+    }).await?;
+    
+    Ok(settings.holidays)
+}
+
+// Import public holidays for a year from the holiday feed, using the
+// configured country/region
+#[tauri::command]
+async fn import_holidays(year: i32, app_handle: AppHandle, state: State<'_, Arc<AppState>>) -> Result<usize, String> {
+    let (country, region) = {
+        let settings = state.settings.lock().unwrap();
+        (settings.holiday_country.clone(), settings.holiday_region.clone())
+    };
+    let country = country.ok_or_else(|| "No holiday country configured".to_string())?;
+    
+    let body = fetch_text(&holidays::feed_url(&country, year)).await?;
+    let imported = holidays::parse_feed_json(&body, region.as_deref())?;
+    
+    let mut added = 0;
+    update_settings(&app_handle, &state, ChangeSource::Ui, |settings| {
+        added = holidays::merge_holidays(&mut settings.holidays, imported);
+    }).await?;
+    
+    info!("Imported {} holidays for {} {}", added, country, year);
+    Ok(added)
+}
+
+// Import all-day events from an ICS calendar as holidays
+#[tauri::command]
+async fn import_holidays_ics(url: String, app_handle: AppHandle, state: State<'_, Arc<AppState>>) -> Result<usize, String> {
+    let body = fetch_text(&url).await?;
+    let imported = holidays::parse_ics(&body)?;
+    
+    let mut added = 0;
+    update_settings(&app_handle, &state, ChangeSource::Ui, |settings| {
+        added = holidays::merge_holidays(&mut settings.holidays, imported);
+    }).await?;
+    
+    info!("Imported {} holidays from ICS calendar", added);
+    Ok(added)
+}
+
+// Get the configured vacation, if any
+#[tauri::command]
+fn get_vacation(state: State<'_, Arc<AppState>>) -> Option<Vacation> {
+    state.settings.lock().unwrap().vacation.clone()
+}
+
+// Set a vacation range (dates as YYYY-MM-DD, inclusive)
+#[tauri::command]
+async fn set_vacation(from: String, to: String, notify_api: bool, app_handle: AppHandle, state: State<'_, Arc<AppState>>) -> Result<Vacation, String> {
+    let from = parse_date(&from)?;
+    let to = parse_date(&to)?;
+    
+    if to < from {
+        return Err("Vacation end date is before its start date".to_string());
+    }
+    
+    let vacation = Vacation {
+        from,
+        to,
+        notify_api,
+        announced: false,
+    };
+    
+    let vacation_clone = vacation.clone();
+    update_settings(&app_handle, &state, ChangeSource::Ui, |settings| settings.vacation = Some(vacation_clone)).await?;
+    
+    info!("Vacation set from {} to {}", from, to);
+    let _ = app_handle.emit("vacation_changed", "");
+    
+    Ok(vacation)
+}
+
+// Cancel the configured vacation
+#[tauri::command]
+async fn clear_vacation(app_handle: AppHandle, state: State<'_, Arc<AppState>>) -> Result<(), String> {
+    update_settings(&app_handle, &state, ChangeSource::Ui, |settings| settings.vacation = None).await?;
+    
+    info!("Vacation cleared");
+    let _ = app_handle.emit("vacation_changed", "");
+    
+    Ok(())
+}
+
+// Get the subscribed ICS calendars
+#[tauri::command]
+fn get_calendar_subscriptions(state: State<'_, Arc<AppState>>) -> Vec<CalendarSubscription> {
+    state.settings.lock().unwrap().calendar_subscriptions.clone()
+}
+
+// Subscribe to an ICS calendar and sync it right away
+#[tauri::command]
+async fn add_calendar_subscription(url: String, keywords: Vec<String>, app_handle: AppHandle, state: State<'_, Arc<AppState>>) -> Result<usize, String> {
+    let subscription = CalendarSubscription { url, keywords };
+    
+    update_settings(&app_handle, &state, ChangeSource::Ui, |settings| {
+        settings.calendar_subscriptions.retain(|s| s.url != subscription.url);
+        settings.calendar_subscriptions.push(subscription);
+    }).await?;
+    
+    sync_calendar_subscriptions(&app_handle, &state).await
+}
+
+// Unsubscribe from an ICS calendar and drop the days it provided
+#[tauri::command]
+async fn remove_calendar_subscription(url: String, app_handle: AppHandle, state: State<'_, Arc<AppState>>) -> Result<usize, String> {
+    update_settings(&app_handle, &state, ChangeSource::Ui, |settings| {
+        settings.calendar_subscriptions.retain(|s| s.url != url);
+    }).await?;
+    
+    sync_calendar_subscriptions(&app_handle, &state).await
+}
+
+// Sync subscribed calendars now
+#[tauri::command]
+async fn sync_calendars(app_handle: AppHandle, state: State<'_, Arc<AppState>>) -> Result<usize, String> {
+    sync_calendar_subscriptions(&app_handle, &state).await
+}
+
+// Fetch and apply the signed settings bundle now, returning whether settings changed
+#[tauri::command]
+async fn sync_remote_config(app_handle: AppHandle, state: State<'_, Arc<AppState>>) -> Result<bool, String> {
+    require_settings_unlocked(&state)?;
+    apply_remote_config(&app_handle, &state).await
+}
+
+// Look the employee ID up in the directory now and save it
+#[tauri::command]
+async fn lookup_employee_id(app_handle: AppHandle, state: State<'_, Arc<AppState>>) -> Result<String, String> {
+    fetch_employee_id(&app_handle, &state).await
+}
+
+// Run `task` every `every_secs` seconds in the background, skipping the runs
+// while `enabled` is false for the current settings. Failures are logged as
+// "Failed to <what>" and the next run tries again.
+fn spawn_periodic<F, Fut>(app_handle: AppHandle, every_secs: u64, what: &'static str, enabled: fn(&Settings) -> bool, task: F)
+where
+    F: Fn(AppHandle, Arc<AppState>) -> Fut + Send + 'static,
+    Fut: std::future::Future<Output = Result<(), String>> + Send,
+{
+    tauri::async_runtime::spawn(async move {
+        let state = app_handle.state::<Arc<AppState>>().inner().clone();
+        let mut interval = time::interval(Duration::from_secs(every_secs));
+        
+        loop {
+            interval.tick().await;
+            
+            if !enabled(&state.settings.lock().unwrap()) {
+                continue;
+            }
+            if let Err(err) = task(app_handle.clone(), state.clone()).await {
+                error!("Failed to {}: {}", what, err);
+            }
+        }
+    });
+}
+
+// Look the employee ID up once when a directory is configured and none is set yet
+fn start_employee_lookup(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let state: State<'_, Arc<AppState>> = app_handle.state();
+        let needed = {
+            let settings = state.settings.lock().unwrap();
+            settings.employee_id.is_none() && settings.directory_url.is_some()
+        };
+        if !needed {
+            return;
+        }
+        
+        match fetch_employee_id(&app_handle, &state).await {
+            Ok(employee_id) => info!("Looked up employee ID {}", employee_id),
+            Err(err) => error!("Failed to look up the employee ID: {}", err),
+        }
+    });
+}
+
+// Ask the directory for the employee ID matching the username and save it
+async fn fetch_employee_id(app_handle: &AppHandle, state: &AppState) -> Result<String, String> {
+    let (template, username) = {
+        let settings = state.settings.lock().unwrap();
+        (settings.directory_url.clone(), settings.username.clone())
+    };
+    let template = template.ok_or("No directory URL is configured")?;
+    let url = directory::lookup_url(&template, &username)?;
+    
+    // The directory is expected to accept the API credentials
+    let secrets = secrets::current(state)?;
+    let mut request = reqwest::Client::new().get(&url);
+    if let Some(token) = &secrets.api_token {
+        request = request.bearer_auth(token);
+    }
+    let response = request.send().await
+        .map_err(|e| format!("Failed to send request: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("Directory lookup failed with status {}", response.status()));
+    }
+    let body = response.text().await
+        .map_err(|e| format!("Failed to read response: {}", e))?;
+    
+    let employee_id = directory::parse_response(&body)?;
+    let settings = update_settings(app_handle, state, ChangeSource::App, |settings| {
+        settings.employee_id = Some(employee_id.clone());
+    }).await?;
+    let _ = app_handle.emit("settings_updated", &settings);
+    Ok(employee_id)
+}
+
+// Refresh the meetings from one calendar. Without the integration its
+// meetings are dropped, so turning it off ends "in a meeting" right away.
+async fn sync_calendar<P: CalendarProvider + Sync>(state: &AppState, calendar: &P) -> Result<(), String> {
+    let fetched = if calendar.enabled() && calendar.signed_in() {
+        let now = Utc::now();
+        let window = chrono::Duration::hours(MEETING_WINDOW_HOURS);
+        calendar.fetch_meetings(now - window, now + window).await?
+    } else {
+        Vec::new()
+    };
+    
+    meetings::replace(&mut state.meetings.lock().unwrap(), calendar.source(), fetched);
+    Ok(())
+}
+
+// Refresh the meetings from every connected calendar. A failing calendar
+// keeps its last meetings and doesn't hold up the others.
+async fn sync_meetings(state: &AppState) -> Result<(), String> {
+    let (google, outlook) = {
+        let settings = state.settings.lock().unwrap();
+        (settings.google_calendar.clone(), settings.outlook_calendar.clone())
+    };
+    
+    let google = sync_calendar(state, &google).await;
+    let outlook = sync_calendar(state, &outlook).await;
+    google.and(outlook)
+}
+
+// Refresh the time of the user's latest push or pull request, forgetting it
+// while the integration is off
+async fn poll_code_activity(state: &AppState) -> Result<(), String> {
+    let settings = state.settings.lock().unwrap().code_activity.clone();
+    if !settings.enabled {
+        *state.code_activity_at.lock().unwrap() = None;
+        return Ok(());
+    }
+    
+    let token = secrets::current(state).unwrap_or_default().code_activity_token;
+    *state.code_activity_at.lock().unwrap() = code_activity::fetch_latest(&settings, token.as_deref()).await?;
+    Ok(())
+}
+
+// Append each finished day since the last export to the Google Sheet. Days
+// are appended one at a time, so a failure resumes with the day that failed.
+async fn export_to_sheets(app_handle: &AppHandle, state: &AppState) -> Result<usize, String> {
+    let settings = state.settings.lock().unwrap().clone();
+    let timezone = settings.timezone.as_deref();
+    let today = clock::today(timezone);
+    
+    let mut appended = 0;
+    for date in google_sheets::days_to_export(load_export_from_store(app_handle, SHEETS_EXPORT_KEY), today) {
+        let rows = {
+            let history = state.history.lock().unwrap();
+            google_sheets::rows(&history.sessions, date, timezone, &settings.username, &settings.device_name)
+        };
+        if !rows.is_empty() {
+            appended += rows.len();
+            google_sheets::append(&settings.google_sheets, rows).await?;
+        }
+        save_export_to_store(app_handle, SHEETS_EXPORT_KEY, date)?;
+    }
+    
+    if appended > 0 {
+        info!("Appended {} sessions to the Google Sheet", appended);
+    }
+    Ok(appended)
+}
+
+// Email the summary of the day or week starting on `start`
+async fn email_period_summary(app_handle: &AppHandle, state: &AppState, start: NaiveDate) -> Result<(), String> {
+    let settings = state.settings.lock().unwrap().clone();
+    let summary = {
+        let history = state.history.lock().unwrap();
+        summary::period_summary(&history, settings.timezone.as_deref(), &settings.holidays, settings.email_report.period, start, Utc::now())
+    };
+    
+    let (subject, body) = email_report::render(&summary, &settings.username);
+    let password = secrets::current(state)?.smtp_password;
+    email_report::send(&settings.email_report, password.as_deref(), &subject, body).await?;
+    save_export_to_store(app_handle, EMAIL_REPORT_KEY, start)?;
+    info!("Emailed the attendance summary for {}", start);
+    Ok(())
+}
+
+// Email the summary of the last day or week once it is due
+async fn send_due_email_report(app_handle: &AppHandle, state: &AppState) -> Result<(), String> {
+    let settings = state.settings.lock().unwrap().clone();
+    let today = clock::today(settings.timezone.as_deref());
+    let sent_through = load_export_from_store(app_handle, EMAIL_REPORT_KEY);
+    match email_report::due(settings.email_report.period, sent_through, today) {
+        Some(start) => email_period_summary(app_handle, state, start).await,
+        None => Ok(()),
+    }
+}
+
+// Post the summary of the day starting on `start` to the Matrix room
+async fn post_matrix_summary(app_handle: &AppHandle, state: &AppState, start: NaiveDate) -> Result<(), String> {
+    let settings = state.settings.lock().unwrap().clone();
+    let summary = {
+        let history = state.history.lock().unwrap();
+        summary::period_summary(&history, settings.timezone.as_deref(), &settings.holidays, OvertimePeriod::Day, start, Utc::now())
+    };
+    
+    let (title, body) = email_report::render(&summary, &settings.username);
+    let access_token = secrets::current(state)?.matrix_access_token;
+    matrix::send(&settings.matrix, access_token.as_deref(), &format!("{}\n{}", title, body)).await?;
+    save_export_to_store(app_handle, MATRIX_SUMMARY_KEY, start)?;
+    info!("Posted the attendance summary for {} to Matrix", start);
+    Ok(())
+}
+
+// Post the summary of the last day to Matrix once it is due
+async fn post_due_matrix_summary(app_handle: &AppHandle, state: &AppState) -> Result<(), String> {
+    let timezone = state.settings.lock().unwrap().timezone.clone();
+    let today = clock::today(timezone.as_deref());
+    let sent_through = load_export_from_store(app_handle, MATRIX_SUMMARY_KEY);
+    match email_report::due(OvertimePeriod::Day, sent_through, today) {
+        Some(start) => post_matrix_summary(app_handle, state, start).await,
+        None => Ok(()),
+    }
+}
+
+// Publish the sessions finished since the last publish to the CalDAV
+// calendar, one at a time so a failure resumes with the session that failed
+async fn publish_to_caldav(app_handle: &AppHandle, state: &AppState) -> Result<usize, String> {
+    let settings = state.settings.lock().unwrap().clone();
+    let sessions: Vec<history::Session> = {
+        let history = state.history.lock().unwrap();
+        let published_through = load_export_from_store(app_handle, CALDAV_EXPORT_KEY);
+        caldav::sessions_to_publish(&history.sessions, published_through, Utc::now()).into_iter().cloned().collect()
+    };
+    let device_id = state.device_id.lock().unwrap().clone();
+    let password = secrets::current(state)?.caldav_password;
+    
+    for session in &sessions {
+        caldav::publish(&settings.caldav, password.as_deref(), session, &device_id, &settings.device_name).await?;
+        if let Some(end) = session.end {
+            save_export_to_store(app_handle, CALDAV_EXPORT_KEY, end)?;
+        }
+    }
+    
+    if !sessions.is_empty() {
+        info!("Published {} sessions to the CalDAV calendar", sessions.len());
+    }
+    Ok(sessions.len())
+}
+
+// Serve the local API while it is enabled, restarting it when its settings change
+fn start_local_api(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let state: State<'_, Arc<AppState>> = app_handle.state();
+        
+        loop {
+            let settings = state.settings.lock().unwrap().local_api.clone();
+            if !settings.enabled {
+                time::sleep(Duration::from_secs(LOCAL_API_RETRY_SECS)).await;
+                continue;
+            }
+            
+            if let Err(err) = local_api::listen(&app_handle, &settings).await {
+                error!("{}", err);
+                time::sleep(Duration::from_secs(LOCAL_API_RETRY_SECS)).await;
+            }
+        }
+    });
+}
+
+// Export logs and spans periodically, and follow the setting turning it on and off
+fn start_telemetry_export(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let state: State<'_, Arc<AppState>> = app_handle.state();
+        let mut interval = time::interval(Duration::from_secs(TELEMETRY_EXPORT_SECS));
+        
+        loop {
+            interval.tick().await;
+            
+            let settings = state.settings.lock().unwrap().clone();
+            telemetry::set_enabled(settings.telemetry.enabled && settings.telemetry.endpoint.is_some());
+            if !telemetry::enabled() {
+                continue;
+            }
+            
+            if let Err(err) = telemetry::export(&settings.telemetry, &settings.username, &settings.device_name).await {
+                debug!("Logs and spans not exported: {}", err);
+            }
+        }
+    });
+}
+
+// Long-poll the Telegram bot for commands while it is enabled
+fn start_telegram_bot(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let state: State<'_, Arc<AppState>> = app_handle.state();
+        let mut offset = 0;
+        
+        loop {
+            let token = secrets::current(&state).unwrap_or_default().telegram_bot_token;
+            let enabled = state.settings.lock().unwrap().telegram.enabled;
+            let Some(token) = token.filter(|_| enabled) else {
+                time::sleep(Duration::from_secs(TELEGRAM_RETRY_SECS)).await;
+                continue;
+            };
+            
+            match telegram::poll(&app_handle, &token, offset).await {
+                Ok(next) => offset = next,
+                Err(err) => {
+                    error!("Failed to poll the Telegram bot: {}", err);
+                    time::sleep(Duration::from_secs(TELEGRAM_RETRY_SECS)).await;
+                }
+            }
+        }
+    });
+}
+
+// Fetch the bundle at `config_url`, check its signature and apply its
+// settings. The managed policy still wins over them.
+async fn apply_remote_config(app_handle: &AppHandle, state: &AppState) -> Result<bool, String> {
+    let (url, public_key) = {
+        let settings = state.settings.lock().unwrap();
+        (settings.config_url.clone(), settings.config_public_key.clone())
+    };
+    let url = url.ok_or("No settings bundle URL is configured")?;
+    let public_key = public_key.ok_or("No public key is configured for the settings bundle")?;
+    
+    // A key the user could change would let anyone with settings access sign
+    // their own bundles, so only a key pinned by the managed policy is trusted
+    if !state.policy.lock().unwrap().locks("config_public_key") {
+        return Err("The settings bundle key must be set by the managed policy".to_string());
+    }
+    
+    let contents = fetch_text(&url).await
+        .map_err(|err| format!("Failed to load settings bundle {}: {}", url, err))?;
+    let (version, values) = remote_config::verify(&contents, &public_key)?;
+    remote_config::check_version(version, store::load_json(app_handle, REMOTE_CONFIG_FILENAME, "version")?)?;
+    
+    let current = state.settings.lock().unwrap().clone();
+    let settings = Policy::new("remote", values).enforce(&current);
+    if serde_json::to_value(&settings).ok() == serde_json::to_value(&current).ok() {
+        store::save_json(app_handle, REMOTE_CONFIG_FILENAME, "version", &version)?;
+        return Ok(false);
+    }
+    
+    let errors = validation::validate(&settings);
+    if !errors.is_empty() {
+        return Err(format!("The settings bundle has invalid settings: {}", validation::summary(&errors)));
+    }
+    
+    apply_settings(app_handle, state, ChangeSource::Remote, settings.clone()).await?;
+    store::save_json(app_handle, REMOTE_CONFIG_FILENAME, "version", &version)?;
+    info!("Applied remote settings from {}", url);
+    let _ = app_handle.emit("settings_updated", &settings);
+    Ok(true)
+}
+
+// Fetch all subscribed calendars and replace the non-working days synced
+// previously. Nothing is replaced if any calendar fails to load.
+async fn sync_calendar_subscriptions(app_handle: &AppHandle, state: &AppState) -> Result<usize, String> {
+    let subscriptions = state.settings.lock().unwrap().calendar_subscriptions.clone();
+    
+    let mut synced = Vec::new();
+    for subscription in &subscriptions {
+        let body = fetch_text(&subscription.url).await
+            .map_err(|err| format!("Failed to load calendar {}: {}", subscription.url, err))?;
+        
+        let days = holidays::parse_ics(&body)?
+            .into_iter()
+            .filter(|day| subscription.matches(&day.name))
+            .map(|day| Holiday { source: HolidaySource::Calendar, ..day });
+        synced.extend(days);
+    }
+    
+    let count = synced.len();
+    update_settings(app_handle, state, ChangeSource::App, |settings| {
+        holidays::sync_calendar_days(&mut settings.holidays, synced);
+    }).await?;
+    
+    info!("Synced {} non-working days from {} calendars", count, subscriptions.len());
+    let _ = app_handle.emit("holidays_changed", count);
+    
+    Ok(count)
+}
+
+// Fetch a text document over HTTP
+async fn fetch_text(url: &str) -> Result<String, String> {
+    let response = reqwest::get(url)
+        .await
+        .map_err(|e| format!("Failed to send request: {}", e))?;
+    
+    if !response.status().is_success() {
+        return Err(format!("Request failed with status {}", response.status()));
+    }
+    
+    response.text().await
+        .map_err(|e| format!("Failed to read response: {}", e))
+}
+
+// The OS login item for a profile, pointing at this executable
+fn login_item(app_handle: &AppHandle, entry: &AutostartEntry) -> Result<AutoLaunch, String> {
+    let exe = std::env::current_exe()
+        .map_err(|err| format!("Failed to find the executable: {}", err))?;
+    
+    // An AppImage is started through its image rather than the extracted binary
+    #[cfg(target_os = "linux")]
+    let exe = app_handle.env().appimage.map(std::path::PathBuf::from).unwrap_or(exe);
+    
+    AutoLaunchBuilder::new()
+        .set_app_name(&autostart::item_name(&app_handle.package_info().name, &entry.profile))
+        .set_app_path(&exe.display().to_string())
+        .set_use_launch_agent(true)
+        .set_args(&autostart::launch_args(entry))
+        .build()
+        .map_err(|err| format!("Failed to set up the login item: {}", err))
+}
+
+// Add, update or remove a profile's login item to match its entry
+fn register_autostart(app_handle: &AppHandle, entry: &AutostartEntry) -> Result<(), String> {
+    let item = login_item(app_handle, entry)?;
+    if entry.enabled {
+        // Enabling again rewrites the item, so it follows a moved executable or new arguments
+        item.enable()
+            .map_err(|err| format!("Failed to enable autostart for '{}': {}", entry.profile, err))
+    } else if item.is_enabled().unwrap_or(true) {
+        item.disable()
+            .map_err(|err| format!("Failed to disable autostart for '{}': {}", entry.profile, err))
+    } else {
+        Ok(())
+    }
+}
+
+// Bring the login items in line with the saved choices. Before there were
+// any, the app enabled a single login item for itself, which becomes the
+// current profile's entry.
+fn sync_autostart(app_handle: &AppHandle, state: &AppState) {
+    let autostart = load_autostart_from_store(app_handle).unwrap_or_else(|| {
+        let active = state.profiles.lock().unwrap().active.clone();
+        let profile = active.unwrap_or_else(|| profiles::DEFAULT_PROFILE.to_string());
+        if profile != profiles::DEFAULT_PROFILE {
+            let legacy = AutostartEntry { profile: profiles::DEFAULT_PROFILE.to_string(), enabled: false, args: Vec::new() };
+            if let Err(err) = register_autostart(app_handle, &legacy) {
+                error!("{}", err);
+            }
+        }
+        
+        info!("Enabling autostart for profile '{}'", profile);
+        let mut autostart = Autostart::default();
+        autostart.set(AutostartEntry { profile, enabled: true, args: Vec::new() });
+        if let Err(err) = store::save_json(app_handle, AUTOSTART_FILENAME, "autostart", &autostart) {
+            error!("{}", err);
+        }
+        autostart
+    });
+    
+    for entry in &autostart.entries {
+        if let Err(err) = register_autostart(app_handle, entry) {
+            error!("{}", err);
+        }
+    }
+    *state.autostart.lock().unwrap() = autostart;
+}
+
+// Change the saved autostart choices and write them to disk
+fn update_autostart<F>(app_handle: &AppHandle, state: &AppState, change: F) -> Result<(), String>
+where
+    F: FnOnce(&mut Autostart),
+{
+    let mut autostart = state.autostart.lock().unwrap();
+    change(&mut autostart);
+    store::save_json(app_handle, AUTOSTART_FILENAME, "autostart", &*autostart)
+}
+
+// Names of all profiles, including the default one before any was saved
+fn profile_names(state: &AppState) -> Vec<String> {
+    let profiles = state.profiles.lock().unwrap();
+    let mut names = profiles.names();
+    if profiles.active.is_none() && !names.iter().any(|name| name == profiles::DEFAULT_PROFILE) {
+        names.insert(0, profiles::DEFAULT_PROFILE.to_string());
+    }
+    names
+}
 
-// Format current date as YYYY-MM-DD
-fn format_current_date() -> String {
-    Local::now().format("%Y-%m-%d").to_string()
+// Autostart choice of every profile, profiles without one don't start at login
+#[tauri::command]
+fn get_autostart(state: State<'_, Arc<AppState>>) -> Vec<AutostartEntry> {
+    let autostart = state.autostart.lock().unwrap().clone();
+    profile_names(&state)
+        .into_iter()
+        .map(|profile| {
+            autostart.find(&profile).cloned().unwrap_or(AutostartEntry { profile, enabled: false, args: Vec::new() })
+        })
+        .collect()
+}
+
+// Choose whether a profile starts at login and with which arguments, e.g. `["--headless"]`
+#[tauri::command]
+fn set_autostart(profile: String, enabled: bool, args: Vec<String>, app_handle: AppHandle, state: State<'_, Arc<AppState>>) -> Result<AutostartEntry, String> {
+    require_settings_unlocked(&state)?;
+    if !profile_names(&state).contains(&profile) {
+        return Err(format!("No profile named '{}'", profile));
+    }
+    let args = autostart::check_args(&profile, &args)?;
+    let entry = AutostartEntry { profile, enabled, args };
+    
+    register_autostart(&app_handle, &entry)?;
+    update_autostart(&app_handle, &state, |autostart| autostart.set(entry.clone()))?;
+    info!("Autostart for profile '{}' {}", entry.profile, if entry.enabled { "enabled" } else { "disabled" });
+    Ok(entry)
+}
+
+// Helper to create the current ISO timestamp
+fn iso_timestamp() -> String {
+    Utc::now().to_rfc3339()
+}
+
+// Format current time as HH:MM:SS in the given timezone
+fn format_current_time(timezone: Option<&str>) -> String {
+    clock::now(timezone).format("%H:%M:%S").to_string()
+}
+
+// Format current date as YYYY-MM-DD in the given timezone
+fn format_current_date(timezone: Option<&str>) -> String {
+    clock::now(timezone).format("%Y-%m-%d").to_string()
+}
+
+// Parse a YYYY-MM-DD date from a command argument
+fn parse_date(date: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|e| format!("Invalid date '{}': {}", date, e))
 }
 
 // Create attendance payload from settings
-fn create_attendance_payload(event_type: &str, settings: &Settings) -> AttendancePayload {
+fn create_attendance_payload(event_type: &str, device_id: &str, settings: &Settings) -> AttendancePayload {
     let config = if settings.developer_mode {
         Some(ConfigData {
-            idle_timeout_mins: settings.idle_timeout_mins,
+            idle_timeout_secs: settings.idle_timeout_secs,
             auto_mode: settings.auto_mode,
         })
     } else {
@@ -441,70 +4225,564 @@ fn create_attendance_payload(event_type: &str, settings: &Settings) -> Attendanc
 
     AttendancePayload {
         event_type: event_type.to_string(),
-        user_id: settings.username.clone(),
+        user_id: settings.user_id(),
+        org_id: settings.org_id.clone(),
         payload: AttendanceData {
-            time: format_current_time(),
-            date: format_current_date(),
-            device_id: settings.device_name.clone(),
+            time: format_current_time(settings.timezone.as_deref()),
+            date: format_current_date(settings.timezone.as_deref()),
+            device_id: device_id.to_string(),
+            device_name: settings.device_name.clone(),
             config,
+            out_of_office: None,
+            note: None,
+        },
+        timestamp: iso_timestamp(),
+    }
+}
+
+// Create break payload from settings. The planned length comes from the break type.
+fn create_break_payload(event_type: &str, device_id: &str, settings: &Settings, break_type: &str, actual_mins: Option<i64>) -> BreakPayload {
+    BreakPayload {
+        event_type: event_type.to_string(),
+        user_id: settings.user_id(),
+        org_id: settings.org_id.clone(),
+        payload: BreakData {
+            time: format_current_time(settings.timezone.as_deref()),
+            date: format_current_date(settings.timezone.as_deref()),
+            device_id: device_id.to_string(),
+            device_name: settings.device_name.clone(),
+            break_type: break_type.to_string(),
+            planned_mins: breaks::find_break_type(&settings.break_types, break_type).and_then(|t| t.max_mins),
+            actual_mins,
         },
         timestamp: iso_timestamp(),
     }
 }
 
+// Quit, first asking whether to check out when a session is running. The
+// frontend answers the `exit_requested` event through `handle_prompt_action`.
+fn request_exit(app_handle: &AppHandle) {
+    let state: State<'_, Arc<AppState>> = app_handle.state();
+    if *state.status.lock().unwrap() == AttendanceStatus::CheckedOut {
+        app_handle.exit(0);
+        return;
+    }
+    
+    let prompt = prompts::exit();
+    {
+        let mut active_prompts = state.active_prompts.lock().unwrap();
+        active_prompts.retain(|p| p.id != prompt.id);
+        active_prompts.push(prompt.clone());
+    }
+    tray::show_main_window(app_handle);
+    let _ = app_handle.emit("exit_requested", &prompt);
+}
+
+// Check out if needed, then quit. Quits even if the API is down, the
+// check-out is queued or logged.
+async fn check_out_and_quit(app_handle: &AppHandle, state: &AppState) {
+    if *state.status.lock().unwrap() != AttendanceStatus::CheckedOut {
+        if let Err(err) = set_attendance(app_handle, state, "check-out").await {
+            error!("Failed to check out before quitting: {}", err);
+        }
+    }
+    app_handle.exit(0);
+}
+
+// Act on the configured launch behavior unless a session was resumed. The
+// launch prompt is kept with the active prompts since the frontend may not
+// be listening yet, and is answered through `handle_prompt_action`.
+fn apply_launch_behavior(app_handle: AppHandle) {
+    let state: State<'_, Arc<AppState>> = app_handle.state();
+    if *state.status.lock().unwrap() != AttendanceStatus::CheckedOut {
+        return;
+    }
+    
+    let settings = state.settings.lock().unwrap().clone();
+    match settings.launch_behavior {
+        LaunchBehavior::Nothing => {}
+        LaunchBehavior::AutoCheckIn => {
+            let today = clock::today(settings.timezone.as_deref());
+            reset_manual_checkout_if_new_day(&state, today);
+            if !may_auto_check_in(&state, &settings, clock::now(settings.timezone.as_deref())) {
+                info!("Not checking in on launch, automatic check-in is blocked right now");
+                return;
+            }
+            
+            let app_handle = app_handle.clone();
+            tauri::async_runtime::spawn(async move {
+                let state: State<'_, Arc<AppState>> = app_handle.state();
+                auto_transition(&app_handle, &state, &settings, AttendanceStatus::CheckedIn, "Checked in on launch.").await;
+            });
+        }
+        LaunchBehavior::Prompt => {
+            let prompt = prompts::launch();
+            state.active_prompts.lock().unwrap().push(prompt.clone());
+            let _ = app_handle.emit("launch_prompt", &prompt);
+        }
+    }
+}
+
+// Check for updates at startup and once a day, telling the user about new versions
+fn start_update_check(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let state: State<'_, Arc<AppState>> = app_handle.state();
+        let mut interval = time::interval(Duration::from_secs(UPDATE_CHECK_INTERVAL_SECS));
+        let mut announced: Option<String> = None;
+        
+        loop {
+            interval.tick().await;
+            
+            let settings = state.settings.lock().unwrap().clone();
+            match updates::check(&app_handle, settings.update_channel).await {
+                Ok(Some(update)) if announced.as_ref() != Some(&update.version) => {
+                    info!("Update {} available on the {:?} channel", update.version, settings.update_channel);
+                    notifications::notify(
+                        &app_handle,
+                        &settings,
+                        NotificationKind::Reminder,
+                        &t("notify.update.title"),
+                        &tf("notify.update.body", &[("version", &update.version)]),
+                    );
+                    let _ = app_handle.emit("update_available", &update);
+                    announced = Some(update.version);
+                }
+                Ok(_) => {}
+                Err(err) => error!("{}", err),
+            }
+        }
+    });
+}
+
+// Create the main window from its entry in tauri.conf.json
+fn create_main_window(app_handle: &AppHandle) -> tauri::Result<WebviewWindow> {
+    let config = app_handle.config().app.windows.iter()
+        .find(|window| window.label == MAIN_WINDOW)
+        .ok_or(tauri::Error::WindowNotFound)?;
+    
+    WebviewWindowBuilder::from_config(app_handle, config)?.build()
+}
+
+// Run a command passed to a second launch. Its output goes to the log since
+// the launching process has already exited.
+fn run_forwarded_command(app_handle: AppHandle, command: CliCommand) {
+    tauri::async_runtime::spawn(async move {
+        let state: State<'_, Arc<AppState>> = app_handle.state();
+        match cli::run(&app_handle, &state, command).await {
+            Ok(message) => info!("Command {:?} from another instance: {}", command, message),
+            Err(err) => error!("Command {:?} from another instance failed: {}", command, err),
+        }
+        tray::refresh(&app_handle, &state);
+    });
+}
+
+// Listen for opened remodance:// links
+fn start_deep_link_handler(app_handle: &AppHandle) {
+    let deep_link = app_handle.deep_link();
+    
+    // Installed bundles register the scheme themselves, development builds need it at runtime
+    #[cfg(all(debug_assertions, any(windows, target_os = "linux")))]
+    if let Err(err) = deep_link.register_all() {
+        error!("Failed to register the {} URL scheme: {}", deep_links::SCHEME, err);
+    }
+    
+    let handle = app_handle.clone();
+    deep_link.on_open_url(move |event| {
+        for url in event.urls() {
+            deep_links::handle(&handle, url.as_str());
+        }
+    });
+    
+    if let Ok(Some(urls)) = deep_link.get_current() {
+        for url in urls {
+            deep_links::handle(app_handle, url.as_str());
+        }
+    }
+}
+
 // Application entry point
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    let context = tauri::generate_context!();
+    
+    // Command-line flags, e.g. `--check-in` from a script
+    let launch_args = match cli::parse(std::env::args().skip(1)) {
+        Ok(launch_args) => launch_args,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(2);
+        }
+    };
+    
+    // The status is read from disk so it works whether or not the app is running
+    if launch_args.command == Some(CliCommand::Status) {
+        match directories::BaseDirs::new() {
+            Some(dirs) => cli::print_status(&dirs.data_dir().join(&context.config().identifier)),
+            None => eprintln!("Could not find the app data directory"),
+        }
+        return;
+    }
+    
     // Create app state
     let app_state = Arc::new(AppState::default());
     
     tauri::Builder::default()
+        // Must come first: a second launch hands over to the running instance
+        // and exits before anything else starts
+        .plugin(tauri_plugin_single_instance::init(|app_handle, args, _cwd| {
+            match cli::parse(args.iter().skip(1)).map(|launch_args| launch_args.command) {
+                Ok(Some(command)) => run_forwarded_command(app_handle.clone(), command),
+                Ok(None) => {
+                    info!("Another instance was launched with {:?}, focusing this one", args);
+                    tray::show_main_window(app_handle);
+                }
+                Err(err) => error!("Ignoring command from another instance: {}", err),
+            }
+        }))
+        .plugin(tauri_plugin_deep_link::init())
         .plugin(tauri_plugin_opener::init())
-        .plugin(tauri_plugin_autostart::init(
-            tauri_plugin_autostart::MacosLauncher::LaunchAgent,
-            None // No extra args
-        ))
-        .plugin(tauri_plugin_log::Builder::default().build())
         .plugin(tauri_plugin_store::Builder::default().build())
-        .setup(|app| {
+        .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_global_shortcut::Builder::new().build())
+        .plugin(tauri_plugin_updater::Builder::new().build())
+        .setup(move |app| {
+            // Log records pass through the telemetry export on their way to the log plugin
+            let (log_plugin, max_level, logger) = tauri_plugin_log::Builder::default().split(app.handle())?;
+            app.handle().plugin(log_plugin)?;
+            if let Err(err) = telemetry::install_logger(logger, max_level) {
+                eprintln!("{}", err);
+            }
+            
             info!("Starting Remodance v{}", env!("CARGO_PKG_VERSION"));
             
             // Load settings from disk
             let app_handle = app.handle().clone();
             let state: State<'_, Arc<AppState>> = app.state();
             
-            tauri::async_runtime::block_on(async {
-                let loaded_settings = load_settings_from_store(&app_handle).await;
+            let (loaded_settings, env_settings) = tauri::async_runtime::block_on(async {
+                let loaded_settings = load_or_provision_settings(&app_handle).await;
                 
-                // Update app state with loaded settings
-                let mut settings_lock = state.settings.lock().unwrap();
-                *settings_lock = loaded_settings;
+                // Update app state with loaded settings. Later sources win:
+                // defaults, then the provisioning file on the first run or
+                // the settings store after it, then `REMODANCE_*`
+                // environment variables, then the managed policy.
+                let env_settings = env_overrides::apply(&loaded_settings, std::env::vars());
+                let policy = policy::load();
+                let settings = policy.enforce(&env_settings);
+                i18n::set_locale(settings.locale.as_deref());
+                *state.settings.lock().unwrap() = settings;
+                *state.policy.lock().unwrap() = policy;
+                (loaded_settings, env_settings)
             });
             
+            // Load the active profile's local history and resume a session left
+            // open by the last run
+            *state.profiles.lock().unwrap() = load_profiles_from_store(&app_handle);
+            let history = load_history_from_store(&app_handle);
+            if history.current_session().is_some() {
+                *state.status.lock().unwrap() = AttendanceStatus::CheckedIn;
+            }
+            *state.history.lock().unwrap() = history;
+            *state.pending.lock().unwrap() = store::load_or_default(&app_handle, PENDING_FILENAME, "pending");
+            *state.onboarding.lock().unwrap() = store::load_or_default(&app_handle, ONBOARDING_FILENAME, "onboarding");
+            *state.settings_pin.lock().unwrap() = store::load_or_default(&app_handle, PIN_FILENAME, "pin_hash");
+            *state.device_id.lock().unwrap() = load_device_id(&app_handle);
+            
+            // Note stored settings the environment or the policy replaced
+            let settings = state.settings.lock().unwrap().clone();
+            audit_settings_change(&app_handle, &state, &loaded_settings, &env_settings, ChangeSource::Environment);
+            audit_settings_change(&app_handle, &state, &env_settings, &settings, ChangeSource::Policy);
+            
+            // Started for a profile, e.g. by its login item
+            if let Some(name) = &launch_args.profile {
+                let active = state.profiles.lock().unwrap().active.clone();
+                if active.as_deref().unwrap_or(profiles::DEFAULT_PROFILE) != name {
+                    if let Err(err) = tauri::async_runtime::block_on(change_profile(&app_handle, &state, name)) {
+                        error!("Failed to start with profile '{}': {}", name, err);
+                    }
+                }
+            }
+            
+            // Launched with a command and no running instance: run it without
+            // showing anything and exit
+            if let Some(command) = launch_args.command {
+                tauri::async_runtime::spawn(async move {
+                    let state: State<'_, Arc<AppState>> = app_handle.state();
+                    let code = match cli::run(&app_handle, &state, command).await {
+                        Ok(message) => {
+                            println!("{}", message);
+                            0
+                        }
+                        Err(err) => {
+                            eprintln!("{}", err);
+                            1
+                        }
+                    };
+                    app_handle.exit(code);
+                });
+                return Ok(());
+            }
+            
+            // The window is created here rather than from the config so headless
+            // mode never loads a webview
+            let headless = launch_args.headless || state.settings.lock().unwrap().headless;
+            if headless {
+                info!("Running headless, the window opens only when asked for");
+            } else if let Err(err) = create_main_window(&app_handle) {
+                error!("Failed to create the main window: {}", err);
+            }
+            
             // Start idle monitor
             let app_handle = app.handle().clone(); // Clone to get owned AppHandle
-            start_idle_monitor(app_handle);
+            start_idle_monitor(app_handle.clone());
+            
+            // Resend events queued while the API was unreachable
+            start_queue_retry(app_handle.clone());
             
-            // Configure auto-launch
-            if let Err(err) = configure_auto_launch(app) {
-                error!("Failed to configure auto-launch: {}", err);
+            // Show the status in the system tray
+            match tray::create(&app_handle, &state) {
+                Ok(()) => tray::start_tooltip_timer(app_handle.clone()),
+                Err(err) => error!("Failed to create tray icon: {}", err),
             }
             
+            // Show notifications held back during Do-Not-Disturb once it ends
+            notifications::start_dnd_watch(app_handle.clone());
+            
+            // Register global hotkeys, conflicts are logged
+            let hotkeys = state.settings.lock().unwrap().hotkeys.clone();
+            hotkeys::register(&app_handle, &hotkeys);
+            
+            // Act on remodance:// links, including the one the app was launched with
+            start_deep_link_handler(&app_handle);
+            
+            // Check in or ask about it, as configured
+            apply_launch_behavior(app_handle.clone());
+            
+            // Announce the entities and the restored status to Home Assistant
+            home_assistant::publish_status(app_handle.clone());
+            
+            // Look for new versions on the chosen release channel
+            start_update_check(app_handle.clone());
+            
+            // Keep subscribed calendars in sync
+            spawn_periodic(app_handle.clone(), CALENDAR_SYNC_INTERVAL_SECS, "sync calendars",
+                |settings| !settings.calendar_subscriptions.is_empty(),
+                |app_handle, state| async move { sync_calendar_subscriptions(&app_handle, &state).await.map(drop) });
+            
+            // Find the employee ID for the username, when a directory is configured
+            start_employee_lookup(app_handle.clone());
+            
+            // Apply the signed settings bundle, when one is configured
+            spawn_periodic(app_handle.clone(), REMOTE_CONFIG_INTERVAL_SECS, "apply remote settings",
+                |settings| settings.config_url.is_some(),
+                |app_handle, state| async move { apply_remote_config(&app_handle, &state).await.map(drop) });
+            
+            // Keep the meetings from connected calendars current
+            spawn_periodic(app_handle.clone(), MEETING_SYNC_SECS, "refresh meetings",
+                |_| true,
+                |_, state| async move { sync_meetings(&state).await });
+            
+            // Count pushes and pull requests made elsewhere as activity
+            spawn_periodic(app_handle.clone(), CODE_ACTIVITY_SECS, "fetch the code activity",
+                |_| true,
+                |_, state| async move { poll_code_activity(&state).await });
+            
+            // Append finished days to the shared attendance sheet
+            spawn_periodic(app_handle.clone(), SHEETS_EXPORT_SECS, "export to Google Sheets",
+                |settings| settings.google_sheets.enabled && google_sheets::signed_in(),
+                |app_handle, state| async move { export_to_sheets(&app_handle, &state).await.map(drop) });
+            
+            // Email the daily or weekly summary when one is due
+            spawn_periodic(app_handle.clone(), EMAIL_REPORT_SECS, "email the attendance summary",
+                |settings| settings.email_report.enabled,
+                |app_handle, state| async move { send_due_email_report(&app_handle, &state).await });
+            
+            // Post the daily summary to the Matrix room when one is due
+            spawn_periodic(app_handle.clone(), MATRIX_SUMMARY_SECS, "post the attendance summary to Matrix",
+                |settings| settings.matrix.enabled && settings.matrix.daily_summary,
+                |app_handle, state| async move { post_due_matrix_summary(&app_handle, &state).await });
+            
+            // Put finished sessions on the CalDAV calendar
+            spawn_periodic(app_handle.clone(), CALDAV_PUBLISH_SECS, "publish to the CalDAV calendar",
+                |settings| settings.caldav.enabled,
+                |app_handle, state| async move { publish_to_caldav(&app_handle, &state).await.map(drop) });
+            
+            // Keep the Discord activity shown while Discord comes and goes
+            spawn_periodic(app_handle.clone(), DISCORD_PRESENCE_SECS, "update the Discord activity",
+                |settings| settings.discord.enabled,
+                |app_handle, _| async move {
+                    discord::update_presence(app_handle);
+                    Ok(())
+                });
+            
+            // Answer commands sent to the Telegram bot
+            start_telegram_bot(app_handle.clone());
+            
+            // Serve the local HTTP API for other desktop tools
+            start_local_api(app_handle.clone());
+            
+            // Ship collected logs and spans to the OTLP collector
+            start_telemetry_export(app_handle.clone());
+            
+            // Keep each profile's login item pointing at this executable
+            sync_autostart(&app_handle, &state);
+            
+            // Pick up settings.json edited outside the app
+            start_settings_watch(app_handle);
+            
             Ok(())
         })
+        .on_window_event(move |window, event| {
+            // The close button follows `Settings::close_behavior`. Headless, the
+            // window just closes and the app keeps running in the tray.
+            if let WindowEvent::CloseRequested { api, .. } = event {
+                let state: State<'_, Arc<AppState>> = window.state();
+                let settings = state.settings.lock().unwrap().clone();
+                if launch_args.headless || settings.headless {
+                    return;
+                }
+                
+                api.prevent_close();
+                match settings.close_behavior {
+                    CloseBehavior::Ask => request_exit(window.app_handle()),
+                    CloseBehavior::HideToTray => {
+                        if let Err(err) = window.hide() {
+                            error!("Failed to hide the main window: {}", err);
+                        }
+                    }
+                    CloseBehavior::Quit => {
+                        let app_handle = window.app_handle().clone();
+                        tauri::async_runtime::spawn(async move {
+                            let state: State<'_, Arc<AppState>> = app_handle.state();
+                            check_out_and_quit(&app_handle, &state).await;
+                        });
+                    }
+                }
+            }
+        })
         .manage(app_state)
         .invoke_handler(tauri::generate_handler![
             send_attendance_event,
             get_attendance_status,
+            start_break,
+            end_break,
+            pause_session,
+            resume_session,
+            get_break_types,
+            save_break_type,
+            delete_break_type,
+            get_break_report,
+            set_do_not_disturb,
+            get_micro_break_stats,
+            get_hotkeys,
+            save_hotkeys,
+            flush_queue,
+            get_onboarding_state,
+            complete_onboarding_step,
+            get_profiles,
+            create_profile,
+            get_profile,
+            update_profile,
+            rename_profile,
+            delete_profile,
+            switch_profile,
+            check_for_updates,
+            install_update,
             get_app_config,
             get_app_version,
             open_settings,
             save_settings,
-            is_auto_launch_enabled,
-            toggle_auto_launch,
+            reset_settings,
+            preview_settings,
+            get_audit_log,
+            get_locale,
+            get_feature_flags,
+            get_settings_schema,
+            get_teams_status,
+            teams_sign_in,
+            teams_sign_out,
+            get_google_calendar_status,
+            google_calendar_sign_in,
+            google_calendar_sign_out,
+            get_outlook_calendar_status,
+            outlook_calendar_sign_in,
+            outlook_calendar_sign_out,
+            get_current_meeting,
+            get_current_call,
+            get_google_sheets_status,
+            google_sheets_sign_in,
+            google_sheets_sign_out,
+            export_to_google_sheets,
+            send_email_report,
+            send_test_push,
+            simulate_idle,
+            dump_state,
+            force_send,
+            get_device_id,
+            regenerate_device_id,
+            get_settings_protection,
+            unlock_settings,
+            lock_settings,
+            set_settings_pin,
+            validate_settings,
+            get_settings_encryption,
+            set_settings_encryption,
+            unlock_settings_store,
+            get_secret_status,
+            save_secret,
+            export_settings,
+            import_settings,
+            get_autostart,
+            set_autostart,
+            get_holidays,
+            add_holiday,
+            remove_holiday,
+            import_holidays,
+            import_holidays_ics,
+            get_vacation,
+            set_vacation,
+            clear_vacation,
+            get_calendar_subscriptions,
+            add_calendar_subscription,
+            remove_calendar_subscription,
+            sync_calendars,
+            sync_remote_config,
+            lookup_employee_id,
+            get_break_compliance,
+            get_block_report,
+            get_summary,
+            get_today_summary,
+            get_dashboard,
+            get_status_line,
+            get_upcoming_transitions,
+            get_day_overrides,
+            set_day_override,
+            clear_day_override,
+            get_schedule_templates,
+            save_schedule_template,
+            delete_schedule_template,
+            set_active_template,
+            get_active_prompts,
+            handle_prompt_action,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(context)
+        .expect("error while building tauri application")
+        .run(move |app_handle, event| {
+            // Closing the window opened from the tray doesn't stop a headless app,
+            // only quitting from the tray does. Other requests that don't come from
+            // `exit` (e.g. Cmd+Q) ask about checking out first.
+            if let RunEvent::ExitRequested { code: None, api, .. } = event {
+                let state: State<'_, Arc<AppState>> = app_handle.state();
+                let headless = launch_args.headless || state.settings.lock().unwrap().headless;
+                let checked_out = *state.status.lock().unwrap() == AttendanceStatus::CheckedOut;
+                if headless || !checked_out {
+                    api.prevent_exit();
+                }
+                if !headless && !checked_out {
+                    request_exit(app_handle);
+                }
+            }
+        });
 }
 
 #[cfg(test)]
@@ -517,21 +4795,114 @@ mod tests {
         assert_eq!(*state.status.lock().unwrap(), AttendanceStatus::CheckedOut);
     }
 
+    #[test]
+    fn test_locked_settings_refuse_profile_switch() {
+        let state = AppState::default();
+        *state.settings_pin.lock().unwrap() = Some("hash".to_string());
+        state.profiles.lock().unwrap().save("evil", Settings { api_endpoint: "https://evil.example".to_string(), ..Settings::default() });
+
+        assert!(edit_profiles(&state, |profiles| {
+            profiles.active = Some("evil".to_string());
+            Ok(())
+        })
+        .is_err());
+        assert_eq!(state.profiles.lock().unwrap().active, None);
+        assert!(require_change_allowed(&state, ChangeSource::Ui).is_err());
+        assert!(require_change_allowed(&state, ChangeSource::App).is_ok());
+
+        *state.settings_unlocked.lock().unwrap() = true;
+        assert!(edit_profiles(&state, |profiles| {
+            profiles.active = Some("evil".to_string());
+            Ok(())
+        })
+        .is_ok());
+    }
+
+    #[test]
+    fn test_wrong_pin_locks_out_the_next_attempt() {
+        let state = AppState::default();
+        *state.settings_pin.lock().unwrap() = Some(settings_pin::hash("4821").unwrap());
+        let now = Instant::now();
+
+        assert!(try_unlock_settings(&state, "0000", now).is_err());
+        // Even the right PIN is refused until the lockout ends
+        assert!(try_unlock_settings(&state, "4821", now).is_err());
+        assert!(!*state.settings_unlocked.lock().unwrap());
+
+        assert!(try_unlock_settings(&state, "4821", now + Duration::from_secs(1)).is_ok());
+        assert!(*state.settings_unlocked.lock().unwrap());
+    }
+
+    #[test]
+    fn test_reset_only_touches_its_scope() {
+        let mut settings = Settings {
+            api_endpoint: "https://attendance.corp.example".to_string(),
+            follow_schedule: true,
+            active_template: Some("night shift".to_string()),
+            morning_reminder: false,
+            quiet_hours: Some(TimeWindow { start: NaiveTime::from_hms_opt(22, 0, 0).unwrap(), end: NaiveTime::from_hms_opt(6, 0, 0).unwrap() }),
+            micro_break_interval_mins: Some(20),
+            break_reminder_interval_mins: Some(45),
+            break_reminder_snooze_mins: 5,
+            ..Settings::default()
+        };
+        let defaults = Settings::default();
+
+        settings.reset(ResetScope::Schedule);
+        assert!(!settings.follow_schedule);
+        assert_eq!(settings.active_template, None);
+        assert_eq!(settings.quiet_hours, defaults.quiet_hours);
+        assert_eq!(settings.lunch_window, defaults.lunch_window);
+        assert_eq!(settings.api_endpoint, "https://attendance.corp.example");
+        assert!(!settings.morning_reminder);
+        assert_eq!(settings.micro_break_interval_mins, Some(20));
+
+        settings.reset(ResetScope::Notifications);
+        assert!(settings.morning_reminder);
+        assert_eq!(settings.micro_break_interval_mins, defaults.micro_break_interval_mins);
+        assert_eq!(settings.break_reminder_interval_mins, defaults.break_reminder_interval_mins);
+        assert_eq!(settings.break_reminder_snooze_mins, defaults.break_reminder_snooze_mins);
+        settings.morning_reminder = false;
+
+        settings.reset(ResetScope::All);
+        assert_eq!(settings.api_endpoint, Settings::default().api_endpoint);
+        assert!(settings.morning_reminder);
+    }
+
+    #[test]
+    fn test_saving_partial_settings_keeps_the_others() {
+        let current = Settings {
+            holidays: vec![Holiday {
+                date: NaiveDate::from_ymd_opt(2024, 12, 25).unwrap(),
+                name: "Christmas".to_string(),
+                source: HolidaySource::Manual,
+            }],
+            ..Default::default()
+        };
+        let saved = merge_settings(&current, serde_json::json!({ "username": "alice", "auto_mode": false })).unwrap();
+        assert_eq!(saved.username, "alice");
+        assert!(!saved.auto_mode);
+        assert_eq!(saved.holidays, current.holidays);
+        assert!(merge_settings(&current, serde_json::json!(["not", "an", "object"])).is_err());
+    }
+
     #[test]
     fn test_create_attendance_payload() {
         let settings = Settings {
             api_endpoint: "https://example.com/api".to_string(),
             username: "testuser".to_string(),
             device_name: "testdevice".to_string(),
-            idle_timeout_mins: 10,
+            idle_timeout_secs: 600,
             auto_mode: true,
             developer_mode: false,
+            ..Settings::default()
         };
 
-        let payload = create_attendance_payload("check-in", &settings);
+        let payload = create_attendance_payload("check-in", "5f0c6f1e-8d2a-4a53-9a51-0c3b7c9d1e22", &settings);
         
         assert_eq!(payload.user_id, "testuser");
-        assert_eq!(payload.payload.device_id, "testdevice");
+        assert_eq!(payload.payload.device_id, "5f0c6f1e-8d2a-4a53-9a51-0c3b7c9d1e22");
+        assert_eq!(payload.payload.device_name, "testdevice");
         
         // Validate time format (HH:MM:SS)
         let time_parts: Vec<&str> = payload.payload.time.split(':').collect();
@@ -542,17 +4913,195 @@ mod tests {
         assert_eq!(date_parts.len(), 3);
     }
 
+    #[test]
+    fn test_payload_identifies_employee_and_org() {
+        let settings = Settings {
+            username: "jdoe".to_string(),
+            employee_id: Some("E-1042".to_string()),
+            org_id: Some("acme".to_string()),
+            ..Settings::default()
+        };
+        let payload = serde_json::to_value(create_attendance_payload("check-in", "device-1", &settings)).unwrap();
+        assert_eq!(payload["user_id"], "E-1042");
+        assert_eq!(payload["org_id"], "acme");
+        
+        let payload = serde_json::to_value(create_break_payload("break-start", "device-1", &Settings::default(), "coffee", None)).unwrap();
+        assert!(payload.get("org_id").is_none());
+    }
+
+    #[test]
+    fn test_break_payload_serialization() {
+        let settings = Settings {
+            username: "testuser".to_string(),
+            device_name: "testdevice".to_string(),
+            ..Settings::default()
+        };
+
+        let start = serde_json::to_value(create_break_payload("break-start", "device-1", &settings, "coffee", None)).unwrap();
+        assert_eq!(start["event_type"], "break-start");
+        assert_eq!(start["payload"]["type"], "coffee");
+        assert_eq!(start["payload"]["planned_mins"], 15);
+        assert!(start["payload"].get("actual_mins").is_none());
+
+        let end = serde_json::to_value(create_break_payload("break-end", "device-1", &settings, "meeting", Some(42))).unwrap();
+        assert_eq!(end["payload"]["actual_mins"], 42);
+        assert!(end["payload"].get("planned_mins").is_none());
+    }
+
+    #[test]
+    fn test_attendance_changed_serialization() {
+        let event = AttendanceChanged {
+            status: AttendanceStatus::OnBreak.label(),
+            event_type: "break-start".to_string(),
+            cause: ChangeCause::Auto,
+            timestamp: Utc::now(),
+            session_secs: Some(5400),
+        };
+
+        let value = serde_json::to_value(event).unwrap();
+        assert_eq!(value["status"], "on-break");
+        assert_eq!(value["cause"], "auto");
+        assert_eq!(value["session_secs"], 5400);
+    }
+
+    #[test]
+    fn test_vacation_contains_range() {
+        let date = |s| NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap();
+        let vacation = Vacation {
+            from: date("2025-07-01"),
+            to: date("2025-07-14"),
+            notify_api: true,
+            announced: false,
+        };
+
+        assert!(vacation.contains(date("2025-07-01")));
+        assert!(vacation.contains(date("2025-07-14")));
+        assert!(!vacation.contains(date("2025-07-15")));
+    }
+
+    #[test]
+    fn test_checkout_warning_answers() {
+        let state = AppState::default();
+        let today = NaiveDate::from_ymd_opt(2025, 3, 10).unwrap();
+        let now = today.and_hms_opt(10, 0, 0).unwrap().and_utc().fixed_offset();
+        *state.status.lock().unwrap() = AttendanceStatus::CheckedIn;
+
+        // "Still here" cancels the pending idle check-out
+        state.active_prompts.lock().unwrap().push(prompts::pre_checkout(30));
+        take_prompt(&state, prompts::PRE_CHECKOUT_PROMPT, prompts::ACTION_STILL_HERE).unwrap();
+        assert!(!answer_checkout_warning(&state, prompts::ACTION_STILL_HERE, today));
+        assert!(idle_since_still_here(&state, Duration::from_secs(600)) < Duration::from_secs(5));
+        assert!(state.active_prompts.lock().unwrap().is_empty());
+
+        // Confirming checks out and keeps auto check-in off for the day
+        state.active_prompts.lock().unwrap().push(prompts::pre_checkout(30));
+        take_prompt(&state, prompts::PRE_CHECKOUT_PROMPT, prompts::ACTION_CONFIRM_CHECK_OUT).unwrap();
+        assert!(answer_checkout_warning(&state, prompts::ACTION_CONFIRM_CHECK_OUT, today));
+        assert!(!may_auto_check_in(&state, &Settings::default(), now));
+
+        // Already checked out: nothing left to confirm
+        *state.status.lock().unwrap() = AttendanceStatus::CheckedOut;
+        assert!(!answer_checkout_warning(&state, prompts::ACTION_CONFIRM_CHECK_OUT, today));
+    }
+
+    #[test]
+    fn test_manual_checkout_resets_on_new_day() {
+        let state = AppState::default();
+        let date = |s| NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap();
+        *state.manual_checkout.lock().unwrap() = true;
+        *state.manual_checkout_date.lock().unwrap() = Some(date("2025-03-10"));
+
+        reset_manual_checkout_if_new_day(&state, date("2025-03-10"));
+        assert!(*state.manual_checkout.lock().unwrap());
+
+        reset_manual_checkout_if_new_day(&state, date("2025-03-11"));
+        assert!(!*state.manual_checkout.lock().unwrap());
+    }
+
+    #[test]
+    fn test_daily_limit_blocks_auto_check_in_until_overridden() {
+        let state = AppState::default();
+        let today = NaiveDate::from_ymd_opt(2025, 3, 10).unwrap();
+        assert!(!daily_limit_blocks_check_in(&state, today));
+
+        *state.daily_limit.lock().unwrap() = Some(DailyLimit { date: today, overridden: false });
+        assert!(daily_limit_blocks_check_in(&state, today));
+        assert!(!daily_limit_blocks_check_in(&state, today.succ_opt().unwrap()));
+
+        state.daily_limit.lock().unwrap().as_mut().unwrap().overridden = true;
+        assert!(!daily_limit_blocks_check_in(&state, today));
+    }
+
+    #[test]
+    fn test_may_auto_check_in() {
+        let state = AppState::default();
+        let at = |hour| NaiveDate::from_ymd_opt(2025, 3, 10).unwrap().and_hms_opt(hour, 0, 0).unwrap().and_utc().fixed_offset();
+        let settings = Settings {
+            quiet_hours: Some(TimeWindow { start: NaiveTime::from_hms_opt(22, 0, 0).unwrap(), end: NaiveTime::from_hms_opt(6, 0, 0).unwrap() }),
+            ..Settings::default()
+        };
+        assert!(may_auto_check_in(&state, &settings, at(10)));
+        assert!(!may_auto_check_in(&state, &settings, at(23)));
+
+        let on_vacation = Settings {
+            vacation: Some(Vacation {
+                from: NaiveDate::from_ymd_opt(2025, 3, 8).unwrap(),
+                to: NaiveDate::from_ymd_opt(2025, 3, 14).unwrap(),
+                notify_api: false,
+                announced: false,
+            }),
+            ..settings.clone()
+        };
+        assert!(!may_auto_check_in(&state, &on_vacation, at(10)));
+
+        mark_manual_checkout(&state, at(10).date_naive());
+        assert!(!may_auto_check_in(&state, &settings, at(10)));
+    }
+
+    #[test]
+    fn test_remind_once_per_day() {
+        let state = AppState::default();
+        let today = NaiveDate::from_ymd_opt(2025, 3, 10).unwrap();
+
+        assert!(remind_once(&state, "end-of-day", today));
+        assert!(!remind_once(&state, "end-of-day", today));
+        assert!(remind_once(&state, "morning", today));
+        assert!(remind_once(&state, "end-of-day", today.succ_opt().unwrap()));
+    }
+
+    #[test]
+    fn test_effective_schedule_uses_active_template() {
+        let on_call = ScheduleTemplate {
+            name: "On-call".to_string(),
+            schedule: WeeklySchedule {
+                sat: WeeklySchedule::default().mon,
+                ..WeeklySchedule::default()
+            },
+        };
+        let mut settings = Settings {
+            schedule_templates: vec![on_call.clone()],
+            ..Settings::default()
+        };
+        assert_eq!(settings.effective_schedule(), &WeeklySchedule::default());
+
+        settings.active_template = Some("On-call".to_string());
+        assert_eq!(settings.effective_schedule(), &on_call.schedule);
+
+        settings.active_template = Some("Missing".to_string());
+        assert_eq!(settings.effective_schedule(), &WeeklySchedule::default());
+    }
+
     #[test]
     fn test_format_current_time() {
-        let now = Local::now();
-        let formatted = format_current_time();
+        let now = chrono::Local::now();
+        let formatted = format_current_time(None);
         assert_eq!(formatted, now.format("%H:%M:%S").to_string());
     }
 
     #[test]
     fn test_format_current_date() {
-        let now = Local::now();
-        let formatted = format_current_date();
+        let now = chrono::Local::now();
+        let formatted = format_current_date(None);
         assert_eq!(formatted, now.format("%Y-%m-%d").to_string());
     }
 } 
@@ -0,0 +1,374 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures_util::{SinkExt, StreamExt};
+use log::{debug, error, info};
+use once_cell::sync::Lazy;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tauri::{AppHandle, Manager, State};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast;
+use tokio_tungstenite::tungstenite::handshake::derive_accept_key;
+use tokio_tungstenite::tungstenite::protocol::Role;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::WebSocketStream;
+
+use crate::{secrets, AppState};
+
+// Requests are small, anything bigger is refused
+const MAX_REQUEST_BYTES: usize = 8192;
+// How long a client may take to send its request
+const READ_TIMEOUT_SECS: u64 = 5;
+// How often the listener checks whether its settings changed
+const SETTINGS_CHECK_SECS: u64 = 5;
+// Updates a slow WebSocket client may fall behind by before it misses some
+const UPDATE_BUFFER: usize = 64;
+
+// Updates for WebSocket clients, see `broadcast`
+static UPDATES: Lazy<broadcast::Sender<String>> = Lazy::new(|| broadcast::channel(UPDATE_BUFFER).0);
+
+// A local HTTP API for Stream Deck plugins, scripts and other desktop tools,
+// with a WebSocket at `/events` streaming updates to widgets and overlays.
+// Every request needs the token from the secrets, see
+// `Secrets::local_api_token`, as `Authorization: Bearer <token>` or, for
+// browsers that can't set headers on a WebSocket, as `?token=<token>`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+#[serde(default)]
+pub struct LocalApiSettings {
+    pub enabled: bool,
+    pub bind_address: String, // Other tools on the network can reach anything but a loopback address
+    pub port: u16,
+}
+
+impl Default for LocalApiSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind_address: "127.0.0.1".to_string(),
+            port: 7483,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Route {
+    Status,
+    CheckIn,
+    CheckOut,
+    History,
+    Events, // WebSocket of live updates
+    Preflight, // CORS preflight of a browser-based tool
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Request {
+    pub method: String,
+    pub path: String, // Without the query
+    pub token: Option<String>,
+    pub websocket_key: Option<String>, // `Sec-WebSocket-Key` of an upgrade request
+}
+
+// The request line, token and WebSocket key from the head of a request
+pub fn parse_request(head: &str) -> Result<Request, String> {
+    let mut lines = head.lines();
+    let mut request_line = lines.next().unwrap_or_default().split_whitespace();
+    let (Some(method), Some(target)) = (request_line.next(), request_line.next()) else {
+        return Err("Malformed request line".to_string());
+    };
+    let headers: Vec<(&str, &str)> = lines.filter_map(|line| line.split_once(':')).map(|(name, value)| (name.trim(), value.trim())).collect();
+    let header = |wanted: &str| headers.iter().find(|(name, _)| name.eq_ignore_ascii_case(wanted)).map(|(_, value)| *value);
+
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let query_token = url::form_urlencoded::parse(query.as_bytes()).find(|(key, _)| key == "token").map(|(_, value)| value.into_owned());
+    let token = header("authorization").and_then(|value| value.strip_prefix("Bearer ")).map(|token| token.trim().to_string()).or(query_token);
+    let upgrade = header("upgrade").is_some_and(|value| value.eq_ignore_ascii_case("websocket"));
+    Ok(Request {
+        method: method.to_string(),
+        path: path.to_string(),
+        token,
+        websocket_key: header("sec-websocket-key").filter(|_| upgrade).map(str::to_string),
+    })
+}
+
+// An update as sent to WebSocket clients, tagged with its `type`
+pub fn update_message(kind: &str, mut data: Value) -> Value {
+    if let Some(fields) = data.as_object_mut() {
+        fields.insert("type".to_string(), Value::from(kind));
+    }
+    data
+}
+
+// Send an update to the connected WebSocket clients, if any
+pub fn broadcast(kind: &str, data: Value) {
+    let _ = UPDATES.send(update_message(kind, data).to_string());
+}
+
+// What a request asks for, or the status to refuse it with
+pub fn route(method: &str, path: &str) -> Result<Route, u16> {
+    let route = match path.trim_end_matches('/') {
+        "/status" => Route::Status,
+        "/check-in" => Route::CheckIn,
+        "/check-out" => Route::CheckOut,
+        "/history" => Route::History,
+        "/events" => Route::Events,
+        _ => return Err(404),
+    };
+    let read_only = matches!(route, Route::Status | Route::History | Route::Events);
+    match method {
+        "OPTIONS" => Ok(Route::Preflight),
+        "GET" if read_only => Ok(route),
+        "POST" if !read_only => Ok(route),
+        _ => Err(405),
+    }
+}
+
+// Compared in constant time, so the token can't be guessed byte by byte
+pub fn authorized(given: Option<&str>, expected: &str) -> bool {
+    let Some(given) = given else {
+        return false;
+    };
+    given.len() == expected.len() && given.bytes().zip(expected.bytes()).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        204 => "No Content",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Payload Too Large",
+        _ => "Internal Server Error",
+    }
+}
+
+// A complete response, closing the connection after it
+pub fn http_response(status: u16, body: Option<&Value>) -> String {
+    let body = body.map(Value::to_string).unwrap_or_default();
+    format!(
+        "HTTP/1.1 {} {}\r\n\
+         Content-Type: application/json\r\n\
+         Content-Length: {}\r\n\
+         Access-Control-Allow-Origin: *\r\n\
+         Access-Control-Allow-Methods: GET, POST, OPTIONS\r\n\
+         Access-Control-Allow-Headers: Authorization\r\n\
+         Connection: close\r\n\r\n{}",
+        status,
+        reason(status),
+        body.len(),
+        body
+    )
+}
+
+fn error_body(message: &str) -> Value {
+    json!({ "error": message })
+}
+
+// Read the head of a request, up to the blank line. Bodies are ignored.
+async fn read_head(stream: &mut TcpStream) -> Result<String, u16> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0; 1024];
+    while !buffer.windows(4).any(|window| window == b"\r\n\r\n") {
+        let read = stream.read(&mut chunk).await.map_err(|_| 400u16)?;
+        if read == 0 {
+            return Err(400);
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+        if buffer.len() > MAX_REQUEST_BYTES {
+            return Err(413);
+        }
+    }
+    Ok(String::from_utf8_lossy(&buffer).into_owned())
+}
+
+// The current status, as answered to requests and sent to new WebSocket clients
+fn status(state: &AppState) -> Value {
+    let status = state.status.lock().unwrap().label();
+    json!({
+        "status": status,
+        "status_line": crate::tray::current_status_line(state),
+        "pending": state.pending.lock().unwrap().len(),
+    })
+}
+
+// Stream updates to a WebSocket client until it goes away, starting with the
+// current status
+async fn stream_updates(app_handle: &AppHandle, stream: TcpStream) {
+    let mut updates = UPDATES.subscribe();
+    let mut socket = WebSocketStream::from_raw_socket(stream, Role::Server, None).await;
+    let snapshot = update_message("status", status(&app_handle.state::<Arc<AppState>>()));
+    if socket.send(Message::text(snapshot.to_string())).await.is_err() {
+        return;
+    }
+
+    loop {
+        tokio::select! {
+            update = updates.recv() => match update {
+                Ok(update) => {
+                    if socket.send(Message::text(update)).await.is_err() {
+                        return;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(missed)) => debug!("A WebSocket client missed {} update(s)", missed),
+                Err(broadcast::error::RecvError::Closed) => return,
+            },
+            // Clients only listen, reading answers their pings and notices them leave
+            incoming = socket.next() => match incoming {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
+
+// Run a request through the same handlers as the UI
+async fn respond(app_handle: &AppHandle, route: Route) -> (u16, Option<Value>) {
+    let state: State<'_, Arc<AppState>> = app_handle.state();
+    let result = match route {
+        Route::Preflight | Route::Events => return (204, None),
+        Route::History => {
+            let history = state.history.lock().unwrap().clone();
+            return (200, Some(json!({ "sessions": history.sessions, "breaks": history.breaks })));
+        }
+        Route::Status => Ok(()),
+        Route::CheckIn => crate::set_attendance_with_note(app_handle, &state, "check-in", None).await,
+        Route::CheckOut => crate::set_attendance_with_note(app_handle, &state, "check-out", None).await,
+    };
+    if route != Route::Status {
+        crate::tray::refresh(app_handle, &state);
+    }
+
+    match result {
+        Ok(()) => (200, Some(status(&state))),
+        Err(err) => (500, Some(error_body(&err))),
+    }
+}
+
+async fn handle(app_handle: AppHandle, mut stream: TcpStream) {
+    let head = tokio::time::timeout(Duration::from_secs(READ_TIMEOUT_SECS), read_head(&mut stream)).await.unwrap_or(Err(400));
+    let (status, body) = match head.map(|head| parse_request(&head).map_err(|_| 400u16)) {
+        Ok(Ok(request)) => match route(&request.method, &request.path) {
+            Ok(Route::Preflight) => (204, None),
+            Ok(route) => {
+                let expected = secrets::current(&app_handle.state::<Arc<AppState>>()).unwrap_or_default().local_api_token.unwrap_or_default();
+                if expected.is_empty() || !authorized(request.token.as_deref(), &expected) {
+                    (401, Some(error_body("Missing or wrong token")))
+                } else if route == Route::Events {
+                    let Some(key) = &request.websocket_key else {
+                        return answer(&mut stream, 400, Some(&error_body("Expected a WebSocket upgrade"))).await;
+                    };
+                    let handshake = format!(
+                        "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+                        derive_accept_key(key.as_bytes())
+                    );
+                    if stream.write_all(handshake.as_bytes()).await.is_ok() {
+                        info!("WebSocket client connected to the local API");
+                        stream_updates(&app_handle, stream).await;
+                    }
+                    return;
+                } else {
+                    info!("Local API request: {} {}", request.method, request.path);
+                    respond(&app_handle, route).await
+                }
+            }
+            Err(status) => (status, Some(error_body(reason(status)))),
+        },
+        Ok(Err(status)) | Err(status) => (status, Some(error_body(reason(status)))),
+    };
+    answer(&mut stream, status, body.as_ref()).await;
+}
+
+async fn answer(stream: &mut TcpStream, status: u16, body: Option<&Value>) {
+    if let Err(err) = stream.write_all(http_response(status, body).as_bytes()).await {
+        debug!("Failed to answer a local API request: {}", err);
+    }
+}
+
+// Serve requests until the settings change, then return so the caller can
+// start over with the new ones
+pub async fn listen(app_handle: &AppHandle, settings: &LocalApiSettings) -> Result<(), String> {
+    let address = format!("{}:{}", settings.bind_address.trim(), settings.port);
+    let listener = TcpListener::bind(&address).await.map_err(|err| format!("Failed to listen on {}: {}", address, err))?;
+    info!("Local API listening on {}", address);
+
+    let state: State<'_, Arc<AppState>> = app_handle.state();
+    let mut check = tokio::time::interval(Duration::from_secs(SETTINGS_CHECK_SECS));
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => {
+                    tauri::async_runtime::spawn(handle(app_handle.clone(), stream));
+                }
+                Err(err) => error!("Failed to accept a local API connection: {}", err),
+            },
+            _ = check.tick() => {
+                if state.settings.lock().unwrap().local_api != *settings {
+                    info!("Local API settings changed, stopped listening on {}", address);
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_request() {
+        let head = "POST /check-in?source=streamdeck HTTP/1.1\r\nHost: 127.0.0.1:7483\r\nauthorization: Bearer s3cret \r\n\r\n";
+        assert_eq!(
+            parse_request(head),
+            Ok(Request { method: "POST".to_string(), path: "/check-in".to_string(), token: Some("s3cret".to_string()), websocket_key: None })
+        );
+        assert_eq!(parse_request("GET /status HTTP/1.1\r\n\r\n").unwrap().token, None);
+        assert!(parse_request("\r\n\r\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_websocket_upgrade() {
+        let head = "GET /events?token=s3%2Fcret HTTP/1.1\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\r\n";
+        let request = parse_request(head).unwrap();
+        assert_eq!(request.path, "/events");
+        assert_eq!(request.token.as_deref(), Some("s3/cret"));
+        assert_eq!(request.websocket_key.as_deref(), Some("dGhlIHNhbXBsZSBub25jZQ=="));
+        assert_eq!(route("GET", &request.path), Ok(Route::Events));
+        assert_eq!(derive_accept_key(b"dGhlIHNhbXBsZSBub25jZQ=="), "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=");
+    }
+
+    #[test]
+    fn test_update_message() {
+        assert_eq!(update_message("countdown", json!({ "secs_left": 30 })), json!({ "type": "countdown", "secs_left": 30 }));
+    }
+
+    #[test]
+    fn test_route() {
+        assert_eq!(route("GET", "/status"), Ok(Route::Status));
+        assert_eq!(route("POST", "/check-out/"), Ok(Route::CheckOut));
+        assert_eq!(route("GET", "/check-in"), Err(405));
+        assert_eq!(route("POST", "/history"), Err(405));
+        assert_eq!(route("OPTIONS", "/check-in"), Ok(Route::Preflight));
+        assert_eq!(route("GET", "/settings"), Err(404));
+    }
+
+    #[test]
+    fn test_authorized() {
+        assert!(authorized(Some("s3cret"), "s3cret"));
+        assert!(!authorized(Some("s3cres"), "s3cret"));
+        assert!(!authorized(Some("s3"), "s3cret"));
+        assert!(!authorized(None, "s3cret"));
+    }
+
+    #[test]
+    fn test_http_response() {
+        let response = http_response(401, Some(&error_body("Missing or wrong token")));
+        assert!(response.starts_with("HTTP/1.1 401 Unauthorized\r\n"));
+        assert!(response.contains("Content-Length: 34\r\n"));
+        assert!(response.ends_with("\r\n\r\n{\"error\":\"Missing or wrong token\"}"));
+    }
+}
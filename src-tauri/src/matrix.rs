@@ -0,0 +1,124 @@
+use log::{error, info};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use url::Url;
+
+use crate::i18n::tf;
+use crate::{clock, secrets, AppState};
+
+// A Matrix room attendance changes and daily summaries are posted to, for
+// teams that self-host their chat. The access token of the account posting
+// them is kept with the other secrets, see `Secrets::matrix_access_token`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+#[serde(default)]
+pub struct MatrixSettings {
+    pub enabled: bool,
+    pub homeserver: String, // e.g. https://matrix.example.org
+    pub room_id: String, // e.g. !abcdef:example.org, not an alias
+    pub attendance: bool, // Post check-ins, check-outs and breaks
+    pub daily_summary: bool, // Post the summary of each finished day
+}
+
+impl Default for MatrixSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            homeserver: String::new(),
+            room_id: String::new(),
+            attendance: true,
+            daily_summary: true,
+        }
+    }
+}
+
+// Where a message is sent. The transaction ID makes retries of the same
+// message idempotent.
+pub fn message_url(homeserver: &str, room_id: &str, txn_id: &str) -> Result<Url, String> {
+    let mut url = Url::parse(homeserver.trim()).map_err(|err| format!("Invalid homeserver URL: {}", err))?;
+    url.path_segments_mut()
+        .map_err(|_| "Invalid homeserver URL".to_string())?
+        .pop_if_empty()
+        .extend(["_matrix", "client", "v3", "rooms", room_id.trim(), "send", "m.room.message", txn_id]);
+    Ok(url)
+}
+
+// Notices rather than text messages, so bots in the room don't answer them
+pub fn notice(body: &str) -> Value {
+    json!({ "msgtype": "m.notice", "body": body })
+}
+
+// The line posted for an attendance event, none for events that aren't posted
+pub fn attendance_line(event_type: &str, name: &str, time: &str) -> Option<String> {
+    let key = match event_type {
+        "check-in" => "matrix.check_in",
+        "check-out" => "matrix.check_out",
+        "break-start" => "matrix.break_start",
+        "break-end" => "matrix.break_end",
+        _ => return None,
+    };
+    Some(tf(key, &[("name", &name), ("time", &time)]))
+}
+
+pub async fn send(settings: &MatrixSettings, access_token: Option<&str>, body: &str) -> Result<(), String> {
+    let access_token = access_token.ok_or("No Matrix access token is set")?;
+    let url = message_url(&settings.homeserver, &settings.room_id, &uuid::Uuid::new_v4().simple().to_string())?;
+    let response = reqwest::Client::new()
+        .put(url)
+        .bearer_auth(access_token)
+        .json(&notice(body))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send request: {}", e))?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let body: Value = response.json().await.unwrap_or_default();
+        return Err(match body["error"].as_str() {
+            Some(error) => format!("Matrix rejected the message with status {}: {}", status, error),
+            None => format!("Matrix request failed with status {}", status),
+        });
+    }
+    Ok(())
+}
+
+// Post an attendance event to the room in the background, when enabled
+pub fn post_attendance(state: &AppState, event_type: &str) {
+    let settings = state.settings.lock().unwrap().clone();
+    if !settings.matrix.enabled || !settings.matrix.attendance {
+        return;
+    }
+    let time = clock::now(settings.timezone.as_deref()).format("%H:%M").to_string();
+    let Some(line) = attendance_line(event_type, &settings.username, &time) else {
+        return;
+    };
+
+    let access_token = secrets::current(state).unwrap_or_default().matrix_access_token;
+    tauri::async_runtime::spawn(async move {
+        match send(&settings.matrix, access_token.as_deref(), &line).await {
+            Ok(()) => info!("Posted '{}' to Matrix", line),
+            Err(err) => error!("Failed to post to Matrix: {}", err),
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message_url() {
+        let url = message_url("https://matrix.example.org/", "!abc:example.org", "t1").unwrap();
+        assert_eq!(url.as_str(), "https://matrix.example.org/_matrix/client/v3/rooms/!abc:example.org/send/m.room.message/t1");
+        let url = message_url("https://example.org/matrix", "!a/b:example.org", "t1").unwrap();
+        assert_eq!(url.path(), "/matrix/_matrix/client/v3/rooms/!a%2Fb:example.org/send/m.room.message/t1");
+        assert!(message_url("matrix.example.org", "!abc:example.org", "t1").is_err());
+    }
+
+    #[test]
+    fn test_attendance_line() {
+        assert!(attendance_line("check-in", "alice", "09:01").is_some());
+        assert_eq!(attendance_line("pause", "alice", "09:01"), None);
+        assert_eq!(notice("alice checked in")["msgtype"], json!("m.notice"));
+    }
+}
@@ -0,0 +1,77 @@
+use std::future::Future;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+// An accepted meeting from a connected calendar. Time inside one counts as
+// "in a meeting": call attendees rarely touch the keyboard, so the idle
+// monitor doesn't check them out.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct Meeting {
+    pub source: &'static str, // Calendar it came from, e.g. "google"
+    pub title: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+// A calendar meetings are read from. Every backend is synced the same way,
+// so busy detection and heartbeat tagging don't depend on where a meeting
+// came from.
+pub trait CalendarProvider {
+    // `Meeting::source` of this calendar's meetings
+    fn source(&self) -> &'static str;
+    fn enabled(&self) -> bool;
+    // Whether `activity_update` carries `in_meeting`
+    fn tag_heartbeats(&self) -> bool;
+    fn signed_in(&self) -> bool;
+    // Accepted meetings between `from` and `to`
+    fn fetch_meetings(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> impl Future<Output = Result<Vec<Meeting>, String>> + Send;
+}
+
+// The meeting running at `now`, the one ending last when they overlap
+pub fn current(meetings: &[Meeting], now: DateTime<Utc>) -> Option<&Meeting> {
+    meetings
+        .iter()
+        .filter(|meeting| meeting.start <= now && now < meeting.end)
+        .max_by_key(|meeting| meeting.end)
+}
+
+// Replace the meetings from one calendar, keeping the others
+pub fn replace(meetings: &mut Vec<Meeting>, source: &str, fetched: Vec<Meeting>) {
+    meetings.retain(|meeting| meeting.source != source);
+    meetings.extend(fetched);
+    meetings.sort_by_key(|meeting| meeting.start);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn meeting(source: &'static str, title: &str, start: DateTime<Utc>, mins: i64) -> Meeting {
+        Meeting { source, title: title.to_string(), start, end: start + Duration::minutes(mins) }
+    }
+
+    #[test]
+    fn test_current_meeting() {
+        let now = Utc::now();
+        let meetings = vec![
+            meeting("google", "Standup", now - Duration::minutes(10), 15),
+            meeting("google", "Planning", now - Duration::minutes(5), 60),
+            meeting("google", "Later", now + Duration::minutes(30), 30),
+        ];
+        assert_eq!(current(&meetings, now).map(|meeting| meeting.title.as_str()), Some("Planning"));
+        assert_eq!(current(&meetings, now + Duration::minutes(56)).map(|meeting| meeting.title.as_str()), Some("Later"));
+        assert_eq!(current(&meetings, now + Duration::minutes(61)), None);
+    }
+
+    #[test]
+    fn test_replace_keeps_other_sources() {
+        let now = Utc::now();
+        let mut meetings = vec![meeting("google", "Old", now, 30), meeting("outlook", "Review", now, 30)];
+        replace(&mut meetings, "google", vec![meeting("google", "New", now - Duration::minutes(5), 30)]);
+
+        let titles: Vec<_> = meetings.iter().map(|meeting| meeting.title.as_str()).collect();
+        assert_eq!(titles, vec!["New", "Review"]);
+    }
+}
@@ -0,0 +1,140 @@
+use log::warn;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+
+// Version of the stored settings layout. Bump it and add a migration to
+// `MIGRATIONS` whenever a field is renamed, moved or changes type.
+pub const SCHEMA_VERSION: u64 = 2;
+
+// `MIGRATIONS[n]` turns settings stored with version `n` into version `n + 1`
+const MIGRATIONS: [fn(&mut Value); SCHEMA_VERSION as usize] = [from_unversioned, idle_timeout_in_secs];
+
+// Settings written before versioning have the same layout as version 1
+fn from_unversioned(_settings: &mut Value) {}
+
+// Version 2 keeps the idle timeout in seconds instead of whole minutes
+fn idle_timeout_in_secs(settings: &mut Value) {
+    let Some(fields) = settings.as_object_mut() else {
+        return;
+    };
+    let Some(mins) = fields.remove("idle_timeout_mins") else {
+        return;
+    };
+    match mins.as_f64() {
+        Some(mins) if mins >= 0.0 => {
+            fields.insert("idle_timeout_secs".to_string(), Value::from((mins * 60.0).round() as u64));
+        }
+        _ => warn!("Dropping idle_timeout_mins {}: not a number of minutes", mins),
+    }
+}
+
+// Bring stored settings up to the current version. Returns whether anything
+// was migrated, so the caller knows to write them back.
+pub fn migrate(settings: &mut Value, version: u64) -> bool {
+    if version > SCHEMA_VERSION {
+        warn!("Settings were saved by a newer version (schema {}), reading what is understood", version);
+        return false;
+    }
+
+    for migration in &MIGRATIONS[version as usize..] {
+        migration(settings);
+    }
+    version < SCHEMA_VERSION
+}
+
+// Bring partial settings without a version up to date, such as a policy, a
+// remote bundle or a saved profile. Migrations only touch the fields they
+// find, so values already in the current layout are left alone.
+pub fn upgrade_partial(settings: &mut Value) {
+    for migration in &MIGRATIONS {
+        migration(settings);
+    }
+}
+
+// Read stored settings, keeping every field that still parses when the whole
+// object doesn't. Fields that fail are reset to their default and returned by
+// name, so one bad value doesn't wipe the rest.
+pub fn read_lenient<T: Serialize + DeserializeOwned + Default>(stored: &Value) -> (T, Vec<String>) {
+    if let Ok(value) = serde_json::from_value(stored.clone()) {
+        return (value, Vec::new());
+    }
+
+    let Ok(mut merged) = serde_json::to_value(T::default()) else {
+        return (T::default(), Vec::new());
+    };
+    let mut dropped = Vec::new();
+    if let Some(fields) = stored.as_object() {
+        for (key, value) in fields {
+            let previous = merged.get(key).cloned();
+            merged[key.as_str()] = value.clone();
+            if serde_json::from_value::<T>(merged.clone()).is_err() {
+                match previous {
+                    Some(previous) => merged[key.as_str()] = previous,
+                    None => {
+                        if let Some(fields) = merged.as_object_mut() {
+                            fields.remove(key);
+                        }
+                    }
+                }
+                dropped.push(key.clone());
+            }
+        }
+    }
+
+    let value = serde_json::from_value(merged).unwrap_or_default();
+    (value, dropped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use serde_json::json;
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    #[serde(default)]
+    struct Example {
+        endpoint: String,
+        timeout: u64,
+    }
+
+    impl Default for Example {
+        fn default() -> Self {
+            Self { endpoint: "https://example.com".to_string(), timeout: 10 }
+        }
+    }
+
+    #[test]
+    fn test_migrate_reports_upgrades_only() {
+        let mut settings = json!({ "timeout": 5 });
+        assert!(migrate(&mut settings, 0));
+        assert!(!migrate(&mut settings, SCHEMA_VERSION));
+        assert!(!migrate(&mut settings, SCHEMA_VERSION + 1));
+        assert_eq!(settings, json!({ "timeout": 5 }));
+    }
+
+    #[test]
+    fn test_idle_timeout_moves_to_seconds() {
+        let mut settings = json!({ "idle_timeout_mins": 10, "auto_mode": true });
+        assert!(migrate(&mut settings, 1));
+        assert_eq!(settings, json!({ "idle_timeout_secs": 600, "auto_mode": true }));
+
+        let mut partial = json!({ "idle_timeout_mins": 0.5 });
+        upgrade_partial(&mut partial);
+        assert_eq!(partial, json!({ "idle_timeout_secs": 30 }));
+
+        let mut current = json!({ "idle_timeout_secs": 45 });
+        upgrade_partial(&mut current);
+        assert_eq!(current, json!({ "idle_timeout_secs": 45 }));
+    }
+
+    #[test]
+    fn test_bad_field_does_not_wipe_the_rest() {
+        let stored = json!({ "endpoint": "https://api.internal", "timeout": "ten" });
+        let (example, dropped): (Example, _) = read_lenient(&stored);
+
+        assert_eq!(example, Example { endpoint: "https://api.internal".to_string(), timeout: 10 });
+        assert_eq!(dropped, vec!["timeout"]);
+    }
+}
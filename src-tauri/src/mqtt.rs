@@ -0,0 +1,176 @@
+use std::time::Duration;
+
+use rumqttc::{AsyncClient, Event, MqttOptions, Outgoing, Packet, Publish, QoS, TlsConfiguration, Transport};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+// Give up on a broker that doesn't acknowledge within this time
+const PUBLISH_TIMEOUT: Duration = Duration::from_secs(15);
+
+// The MQTT broker shared by everything publishing over MQTT. The password is
+// kept with the other secrets, see `Secrets::mqtt_password`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+#[serde(default)]
+pub struct MqttSettings {
+    pub host: String,
+    pub port: u16,
+    pub tls: bool,
+    pub username: Option<String>, // No authentication when unset
+}
+
+impl Default for MqttSettings {
+    fn default() -> Self {
+        Self {
+            host: String::new(),
+            port: 1883,
+            tls: false,
+            username: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MqttMessage {
+    pub topic: String,
+    pub payload: String,
+    pub retain: bool,
+}
+
+fn options(settings: &MqttSettings, password: Option<&str>) -> Result<MqttOptions, String> {
+    let host = settings.host.trim();
+    if host.is_empty() {
+        return Err("No MQTT broker is configured".to_string());
+    }
+
+    // Each publish uses its own short-lived connection, so the ID only has to be unique
+    let client_id = format!("remodance-{}", uuid::Uuid::new_v4().simple());
+    let mut options = MqttOptions::new(client_id, host, settings.port);
+    options.set_keep_alive(Duration::from_secs(30));
+    if settings.tls {
+        options.set_transport(Transport::tls_with_config(TlsConfiguration::Native));
+    }
+    if let Some(username) = &settings.username {
+        options.set_credentials(username, password.unwrap_or_default());
+    }
+    Ok(options)
+}
+
+// The PUBLISH packet a message is sent as, delivered at least once
+fn publish_packet(message: MqttMessage) -> Publish {
+    let mut packet = Publish::new(message.topic, QoS::AtLeastOnce, message.payload);
+    packet.retain = message.retain;
+    packet
+}
+
+// Connect, publish the messages at least once and disconnect again
+pub async fn publish(settings: &MqttSettings, password: Option<&str>, messages: Vec<MqttMessage>) -> Result<(), String> {
+    if messages.is_empty() {
+        return Ok(());
+    }
+    let (client, mut event_loop) = AsyncClient::new(options(settings, password)?, messages.len());
+    let count = messages.len();
+    for packet in messages.into_iter().map(publish_packet) {
+        client
+            .publish_bytes(packet.topic, packet.qos, packet.retain, packet.payload)
+            .await
+            .map_err(|err| format!("Failed to queue the MQTT message: {}", err))?;
+    }
+
+    let delivered = async {
+        let mut acknowledged = 0;
+        loop {
+            match event_loop.poll().await.map_err(|err| format!("MQTT connection failed: {}", err))? {
+                Event::Incoming(Packet::PubAck(_)) => {
+                    acknowledged += 1;
+                    if acknowledged == count {
+                        client.disconnect().await.map_err(|err| format!("Failed to disconnect: {}", err))?;
+                    }
+                }
+                Event::Outgoing(Outgoing::Disconnect) => return Ok(()),
+                _ => {}
+            }
+        }
+    };
+    tokio::time::timeout(PUBLISH_TIMEOUT, delivered).await.map_err(|_| "The MQTT broker didn't respond".to_string())?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rumqttc::Connect;
+
+    // The CONNECT packet the event loop sends for these options
+    fn connect_packet(options: &MqttOptions) -> Connect {
+        let mut packet = Connect::new(options.client_id());
+        packet.keep_alive = options.keep_alive().as_secs() as u16;
+        packet.clean_session = options.clean_session();
+        packet.login = options.credentials();
+        packet
+    }
+
+    fn settings() -> MqttSettings {
+        MqttSettings { host: " broker.local ".to_string(), username: Some("alice".to_string()), ..MqttSettings::default() }
+    }
+
+    #[test]
+    fn test_options_need_a_broker() {
+        assert!(options(&MqttSettings::default(), None).is_err());
+        let options = options(&settings(), Some("secret")).unwrap();
+        assert_eq!(options.broker_address(), ("broker.local".to_string(), 1883));
+        assert!(options.client_id().starts_with("remodance-"));
+    }
+
+    #[test]
+    fn test_connect_packet() {
+        let mut options = options(&settings(), Some("secret")).unwrap();
+        options.set_client_id("remodance-1".to_string());
+        let mut buffer = Default::default();
+        let len = connect_packet(&options).write(&mut buffer).unwrap();
+
+        let bytes: &[u8] = &buffer;
+        assert_eq!(len, bytes.len());
+        // Fixed header with the remaining length, then protocol name and level
+        assert_eq!(&bytes[..10], &[0x10, 38, 0, 4, b'M', b'Q', b'T', b'T', 4, 0b1100_0010]);
+        // Keep alive of 30 seconds, then client ID, username and password
+        assert_eq!(&bytes[10..12], &[0, 30]);
+        assert_eq!(&bytes[12..25], b"\0\x0bremodance-1");
+        assert_eq!(&bytes[25..], b"\0\x05alice\0\x06secret");
+    }
+
+    #[test]
+    fn test_publish_packet() {
+        let message = MqttMessage { topic: "remodance/alice/status".to_string(), payload: "on-break".to_string(), retain: true };
+        let mut packet = publish_packet(message);
+        packet.pkid = 1;
+        let mut buffer = Default::default();
+        packet.write(&mut buffer).unwrap();
+
+        // PUBLISH with QoS 1 and retain, then topic, packet ID and payload
+        let bytes: &[u8] = &buffer;
+        assert_eq!(&bytes[..4], &[0x33, 34, 0, 22]);
+        assert_eq!(&bytes[4..26], b"remodance/alice/status");
+        assert_eq!(&bytes[26..], b"\0\x01on-break");
+
+        // Without retain
+        let message = MqttMessage { topic: "t".to_string(), payload: "x".to_string(), retain: false };
+        let mut packet = publish_packet(message);
+        packet.pkid = 1;
+        let mut buffer = Default::default();
+        packet.write(&mut buffer).unwrap();
+        assert_eq!((&buffer as &[u8])[0], 0x32);
+    }
+
+    #[test]
+    fn test_remaining_length_takes_two_bytes_past_127() {
+        let message = MqttMessage { topic: "remodance/alice/status".to_string(), payload: "x".repeat(200), retain: false };
+        let mut packet = publish_packet(message);
+        packet.pkid = 1;
+        let mut buffer = Default::default();
+        let len = packet.write(&mut buffer).unwrap();
+
+        // 2 + 22 topic + 2 packet ID + 200 payload = 226, as 0xe2 0x01
+        let bytes: &[u8] = &buffer;
+        assert_eq!(&bytes[..3], &[0x32, 0xe2, 0x01]);
+        assert_eq!(len, 3 + 226);
+    }
+}
@@ -0,0 +1,248 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::NaiveTime;
+use log::{debug, error, info};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State};
+use tauri_plugin_notification::NotificationExt;
+
+use crate::clock;
+use crate::schedule::TimeWindow;
+use crate::{AppState, Settings};
+
+// How often held notifications are checked against the OS Do-Not-Disturb state
+const DND_CHECK_SECS: u64 = 30;
+// Notifications held during Do-Not-Disturb beyond this are dropped, oldest first
+const MAX_HELD_NOTIFICATIONS: usize = 20;
+
+// Groups of native notifications the user can switch on and off
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NotificationKind {
+    AutoCheckOut, // Also covers breaks recorded by the idle monitor
+    AutoCheckIn,
+    Reminder,
+    CheckoutWarning, // The pre-checkout prompt, shown with reminders
+    ApiFailure,
+    PluginFailure,
+}
+
+impl NotificationKind {
+    // Urgent notifications may break through the OS Do-Not-Disturb mode
+    fn is_urgent(self) -> bool {
+        self == NotificationKind::CheckoutWarning
+    }
+}
+
+// A notification held back while the OS is in Do-Not-Disturb mode
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeldNotification {
+    channel: Option<String>,
+    title: String,
+    body: String,
+}
+
+// Notification preferences
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+#[serde(default)]
+pub struct NotificationSettings {
+    pub auto_check_out: bool,
+    pub auto_check_in: bool,
+    pub reminders: bool,
+    pub api_failures: bool,
+    pub plugin_failures: bool,
+    pub quiet_hours: Option<TimeWindow>, // No notifications at all in this window
+    pub respect_os_dnd: bool, // Hold back notifications while the OS is in Do-Not-Disturb/Focus mode
+    pub hold_during_dnd: bool, // Show them once Do-Not-Disturb ends instead of dropping them
+    pub urgent_breaks_dnd: bool, // The pre-checkout warning is shown regardless
+}
+
+impl NotificationSettings {
+    pub fn allows(&self, kind: NotificationKind, time: NaiveTime) -> bool {
+        if self.quiet_hours.as_ref().is_some_and(|quiet_hours| quiet_hours.contains(time)) {
+            return false;
+        }
+
+        match kind {
+            NotificationKind::AutoCheckOut => self.auto_check_out,
+            NotificationKind::AutoCheckIn => self.auto_check_in,
+            NotificationKind::Reminder | NotificationKind::CheckoutWarning => self.reminders,
+            NotificationKind::ApiFailure => self.api_failures,
+            NotificationKind::PluginFailure => self.plugin_failures,
+        }
+    }
+
+    fn blocked_by_dnd(&self, kind: NotificationKind, dnd_active: bool) -> bool {
+        self.respect_os_dnd && dnd_active && !(kind.is_urgent() && self.urgent_breaks_dnd)
+    }
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        Self {
+            auto_check_out: true,
+            auto_check_in: true,
+            reminders: true,
+            api_failures: true,
+            plugin_failures: true,
+            quiet_hours: None,
+            respect_os_dnd: true,
+            hold_during_dnd: true,
+            urgent_breaks_dnd: true,
+        }
+    }
+}
+
+// Show a native notification if the user's preferences allow it
+pub fn notify(app_handle: &AppHandle, settings: &Settings, kind: NotificationKind, title: &str, body: &str) {
+    notify_on_channel(app_handle, settings, kind, None, title, body);
+}
+
+// Show a native notification, optionally on a separate channel so it can be
+// configured on its own where the platform supports channels
+pub fn notify_on_channel(
+    app_handle: &AppHandle,
+    settings: &Settings,
+    kind: NotificationKind,
+    channel: Option<&str>,
+    title: &str,
+    body: &str,
+) {
+    let now = clock::now(settings.timezone.as_deref()).time();
+    if !settings.notifications.allows(kind, now) {
+        debug!("Notification suppressed by preferences: {}", title);
+        return;
+    }
+
+    // The OS is only asked when its Do-Not-Disturb state is respected at all
+    let preferences = &settings.notifications;
+    if preferences.respect_os_dnd && preferences.blocked_by_dnd(kind, os_dnd_active()) {
+        if preferences.hold_during_dnd {
+            debug!("Holding notification until Do-Not-Disturb ends: {}", title);
+            hold(app_handle, HeldNotification {
+                channel: channel.map(str::to_string),
+                title: title.to_string(),
+                body: body.to_string(),
+            });
+        } else {
+            debug!("Notification dropped during Do-Not-Disturb: {}", title);
+        }
+        return;
+    }
+
+    show(app_handle, channel, title, body);
+}
+
+fn show(app_handle: &AppHandle, channel: Option<&str>, title: &str, body: &str) {
+    let mut builder = app_handle.notification().builder().title(title).body(body);
+    if let Some(channel) = channel {
+        builder = builder.channel_id(channel);
+    }
+    if let Err(err) = builder.show() {
+        error!("Failed to show notification: {}", err);
+    }
+}
+
+fn hold(app_handle: &AppHandle, notification: HeldNotification) {
+    let state: State<'_, Arc<AppState>> = app_handle.state();
+    let mut held = state.held_notifications.lock().unwrap();
+    held.push(notification);
+    if held.len() > MAX_HELD_NOTIFICATIONS {
+        held.remove(0);
+    }
+}
+
+// Show held notifications once the OS leaves Do-Not-Disturb mode
+pub fn start_dnd_watch(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let state: State<'_, Arc<AppState>> = app_handle.state();
+        let mut interval = tokio::time::interval(Duration::from_secs(DND_CHECK_SECS));
+
+        loop {
+            interval.tick().await;
+
+            if state.held_notifications.lock().unwrap().is_empty() || os_dnd_active() {
+                continue;
+            }
+            let held = std::mem::take(&mut *state.held_notifications.lock().unwrap());
+            info!("Do-Not-Disturb ended, showing {} held notification(s)", held.len());
+            for notification in held {
+                show(&app_handle, notification.channel.as_deref(), &notification.title, &notification.body);
+            }
+        }
+    });
+}
+
+// Whether macOS Focus is on. There is no public API, but the active Focus
+// assertions are written to this file.
+#[cfg(target_os = "macos")]
+fn os_dnd_active() -> bool {
+    let Some(home) = std::env::var_os("HOME") else {
+        return false;
+    };
+    let path = std::path::Path::new(&home).join("Library/DoNotDisturb/DB/Assertions.json");
+
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|contents| serde_json::from_str::<serde_json::Value>(&contents).ok())
+        .and_then(|assertions| assertions["data"][0]["storeAssertionRecords"].as_array().map(|records| !records.is_empty()))
+        .unwrap_or(false)
+}
+
+// Whether Windows is in quiet time, presentation mode or running a full-screen app
+#[cfg(target_os = "windows")]
+fn os_dnd_active() -> bool {
+    #[link(name = "shell32")]
+    extern "system" {
+        fn SHQueryUserNotificationState(state: *mut i32) -> i32;
+    }
+    const QUNS_ACCEPTS_NOTIFICATIONS: i32 = 5;
+
+    let mut notification_state = 0;
+    // SAFETY: the function only writes the state to the pointer it is given
+    let result = unsafe { SHQueryUserNotificationState(&mut notification_state) };
+    result == 0 && notification_state != QUNS_ACCEPTS_NOTIFICATIONS
+}
+
+// Whether GNOME's Do Not Disturb switch is on. Other desktops count as off.
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn os_dnd_active() -> bool {
+    std::process::Command::new("gsettings")
+        .args(["get", "org.gnome.desktop.notifications", "show-banners"])
+        .output()
+        .is_ok_and(|output| output.status.success() && String::from_utf8_lossy(&output.stdout).trim() == "false")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quiet_hours_suppress_every_kind() {
+        let time = |h| NaiveTime::from_hms_opt(h, 0, 0).unwrap();
+        let preferences = NotificationSettings {
+            auto_check_in: false,
+            quiet_hours: Some(TimeWindow { start: time(22), end: time(7) }),
+            ..NotificationSettings::default()
+        };
+
+        assert!(preferences.allows(NotificationKind::AutoCheckOut, time(12)));
+        assert!(!preferences.allows(NotificationKind::AutoCheckIn, time(12)));
+        assert!(!preferences.allows(NotificationKind::ApiFailure, time(23)));
+    }
+
+    #[test]
+    fn test_only_urgent_notifications_break_through_dnd() {
+        let preferences = NotificationSettings::default();
+        assert!(preferences.blocked_by_dnd(NotificationKind::Reminder, true));
+        assert!(!preferences.blocked_by_dnd(NotificationKind::CheckoutWarning, true));
+        assert!(!preferences.blocked_by_dnd(NotificationKind::Reminder, false));
+
+        let strict = NotificationSettings { urgent_breaks_dnd: false, ..preferences.clone() };
+        assert!(strict.blocked_by_dnd(NotificationKind::CheckoutWarning, true));
+
+        let ignoring = NotificationSettings { respect_os_dnd: false, ..preferences };
+        assert!(!ignoring.blocked_by_dnd(NotificationKind::Reminder, true));
+    }
+}
@@ -0,0 +1,325 @@
+use std::time::Duration as StdDuration;
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use url::Url;
+
+// Refresh this long before a token runs out, so it doesn't expire mid-request
+const EXPIRY_MARGIN_SECS: i64 = 60;
+// Poll interval when the provider doesn't give one, per RFC 8628
+const DEFAULT_POLL_SECS: u64 = 5;
+// How long a browser sign-in may take before the app stops waiting
+const BROWSER_SIGN_IN_SECS: u64 = 5 * 60;
+
+// An OAuth provider. Sign-in uses the device authorization grant (RFC 8628)
+// where the provider allows it: the user signs in on another device with a
+// short code. Otherwise the browser is sent to `authorize_url` and redirected
+// back to a port on this machine (RFC 8252), with PKCE in place of a secret.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Provider {
+    pub device_code_url: String,
+    pub authorize_url: String,
+    pub token_url: String,
+    pub client_id: String,
+    pub client_secret: Option<String>, // Issued to desktop apps by some providers, not actually secret
+    pub scope: String,
+}
+
+// Code the user enters at `verification_uri` to sign in
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct DeviceCode {
+    pub device_code: String,
+    pub user_code: String,
+    #[serde(alias = "verification_url")]
+    pub verification_uri: String,
+    pub expires_in: u64,
+    #[serde(default = "default_interval")]
+    pub interval: u64,
+    pub message: Option<String>, // Sign-in instructions worded by the provider
+}
+
+fn default_interval() -> u64 {
+    DEFAULT_POLL_SECS
+}
+
+// Signed-in tokens, kept in the keychain, see `secrets::load_token`
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Token {
+    pub access_token: String,
+    pub refresh_token: Option<String>,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl Token {
+    pub fn expired(&self, now: DateTime<Utc>) -> bool {
+        now + Duration::seconds(EXPIRY_MARGIN_SECS) >= self.expires_at
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    refresh_token: Option<String>,
+    expires_in: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct ErrorResponse {
+    error: String,
+    error_description: Option<String>,
+}
+
+// Answer of the token endpoint while the user signs in
+#[derive(Debug, PartialEq)]
+pub enum Poll {
+    Pending,
+    SlowDown, // Asked to poll less often
+    Done(Token),
+}
+
+// Tokens from a token endpoint response
+pub fn parse_token(body: &str, now: DateTime<Utc>) -> Result<Token, String> {
+    if let Ok(error) = serde_json::from_str::<ErrorResponse>(body) {
+        return Err(describe(&error));
+    }
+    let response: TokenResponse = serde_json::from_str(body).map_err(|err| format!("Unexpected token response: {}", err))?;
+    Ok(Token {
+        access_token: response.access_token,
+        refresh_token: response.refresh_token,
+        expires_at: now + Duration::seconds(response.expires_in),
+    })
+}
+
+// Whether a device code was redeemed yet
+pub fn parse_poll(body: &str, now: DateTime<Utc>) -> Result<Poll, String> {
+    match serde_json::from_str::<ErrorResponse>(body) {
+        Ok(error) if error.error == "authorization_pending" => Ok(Poll::Pending),
+        Ok(error) if error.error == "slow_down" => Ok(Poll::SlowDown),
+        Ok(error) if error.error == "expired_token" => Err("The sign-in code expired, start again".to_string()),
+        Ok(error) if matches!(error.error.as_str(), "authorization_declined" | "access_denied") => Err("Sign-in was declined".to_string()),
+        _ => parse_token(body, now).map(Poll::Done),
+    }
+}
+
+fn describe(error: &ErrorResponse) -> String {
+    match &error.error_description {
+        Some(description) => format!("{}: {}", error.error, description.lines().next().unwrap_or_default()),
+        None => error.error.clone(),
+    }
+}
+
+// PKCE verifier and the challenge derived from it (RFC 7636)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pkce {
+    pub verifier: String,
+    pub challenge: String,
+}
+
+pub fn pkce() -> Pkce {
+    let verifier = format!("{}{}", uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple());
+    let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()));
+    Pkce { verifier, challenge }
+}
+
+// Page the browser is sent to for a sign-in that redirects back to `redirect_uri`
+pub fn authorization_url(provider: &Provider, redirect_uri: &str, state: &str, pkce: &Pkce) -> Result<Url, String> {
+    let mut url = Url::parse(&provider.authorize_url).map_err(|err| format!("Invalid authorization URL: {}", err))?;
+    url.query_pairs_mut()
+        .append_pair("response_type", "code")
+        .append_pair("client_id", &provider.client_id)
+        .append_pair("redirect_uri", redirect_uri)
+        .append_pair("scope", &provider.scope)
+        .append_pair("state", state)
+        .append_pair("code_challenge", &pkce.challenge)
+        .append_pair("code_challenge_method", "S256");
+    Ok(url)
+}
+
+// Authorization code from the first line of the redirected request, e.g.
+// `GET /?state=...&code=... HTTP/1.1`
+pub fn parse_redirect(request_line: &str, expected_state: &str) -> Result<String, String> {
+    let target = request_line.split_whitespace().nth(1).ok_or("Unexpected sign-in redirect")?;
+    let url = Url::parse(&format!("http://localhost{}", target)).map_err(|err| format!("Unexpected sign-in redirect: {}", err))?;
+    let param = |name: &str| url.query_pairs().find(|(key, _)| key == name).map(|(_, value)| value.into_owned());
+
+    if let Some(error) = param("error") {
+        return Err(format!("Sign-in failed: {}", error));
+    }
+    if param("state").as_deref() != Some(expected_state) {
+        return Err("Sign-in redirect did not match the request".to_string());
+    }
+    param("code").ok_or_else(|| "Sign-in redirect had no code".to_string())
+}
+
+// Sign in through the browser: `open` is given the page to show, the
+// redirect is received on a free local port and the code traded for tokens.
+pub async fn sign_in_with_browser<F: FnOnce(&str) -> Result<(), String>>(provider: &Provider, open: F) -> Result<Token, String> {
+    let listener = TcpListener::bind("127.0.0.1:0").await.map_err(|err| format!("Failed to listen for the sign-in: {}", err))?;
+    let port = listener.local_addr().map_err(|err| format!("Failed to listen for the sign-in: {}", err))?.port();
+    let redirect_uri = format!("http://127.0.0.1:{}", port);
+    let state = uuid::Uuid::new_v4().simple().to_string();
+    let pkce = pkce();
+
+    open(authorization_url(provider, &redirect_uri, &state, &pkce)?.as_str())?;
+
+    let accept = tokio::time::timeout(StdDuration::from_secs(BROWSER_SIGN_IN_SECS), listener.accept());
+    let (mut stream, _) = accept.await
+        .map_err(|_| "Timed out waiting for the sign-in".to_string())?
+        .map_err(|err| format!("Failed to receive the sign-in: {}", err))?;
+    let mut buffer = vec![0; 8192];
+    let read = stream.read(&mut buffer).await.map_err(|err| format!("Failed to receive the sign-in: {}", err))?;
+    let request = String::from_utf8_lossy(&buffer[..read]);
+    let code = parse_redirect(request.lines().next().unwrap_or_default(), &state);
+
+    let page = match &code {
+        Ok(_) => "Signed in to Remodance. You can close this tab.",
+        Err(_) => "Remodance could not sign in. Close this tab and try again.",
+    };
+    let response = format!("HTTP/1.1 200 OK\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", page.len(), page);
+    let _ = stream.write_all(response.as_bytes()).await;
+
+    exchange_code(provider, &code?, &redirect_uri, &pkce.verifier).await
+}
+
+// Trade an authorization code for tokens
+pub async fn exchange_code(provider: &Provider, code: &str, redirect_uri: &str, verifier: &str) -> Result<Token, String> {
+    let mut form = vec![
+        ("grant_type", "authorization_code"),
+        ("client_id", provider.client_id.as_str()),
+        ("code", code),
+        ("redirect_uri", redirect_uri),
+        ("code_verifier", verifier),
+    ];
+    if let Some(secret) = &provider.client_secret {
+        form.push(("client_secret", secret));
+    }
+    let response = reqwest::Client::new()
+        .post(&provider.token_url)
+        .form(&form)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send request: {}", e))?;
+    let body = response.text().await.map_err(|e| format!("Failed to read response: {}", e))?;
+    parse_token(&body, Utc::now())
+}
+
+// Start a sign-in, returning the code to show the user
+pub async fn request_device_code(provider: &Provider) -> Result<DeviceCode, String> {
+    let response = reqwest::Client::new()
+        .post(&provider.device_code_url)
+        .form(&[("client_id", provider.client_id.as_str()), ("scope", provider.scope.as_str())])
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send request: {}", e))?;
+    let body = response.text().await.map_err(|e| format!("Failed to read response: {}", e))?;
+
+    if let Ok(error) = serde_json::from_str::<ErrorResponse>(&body) {
+        return Err(format!("Sign-in could not start: {}", describe(&error)));
+    }
+    serde_json::from_str(&body).map_err(|err| format!("Unexpected device code response: {}", err))
+}
+
+// Wait for the user to enter the code, returning their tokens
+pub async fn poll(provider: &Provider, code: &DeviceCode) -> Result<Token, String> {
+    let client = reqwest::Client::new();
+    let deadline = Utc::now() + Duration::seconds(code.expires_in as i64);
+    let mut interval = code.interval.max(1);
+
+    while Utc::now() < deadline {
+        tokio::time::sleep(StdDuration::from_secs(interval)).await;
+        let response = client
+            .post(&provider.token_url)
+            .form(&[
+                ("grant_type", "urn:ietf:params:oauth:grant-type:device_code"),
+                ("client_id", provider.client_id.as_str()),
+                ("device_code", code.device_code.as_str()),
+            ])
+            .send()
+            .await
+            .map_err(|e| format!("Failed to send request: {}", e))?;
+        let body = response.text().await.map_err(|e| format!("Failed to read response: {}", e))?;
+
+        match parse_poll(&body, Utc::now())? {
+            Poll::Pending => {}
+            Poll::SlowDown => interval += DEFAULT_POLL_SECS,
+            Poll::Done(token) => return Ok(token),
+        }
+    }
+    Err("The sign-in code expired, start again".to_string())
+}
+
+// New tokens for an expired one. Providers that don't rotate refresh tokens
+// return none, so the old one is kept.
+pub async fn refresh(provider: &Provider, token: &Token) -> Result<Token, String> {
+    let refresh_token = token.refresh_token.as_deref().ok_or("Signed out, sign in again")?;
+    let mut form = vec![
+        ("grant_type", "refresh_token"),
+        ("client_id", provider.client_id.as_str()),
+        ("refresh_token", refresh_token),
+        ("scope", provider.scope.as_str()),
+    ];
+    if let Some(secret) = &provider.client_secret {
+        form.push(("client_secret", secret));
+    }
+    let response = reqwest::Client::new()
+        .post(&provider.token_url)
+        .form(&form)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send request: {}", e))?;
+    let body = response.text().await.map_err(|e| format!("Failed to read response: {}", e))?;
+
+    let mut refreshed = parse_token(&body, Utc::now()).map_err(|err| format!("Failed to refresh the sign-in: {}", err))?;
+    if refreshed.refresh_token.is_none() {
+        refreshed.refresh_token = token.refresh_token.clone();
+    }
+    Ok(refreshed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_poll_waits_then_returns_token() {
+        let now = Utc::now();
+        assert_eq!(parse_poll(r#"{"error":"authorization_pending"}"#, now), Ok(Poll::Pending));
+        assert_eq!(parse_poll(r#"{"error":"slow_down"}"#, now), Ok(Poll::SlowDown));
+        assert!(parse_poll(r#"{"error":"expired_token"}"#, now).is_err());
+
+        let Ok(Poll::Done(token)) = parse_poll(r#"{"access_token":"at","refresh_token":"rt","expires_in":3600,"token_type":"Bearer"}"#, now) else {
+            panic!("expected a token");
+        };
+        assert_eq!(token.refresh_token.as_deref(), Some("rt"));
+        assert!(!token.expired(now));
+        assert!(token.expired(now + Duration::seconds(3590)));
+    }
+
+    #[test]
+    fn test_redirect_must_match_state() {
+        assert_eq!(parse_redirect("GET /?state=abc&code=4%2F0Ab HTTP/1.1", "abc"), Ok("4/0Ab".to_string()));
+        assert!(parse_redirect("GET /?state=other&code=4 HTTP/1.1", "abc").is_err());
+        assert!(parse_redirect("GET /?error=access_denied&state=abc HTTP/1.1", "abc").is_err());
+
+        // The challenge is the unpadded base64url SHA-256 of the verifier
+        let pkce = pkce();
+        assert_eq!(pkce.verifier.len(), 64);
+        assert_eq!(pkce.challenge, URL_SAFE_NO_PAD.encode(Sha256::digest(pkce.verifier.as_bytes())));
+        assert!(!pkce.challenge.contains('='));
+    }
+
+    #[test]
+    fn test_device_code_defaults_interval() {
+        let code: DeviceCode = serde_json::from_str(
+            r#"{"device_code":"dc","user_code":"ABCD-EFGH","verification_url":"https://example.com/device","expires_in":900}"#,
+        )
+        .unwrap();
+        assert_eq!(code.verification_uri, "https://example.com/device");
+        assert_eq!(code.interval, DEFAULT_POLL_SECS);
+    }
+}
@@ -0,0 +1,150 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::backend::{self, BackendAdapter, Clock, Reply, SendError};
+
+const MODEL: &str = "hr.attendance";
+
+// An Odoo database with the Attendances app, where each session becomes an
+// `hr.attendance` record: created on clock-in, closed on clock-out.
+// `Settings::user_id` must be the numeric ID of the employee. The API key of
+// `login` is kept with the other secrets, see `Secrets::odoo_api_key`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default, JsonSchema)]
+#[serde(default)]
+pub struct OdooSettings {
+    pub url: String, // e.g. https://example.odoo.com
+    pub database: String,
+    pub login: String, // User the records are written as
+}
+
+pub struct Odoo<'a> {
+    pub settings: &'a OdooSettings,
+    pub api_key: Option<&'a str>,
+}
+
+// A JSON-RPC call of `method` on an Odoo service
+pub fn rpc_body(service: &str, method: &str, args: Value) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "call",
+        "params": { "service": service, "method": method, "args": args },
+        "id": 1,
+    })
+}
+
+// The result of a call. Odoo answers errors with status 200 and an `error`
+// object, whose `data.message` is the readable part.
+pub fn parse_result(body: &str) -> Result<Value, String> {
+    let mut body: Value = serde_json::from_str(body).map_err(|err| format!("Unexpected Odoo response: {}", err))?;
+    if let Some(error) = body.get("error") {
+        let message = error["data"]["message"].as_str().or(error["message"].as_str()).unwrap_or("Unknown error");
+        return Err(format!("Odoo rejected the call: {}", message));
+    }
+    Ok(body["result"].take())
+}
+
+pub fn employee_id(payload: &Value) -> Result<i64, String> {
+    let employee = backend::employee(payload)?;
+    employee.trim().parse().map_err(|_| format!("'{}' is not an Odoo employee ID, set the employee ID to the number of the employee", employee))
+}
+
+// Odoo stores datetimes in UTC without a zone
+pub fn odoo_time(payload: &Value) -> Result<String, String> {
+    Ok(backend::event_utc(payload)?.format("%Y-%m-%d %H:%M:%S").to_string())
+}
+
+// The result of a reply, whatever the call was
+fn reply_result(reply: &Reply) -> Result<Value, SendError> {
+    if !reply.status.is_success() {
+        return Err(SendError::Rejected(format!("Odoo request failed with status {}", reply.status)));
+    }
+    parse_result(&reply.body).map_err(SendError::Rejected)
+}
+
+impl Odoo<'_> {
+    async fn post(&self, client: &reqwest::Client, service: &str, method: &str, args: Value) -> Result<Reply, SendError> {
+        let url = format!("{}/jsonrpc", self.settings.url.trim().trim_end_matches('/'));
+        backend::request(client.post(url).json(&rpc_body(service, method, args))).await
+    }
+
+    // `execute_kw` on the attendance model as the authenticated user
+    async fn execute(&self, client: &reqwest::Client, session: &Session<'_>, method: &str, args: Value, kwargs: Value) -> Result<Reply, SendError> {
+        let (uid, api_key) = *session;
+        self.post(client, "object", "execute_kw", json!([self.settings.database, uid, api_key, MODEL, method, args, kwargs])).await
+    }
+
+    // The open attendance of the employee, if any
+    async fn open_attendance(&self, client: &reqwest::Client, session: &Session<'_>, employee_id: i64) -> Result<Option<i64>, SendError> {
+        let domain = json!([[["employee_id", "=", employee_id], ["check_out", "=", false]]]);
+        let found = reply_result(&self.execute(client, session, "search", domain, json!({ "limit": 1 })).await?)?;
+        Ok(found.as_array().and_then(|ids| ids.first()).and_then(Value::as_i64))
+    }
+}
+
+// The authenticated user ID and the API key calls are made with
+type Session<'a> = (i64, &'a str);
+
+impl<'a> BackendAdapter for Odoo<'a> {
+    // Whether to check in or out, the employee ID and the time
+    type Event = (Clock, i64, String);
+    type Session = Session<'a>;
+
+    fn name(&self) -> &'static str {
+        "Odoo"
+    }
+
+    fn build(&self, event_type: &str, payload: &Value) -> Result<Option<Self::Event>, String> {
+        let Some(clock) = backend::clock(event_type) else {
+            return Ok(None);
+        };
+        Ok(Some((clock, employee_id(payload)?, odoo_time(payload)?)))
+    }
+
+    async fn authenticate(&self, client: &reqwest::Client) -> Result<Session<'a>, SendError> {
+        let api_key = self.api_key.ok_or_else(|| SendError::Rejected("No Odoo API key is set".to_string()))?;
+        let settings = self.settings;
+        let reply = self.post(client, "common", "authenticate", json!([settings.database, settings.login, api_key, {}])).await?;
+        let uid = reply_result(&reply)?.as_i64().ok_or_else(|| SendError::Rejected("Odoo didn't accept the login and API key".to_string()))?;
+        Ok((uid, api_key))
+    }
+
+    async fn submit(&self, client: &reqwest::Client, session: &Session<'a>, (clock, employee_id, time): Self::Event) -> Result<Option<Reply>, SendError> {
+        let open = self.open_attendance(client, session, employee_id).await?;
+        let reply = match (clock, open) {
+            (Clock::In, None) => self.execute(client, session, "create", json!([{ "employee_id": employee_id, "check_in": time }]), json!({})).await?,
+            // Odoo refuses a second open attendance, the one that is open already counts
+            (Clock::In, Some(_)) => return Ok(None),
+            (Clock::Out, Some(id)) => self.execute(client, session, "write", json!([[id], { "check_out": time }]), json!({})).await?,
+            (Clock::Out, None) => return Err(SendError::Rejected("There is no open attendance in Odoo to check out of".to_string())),
+        };
+        Ok(Some(reply))
+    }
+
+    fn interpret(&self, reply: &Reply) -> Result<(), SendError> {
+        reply_result(reply).map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_result() {
+        assert_eq!(parse_result(r#"{"jsonrpc": "2.0", "id": 1, "result": 7}"#), Ok(json!(7)));
+        let error = r#"{"jsonrpc": "2.0", "id": 1, "error": {"code": 200, "message": "Odoo Server Error", "data": {"name": "odoo.exceptions.ValidationError", "message": "Cannot create new attendance record for Alice"}}}"#;
+        assert_eq!(parse_result(error), Err("Odoo rejected the call: Cannot create new attendance record for Alice".to_string()));
+    }
+
+    #[test]
+    fn test_event_fields() {
+        let payload = json!({ "user_id": " 42 ", "timestamp": "2024-05-06T08:59:30+02:00" });
+        assert_eq!(employee_id(&payload), Ok(42));
+        assert_eq!(odoo_time(&payload), Ok("2024-05-06 06:59:30".to_string()));
+        assert!(employee_id(&json!({ "user_id": "alice" })).is_err());
+
+        let body = rpc_body("common", "authenticate", json!(["db", "bot@example.com", "key", {}]));
+        assert_eq!(body["params"]["args"][0], json!("db"));
+    }
+}
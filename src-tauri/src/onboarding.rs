@@ -0,0 +1,82 @@
+use serde::{Deserialize, Serialize};
+
+// Steps of the first-run setup wizard, in the order the wizard shows them
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum OnboardingStep {
+    EndpointConfigured,
+    TestPassed, // A test event reached the API
+    PermissionsGranted, // Notifications and idle detection
+    AutostartChosen, // Enabled or declined, either way the question was answered
+}
+
+pub const STEPS: [OnboardingStep; 4] = [
+    OnboardingStep::EndpointConfigured,
+    OnboardingStep::TestPassed,
+    OnboardingStep::PermissionsGranted,
+    OnboardingStep::AutostartChosen,
+];
+
+// Steps finished so far, persisted so the wizard resumes after a restart
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct Onboarding {
+    pub completed: Vec<OnboardingStep>,
+}
+
+// What the wizard needs to render, returned by the onboarding commands
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct OnboardingState {
+    pub completed: Vec<OnboardingStep>,
+    pub next_step: Option<OnboardingStep>,
+    pub done: bool,
+}
+
+impl Onboarding {
+    // Mark a step as done. Returns false if it already was.
+    pub fn complete(&mut self, step: OnboardingStep) -> bool {
+        if self.completed.contains(&step) {
+            return false;
+        }
+        self.completed.push(step);
+        true
+    }
+
+    // First step in wizard order that is not done yet
+    pub fn next_step(&self) -> Option<OnboardingStep> {
+        STEPS.into_iter().find(|step| !self.completed.contains(step))
+    }
+
+    pub fn state(&self) -> OnboardingState {
+        let next_step = self.next_step();
+        OnboardingState {
+            completed: STEPS.into_iter().filter(|step| self.completed.contains(step)).collect(),
+            next_step,
+            done: next_step.is_none(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_steps_complete_in_any_order() {
+        let mut onboarding = Onboarding::default();
+        assert_eq!(onboarding.next_step(), Some(OnboardingStep::EndpointConfigured));
+
+        assert!(onboarding.complete(OnboardingStep::AutostartChosen));
+        assert!(onboarding.complete(OnboardingStep::EndpointConfigured));
+        assert!(!onboarding.complete(OnboardingStep::EndpointConfigured));
+
+        let state = onboarding.state();
+        assert_eq!(state.completed, vec![OnboardingStep::EndpointConfigured, OnboardingStep::AutostartChosen]);
+        assert_eq!(state.next_step, Some(OnboardingStep::TestPassed));
+        assert!(!state.done);
+
+        onboarding.complete(OnboardingStep::TestPassed);
+        onboarding.complete(OnboardingStep::PermissionsGranted);
+        assert!(onboarding.state().done);
+    }
+}
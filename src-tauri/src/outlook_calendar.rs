@@ -0,0 +1,263 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
+use log::{error, info};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager, State};
+use url::Url;
+
+use crate::meetings::{CalendarProvider, Meeting};
+use crate::oauth::{self, Provider};
+use crate::secrets;
+use crate::teams::SignInPrompt;
+use crate::AppState;
+
+// Keychain name of the Microsoft 365 calendar sign-in, see `secrets::load_token`
+pub const KEYCHAIN_NAME: &str = "outlook-calendar";
+// `Meeting::source` of meetings from Outlook
+pub const SOURCE: &str = "outlook";
+
+const CALENDAR_VIEW_URL: &str = "https://graph.microsoft.com/v1.0/me/calendarView";
+const SCOPE: &str = "Calendars.Read offline_access";
+const MAX_EVENTS: &str = "250";
+
+// The Microsoft 365 or Exchange Online calendar as a source of meetings, read
+// through Graph. The app registration in `client_id` needs the delegated
+// `Calendars.Read` permission and public client flows enabled; it can be the
+// same one used for Teams.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+#[serde(default)]
+pub struct OutlookCalendarSettings {
+    pub enabled: bool,
+    pub client_id: Option<String>, // Application (client) ID of the organization's Entra ID app
+    pub tenant: String, // Directory (tenant) ID or domain, "organizations" for any work account
+    pub tag_heartbeats: bool, // Send `in_meeting` with `activity_update`
+}
+
+impl Default for OutlookCalendarSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            client_id: None,
+            tenant: "organizations".to_string(),
+            tag_heartbeats: false,
+        }
+    }
+}
+
+// Whether the Outlook calendar is set up, for the settings screen
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct OutlookCalendarStatus {
+    pub enabled: bool,
+    pub signed_in: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct EventList {
+    #[serde(default)]
+    value: Vec<Event>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Event {
+    #[serde(default)]
+    subject: String,
+    #[serde(default)]
+    is_cancelled: bool,
+    #[serde(default)]
+    is_all_day: bool,
+    #[serde(default)]
+    show_as: String,
+    response_status: Option<ResponseStatus>,
+    #[serde(default)]
+    attendees: Vec<serde_json::Value>,
+    start: EventTime,
+    end: EventTime,
+}
+
+#[derive(Debug, Deserialize)]
+struct ResponseStatus {
+    #[serde(default)]
+    response: String,
+}
+
+// Times are requested in UTC with the `Prefer` header, Graph sends them
+// without an offset
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EventTime {
+    date_time: NaiveDateTime,
+}
+
+pub fn provider(settings: &OutlookCalendarSettings) -> Result<Provider, String> {
+    let client_id = settings.client_id.clone().ok_or("No Outlook client ID is configured")?;
+    let base = format!("https://login.microsoftonline.com/{}/oauth2/v2.0", settings.tenant);
+    Ok(Provider {
+        device_code_url: format!("{}/devicecode", base),
+        authorize_url: format!("{}/authorize", base),
+        token_url: format!("{}/token", base),
+        client_id,
+        client_secret: None,
+        scope: SCOPE.to_string(),
+    })
+}
+
+pub fn calendar_view_url(from: DateTime<Utc>, to: DateTime<Utc>) -> Result<Url, String> {
+    let mut url = Url::parse(CALENDAR_VIEW_URL).map_err(|err| format!("Invalid calendar URL: {}", err))?;
+    url.query_pairs_mut()
+        .append_pair("startDateTime", &from.to_rfc3339())
+        .append_pair("endDateTime", &to.to_rfc3339())
+        .append_pair("$select", "subject,start,end,isCancelled,isAllDay,showAs,responseStatus,attendees")
+        .append_pair("$top", MAX_EVENTS);
+    Ok(url)
+}
+
+// Meetings the user accepted or organized, from a calendar view. Events
+// without attendees, tentative or declined invitations, and events shown as
+// free, out of office or working elsewhere are left out, as with Google.
+pub fn parse_events(body: &str) -> Result<Vec<Meeting>, String> {
+    let list: EventList = serde_json::from_str(body).map_err(|err| format!("Unexpected calendar response: {}", err))?;
+
+    Ok(list
+        .value
+        .into_iter()
+        .filter(|event| !event.is_cancelled && !event.is_all_day)
+        .filter(|event| event.show_as == "busy")
+        .filter(|event| !event.attendees.is_empty())
+        .filter(|event| {
+            event.response_status.as_ref().is_some_and(|status| status.response == "accepted" || status.response == "organizer")
+        })
+        .map(|event| Meeting {
+            source: SOURCE,
+            title: event.subject,
+            start: event.start.date_time.and_utc(),
+            end: event.end.date_time.and_utc(),
+        })
+        .collect())
+}
+
+pub fn signed_in() -> bool {
+    matches!(secrets::load_token(KEYCHAIN_NAME), Ok(Some(_)))
+}
+
+pub fn status(enabled: bool) -> OutlookCalendarStatus {
+    OutlookCalendarStatus { enabled, signed_in: signed_in() }
+}
+
+// Start signing in with a device code, like Teams. The app waits for the
+// sign-in in the background, emits `outlook_calendar_signed_in` or
+// `outlook_calendar_sign_in_failed`, and loads the meetings.
+pub async fn sign_in(app_handle: &AppHandle, settings: &OutlookCalendarSettings) -> Result<SignInPrompt, String> {
+    let provider = provider(settings)?;
+    let code = oauth::request_device_code(&provider).await?;
+    let prompt = SignInPrompt::from(&code);
+
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        let result = oauth::poll(&provider, &code).await.and_then(|token| secrets::save_token(KEYCHAIN_NAME, Some(&token)));
+        match result {
+            Ok(()) => {
+                info!("Signed in to the Outlook calendar");
+                let _ = app_handle.emit("outlook_calendar_signed_in", ());
+                let state: State<'_, std::sync::Arc<AppState>> = app_handle.state();
+                if let Err(err) = crate::sync_meetings(&state).await {
+                    error!("Failed to refresh meetings: {}", err);
+                }
+            }
+            Err(err) => {
+                error!("Outlook calendar sign-in failed: {}", err);
+                let _ = app_handle.emit("outlook_calendar_sign_in_failed", err);
+            }
+        }
+    });
+    Ok(prompt)
+}
+
+pub fn sign_out() -> Result<(), String> {
+    secrets::save_token(KEYCHAIN_NAME, None)?;
+    info!("Signed out of the Outlook calendar");
+    Ok(())
+}
+
+impl CalendarProvider for OutlookCalendarSettings {
+    fn source(&self) -> &'static str {
+        SOURCE
+    }
+
+    fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn tag_heartbeats(&self) -> bool {
+        self.tag_heartbeats
+    }
+
+    fn signed_in(&self) -> bool {
+        signed_in()
+    }
+
+    async fn fetch_meetings(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> Result<Vec<Meeting>, String> {
+        let token = secrets::load_token(KEYCHAIN_NAME)?.ok_or("Not signed in to the Outlook calendar")?;
+        let token = if token.expired(Utc::now()) {
+            let refreshed = oauth::refresh(&provider(self)?, &token).await?;
+            secrets::save_token(KEYCHAIN_NAME, Some(&refreshed))?;
+            refreshed
+        } else {
+            token
+        };
+
+        let response = reqwest::Client::new()
+            .get(calendar_view_url(from, to)?)
+            .bearer_auth(&token.access_token)
+            .header("Prefer", "outlook.timezone=\"UTC\"")
+            .send()
+            .await
+            .map_err(|e| format!("Failed to send request: {}", e))?;
+        if !response.status().is_success() {
+            return Err(format!("Outlook calendar request failed with status {}", response.status()));
+        }
+        let body = response.text().await.map_err(|e| format!("Failed to read response: {}", e))?;
+        parse_events(&body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_accepted_meetings_count() {
+        let body = r#"{"value": [
+            {"subject": "Standup", "isCancelled": false, "isAllDay": false, "showAs": "busy", "responseStatus": {"response": "accepted"},
+             "attendees": [{"emailAddress": {"address": "a@example.com"}}],
+             "start": {"dateTime": "2024-05-06T07:00:00.0000000", "timeZone": "UTC"}, "end": {"dateTime": "2024-05-06T07:15:00.0000000", "timeZone": "UTC"}},
+            {"subject": "Review", "showAs": "busy", "responseStatus": {"response": "organizer"},
+             "attendees": [{"emailAddress": {"address": "b@example.com"}}],
+             "start": {"dateTime": "2024-05-06T09:00:00.0000000", "timeZone": "UTC"}, "end": {"dateTime": "2024-05-06T10:00:00.0000000", "timeZone": "UTC"}},
+            {"subject": "Maybe", "showAs": "tentative", "responseStatus": {"response": "tentativelyAccepted"},
+             "attendees": [{"emailAddress": {"address": "c@example.com"}}],
+             "start": {"dateTime": "2024-05-06T11:00:00.0000000", "timeZone": "UTC"}, "end": {"dateTime": "2024-05-06T12:00:00.0000000", "timeZone": "UTC"}},
+            {"subject": "Dentist", "showAs": "busy", "responseStatus": {"response": "organizer"}, "attendees": [],
+             "start": {"dateTime": "2024-05-06T12:00:00.0000000", "timeZone": "UTC"}, "end": {"dateTime": "2024-05-06T13:00:00.0000000", "timeZone": "UTC"}},
+            {"subject": "Cancelled", "isCancelled": true, "showAs": "busy", "responseStatus": {"response": "accepted"},
+             "attendees": [{"emailAddress": {"address": "d@example.com"}}],
+             "start": {"dateTime": "2024-05-06T14:00:00.0000000", "timeZone": "UTC"}, "end": {"dateTime": "2024-05-06T15:00:00.0000000", "timeZone": "UTC"}}
+        ]}"#;
+
+        let meetings = parse_events(body).unwrap();
+        let titles: Vec<_> = meetings.iter().map(|meeting| meeting.title.as_str()).collect();
+        assert_eq!(titles, vec!["Standup", "Review"]);
+        assert_eq!(meetings[0].start.to_rfc3339(), "2024-05-06T07:00:00+00:00");
+        assert_eq!(meetings[0].source, SOURCE);
+    }
+
+    #[test]
+    fn test_provider_uses_tenant() {
+        assert!(provider(&OutlookCalendarSettings::default()).is_err());
+
+        let settings = OutlookCalendarSettings { client_id: Some("app".to_string()), tenant: "contoso.com".to_string(), ..OutlookCalendarSettings::default() };
+        assert_eq!(provider(&settings).unwrap().device_code_url, "https://login.microsoftonline.com/contoso.com/oauth2/v2.0/devicecode");
+        let from = DateTime::parse_from_rfc3339("2024-05-06T00:00:00Z").unwrap().with_timezone(&Utc);
+        assert!(calendar_view_url(from, from).unwrap().query().unwrap().contains("startDateTime=2024-05-06T00%3A00%3A00%2B00%3A00"));
+    }
+}
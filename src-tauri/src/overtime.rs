@@ -0,0 +1,188 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc, Weekday};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::clock;
+use crate::history::History;
+use crate::holidays::{self, Holiday};
+use crate::transitions::ScheduleContext;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum OvertimePeriod {
+    Day,
+    Week,
+}
+
+// Payload of the `overtime_warning` event
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct OvertimeWarning {
+    pub period: OvertimePeriod,
+    pub period_start: NaiveDate,
+    pub worked_mins: i64,
+    pub threshold_mins: u64,
+}
+
+// Daily target, which is zero on holidays so any holiday work counts as overtime
+pub fn daily_threshold(threshold_mins: u64, holidays: &[Holiday], date: NaiveDate) -> u64 {
+    if holidays::is_holiday(holidays, date) {
+        0
+    } else {
+        threshold_mins
+    }
+}
+
+// Weekly target, spread over the working days of the regular schedule and
+// counted for the days that are still working days in this week, i.e. not
+// holidays and not overridden as days off. A schedule without any working
+// days is read as Monday to Friday.
+pub fn weekly_threshold(threshold_mins: u64, context: &ScheduleContext, week_start: NaiveDate) -> u64 {
+    let week: Vec<NaiveDate> = (0..7).map(|offset| week_start + Duration::days(offset)).collect();
+    let no_schedule = week.iter().all(|date| context.schedule.blocks_for(date.weekday()).is_empty());
+    let is_weekday = |date: &NaiveDate| !matches!(date.weekday(), Weekday::Sat | Weekday::Sun);
+
+    let regular_days = if no_schedule {
+        week.iter().filter(|date| is_weekday(date)).count()
+    } else {
+        week.iter().filter(|date| !context.schedule.blocks_for(date.weekday()).is_empty()).count()
+    } as u64;
+    let working_days = week
+        .iter()
+        .filter(|date| match context.overrides.iter().find(|o| o.date == **date) {
+            Some(day_override) => !day_override.blocks.is_empty(),
+            None if no_schedule => is_weekday(date),
+            None => !context.schedule.blocks_for(date.weekday()).is_empty(),
+        })
+        .filter(|date| !holidays::is_holiday(context.holidays, **date))
+        .count() as u64;
+
+    threshold_mins * working_days / regular_days
+}
+
+// Worked minutes on a calendar day in the configured timezone
+pub fn worked_mins_on(history: &History, timezone: Option<&str>, date: NaiveDate, now: DateTime<Utc>) -> i64 {
+    let (from, to) = clock::day_bounds(timezone, date);
+    history.worked_between(from, to, now).num_minutes()
+}
+
+// Compare worked time for the day and week containing `now` against the
+// configured thresholds and return the periods that are exceeded
+pub fn check(
+    history: &History,
+    context: &ScheduleContext,
+    daily_mins: Option<u64>,
+    weekly_mins: Option<u64>,
+    now: DateTime<Utc>,
+) -> Vec<OvertimeWarning> {
+    let (holidays, timezone) = (context.holidays, context.timezone);
+    let today = clock::date_of(timezone, now);
+    let mut warnings = Vec::new();
+
+    if let Some(daily_mins) = daily_mins {
+        let threshold = daily_threshold(daily_mins, holidays, today);
+        let worked = worked_mins_on(history, timezone, today, now);
+
+        if worked > threshold as i64 {
+            warnings.push(OvertimeWarning {
+                period: OvertimePeriod::Day,
+                period_start: today,
+                worked_mins: worked,
+                threshold_mins: threshold,
+            });
+        }
+    }
+
+    if let Some(weekly_mins) = weekly_mins {
+        let week_start = clock::week_start(today);
+        let threshold = weekly_threshold(weekly_mins, context, week_start);
+        let (from, _) = clock::day_bounds(timezone, week_start);
+        let (_, to) = clock::day_bounds(timezone, week_start + Duration::days(6));
+        let worked = history.worked_between(from, to, now).num_minutes();
+
+        if worked > threshold as i64 {
+            warnings.push(OvertimeWarning {
+                period: OvertimePeriod::Week,
+                period_start: week_start,
+                worked_mins: worked,
+                threshold_mins: threshold,
+            });
+        }
+    }
+
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::holidays::HolidaySource;
+    use crate::schedule::{DayOverride, WeeklySchedule};
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn at(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    fn holiday(s: &str) -> Holiday {
+        Holiday {
+            date: date(s),
+            name: "Holiday".to_string(),
+            source: HolidaySource::Manual,
+        }
+    }
+
+    fn context<'a>(schedule: &'a WeeklySchedule, overrides: &'a [DayOverride], holidays: &'a [Holiday]) -> ScheduleContext<'a> {
+        ScheduleContext {
+            schedule,
+            overrides,
+            holidays,
+            vacation: None,
+            follow_schedule: false,
+            end_of_day_reminder: None,
+            timezone: Some("UTC"),
+        }
+    }
+
+    #[test]
+    fn test_weekly_threshold_reduced_by_weekday_holidays() {
+        let schedule = WeeklySchedule::default();
+        let holidays = vec![holiday("2025-04-18"), holiday("2025-04-19")];
+        assert_eq!(weekly_threshold(2400, &context(&schedule, &[], &holidays), date("2025-04-14")), 1920);
+    }
+
+    #[test]
+    fn test_weekly_threshold_follows_the_schedule() {
+        // Four days a week, Monday to Thursday
+        let schedule = WeeklySchedule { fri: Vec::new(), ..WeeklySchedule::default() };
+        let holidays = vec![holiday("2025-04-18")];
+        assert_eq!(weekly_threshold(2400, &context(&schedule, &[], &holidays), date("2025-04-14")), 2400);
+
+        // A day off overridden on Tuesday, and a working Saturday
+        let overrides = vec![
+            DayOverride { date: date("2025-04-15"), blocks: Vec::new() },
+            DayOverride { date: date("2025-04-19"), blocks: schedule.mon.clone() },
+        ];
+        assert_eq!(weekly_threshold(2400, &context(&schedule, &overrides, &[]), date("2025-04-14")), 2400);
+        assert_eq!(weekly_threshold(2400, &context(&schedule, &overrides[..1], &[]), date("2025-04-14")), 1800);
+
+        // Nothing scheduled at all counts Monday to Friday
+        let empty = WeeklySchedule { mon: Vec::new(), tue: Vec::new(), wed: Vec::new(), thu: Vec::new(), ..schedule.clone() };
+        assert_eq!(weekly_threshold(2400, &context(&empty, &[], &holidays), date("2025-04-14")), 1920);
+    }
+
+    #[test]
+    fn test_check_reports_exceeded_periods() {
+        let mut history = History::default();
+        history.start_session(at("2025-03-10T08:00:00Z"));
+
+        let schedule = WeeklySchedule::default();
+        let warnings = check(&history, &context(&schedule, &[], &[]), Some(480), Some(2400), at("2025-03-10T17:00:00Z"));
+
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].period, OvertimePeriod::Day);
+        assert_eq!(warnings[0].worked_mins, 540);
+    }
+}
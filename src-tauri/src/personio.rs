@@ -0,0 +1,129 @@
+use chrono::{Duration, NaiveDateTime};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::backend::{self, BackendAdapter, Clock, Reply, SendError};
+
+const API_URL: &str = "https://api.personio.de/v1";
+
+// A Personio company where each session becomes an attendance period: opened
+// on clock-in, ended on clock-out. `Settings::user_id` must be the numeric
+// employee ID. The client secret of the API credentials is kept with the
+// other secrets, see `Secrets::personio_client_secret`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default, JsonSchema)]
+#[serde(default)]
+pub struct PersonioSettings {
+    pub client_id: String,
+}
+
+pub struct Personio<'a> {
+    pub settings: &'a PersonioSettings,
+    pub client_secret: Option<&'a str>,
+}
+
+pub fn employee_id(payload: &Value) -> Result<u64, String> {
+    let employee = backend::employee(payload)?;
+    employee.trim().parse().map_err(|_| format!("'{}' is not a Personio employee ID, set the employee ID to the number of the employee", employee))
+}
+
+// The `data` of a response. Personio wraps everything in
+// `{"success": ..., "data": ...}` and explains failures in `error.message`.
+pub fn parse_data(body: &str) -> Result<Value, String> {
+    let mut body: Value = serde_json::from_str(body).map_err(|err| format!("Unexpected Personio response: {}", err))?;
+    if body["success"].as_bool() != Some(true) {
+        let message = body["error"]["message"].as_str().unwrap_or("Unknown error");
+        return Err(format!("Personio rejected the request: {}", message));
+    }
+    Ok(body["data"].take())
+}
+
+// The ID of the employee's attendance period that has no end yet
+pub fn open_period(periods: &Value, employee_id: u64) -> Option<u64> {
+    periods.as_array()?.iter().find_map(|period| {
+        let attributes = &period["attributes"];
+        let open = attributes["end_time"].is_null() && attributes["employee"].as_u64() == Some(employee_id);
+        if open { period["id"].as_u64() } else { None }
+    })
+}
+
+fn reply_data(reply: &Reply) -> Result<Value, SendError> {
+    parse_data(&reply.body).map_err(|err| {
+        SendError::Rejected(if reply.status.is_success() { err } else { format!("{} (status {})", err, reply.status) })
+    })
+}
+
+impl BackendAdapter for Personio<'_> {
+    // Whether to check in or out, the employee ID and the local time
+    type Event = (Clock, u64, NaiveDateTime);
+    // A bearer token for the client credentials
+    type Session = String;
+
+    fn name(&self) -> &'static str {
+        "Personio"
+    }
+
+    fn build(&self, event_type: &str, payload: &Value) -> Result<Option<Self::Event>, String> {
+        let Some(clock) = backend::clock(event_type) else {
+            return Ok(None);
+        };
+        Ok(Some((clock, employee_id(payload)?, backend::event_time(payload)?)))
+    }
+
+    async fn authenticate(&self, client: &reqwest::Client) -> Result<String, SendError> {
+        let client_secret = self.client_secret.ok_or_else(|| SendError::Rejected("No Personio client secret is set".to_string()))?;
+        let credentials = json!({ "client_id": self.settings.client_id.trim(), "client_secret": client_secret });
+        let data = reply_data(&backend::request(client.post(format!("{}/auth", API_URL)).json(&credentials)).await?)?;
+        data["token"].as_str().map(str::to_string).ok_or_else(|| SendError::Rejected("Personio returned no token".to_string()))
+    }
+
+    async fn submit(&self, client: &reqwest::Client, token: &String, (clock, employee_id, time): Self::Event) -> Result<Option<Reply>, SendError> {
+        // Sessions that run past midnight started the day before
+        let query = [
+            ("start_date", (time.date() - Duration::days(1)).format("%Y-%m-%d").to_string()),
+            ("end_date", time.date().format("%Y-%m-%d").to_string()),
+            ("employees[]", employee_id.to_string()),
+        ];
+        let periods = reply_data(&backend::request(client.get(format!("{}/company/attendances", API_URL)).bearer_auth(token).query(&query)).await?)?;
+
+        let hour = time.format("%H:%M").to_string();
+        let request = match (clock, open_period(&periods, employee_id)) {
+            (Clock::In, None) => {
+                let period = json!({ "employee": employee_id, "date": time.format("%Y-%m-%d").to_string(), "start_time": hour, "end_time": null, "break": 0 });
+                client.post(format!("{}/company/attendances", API_URL)).json(&json!({ "attendances": [period] }))
+            }
+            // An open period already counts as checked in
+            (Clock::In, Some(_)) => return Ok(None),
+            (Clock::Out, Some(id)) => client.patch(format!("{}/company/attendances/{}", API_URL, id)).json(&json!({ "end_time": hour })),
+            (Clock::Out, None) => return Err(SendError::Rejected("There is no open attendance in Personio to check out of".to_string())),
+        };
+        backend::request(request.bearer_auth(token)).await.map(Some)
+    }
+
+    fn interpret(&self, reply: &Reply) -> Result<(), SendError> {
+        reply_data(reply).map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_data() {
+        assert_eq!(parse_data(r#"{"success": true, "data": {"token": "abc"}}"#), Ok(json!({ "token": "abc" })));
+        let error = r#"{"success": false, "error": {"code": 400, "message": "The attendance overlaps with an existing one"}}"#;
+        assert_eq!(parse_data(error), Err("Personio rejected the request: The attendance overlaps with an existing one".to_string()));
+    }
+
+    #[test]
+    fn test_open_period() {
+        let periods = json!([
+            { "id": 1, "type": "AttendancePeriod", "attributes": { "employee": 42, "date": "2024-05-06", "start_time": "08:00", "end_time": "12:00" } },
+            { "id": 2, "type": "AttendancePeriod", "attributes": { "employee": 42, "date": "2024-05-06", "start_time": "13:00", "end_time": null } },
+        ]);
+        assert_eq!(open_period(&periods, 42), Some(2));
+        assert_eq!(open_period(&periods, 7), None);
+        assert_eq!(employee_id(&json!({ "user_id": "42" })), Ok(42));
+    }
+}
@@ -0,0 +1,156 @@
+use std::process::Stdio;
+use std::time::Duration;
+
+use log::{error, info};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::AppHandle;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+use crate::i18n::{t, tf};
+use crate::notifications::{self, NotificationKind};
+use crate::AppState;
+
+// Longest stderr excerpt kept in a failure report
+const MAX_ERROR_CHARS: usize = 200;
+
+// An executable or script run on every status change, with the event as
+// JSON on stdin. Hooks run side by side and don't hold up the change.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+#[serde(default)]
+pub struct PluginHook {
+    pub name: String, // Shown when the hook fails
+    pub command: String, // Path of the executable, not run through a shell
+    pub args: Vec<String>,
+    pub timeout_secs: u64, // The hook is killed after this long
+    pub enabled: bool,
+}
+
+impl Default for PluginHook {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            command: String::new(),
+            args: Vec::new(),
+            timeout_secs: 10,
+            enabled: true,
+        }
+    }
+}
+
+impl PluginHook {
+    fn label(&self) -> &str {
+        if self.name.trim().is_empty() { &self.command } else { &self.name }
+    }
+}
+
+// What a hook reads on stdin: the `attendance_changed` event plus who and
+// where it happened
+pub fn stdin_body(mut event: Value, username: &str, device_name: &str) -> Value {
+    if let Some(fields) = event.as_object_mut() {
+        fields.insert("username".to_string(), Value::from(username));
+        fields.insert("device_name".to_string(), Value::from(device_name));
+    }
+    event
+}
+
+// Why a hook that exited unsuccessfully failed, from its status and the last
+// line it wrote to stderr
+pub fn exit_error(code: Option<i32>, stderr: &str) -> String {
+    let status = match code {
+        Some(code) => format!("Exited with status {}", code),
+        None => "Killed by a signal".to_string(),
+    };
+    match stderr.lines().rev().map(str::trim).find(|line| !line.is_empty()) {
+        Some(line) => format!("{}: {}", status, line.chars().take(MAX_ERROR_CHARS).collect::<String>()),
+        None => status,
+    }
+}
+
+// Run one hook to completion
+pub async fn run(hook: &PluginHook, body: &str) -> Result<(), String> {
+    let mut child = Command::new(hook.command.trim())
+        .args(&hook.args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|err| format!("Failed to start {}: {}", hook.command, err))?;
+
+    // Writing counts towards the timeout too, a hook that never reads its
+    // input would block it once the body fills the pipe
+    let finished = async move {
+        if let Some(mut stdin) = child.stdin.take() {
+            // A hook that doesn't read its input closes stdin early, that's fine
+            let _ = stdin.write_all(body.as_bytes()).await;
+        }
+        child.wait_with_output().await
+    };
+    let output = tokio::time::timeout(Duration::from_secs(hook.timeout_secs), finished)
+        .await
+        .map_err(|_| format!("Timed out after {} seconds", hook.timeout_secs))?
+        .map_err(|err| format!("Failed to wait for {}: {}", hook.command, err))?;
+
+    if !output.status.success() {
+        return Err(exit_error(output.status.code(), &String::from_utf8_lossy(&output.stderr)));
+    }
+    Ok(())
+}
+
+// Run the enabled hooks in the background, telling the user about failures
+pub fn fire(app_handle: &AppHandle, state: &AppState, event: Value) {
+    let settings = state.settings.lock().unwrap().clone();
+    let body = stdin_body(event, &settings.username, &settings.device_name).to_string();
+
+    for hook in settings.plugins.iter().filter(|hook| hook.enabled && !hook.command.trim().is_empty()) {
+        let (app_handle, settings, hook, body) = (app_handle.clone(), settings.clone(), hook.clone(), body.clone());
+        tauri::async_runtime::spawn(async move {
+            match run(&hook, &body).await {
+                Ok(()) => info!("Ran the {} plugin", hook.label()),
+                Err(err) => {
+                    error!("The {} plugin failed: {}", hook.label(), err);
+                    notifications::notify(
+                        &app_handle,
+                        &settings,
+                        NotificationKind::PluginFailure,
+                        &t("notify.plugin_failure.title"),
+                        &tf("notify.plugin_failure.body", &[("name", &hook.label()), ("error", &err)]),
+                    );
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_stdin_body() {
+        let event = json!({ "status": "checked-in", "event_type": "check-in", "cause": "manual" });
+        let body = stdin_body(event, "alice", "laptop");
+        assert_eq!(body["username"], json!("alice"));
+        assert_eq!(body["device_name"], json!("laptop"));
+        assert_eq!(body["event_type"], json!("check-in"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_timeout_covers_writing_stdin() {
+        // Never reads stdin, so a body larger than the pipe buffer can't be written
+        let hook = PluginHook { command: "sleep".to_string(), args: vec!["30".to_string()], timeout_secs: 1, ..PluginHook::default() };
+        let body = "x".repeat(1024 * 1024);
+        assert_eq!(run(&hook, &body).await, Err("Timed out after 1 seconds".to_string()));
+    }
+
+    #[test]
+    fn test_exit_error() {
+        assert_eq!(exit_error(Some(2), "warming up\nno such list: Work\n\n"), "Exited with status 2: no such list: Work");
+        assert_eq!(exit_error(None, ""), "Killed by a signal");
+    }
+}
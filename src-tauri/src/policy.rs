@@ -0,0 +1,195 @@
+use log::{error, info};
+use serde_json::{Map, Value};
+
+use crate::migrations;
+use crate::Settings;
+
+// Settings set by the organization in a system location the user can't
+// change. Policy values override the user's settings and lock them in the
+// settings screen. Nested objects only lock the fields they contain.
+#[derive(Debug, Clone, Default)]
+pub struct Policy {
+    values: Map<String, Value>,
+}
+
+impl Policy {
+    // Keep the values that fit the settings, logging the others with where they came from
+    pub fn new(source: &str, values: Map<String, Value>) -> Self {
+        let defaults = serde_json::to_value(Settings::default()).unwrap_or_default();
+        let mut values = Value::Object(values);
+        migrations::upgrade_partial(&mut values);
+        let Value::Object(values) = values else {
+            unreachable!("migrations keep settings an object");
+        };
+        let mut policy = Self::default();
+        for (key, value) in values {
+            let mut merged = defaults.clone();
+            merge(&mut merged, &Value::Object(Map::from_iter([(key.clone(), value.clone())])));
+            if defaults.get(&key).is_some() && serde_json::from_value::<Settings>(merged).is_ok() {
+                policy.values.insert(key, value);
+            } else {
+                error!("Ignoring {} setting '{}': not a setting or invalid value", source, key);
+            }
+        }
+        policy
+    }
+
+    // Settings with the policy values applied
+    pub fn enforce(&self, settings: &Settings) -> Settings {
+        if self.values.is_empty() {
+            return settings.clone();
+        }
+
+        let mut merged = serde_json::to_value(settings).unwrap_or_default();
+        merge(&mut merged, &Value::Object(self.values.clone()));
+        serde_json::from_value(merged).unwrap_or_else(|err| {
+            error!("Failed to apply the policy: {}", err);
+            settings.clone()
+        })
+    }
+
+    // Whether the policy sets a top-level setting
+    pub fn locks(&self, field: &str) -> bool {
+        self.values.contains_key(field)
+    }
+
+    // Dotted paths of the settings the policy sets, e.g. `notifications.reminders`
+    pub fn locked_fields(&self) -> Vec<String> {
+        let mut fields = Vec::new();
+        collect_paths(&self.values, "", &mut fields);
+        fields
+    }
+}
+
+// Overlay `patch` on `target`, descending into objects present in both
+fn merge(target: &mut Value, patch: &Value) {
+    match (target, patch) {
+        (Value::Object(target), Value::Object(patch)) => {
+            for (key, value) in patch {
+                match target.get_mut(key) {
+                    Some(existing) if existing.is_object() && value.is_object() => merge(existing, value),
+                    _ => {
+                        target.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        (target, patch) => *target = patch.clone(),
+    }
+}
+
+fn collect_paths(values: &Map<String, Value>, prefix: &str, paths: &mut Vec<String>) {
+    for (key, value) in values {
+        let path = format!("{}{}", prefix, key);
+        match value {
+            Value::Object(nested) if !nested.is_empty() => collect_paths(nested, &format!("{}.", path), paths),
+            _ => paths.push(path),
+        }
+    }
+}
+
+// Read the policy for this machine. No policy means nothing is locked.
+pub fn load() -> Policy {
+    match read_policy() {
+        Ok(Some(values)) => {
+            info!("Applying the managed policy");
+            Policy::new("policy", values)
+        }
+        Ok(None) => Policy::default(),
+        Err(err) => {
+            error!("Failed to read the managed policy: {}", err);
+            Policy::default()
+        }
+    }
+}
+
+fn parse(contents: &str) -> Result<Map<String, Value>, String> {
+    serde_json::from_str(contents).map_err(|err| format!("The policy is not a JSON object: {}", err))
+}
+
+// Policy file in /etc, JSON
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn read_policy() -> Result<Option<Map<String, Value>>, String> {
+    match std::fs::read_to_string("/etc/remodance/policy.json") {
+        Ok(contents) => parse(&contents).map(Some),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+// Managed preferences deployed by an MDM profile, converted to JSON by plutil
+#[cfg(target_os = "macos")]
+fn read_policy() -> Result<Option<Map<String, Value>>, String> {
+    let path = "/Library/Managed Preferences/com.remodance.app.plist";
+    if !std::path::Path::new(path).exists() {
+        return Ok(None);
+    }
+
+    let output = std::process::Command::new("plutil")
+        .args(["-convert", "json", "-o", "-", path])
+        .output()
+        .map_err(|err| format!("Failed to run plutil: {}", err))?;
+    if !output.status.success() {
+        return Err(format!("plutil could not read {}", path));
+    }
+    parse(&String::from_utf8_lossy(&output.stdout)).map(Some)
+}
+
+// JSON in the `Policy` value of HKLM\SOFTWARE\Policies\Remodance, set by Group Policy
+#[cfg(target_os = "windows")]
+fn read_policy() -> Result<Option<Map<String, Value>>, String> {
+    #[link(name = "advapi32")]
+    extern "system" {
+        fn RegGetValueW(key: isize, sub_key: *const u16, value: *const u16, flags: u32, kind: *mut u32, data: *mut u16, size: *mut u32) -> i32;
+    }
+    const HKEY_LOCAL_MACHINE: isize = 0x80000002u32 as i32 as isize;
+    const RRF_RT_REG_SZ: u32 = 0x2;
+    const ERROR_FILE_NOT_FOUND: i32 = 2;
+
+    let wide = |text: &str| text.encode_utf16().chain(Some(0)).collect::<Vec<u16>>();
+    let (sub_key, value) = (wide("SOFTWARE\\Policies\\Remodance"), wide("Policy"));
+
+    let mut size = 0u32;
+    // SAFETY: the first call only writes the needed size, the second writes at
+    // most `size` bytes into a buffer of that size
+    let result = unsafe { RegGetValueW(HKEY_LOCAL_MACHINE, sub_key.as_ptr(), value.as_ptr(), RRF_RT_REG_SZ, std::ptr::null_mut(), std::ptr::null_mut(), &mut size) };
+    if result == ERROR_FILE_NOT_FOUND {
+        return Ok(None);
+    } else if result != 0 {
+        return Err(format!("Registry error {}", result));
+    }
+
+    let mut data = vec![0u16; (size as usize).div_ceil(2)];
+    let result = unsafe { RegGetValueW(HKEY_LOCAL_MACHINE, sub_key.as_ptr(), value.as_ptr(), RRF_RT_REG_SZ, std::ptr::null_mut(), data.as_mut_ptr(), &mut size) };
+    if result != 0 {
+        return Err(format!("Registry error {}", result));
+    }
+    let text = String::from_utf16_lossy(&data);
+    parse(text.trim_end_matches('\0')).map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_policy_overrides_and_locks_fields() {
+        let values = json!({
+            "api_endpoint": "https://attendance.corp.example",
+            "notifications": { "reminders": false },
+            "no_such_setting": true,
+            "idle_timeout_secs": "ten",
+        });
+        let policy = Policy::new("policy", values.as_object().unwrap().clone());
+        assert_eq!(policy.locked_fields(), vec!["api_endpoint", "notifications.reminders"]);
+        assert!(policy.locks("api_endpoint"));
+        assert!(!policy.locks("idle_timeout_secs"));
+
+        let user = Settings { idle_timeout_secs: 25, ..Settings::default() };
+        let enforced = policy.enforce(&user);
+        assert_eq!(enforced.api_endpoint, "https://attendance.corp.example");
+        assert!(!enforced.notifications.reminders);
+        assert_eq!(enforced.idle_timeout_secs, 25);
+    }
+}
@@ -0,0 +1,53 @@
+use serde::Serialize;
+
+use crate::settings_audit::Change;
+
+// Parts of the app that pick up changed settings by restarting
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "kebab-case")]
+pub enum Subsystem {
+    Monitor, // Idle detection, breaks and limits
+    Scheduler, // Working hours, holidays and reminders
+    Transport, // Sending events to the API
+}
+
+// The subsystem a top-level setting belongs to, if changing it restarts one
+fn subsystem(field: &str) -> Option<Subsystem> {
+    let top = field.split('.').next().unwrap_or(field);
+    match top {
+        "api_endpoint" | "username" | "employee_id" | "directory_url" | "device_name" | "org_id" | "config_url"
+        | "config_public_key" => {
+            Some(Subsystem::Transport)
+        }
+        "idle_timeout_secs" | "auto_mode" | "checkout_warning_secs" | "idle_break_max_mins" | "lunch_window"
+        | "max_daily_mins" | "overtime_daily_mins" | "overtime_weekly_mins" | "break_types" | "break_budget_mins"
+        | "break_reminder_after_mins" | "break_reminder_interval_mins" | "break_reminder_snooze_mins"
+        | "micro_break_interval_mins" | "micro_break_secs" => Some(Subsystem::Monitor),
+        "schedule" | "schedule_templates" | "active_template" | "day_overrides" | "follow_schedule" | "quiet_hours"
+        | "timezone" | "holidays" | "vacation" | "calendar_subscriptions" | "block_end_warning_mins"
+        | "end_of_day_reminder" | "morning_reminder" => Some(Subsystem::Scheduler),
+        _ => None,
+    }
+}
+
+// Subsystems the changes would restart, each once
+pub fn restarts(changes: &[Change]) -> Vec<Subsystem> {
+    let mut subsystems: Vec<_> = changes.iter().filter_map(|change| subsystem(&change.field)).collect();
+    subsystems.sort();
+    subsystems.dedup();
+    subsystems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_restarts_each_affected_subsystem_once() {
+        let change = |field: &str| Change { field: field.to_string(), old: json!(1), new: json!(2) };
+        let changes = [change("idle_timeout_secs"), change("api_endpoint"), change("auto_mode"), change("status_line_format")];
+        assert_eq!(restarts(&changes), vec![Subsystem::Monitor, Subsystem::Transport]);
+        assert_eq!(restarts(&[change("notifications.reminders")]), vec![]);
+    }
+}
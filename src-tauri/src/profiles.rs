@@ -0,0 +1,155 @@
+use serde::{Deserialize, Serialize};
+
+use crate::Settings;
+
+// Name the current settings are saved under when switching away before any profile was active
+pub const DEFAULT_PROFILE: &str = "default";
+
+const MAX_NAME_LEN: usize = 64;
+
+// A named set of settings, e.g. one per client
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Profile {
+    pub name: String,
+    pub settings: Settings,
+}
+
+// Saved profiles. The active profile's settings are the ones in use, so its
+// entry here is only brought up to date when switching away from it. Each
+// profile keeps its own local history, see `history_filename`.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct Profiles {
+    pub active: Option<String>,
+    pub profiles: Vec<Profile>,
+}
+
+// Profile names for the switcher, sent with `profile_changed`
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct ProfileList {
+    pub active: Option<String>,
+    pub names: Vec<String>,
+}
+
+impl Profiles {
+    pub fn find(&self, name: &str) -> Option<&Profile> {
+        self.profiles.iter().find(|profile| profile.name == name)
+    }
+
+    // Add a profile or replace the settings of an existing one
+    pub fn save(&mut self, name: &str, settings: Settings) {
+        match self.profiles.iter_mut().find(|profile| profile.name == name) {
+            Some(existing) => existing.settings = settings,
+            None => self.profiles.push(Profile { name: name.to_string(), settings }),
+        }
+    }
+
+    // Rename a profile, following it with the active pointer
+    pub fn rename(&mut self, name: &str, new_name: &str) -> Result<(), String> {
+        if self.find(new_name).is_some() {
+            return Err(format!("A profile named '{}' already exists", new_name));
+        }
+        let profile = self.profiles.iter_mut()
+            .find(|profile| profile.name == name)
+            .ok_or_else(|| format!("No profile named '{}'", name))?;
+
+        profile.name = new_name.to_string();
+        if self.active.as_deref() == Some(name) {
+            self.active = Some(new_name.to_string());
+        }
+        Ok(())
+    }
+
+    // Remove a profile other than the active one
+    pub fn remove(&mut self, name: &str) -> Result<Profile, String> {
+        if self.active.as_deref() == Some(name) {
+            return Err("The active profile can't be deleted, switch to another one first".to_string());
+        }
+        let index = self.profiles.iter()
+            .position(|profile| profile.name == name)
+            .ok_or_else(|| format!("No profile named '{}'", name))?;
+        Ok(self.profiles.remove(index))
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.profiles.iter().map(|profile| profile.name.clone()).collect()
+    }
+
+    pub fn list(&self) -> ProfileList {
+        ProfileList {
+            active: self.active.clone(),
+            names: self.names(),
+        }
+    }
+}
+
+// Trimmed profile name, or why it can't be used
+pub fn check_name(name: &str) -> Result<&str, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Profile name must not be empty".to_string());
+    }
+    if name.chars().count() > MAX_NAME_LEN {
+        return Err(format!("Profile name must be at most {} characters", MAX_NAME_LEN));
+    }
+    Ok(name)
+}
+
+// Store file holding a profile's history. The default profile, or no profile,
+// keeps the history file from before profiles existed. Other names are
+// encoded so every name gets its own file.
+pub fn history_filename(profile: Option<&str>) -> String {
+    match profile {
+        None | Some(DEFAULT_PROFILE) => crate::HISTORY_FILENAME.to_string(),
+        Some(name) => {
+            let encoded: String = name
+                .chars()
+                .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c.to_string() } else { format!("~{:x}", c as u32) })
+                .collect();
+            format!("history-{}.json", encoded)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_save_replaces_profile_with_same_name() {
+        let mut profiles = Profiles::default();
+        profiles.save("work", Settings::default());
+        profiles.save("client", Settings::default());
+        profiles.save("work", Settings { idle_timeout_secs: 5, ..Settings::default() });
+
+        assert_eq!(profiles.names(), vec!["work", "client"]);
+        assert_eq!(profiles.find("work").map(|p| p.settings.idle_timeout_secs), Some(5));
+        assert!(profiles.find("home").is_none());
+    }
+
+    #[test]
+    fn test_rename_and_remove() {
+        let mut profiles = Profiles::default();
+        profiles.save("work", Settings::default());
+        profiles.save("client", Settings::default());
+        profiles.active = Some("work".to_string());
+
+        assert!(profiles.rename("work", "client").is_err());
+        profiles.rename("work", "acme").unwrap();
+        assert_eq!(profiles.active.as_deref(), Some("acme"));
+
+        assert!(profiles.remove("acme").is_err());
+        assert_eq!(profiles.remove("client").map(|p| p.name), Ok("client".to_string()));
+        assert_eq!(profiles.names(), vec!["acme"]);
+    }
+
+    #[test]
+    fn test_history_file_per_profile() {
+        assert_eq!(history_filename(None), crate::HISTORY_FILENAME);
+        assert_eq!(history_filename(Some(DEFAULT_PROFILE)), crate::HISTORY_FILENAME);
+        assert_eq!(history_filename(Some("Acme Corp")), "history-Acme~20Corp.json");
+        assert_ne!(history_filename(Some("a b")), history_filename(Some("a-b")));
+        assert_eq!(check_name("  work "), Ok("work"));
+        assert!(check_name(" ").is_err());
+    }
+}
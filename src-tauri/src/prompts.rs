@@ -0,0 +1,173 @@
+use chrono::NaiveTime;
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::{t, tf};
+
+// Prompt identifiers
+pub const END_OF_DAY_PROMPT: &str = "end-of-day";
+pub const MORNING_PROMPT: &str = "morning";
+pub const BREAK_REMINDER_PROMPT: &str = "break-reminder";
+pub const PRE_CHECKOUT_PROMPT: &str = "pre-checkout";
+pub const LAUNCH_PROMPT: &str = "launch";
+pub const EXIT_PROMPT: &str = "exit";
+pub const BLOCK_END_PROMPT: &str = "block-end";
+
+// Action identifiers understood by `handle_prompt_action`
+pub const ACTION_CHECK_IN: &str = "check-in";
+pub const ACTION_CHECK_OUT: &str = "check-out";
+pub const ACTION_DISMISS: &str = "dismiss";
+pub const ACTION_SNOOZE: &str = "snooze";
+pub const ACTION_STILL_HERE: &str = "still-here";
+pub const ACTION_CONFIRM_CHECK_OUT: &str = "confirm-check-out"; // Check out as the idle monitor would
+pub const ACTION_CHECK_OUT_AND_QUIT: &str = "check-out-and-quit";
+pub const ACTION_QUIT: &str = "quit";
+pub const ACTION_EXTEND: &str = "extend"; // Postpone the block-end check-out by an hour
+
+// A button shown with a prompt
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PromptAction {
+    pub id: String,
+    pub label: String,
+}
+
+// A question shown as a native notification and mirrored to the frontend as
+// an `action_prompt` event, since desktop notifications cannot carry buttons.
+// The frontend renders the actions and routes the choice back to the backend.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Prompt {
+    pub id: String,
+    pub title: String,
+    pub body: String,
+    pub actions: Vec<PromptAction>,
+}
+
+impl Prompt {
+    pub fn new(id: &str, title: &str, body: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            title: title.to_string(),
+            body: body.to_string(),
+            actions: Vec::new(),
+        }
+    }
+
+    pub fn action(mut self, id: &str, label: &str) -> Self {
+        self.actions.push(PromptAction {
+            id: id.to_string(),
+            label: label.to_string(),
+        });
+        self
+    }
+
+    pub fn has_action(&self, id: &str) -> bool {
+        self.actions.iter().any(|action| action.id == id)
+    }
+}
+
+// Reminder to check out when still checked in late in the day
+pub fn end_of_day() -> Prompt {
+    Prompt::new(END_OF_DAY_PROMPT, &t("prompt.end_of_day.title"), &t("prompt.end_of_day.body"))
+        .action(ACTION_CHECK_OUT, &t("action.check_out"))
+        .action(ACTION_DISMISS, &t("action.not_now"))
+}
+
+// Warning shortly before the automatic check-out at the end of a scheduled block
+pub fn block_end(check_out_at: NaiveTime) -> Prompt {
+    Prompt::new(
+        BLOCK_END_PROMPT,
+        &t("prompt.block_end.title"),
+        &tf("prompt.block_end.body", &[("time", &check_out_at.format("%H:%M"))]),
+    )
+    .action(ACTION_EXTEND, &t("action.extend"))
+    .action(ACTION_CHECK_OUT, &t("action.check_out_now"))
+    .action(ACTION_DISMISS, &t("action.ok"))
+}
+
+// Question asked when the app starts while checked out
+pub fn launch() -> Prompt {
+    Prompt::new(LAUNCH_PROMPT, &t("prompt.launch.title"), &t("prompt.launch.body"))
+        .action(ACTION_CHECK_IN, &t("action.check_in"))
+        .action(ACTION_DISMISS, &t("action.not_yet"))
+}
+
+// Question asked when quitting while checked in
+pub fn exit() -> Prompt {
+    Prompt::new(EXIT_PROMPT, &t("prompt.exit.title"), &t("prompt.exit.body"))
+        .action(ACTION_CHECK_OUT_AND_QUIT, &t("action.check_out_and_quit"))
+        .action(ACTION_QUIT, &t("action.quit_without_check_out"))
+        .action(ACTION_DISMISS, &t("action.cancel"))
+}
+
+// Reminder to check in when the working day has started
+pub fn morning_check_in() -> Prompt {
+    Prompt::new(MORNING_PROMPT, &t("prompt.morning.title"), &t("prompt.morning.body"))
+        .action(ACTION_CHECK_IN, &t("action.check_in"))
+        .action(ACTION_DISMISS, &t("action.not_now"))
+}
+
+// Recurring suggestion to take a break while checked in
+pub fn break_reminder(worked_mins: i64, snooze_mins: u64) -> Prompt {
+    Prompt::new(
+        BREAK_REMINDER_PROMPT,
+        &t("prompt.break_reminder.title"),
+        &tf("prompt.break_reminder.body", &[("hours", &(worked_mins / 60)), ("mins", &(worked_mins % 60))]),
+    )
+    .action(ACTION_SNOOZE, &tf("action.snooze", &[("mins", &snooze_mins)]))
+    .action(ACTION_DISMISS, &t("action.dismiss"))
+}
+
+// Warning shortly before an automatic check-out for inactivity
+pub fn pre_checkout(secs_left: u64) -> Prompt {
+    Prompt::new(
+        PRE_CHECKOUT_PROMPT,
+        &t("prompt.pre_checkout.title"),
+        &tf("prompt.pre_checkout.body", &[("secs", &secs_left)]),
+    )
+    .action(ACTION_STILL_HERE, &t("action.still_here"))
+    .action(ACTION_CONFIRM_CHECK_OUT, &t("action.check_out_now"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_end_of_day_prompt_offers_check_out() {
+        let prompt = end_of_day();
+        assert_eq!(prompt.id, END_OF_DAY_PROMPT);
+        assert!(prompt.has_action(ACTION_CHECK_OUT));
+        assert!(!prompt.has_action(ACTION_CHECK_IN));
+    }
+
+    #[test]
+    fn test_launch_prompt_offers_check_in() {
+        let prompt = launch();
+        assert_eq!(prompt.id, LAUNCH_PROMPT);
+        assert!(prompt.has_action(ACTION_CHECK_IN));
+        assert!(prompt.has_action(ACTION_DISMISS));
+    }
+
+    #[test]
+    fn test_exit_prompt_can_check_out_quit_or_cancel() {
+        let prompt = exit();
+        assert!(prompt.has_action(ACTION_CHECK_OUT_AND_QUIT));
+        assert!(prompt.has_action(ACTION_QUIT));
+        assert!(prompt.has_action(ACTION_DISMISS));
+    }
+
+    #[test]
+    fn test_pre_checkout_prompt_can_cancel_or_confirm() {
+        let prompt = pre_checkout(60);
+        assert!(prompt.body.contains("60 seconds"));
+        assert!(prompt.has_action(ACTION_STILL_HERE));
+        assert!(prompt.has_action(ACTION_CONFIRM_CHECK_OUT));
+    }
+
+    #[test]
+    fn test_block_end_prompt_can_extend() {
+        let prompt = block_end(NaiveTime::from_hms_opt(18, 0, 0).unwrap());
+        assert!(prompt.body.contains("18:00"));
+        assert!(prompt.has_action(ACTION_EXTEND));
+        assert!(prompt.has_action(ACTION_CHECK_OUT));
+    }
+}
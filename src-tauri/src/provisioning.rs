@@ -0,0 +1,65 @@
+use std::path::PathBuf;
+
+use log::error;
+use serde_json::{Map, Value};
+
+const FILENAME: &str = "remodance-provisioning.json";
+
+// Places a deployment can put the provisioning file: next to the app's
+// executable, then a system-wide location
+fn candidates() -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    if let Some(dir) = std::env::current_exe().ok().and_then(|exe| exe.parent().map(PathBuf::from)) {
+        paths.push(dir.join(FILENAME));
+    }
+    paths.push(system_path());
+    paths
+}
+
+#[cfg(target_os = "windows")]
+fn system_path() -> PathBuf {
+    let program_data = std::env::var_os("ProgramData").unwrap_or_else(|| "C:\\ProgramData".into());
+    PathBuf::from(program_data).join("Remodance").join(FILENAME)
+}
+
+#[cfg(target_os = "macos")]
+fn system_path() -> PathBuf {
+    PathBuf::from("/Library/Application Support/Remodance").join(FILENAME)
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn system_path() -> PathBuf {
+    PathBuf::from("/etc/remodance").join(FILENAME)
+}
+
+// Settings to start from on the first run, from the first provisioning file
+// found. The file is a JSON object of settings, like the managed policy, but
+// only seeds them: the user can change them afterwards.
+pub fn load() -> Option<(PathBuf, Map<String, Value>)> {
+    candidates().into_iter().find_map(|path| {
+        let contents = std::fs::read_to_string(&path).ok()?;
+        match parse(&contents) {
+            Ok(values) => Some((path, values)),
+            Err(err) => {
+                error!("Ignoring provisioning file {}: {}", path.display(), err);
+                None
+            }
+        }
+    })
+}
+
+fn parse(contents: &str) -> Result<Map<String, Value>, String> {
+    serde_json::from_str(contents).map_err(|err| format!("not a JSON object: {}", err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_requires_an_object() {
+        let values = parse(r#"{ "api_endpoint": "https://attendance.corp.example" }"#).unwrap();
+        assert_eq!(values["api_endpoint"], "https://attendance.corp.example");
+        assert!(parse("[]").is_err());
+    }
+}
@@ -0,0 +1,150 @@
+use log::info;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+const NTFY_URL: &str = "https://ntfy.sh";
+const PUSHOVER_URL: &str = "https://api.pushover.net/1/messages.json";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum PushService {
+    Ntfy,
+    Pushover,
+    Gotify,
+}
+
+// Critical events pushed to the user's phone, on top of desktop
+// notifications. The ntfy access token, or the Pushover or Gotify
+// application token, is kept with the other secrets, see `Secrets::push_token`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+#[serde(default)]
+pub struct PushSettings {
+    pub service: Option<PushService>, // Off when unset
+    pub server_url: Option<String>, // ntfy or Gotify server, ntfy.sh when unset for ntfy
+    pub topic: Option<String>, // ntfy topic, or the Pushover user key
+    pub api_unreachable_mins: Option<u64>, // Push when events have waited this long for the API
+    pub forgot_check_out: bool, // Push along with the end-of-day reminder
+}
+
+impl Default for PushSettings {
+    fn default() -> Self {
+        Self {
+            service: None,
+            server_url: None,
+            topic: None,
+            api_unreachable_mins: Some(60),
+            forgot_check_out: true,
+        }
+    }
+}
+
+// An HTTP request delivering a push, built without sending so it can be checked
+#[derive(Debug, Clone, PartialEq)]
+pub struct PushRequest {
+    pub url: String,
+    pub headers: Vec<(&'static str, String)>,
+    pub body: PushBody,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PushBody {
+    Text(String),
+    Form(Vec<(&'static str, String)>),
+    Json(Value),
+}
+
+fn required<'a>(value: &'a Option<String>, what: &str) -> Result<&'a str, String> {
+    value.as_deref().map(str::trim).filter(|value| !value.is_empty()).ok_or_else(|| format!("No {} is configured", what))
+}
+
+// The request pushing `title` and `message` through the configured service,
+// at high priority since only critical events are pushed
+pub fn request(settings: &PushSettings, token: Option<&str>, title: &str, message: &str) -> Result<PushRequest, String> {
+    let service = settings.service.ok_or("No push service is configured")?;
+    match service {
+        PushService::Ntfy => {
+            let server = settings.server_url.as_deref().unwrap_or(NTFY_URL).trim_end_matches('/');
+            let mut headers = vec![("Title", title.to_string()), ("Priority", "high".to_string()), ("Tags", "warning".to_string())];
+            if let Some(token) = token {
+                headers.push(("Authorization", format!("Bearer {}", token)));
+            }
+            Ok(PushRequest {
+                url: format!("{}/{}", server, required(&settings.topic, "ntfy topic")?),
+                headers,
+                body: PushBody::Text(message.to_string()),
+            })
+        }
+        PushService::Pushover => Ok(PushRequest {
+            url: PUSHOVER_URL.to_string(),
+            headers: Vec::new(),
+            body: PushBody::Form(vec![
+                ("token", token.ok_or("No Pushover application token is set")?.to_string()),
+                ("user", required(&settings.topic, "Pushover user key")?.to_string()),
+                ("title", title.to_string()),
+                ("message", message.to_string()),
+                ("priority", "1".to_string()),
+            ]),
+        }),
+        PushService::Gotify => {
+            let server = required(&settings.server_url, "Gotify server")?.trim_end_matches('/');
+            Ok(PushRequest {
+                url: format!("{}/message", server),
+                headers: vec![("X-Gotify-Key", token.ok_or("No Gotify application token is set")?.to_string())],
+                body: PushBody::Json(json!({ "title": title, "message": message, "priority": 8 })),
+            })
+        }
+    }
+}
+
+pub async fn send(settings: &PushSettings, token: Option<&str>, title: &str, message: &str) -> Result<(), String> {
+    let push = request(settings, token, title, message)?;
+    let mut request = reqwest::Client::new().post(&push.url);
+    for (name, value) in &push.headers {
+        request = request.header(*name, value);
+    }
+    let request = match push.body {
+        PushBody::Text(text) => request.body(text),
+        PushBody::Form(form) => request.form(&form),
+        PushBody::Json(body) => request.json(&body),
+    };
+
+    let response = request.send().await.map_err(|e| format!("Failed to send request: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("Push request failed with status {}", response.status()));
+    }
+    info!("Pushed '{}'", title);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(service: PushService) -> PushSettings {
+        PushSettings { service: Some(service), topic: Some("remodance-alice".to_string()), ..PushSettings::default() }
+    }
+
+    #[test]
+    fn test_ntfy_posts_to_the_topic() {
+        let push = request(&settings(PushService::Ntfy), None, "Still checked in", "Check out now?").unwrap();
+        assert_eq!(push.url, "https://ntfy.sh/remodance-alice");
+        assert!(push.headers.contains(&("Title", "Still checked in".to_string())));
+        assert_eq!(push.body, PushBody::Text("Check out now?".to_string()));
+
+        let unset = PushSettings { topic: Some(" ".to_string()), ..settings(PushService::Ntfy) };
+        assert!(request(&unset, None, "", "").is_err());
+    }
+
+    #[test]
+    fn test_pushover_and_gotify_need_a_token() {
+        assert!(request(&settings(PushService::Pushover), None, "", "").is_err());
+        let push = request(&settings(PushService::Pushover), Some("app"), "Title", "Body").unwrap();
+        assert!(matches!(push.body, PushBody::Form(form) if form.contains(&("user", "remodance-alice".to_string()))));
+
+        let gotify = PushSettings { server_url: Some("https://push.example.com/".to_string()), ..settings(PushService::Gotify) };
+        let push = request(&gotify, Some("app"), "Title", "Body").unwrap();
+        assert_eq!(push.url, "https://push.example.com/message");
+        assert_eq!(push.headers, vec![("X-Gotify-Key", "app".to_string())]);
+    }
+}
@@ -0,0 +1,77 @@
+use std::collections::VecDeque;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+// Events kept while the API is unreachable. The oldest are dropped past this.
+const MAX_PENDING_EVENTS: usize = 500;
+
+// An event that could not be sent, with its payload as it would have been posted
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PendingEvent {
+    pub event_type: String,
+    pub payload: Value,
+    pub queued_at: DateTime<Utc>,
+}
+
+// Events waiting to be resent, oldest first
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct PendingQueue {
+    events: VecDeque<PendingEvent>,
+}
+
+impl PendingQueue {
+    // Queue an event. Returns the event dropped to stay within the limit, if any.
+    pub fn push(&mut self, event: PendingEvent) -> Option<PendingEvent> {
+        self.events.push_back(event);
+        if self.events.len() > MAX_PENDING_EVENTS {
+            self.events.pop_front()
+        } else {
+            None
+        }
+    }
+
+    pub fn front(&self) -> Option<&PendingEvent> {
+        self.events.front()
+    }
+
+    pub fn pop_front(&mut self) -> Option<PendingEvent> {
+        self.events.pop_front()
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(n: usize) -> PendingEvent {
+        PendingEvent {
+            event_type: "check-in".to_string(),
+            payload: Value::from(n),
+            queued_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_queue_keeps_order_and_drops_oldest_past_limit() {
+        let mut queue = PendingQueue::default();
+        for n in 0..MAX_PENDING_EVENTS {
+            assert_eq!(queue.push(event(n)), None);
+        }
+
+        let dropped = queue.push(event(MAX_PENDING_EVENTS));
+        assert_eq!(dropped.map(|e| e.payload), Some(Value::from(0)));
+        assert_eq!(queue.len(), MAX_PENDING_EVENTS);
+        assert_eq!(queue.front().map(|e| e.payload.clone()), Some(Value::from(1)));
+    }
+}
@@ -0,0 +1,103 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use log::error;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+// Settings a bundle can't change, so a bundle never decides where the next
+// one comes from or which key signs it
+const PROTECTED: [&str; 2] = ["config_url", "config_public_key"];
+
+// Settings bundle served at `config_url`. `payload` is a base64 JSON object of
+// settings, `signature` the base64 Ed25519 signature of the decoded payload.
+// The payload's `version` must grow with every bundle published, so an older
+// signed bundle can't be served again to roll settings back.
+#[derive(Debug, Deserialize)]
+struct Bundle {
+    payload: String,
+    signature: String,
+}
+
+// Read a base64 Ed25519 public key
+pub fn parse_public_key(encoded: &str) -> Result<VerifyingKey, String> {
+    let bytes = STANDARD.decode(encoded.trim()).map_err(|err| format!("The public key is not base64: {}", err))?;
+    let bytes: [u8; 32] = bytes.try_into().map_err(|_| "The public key must be 32 bytes".to_string())?;
+    VerifyingKey::from_bytes(&bytes).map_err(|err| format!("Invalid public key: {}", err))
+}
+
+// Check a fetched bundle against the pinned key and return its version and
+// settings
+pub fn verify(contents: &str, public_key: &str) -> Result<(u64, Map<String, Value>), String> {
+    let key = parse_public_key(public_key)?;
+    let bundle: Bundle = serde_json::from_str(contents).map_err(|err| format!("Invalid settings bundle: {}", err))?;
+    let payload = STANDARD.decode(&bundle.payload).map_err(|err| format!("Invalid settings bundle payload: {}", err))?;
+    let signature = STANDARD.decode(&bundle.signature).map_err(|err| format!("Invalid settings bundle signature: {}", err))?;
+    let signature = Signature::from_slice(&signature).map_err(|err| format!("Invalid settings bundle signature: {}", err))?;
+
+    key.verify(&payload, &signature).map_err(|_| "The settings bundle signature does not match the pinned key".to_string())?;
+
+    let mut values: Map<String, Value> =
+        serde_json::from_slice(&payload).map_err(|err| format!("The settings bundle payload is not a JSON object: {}", err))?;
+    let version = values
+        .remove("version")
+        .and_then(|version| version.as_u64())
+        .ok_or("The settings bundle has no version")?;
+    for key in PROTECTED {
+        if values.remove(key).is_some() {
+            error!("Ignoring '{}' in the settings bundle", key);
+        }
+    }
+    Ok((version, values))
+}
+
+// Refuse a bundle older than the last one applied. The same version is
+// applied again, which restores settings the user changed since.
+pub fn check_version(version: u64, applied: Option<u64>) -> Result<(), String> {
+    match applied {
+        Some(applied) if version < applied => {
+            Err(format!("The settings bundle version {} is older than the applied version {}", version, applied))
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+    use serde_json::json;
+
+    #[test]
+    fn test_verify_accepts_signed_and_rejects_tampered_bundles() {
+        let signing_key = SigningKey::from_bytes(&[7; 32]);
+        let public_key = STANDARD.encode(signing_key.verifying_key().as_bytes());
+        let payload = json!({ "version": 3, "idle_timeout_secs": 5, "config_url": "https://evil.example" }).to_string();
+        let bundle = |payload: &str| {
+            json!({
+                "payload": STANDARD.encode(payload),
+                "signature": STANDARD.encode(signing_key.sign(payload.as_bytes()).to_bytes()),
+            })
+        };
+
+        let (version, values) = verify(&bundle(&payload).to_string(), &public_key).unwrap();
+        assert_eq!(version, 3);
+        assert_eq!(Value::Object(values), json!({ "idle_timeout_secs": 5 }));
+        assert!(verify(&bundle(r#"{"idle_timeout_secs":5}"#).to_string(), &public_key).unwrap_err().contains("no version"));
+
+        let mut tampered = bundle(&payload);
+        tampered["payload"] = json!(STANDARD.encode(r#"{"idle_timeout_secs":500}"#));
+        assert!(verify(&tampered.to_string(), &public_key).unwrap_err().contains("does not match"));
+
+        let other_key = STANDARD.encode(SigningKey::from_bytes(&[8; 32]).verifying_key().as_bytes());
+        assert!(verify(&bundle(&payload).to_string(), &other_key).is_err());
+    }
+
+    #[test]
+    fn test_check_version_refuses_older_bundles() {
+        assert!(check_version(1, None).is_ok());
+        assert!(check_version(3, Some(3)).is_ok());
+        assert!(check_version(4, Some(3)).is_ok());
+        assert!(check_version(2, Some(3)).is_err());
+    }
+}
@@ -0,0 +1,174 @@
+use chrono::{Datelike, NaiveDate, NaiveTime, Weekday};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+// A period of scheduled work within a day
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+pub struct WorkBlock {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl WorkBlock {
+    pub fn new(start: NaiveTime, end: NaiveTime) -> Self {
+        Self { start, end }
+    }
+
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        time >= self.start && time < self.end
+    }
+}
+
+// A daily time range that may wrap past midnight (e.g. 22:00–06:00)
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+pub struct TimeWindow {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl TimeWindow {
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            time >= self.start && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+// Working hours for each day of the week. Days without blocks are days off.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+#[serde(default)]
+pub struct WeeklySchedule {
+    pub mon: Vec<WorkBlock>,
+    pub tue: Vec<WorkBlock>,
+    pub wed: Vec<WorkBlock>,
+    pub thu: Vec<WorkBlock>,
+    pub fri: Vec<WorkBlock>,
+    pub sat: Vec<WorkBlock>,
+    pub sun: Vec<WorkBlock>,
+}
+
+impl WeeklySchedule {
+    pub fn blocks_for(&self, weekday: Weekday) -> &[WorkBlock] {
+        match weekday {
+            Weekday::Mon => &self.mon,
+            Weekday::Tue => &self.tue,
+            Weekday::Wed => &self.wed,
+            Weekday::Thu => &self.thu,
+            Weekday::Fri => &self.fri,
+            Weekday::Sat => &self.sat,
+            Weekday::Sun => &self.sun,
+        }
+    }
+
+}
+
+// A one-off change to the working hours of a single date. No blocks means a day off.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+pub struct DayOverride {
+    pub date: NaiveDate,
+    pub blocks: Vec<WorkBlock>,
+}
+
+// Working blocks on a date, honouring one-off overrides
+pub fn blocks_on<'a>(schedule: &'a WeeklySchedule, overrides: &'a [DayOverride], date: NaiveDate) -> &'a [WorkBlock] {
+    match overrides.iter().find(|o| o.date == date) {
+        Some(day_override) => &day_override.blocks,
+        None => schedule.blocks_for(date.weekday()),
+    }
+}
+
+// The block covering the given time, if any
+pub fn active_block(blocks: &[WorkBlock], time: NaiveTime) -> Option<&WorkBlock> {
+    blocks.iter().find(|block| block.contains(time))
+}
+
+// Scheduled start of the working day, if it is a working day
+pub fn day_start(blocks: &[WorkBlock]) -> Option<NaiveTime> {
+    blocks.iter().map(|block| block.start).min()
+}
+
+// A named weekly schedule the user can switch to ("Office week", "On-call")
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+pub struct ScheduleTemplate {
+    pub name: String,
+    pub schedule: WeeklySchedule,
+}
+
+// Look up a template by name
+pub fn find_template<'a>(templates: &'a [ScheduleTemplate], name: &str) -> Option<&'a ScheduleTemplate> {
+    templates.iter().find(|template| template.name == name)
+}
+
+impl Default for WeeklySchedule {
+    fn default() -> Self {
+        let office_hours = vec![WorkBlock::new(
+            NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+            NaiveTime::from_hms_opt(17, 0, 0).unwrap(),
+        )];
+
+        Self {
+            mon: office_hours.clone(),
+            tue: office_hours.clone(),
+            wed: office_hours.clone(),
+            thu: office_hours.clone(),
+            fri: office_hours,
+            sat: Vec::new(),
+            sun: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_schedule_is_weekdays_nine_to_five() {
+        let schedule = WeeklySchedule::default();
+        assert_eq!(day_start(schedule.blocks_for(Weekday::Mon)), NaiveTime::from_hms_opt(9, 0, 0));
+        assert_eq!(day_start(schedule.blocks_for(Weekday::Sat)), None);
+    }
+
+    #[test]
+    fn test_time_window_wraps_midnight() {
+        let time = |h| NaiveTime::from_hms_opt(h, 0, 0).unwrap();
+        let quiet_hours = TimeWindow { start: time(22), end: time(6) };
+
+        assert!(quiet_hours.contains(time(23)));
+        assert!(quiet_hours.contains(time(0)));
+        assert!(quiet_hours.contains(time(5)));
+        assert!(!quiet_hours.contains(time(6)));
+        assert!(!quiet_hours.contains(time(12)));
+    }
+
+    #[test]
+    fn test_start_time_is_earliest_block() {
+        let time = |h| NaiveTime::from_hms_opt(h, 0, 0).unwrap();
+        let schedule = WeeklySchedule {
+            mon: vec![WorkBlock::new(time(16), time(20)), WorkBlock::new(time(8), time(12))],
+            ..WeeklySchedule::default()
+        };
+
+        let blocks = schedule.blocks_for(Weekday::Mon);
+        assert_eq!(day_start(blocks), Some(time(8)));
+        assert_eq!(active_block(blocks, time(17)), Some(&WorkBlock::new(time(16), time(20))));
+        assert_eq!(active_block(blocks, time(13)), None);
+    }
+
+    #[test]
+    fn test_day_override_replaces_weekday_blocks() {
+        let time = |h| NaiveTime::from_hms_opt(h, 0, 0).unwrap();
+        let schedule = WeeklySchedule::default();
+        let monday = NaiveDate::from_ymd_opt(2025, 3, 10).unwrap();
+        let overrides = vec![
+            DayOverride { date: monday, blocks: vec![WorkBlock::new(time(9), time(13))] },
+            DayOverride { date: monday.succ_opt().unwrap(), blocks: Vec::new() },
+        ];
+
+        assert_eq!(blocks_on(&schedule, &overrides, monday), &[WorkBlock::new(time(9), time(13))]);
+        assert!(blocks_on(&schedule, &overrides, monday.succ_opt().unwrap()).is_empty());
+        assert_eq!(blocks_on(&schedule, &[], monday), schedule.blocks_for(Weekday::Mon));
+    }
+}
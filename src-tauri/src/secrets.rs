@@ -0,0 +1,212 @@
+use hmac::{Hmac, Mac};
+use keyring::Entry;
+use log::error;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::Sha256;
+
+use crate::oauth::Token;
+use crate::AppState;
+
+// Keychain entry holding the secrets, kept out of settings.json
+const KEYCHAIN_SERVICE: &str = "remodance";
+const KEYCHAIN_USER: &str = "api-credentials";
+
+// Credentials for the attendance API. Never sent to the frontend.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct Secrets {
+    pub api_token: Option<String>, // Sent as a bearer token
+    pub hmac_key: Option<String>,  // Signs request bodies, see `sign`
+    pub toggl_api_token: Option<String>, // Toggl Track API token, see `toggl`
+    pub clockify_api_key: Option<String>, // Clockify API key, see `clockify`
+    pub harvest_token: Option<String>, // Harvest personal access token, see `harvest`
+    pub smtp_password: Option<String>, // Mail server password for `email_report`
+    pub push_token: Option<String>, // Token of the push service, see `push`
+    pub mqtt_password: Option<String>, // Password for the broker in `mqtt`
+    pub telegram_bot_token: Option<String>, // Token of the bot in `telegram`
+    pub erpnext_api_secret: Option<String>, // Secret of the API key in `erpnext`
+    pub odoo_api_key: Option<String>, // API key of the login in `odoo`
+    pub bamboohr_api_key: Option<String>,
+    pub personio_client_secret: Option<String>, // Secret of the client ID in `personio`
+    pub local_api_token: Option<String>, // Bearer token callers of the local API must send
+    pub matrix_access_token: Option<String>,
+    pub caldav_password: Option<String>, // Password, or app password, of the user in `caldav`
+    pub code_activity_token: Option<String>, // GitHub or GitLab token, see `code_activity`
+}
+
+// Which secrets are set, for the settings screen
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct SecretStatus {
+    pub api_token: bool,
+    pub hmac_key: bool,
+    pub toggl_api_token: bool,
+    pub clockify_api_key: bool,
+    pub harvest_token: bool,
+    pub smtp_password: bool,
+    pub push_token: bool,
+    pub mqtt_password: bool,
+    pub telegram_bot_token: bool,
+    pub erpnext_api_secret: bool,
+    pub odoo_api_key: bool,
+    pub bamboohr_api_key: bool,
+    pub personio_client_secret: bool,
+    pub local_api_token: bool,
+    pub matrix_access_token: bool,
+    pub caldav_password: bool,
+    pub code_activity_token: bool,
+}
+
+impl Secrets {
+    // Set one secret by its field name, e.g. "toggl_api_token", leaving the
+    // others as they are. An empty value removes it.
+    pub fn set(&mut self, field: &str, value: Option<String>) -> Result<(), String> {
+        let mut fields = serde_json::to_value(&*self).map_err(|err| format!("Failed to serialize secrets: {}", err))?;
+        let slot = fields.get_mut(field).ok_or_else(|| format!("No secret named '{}'", field))?;
+        *slot = value.filter(|value| !value.is_empty()).map(Value::from).unwrap_or(Value::Null);
+        *self = serde_json::from_value(fields).map_err(|err| format!("Failed to update secrets: {}", err))?;
+        Ok(())
+    }
+
+    pub fn status(&self) -> SecretStatus {
+        SecretStatus {
+            api_token: self.api_token.is_some(),
+            hmac_key: self.hmac_key.is_some(),
+            toggl_api_token: self.toggl_api_token.is_some(),
+            clockify_api_key: self.clockify_api_key.is_some(),
+            harvest_token: self.harvest_token.is_some(),
+            smtp_password: self.smtp_password.is_some(),
+            push_token: self.push_token.is_some(),
+            mqtt_password: self.mqtt_password.is_some(),
+            telegram_bot_token: self.telegram_bot_token.is_some(),
+            erpnext_api_secret: self.erpnext_api_secret.is_some(),
+            odoo_api_key: self.odoo_api_key.is_some(),
+            bamboohr_api_key: self.bamboohr_api_key.is_some(),
+            personio_client_secret: self.personio_client_secret.is_some(),
+            local_api_token: self.local_api_token.is_some(),
+            matrix_access_token: self.matrix_access_token.is_some(),
+            caldav_password: self.caldav_password.is_some(),
+            code_activity_token: self.code_activity_token.is_some(),
+        }
+    }
+}
+
+fn entry() -> Result<Entry, String> {
+    Entry::new(KEYCHAIN_SERVICE, KEYCHAIN_USER).map_err(|err| format!("Keychain unavailable: {}", err))
+}
+
+fn load() -> Result<Secrets, String> {
+    match entry()?.get_password() {
+        Ok(stored) => serde_json::from_str(&stored).map_err(|err| format!("Failed to parse stored secrets: {}", err)),
+        Err(keyring::Error::NoEntry) => Ok(Secrets::default()),
+        Err(err) => Err(format!("Failed to read secrets from the keychain: {}", err)),
+    }
+}
+
+// Secrets, read from the keychain the first time they are needed. A failed
+// read isn't remembered, the keychain is asked again next time.
+pub fn current(state: &AppState) -> Result<Secrets, String> {
+    let mut cached = state.secrets.lock().unwrap();
+    if let Some(secrets) = cached.as_ref() {
+        return Ok(secrets.clone());
+    }
+
+    let secrets = load().inspect_err(|err| error!("{}", err))?;
+    *cached = Some(secrets.clone());
+    Ok(secrets)
+}
+
+// Store the secrets in the keychain, removing the entry when none are set
+pub fn save(state: &AppState, secrets: Secrets) -> Result<(), String> {
+    let entry = entry()?;
+    if secrets == Secrets::default() {
+        match entry.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => {}
+            Err(err) => return Err(format!("Failed to remove secrets from the keychain: {}", err)),
+        }
+    } else {
+        let stored = serde_json::to_string(&secrets).map_err(|err| format!("Failed to serialize secrets: {}", err))?;
+        entry.set_password(&stored).map_err(|err| format!("Failed to store secrets in the keychain: {}", err))?;
+    }
+
+    *state.secrets.lock().unwrap() = Some(secrets);
+    Ok(())
+}
+
+// Change one stored secret, keeping the others. The settings screen only
+// knows which secrets are set, not their values, so it can't send them all.
+pub fn save_field(state: &AppState, field: &str, value: Option<String>) -> Result<SecretStatus, String> {
+    let mut secrets = current(state)?;
+    secrets.set(field, value)?;
+    let status = secrets.status();
+    save(state, secrets)?;
+    Ok(status)
+}
+
+// Keychain entry of an integration's sign-in, e.g. "teams". Kept apart from
+// the API credentials, which are changed through `save_field`.
+fn token_entry(integration: &str) -> Result<Entry, String> {
+    Entry::new(KEYCHAIN_SERVICE, &format!("{}-token", integration)).map_err(|err| format!("Keychain unavailable: {}", err))
+}
+
+// An integration's tokens, `None` when it isn't signed in
+pub fn load_token(integration: &str) -> Result<Option<Token>, String> {
+    match token_entry(integration)?.get_password() {
+        Ok(stored) => serde_json::from_str(&stored).map(Some).map_err(|err| format!("Failed to parse stored sign-in: {}", err)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(err) => Err(format!("Failed to read the sign-in from the keychain: {}", err)),
+    }
+}
+
+// Store an integration's tokens, or remove them to sign out
+pub fn save_token(integration: &str, token: Option<&Token>) -> Result<(), String> {
+    let entry = token_entry(integration)?;
+    match token {
+        Some(token) => {
+            let stored = serde_json::to_string(token).map_err(|err| format!("Failed to serialize the sign-in: {}", err))?;
+            entry.set_password(&stored).map_err(|err| format!("Failed to store the sign-in in the keychain: {}", err))
+        }
+        None => match entry.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(err) => Err(format!("Failed to remove the sign-in from the keychain: {}", err)),
+        },
+    }
+}
+
+// Hex HMAC-SHA256 of a request body, sent as `X-Remodance-Signature`
+pub fn sign(key: &str, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(body.as_bytes());
+    mac.finalize().into_bytes().iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_keeps_other_secrets() {
+        let mut secrets = Secrets {
+            api_token: Some("token".to_string()),
+            hmac_key: Some("key".to_string()),
+            ..Secrets::default()
+        };
+        secrets.set("toggl_api_token", Some("toggl".to_string())).unwrap();
+        assert_eq!(secrets.toggl_api_token.as_deref(), Some("toggl"));
+        assert_eq!(secrets.api_token.as_deref(), Some("token"));
+        assert_eq!(secrets.hmac_key.as_deref(), Some("key"));
+
+        secrets.set("hmac_key", Some(String::new())).unwrap();
+        assert_eq!(secrets.hmac_key, None);
+        assert_eq!(secrets.api_token.as_deref(), Some("token"));
+        assert!(secrets.set("password", Some("x".to_string())).is_err());
+    }
+
+    #[test]
+    fn test_sign_matches_known_digest() {
+        assert_eq!(
+            sign("key", "The quick brown fox jumps over the lazy dog"),
+            "f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8"
+        );
+    }
+}
@@ -0,0 +1,95 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+// Audit log action for a changed setting
+pub const ACTION: &str = "change-setting";
+
+// Longest value shown in an audit entry, longer ones are cut
+const MAX_VALUE_LEN: usize = 60;
+
+// Where a settings change came from
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ChangeSource {
+    Ui, // The user, through the window, tray or a command
+    App, // The app itself, e.g. ending a vacation or syncing calendars
+    Policy,
+    Environment, // `REMODANCE_*` variables at startup
+    Remote, // The signed settings bundle
+    File, // settings.json edited outside the app
+}
+
+// One changed setting. `field` is a dotted path for nested settings.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct Change {
+    pub field: String,
+    pub old: Value,
+    pub new: Value,
+}
+
+impl Change {
+    // Audit log detail, e.g. "idle_timeout_secs: 10 → 5 (remote)"
+    pub fn describe(&self, source: ChangeSource, user: &str) -> String {
+        let by = match source {
+            ChangeSource::Ui => format!("by {}", user),
+            ChangeSource::App => "by the app".to_string(),
+            ChangeSource::Policy => "by the managed policy".to_string(),
+            ChangeSource::Environment => "by an environment variable".to_string(),
+            ChangeSource::Remote => "by the remote settings bundle".to_string(),
+            ChangeSource::File => "by editing settings.json".to_string(),
+        };
+        format!("{}: {} → {} ({})", self.field, show(&self.old), show(&self.new), by)
+    }
+}
+
+fn show(value: &Value) -> String {
+    let text = value.to_string();
+    if text.chars().count() <= MAX_VALUE_LEN {
+        return text;
+    }
+    let cut: String = text.chars().take(MAX_VALUE_LEN).collect();
+    format!("{}…", cut)
+}
+
+// Settings that differ between two serialized settings, descending into
+// nested objects. Lists are compared as a whole.
+pub fn changes(old: &Value, new: &Value) -> Vec<Change> {
+    let mut changes = Vec::new();
+    collect("", old, new, &mut changes);
+    changes
+}
+
+fn collect(path: &str, old: &Value, new: &Value, changes: &mut Vec<Change>) {
+    match (old, new) {
+        (Value::Object(old), Value::Object(new)) => {
+            for (key, new_value) in new {
+                let field = if path.is_empty() { key.clone() } else { format!("{}.{}", path, key) };
+                collect(&field, old.get(key).unwrap_or(&Value::Null), new_value, changes);
+            }
+        }
+        (old, new) if old != new => changes.push(Change {
+            field: path.to_string(),
+            old: old.clone(),
+            new: new.clone(),
+        }),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_changes_lists_nested_fields() {
+        let old = json!({ "idle_timeout_secs": 10, "notifications": { "reminders": true, "sound": false }, "holidays": [] });
+        let new = json!({ "idle_timeout_secs": 5, "notifications": { "reminders": false, "sound": false }, "holidays": [] });
+
+        let changes = changes(&old, &new);
+        let fields: Vec<_> = changes.iter().map(|change| change.field.as_str()).collect();
+        assert_eq!(fields, vec!["idle_timeout_secs", "notifications.reminders"]);
+        assert_eq!(changes[0].describe(ChangeSource::Remote, "alice"), "idle_timeout_secs: 10 → 5 (by the remote settings bundle)");
+        assert_eq!(changes[1].describe(ChangeSource::Ui, "alice"), "notifications.reminders: true → false (by alice)");
+    }
+}
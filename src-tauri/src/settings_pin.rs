@@ -0,0 +1,86 @@
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use std::time::{Duration, Instant};
+
+const MIN_PIN_LEN: usize = 4;
+const MAX_LOCKOUT_SECS: u64 = 15 * 60;
+
+// Hash a new PIN for storage, refusing ones too short to stop a passer-by
+pub fn hash(pin: &str) -> Result<String, String> {
+    if pin.chars().count() < MIN_PIN_LEN {
+        return Err(format!("The PIN must be at least {} characters", MIN_PIN_LEN));
+    }
+
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(pin.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|err| format!("Failed to hash the PIN: {}", err))
+}
+
+// Whether a PIN matches the stored hash. A corrupted hash matches nothing.
+pub fn verify(pin: &str, hash: &str) -> bool {
+    PasswordHash::new(hash).is_ok_and(|hash| Argon2::default().verify_password(pin.as_bytes(), &hash).is_ok())
+}
+
+// Wrong PINs entered since the last right one. Every wrong PIN locks out
+// further attempts, twice as long as the one before, so guessing stays slow
+// even when attempts are sent in parallel.
+#[derive(Debug, Default)]
+pub struct Attempts {
+    failures: u32,
+    locked_until: Option<Instant>,
+}
+
+impl Attempts {
+    // How long attempts are still refused at `now`
+    pub fn locked_for(&self, now: Instant) -> Option<Duration> {
+        self.locked_until.filter(|until| *until > now).map(|until| until - now)
+    }
+
+    pub fn failed(&mut self, now: Instant) {
+        self.failures = self.failures.saturating_add(1);
+        let secs = 2u64.saturating_pow(self.failures - 1).min(MAX_LOCKOUT_SECS);
+        self.locked_until = Some(now + Duration::from_secs(secs));
+    }
+
+    pub fn succeeded(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_and_verify() {
+        assert!(hash("123").is_err());
+
+        let stored = hash("4821").unwrap();
+        assert!(!stored.contains("4821"));
+        assert!(verify("4821", &stored));
+        assert!(!verify("4822", &stored));
+        assert!(!verify("4821", "not a hash"));
+    }
+
+    #[test]
+    fn test_wrong_pins_lock_out_attempts() {
+        let now = Instant::now();
+        let mut attempts = Attempts::default();
+        assert_eq!(attempts.locked_for(now), None);
+
+        attempts.failed(now);
+        assert_eq!(attempts.locked_for(now), Some(Duration::from_secs(1)));
+        assert_eq!(attempts.locked_for(now + Duration::from_secs(1)), None);
+
+        let later = now + Duration::from_secs(1);
+        attempts.failed(later);
+        attempts.failed(later);
+        assert_eq!(attempts.locked_for(later), Some(Duration::from_secs(4)));
+
+        attempts.succeeded();
+        assert_eq!(attempts.locked_for(later), None);
+    }
+}
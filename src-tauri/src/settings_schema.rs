@@ -0,0 +1,162 @@
+use schemars::gen::SchemaSettings;
+use serde_json::{json, Value};
+
+use crate::features;
+use crate::i18n;
+use crate::validation;
+use crate::Settings;
+
+// What each setting is for, shown next to its input. Nested types are
+// described by their own field names.
+const DESCRIPTIONS: [(&str, &str); 77] = [
+    ("backend", "Where attendance events are sent: the webhook at the API endpoint or an HR system"),
+    ("api_endpoint", "URL attendance events are posted to"),
+    ("username", "Name events are reported under"),
+    ("employee_id", "HR identifier sent as the user ID, the username is sent when unset"),
+    ("directory_url", "Directory the employee ID is looked up in when it is unset"),
+    ("device_name", "Name of this computer in reports"),
+    ("org_id", "Organization this client reports to"),
+    ("locale", "Language of notifications and the tray, the system language when unset"),
+    ("feature_flags", "Experimental features turned on or off, by name"),
+    ("idle_timeout_secs", "Seconds without input after which the user counts as away"),
+    ("auto_mode", "Check in and out automatically based on activity"),
+    ("developer_mode", "Enable debugging commands"),
+    ("holidays", "Non-working days"),
+    ("holiday_country", "ISO country code used for holiday imports"),
+    ("holiday_region", "Subdivision used for holiday imports, e.g. DE-BY"),
+    ("calendar_subscriptions", "ICS calendars providing non-working days"),
+    ("vacation", "Out-of-office range during which monitoring is disabled"),
+    ("timezone", "IANA timezone name, the system timezone when unset"),
+    ("overtime_daily_mins", "Daily worked-time target, no alerts when unset"),
+    ("overtime_weekly_mins", "Weekly worked-time target, no alerts when unset"),
+    ("max_daily_mins", "Daily limit after which the user is checked out"),
+    ("break_reminder_after_mins", "Continuous work after which a break is suggested"),
+    ("lunch_window", "Idle periods in this window are recorded as lunch breaks"),
+    ("end_of_day_reminder", "Remind to check out when still checked in after this time"),
+    ("schedule", "Working hours for each weekday"),
+    ("morning_reminder", "Remind to check in when active after the scheduled start"),
+    ("quiet_hours", "No automatic check-ins or reminders in this window"),
+    ("follow_schedule", "Check in automatically only inside scheduled hours and out at their end"),
+    ("schedule_templates", "Named weekly schedules to switch between"),
+    ("active_template", "Template used instead of the schedule when set"),
+    ("day_overrides", "One-off working hours for single dates"),
+    ("break_types", "Kinds of breaks that can be started"),
+    ("break_reminder_interval_mins", "Suggest a break this often while checked in"),
+    ("break_reminder_snooze_mins", "Minutes a snoozed break reminder waits"),
+    ("micro_break_interval_mins", "Eye and stretch nudges while checked in, off when unset"),
+    ("micro_break_secs", "Rest time that counts as taking a micro-break"),
+    ("idle_break_max_mins", "Idle periods past the idle timeout but shorter than this are breaks"),
+    ("break_budget_mins", "Daily break allowance, break time over it is deducted"),
+    ("notifications", "Which notifications are shown"),
+    ("headless", "Start with only the tray icon"),
+    ("update_channel", "Release channel updates are installed from"),
+    ("checkout_warning_secs", "Ask whether the user is still there this long before an idle check-out, 0 disables"),
+    ("hotkeys", "Global shortcuts"),
+    ("launch_behavior", "What happens when the app starts while checked out"),
+    ("sounds", "Sounds played on check-in, check-out and reminders"),
+    ("status_line_format", "Template of the status line in the tray"),
+    ("close_behavior", "What the window's close button does"),
+    ("block_end_warning_mins", "Warn this long before checking out at the end of a scheduled block, 0 disables"),
+    ("config_url", "HTTPS URL of a signed settings bundle fetched periodically"),
+    ("config_public_key", "Base64 Ed25519 key the settings bundle must be signed with, only trusted when set by the managed policy"),
+    ("probe_endpoint_on_save", "Try a changed API endpoint when saving and warn if it doesn't answer"),
+    ("teams", "Set the Microsoft Teams presence from the attendance status"),
+    ("google_calendar", "Keep the user checked in during accepted Google Calendar meetings"),
+    ("outlook_calendar", "Keep the user checked in during accepted Microsoft 365 or Exchange meetings"),
+    ("call_detection", "Keep the user checked in while Zoom, Teams, a browser or another conferencing app uses the microphone"),
+    ("time_tracker", "Run a time entry in this tracker while checked in"),
+    ("toggl", "Workspace and project of Toggl Track entries"),
+    ("clockify", "Workspace and project of Clockify entries"),
+    ("harvest", "Account, project and task of Harvest timers"),
+    ("google_sheets", "Append each finished day's check-ins and check-outs to a shared Google Sheet"),
+    ("email_report", "Email an attendance summary after each day or week"),
+    ("push", "Push critical events to the phone through ntfy, Pushover or Gotify"),
+    ("mqtt", "MQTT broker shared by everything published over MQTT"),
+    ("home_assistant", "Publish the attendance status to Home Assistant through MQTT discovery"),
+    ("discord", "Show a Discord activity with the time since check-in while checked in"),
+    ("automation_triggers", "Webhook URLs of Zapier, IFTTT or n8n posted to on check-in, check-out, idle warnings and API failures"),
+    ("erpnext", "ERPNext or Frappe HR site events are recorded on as Employee Checkins"),
+    ("odoo", "Odoo database events are recorded in as attendances"),
+    ("bamboohr", "BambooHR account events are recorded in as timesheet clock entries"),
+    ("personio", "Personio API credentials events are recorded with as attendance periods"),
+    ("plugins", "Executables run on every status change, with the event as JSON on stdin"),
+    ("local_api", "Local HTTP API that lets other desktop tools read the status and check in or out"),
+    ("matrix", "Matrix room attendance changes and daily summaries are posted to"),
+    ("caldav", "CalDAV calendar, e.g. on Nextcloud or Fastmail, finished sessions are published to as events"),
+    ("code_activity", "Own pushes and pull requests on GitHub or GitLab, counted as activity while this computer is idle"),
+    ("telemetry", "Export logs and spans around API sends and status changes to an OpenTelemetry collector over OTLP/HTTP"),
+    ("telegram", "Alert allow-listed Telegram chats of auto check-outs and take /status, /checkin and /checkout from them"),
+];
+
+// JSON Schema of the settings, generated from the Rust types so the settings
+// screen can build its form from it. Nested types are inlined, and fields in
+// `locked` (dotted paths as in `Policy::locked_fields`) are marked `readOnly`
+// with `x-locked`.
+pub fn generate(locked: &[String]) -> Value {
+    let generator = SchemaSettings::draft07()
+        .with(|settings| settings.inline_subschemas = true)
+        .into_generator();
+    let mut schema = serde_json::to_value(generator.into_root_schema_for::<Settings>()).unwrap_or_default();
+
+    for (field, description) in DESCRIPTIONS {
+        if let Some(property) = property_mut(&mut schema, field) {
+            property["description"] = json!(description);
+        }
+    }
+    for (field, _) in validation::minutes(&Settings::default()) {
+        if let Some(property) = property_mut(&mut schema, field) {
+            property["minimum"] = json!(1);
+        }
+    }
+    if let Some(property) = property_mut(&mut schema, "idle_timeout_secs") {
+        property["minimum"] = json!(validation::MIN_IDLE_TIMEOUT_SECS);
+    }
+    if let Some(property) = property_mut(&mut schema, "locale") {
+        let mut locales: Vec<Value> = i18n::LOCALES.iter().map(|locale| json!(locale)).collect();
+        locales.push(Value::Null);
+        property["enum"] = Value::Array(locales);
+    }
+    if let Some(property) = property_mut(&mut schema, "feature_flags") {
+        let names: Vec<&str> = features::ALL.iter().map(|feature| feature.name()).collect();
+        property["propertyNames"] = json!({ "enum": names });
+    }
+    for field in locked {
+        if let Some(property) = property_mut(&mut schema, field) {
+            property["readOnly"] = json!(true);
+            property["x-locked"] = json!(true);
+        }
+    }
+
+    schema
+}
+
+// The schema of a dotted field path such as `notifications.reminders`
+fn property_mut<'a>(schema: &'a mut Value, path: &str) -> Option<&'a mut Value> {
+    path.split('.').try_fold(schema, |schema, key| schema.get_mut("properties")?.get_mut(key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describes_every_setting() {
+        let schema = generate(&[]);
+        let properties = schema["properties"].as_object().unwrap();
+        for (field, property) in properties {
+            assert!(property.get("description").is_some(), "{} has no description", field);
+        }
+        assert_eq!(schema["properties"]["idle_timeout_secs"]["minimum"], json!(validation::MIN_IDLE_TIMEOUT_SECS));
+        assert_eq!(schema["properties"]["overtime_daily_mins"]["minimum"], json!(1));
+        assert_eq!(schema["properties"]["locale"]["enum"], json!(["en", "de", null]));
+        assert!(schema["properties"]["close_behavior"]["enum"].as_array().is_some_and(|values| values.contains(&json!("hide-to-tray"))));
+    }
+
+    #[test]
+    fn test_marks_locked_fields() {
+        let schema = generate(&["api_endpoint".to_string(), "notifications.reminders".to_string()]);
+        assert_eq!(schema["properties"]["api_endpoint"]["readOnly"], json!(true));
+        assert_eq!(schema["properties"]["notifications"]["properties"]["reminders"]["x-locked"], json!(true));
+        assert!(schema["properties"]["username"].get("readOnly").is_none());
+    }
+}
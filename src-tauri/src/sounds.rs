@@ -0,0 +1,135 @@
+#[cfg(not(target_os = "windows"))]
+use std::process::Command;
+
+use chrono::NaiveTime;
+use log::{debug, error};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::clock;
+use crate::Settings;
+
+// Transitions that can play a sound
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SoundCue {
+    AutoCheckOut,
+    CheckoutWarning,
+    BreakReminder,
+}
+
+// Sound preferences. Everything is off unless enabled.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, JsonSchema)]
+#[serde(default)]
+pub struct SoundSettings {
+    pub auto_check_out: bool,
+    pub checkout_warning: bool,
+    pub break_reminder: bool,
+    pub sound_file: Option<String>, // Played instead of the system alert sound
+}
+
+impl SoundSettings {
+    pub fn enabled(&self, cue: SoundCue) -> bool {
+        match cue {
+            SoundCue::AutoCheckOut => self.auto_check_out,
+            SoundCue::CheckoutWarning => self.checkout_warning,
+            SoundCue::BreakReminder => self.break_reminder,
+        }
+    }
+}
+
+// Whether a cue should sound now. Notification quiet hours silence sounds too.
+fn should_play(settings: &Settings, cue: SoundCue, time: NaiveTime) -> bool {
+    settings.sounds.enabled(cue)
+        && !settings.notifications.quiet_hours.as_ref().is_some_and(|quiet_hours| quiet_hours.contains(time))
+}
+
+// Play the sound for a cue if it is enabled. The sound plays in the background.
+pub fn play(settings: &Settings, cue: SoundCue) {
+    let now = clock::now(settings.timezone.as_deref()).time();
+    if !should_play(settings, cue, now) {
+        return;
+    }
+
+    debug!("Playing sound for {:?}", cue);
+    let sound_file = settings.sounds.sound_file.clone();
+    std::thread::spawn(move || {
+        if let Err(err) = play_sound(sound_file.as_deref()) {
+            error!("Failed to play sound: {}", err);
+        }
+    });
+}
+
+#[cfg(target_os = "macos")]
+fn play_sound(sound_file: Option<&str>) -> Result<(), String> {
+    run(Command::new("afplay").arg(sound_file.unwrap_or("/System/Library/Sounds/Glass.aiff")))
+}
+
+#[cfg(target_os = "windows")]
+fn play_sound(sound_file: Option<&str>) -> Result<(), String> {
+    use std::os::windows::ffi::OsStrExt;
+
+    #[link(name = "user32")]
+    extern "system" {
+        fn MessageBeep(kind: u32) -> i32;
+    }
+    #[link(name = "winmm")]
+    extern "system" {
+        fn PlaySoundW(sound: *const u16, module: *mut std::ffi::c_void, flags: u32) -> i32;
+    }
+    const MB_ICONEXCLAMATION: u32 = 0x30;
+    const SND_FILENAME: u32 = 0x20000;
+
+    // SAFETY: both functions only read their arguments, and the file name is a
+    // NUL-terminated UTF-16 string that outlives the synchronous call
+    let played = unsafe {
+        match sound_file {
+            Some(file) => {
+                let wide: Vec<u16> = std::ffi::OsStr::new(file).encode_wide().chain(Some(0)).collect();
+                PlaySoundW(wide.as_ptr(), std::ptr::null_mut(), SND_FILENAME)
+            }
+            None => MessageBeep(MB_ICONEXCLAMATION),
+        }
+    };
+
+    if played != 0 {
+        Ok(())
+    } else {
+        Err("The sound could not be played".to_string())
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn play_sound(sound_file: Option<&str>) -> Result<(), String> {
+    run(Command::new("paplay").arg(sound_file.unwrap_or("/usr/share/sounds/freedesktop/stereo/message.oga")))
+}
+
+#[cfg(not(target_os = "windows"))]
+fn run(command: &mut Command) -> Result<(), String> {
+    let status = command.status().map_err(|err| format!("Failed to start {:?}: {}", command.get_program(), err))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("{:?} exited with {}", command.get_program(), status))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schedule::TimeWindow;
+
+    #[test]
+    fn test_cues_follow_flags_and_quiet_hours() {
+        let time = |h| NaiveTime::from_hms_opt(h, 0, 0).unwrap();
+        let mut settings = Settings {
+            sounds: SoundSettings { checkout_warning: true, ..SoundSettings::default() },
+            ..Settings::default()
+        };
+
+        assert!(should_play(&settings, SoundCue::CheckoutWarning, time(12)));
+        assert!(!should_play(&settings, SoundCue::AutoCheckOut, time(12)));
+
+        settings.notifications.quiet_hours = Some(TimeWindow { start: time(22), end: time(7) });
+        assert!(!should_play(&settings, SoundCue::CheckoutWarning, time(23)));
+    }
+}
@@ -0,0 +1,72 @@
+use std::path::PathBuf;
+
+use log::error;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_store::StoreBuilder;
+
+// Read the value saved under `key` in one of the app's store files. `None`
+// when nothing was saved yet.
+pub fn load_json<T: DeserializeOwned>(app_handle: &AppHandle, filename: &str, key: &str) -> Result<Option<T>, String> {
+    let store = StoreBuilder::new(app_handle, PathBuf::from(filename))
+        .build()
+        .map_err(|err| format!("Failed to open {}: {}", filename, err))?;
+
+    store
+        .get(key)
+        .map(|value| serde_json::from_value(value).map_err(|err| format!("Failed to parse {} in {}: {}", key, filename, err)))
+        .transpose()
+}
+
+// Like `load_json`, but logs failures and starts over with the default value
+pub fn load_or_default<T: DeserializeOwned + Default>(app_handle: &AppHandle, filename: &str, key: &str) -> T {
+    load_json(app_handle, filename, key)
+        .unwrap_or_else(|err| {
+            error!("{}. Starting over with defaults.", err);
+            None
+        })
+        .unwrap_or_default()
+}
+
+// Save a value under `key` in one of the app's store files
+pub fn save_json<T: Serialize + ?Sized>(app_handle: &AppHandle, filename: &str, key: &str, value: &T) -> Result<(), String> {
+    let store = StoreBuilder::new(app_handle, PathBuf::from(filename))
+        .build()
+        .map_err(|err| format!("Failed to open {}: {}", filename, err))?;
+
+    let value = serde_json::to_value(value).map_err(|err| format!("Failed to serialize {}: {}", key, err))?;
+    store.set(key.to_string(), value);
+
+    store.save().map_err(|err| format!("Failed to save {}: {}", filename, err))
+}
+
+// Remove the value saved under `key`
+pub fn delete_json(app_handle: &AppHandle, filename: &str, key: &str) -> Result<(), String> {
+    let store = StoreBuilder::new(app_handle, PathBuf::from(filename))
+        .build()
+        .map_err(|err| format!("Failed to open {}: {}", filename, err))?;
+
+    store.delete(key);
+    store.save().map_err(|err| format!("Failed to save {}: {}", filename, err))
+}
+
+// Empty a store file and remove it from the data directory
+pub fn delete_file(app_handle: &AppHandle, filename: &str) -> Result<(), String> {
+    let store = StoreBuilder::new(app_handle, PathBuf::from(filename))
+        .build()
+        .map_err(|err| format!("Failed to open {}: {}", filename, err))?;
+    store.clear();
+    store.save().map_err(|err| format!("Failed to save {}: {}", filename, err))?;
+
+    let path = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|err| format!("Failed to find the data directory: {}", err))?
+        .join(filename);
+    match std::fs::remove_file(&path) {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(format!("Failed to remove {}: {}", path.display(), err)),
+    }
+}
@@ -0,0 +1,126 @@
+use argon2::Argon2;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+const SALT_LEN: usize = 16;
+
+// Where the key for an encrypted settings store comes from
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum EncryptionMode {
+    Machine, // This machine's identifier, so the file only opens here
+    Passphrase, // Entered by the user after every start
+}
+
+// Encrypted settings as written to the store. Everything but the mode is base64.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Envelope {
+    pub mode: EncryptionMode,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+// A derived key, kept in memory to encrypt later saves with the same salt
+#[derive(Clone)]
+pub struct StoreKey {
+    pub mode: EncryptionMode,
+    salt: Vec<u8>,
+    key: Key,
+}
+
+impl std::fmt::Debug for StoreKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StoreKey").field("mode", &self.mode).finish_non_exhaustive()
+    }
+}
+
+impl StoreKey {
+    // Derive a key with a fresh salt
+    pub fn new(mode: EncryptionMode, secret: &str) -> Result<Self, String> {
+        let mut salt = vec![0; SALT_LEN];
+        OsRng.fill_bytes(&mut salt);
+        Self::derive(mode, secret, salt)
+    }
+
+    // Derive the key an envelope was written with
+    pub fn for_envelope(envelope: &Envelope, secret: &str) -> Result<Self, String> {
+        Self::derive(envelope.mode, secret, decode("salt", &envelope.salt)?)
+    }
+
+    fn derive(mode: EncryptionMode, secret: &str, salt: Vec<u8>) -> Result<Self, String> {
+        let mut key = Key::default();
+        Argon2::default()
+            .hash_password_into(secret.as_bytes(), &salt, &mut key)
+            .map_err(|err| format!("Failed to derive the settings key: {}", err))?;
+        Ok(Self { mode, salt, key })
+    }
+
+    pub fn encrypt(&self, value: &Value) -> Result<Envelope, String> {
+        let plaintext = serde_json::to_vec(value).map_err(|err| format!("Failed to serialize settings: {}", err))?;
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = ChaCha20Poly1305::new(&self.key)
+            .encrypt(&nonce, plaintext.as_slice())
+            .map_err(|_| "Failed to encrypt settings".to_string())?;
+
+        Ok(Envelope {
+            mode: self.mode,
+            salt: STANDARD.encode(&self.salt),
+            nonce: STANDARD.encode(nonce),
+            ciphertext: STANDARD.encode(ciphertext),
+        })
+    }
+
+    pub fn decrypt(&self, envelope: &Envelope) -> Result<Value, String> {
+        let nonce = decode("nonce", &envelope.nonce)?;
+        if nonce.len() != 12 {
+            return Err("Encrypted settings are corrupted: the nonce has the wrong length".to_string());
+        }
+        let ciphertext = decode("ciphertext", &envelope.ciphertext)?;
+        let plaintext = ChaCha20Poly1305::new(&self.key)
+            .decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+            .map_err(|_| match envelope.mode {
+                EncryptionMode::Machine => "Encrypted settings could not be opened: they were written on another machine or are corrupted",
+                EncryptionMode::Passphrase => "Encrypted settings could not be opened: wrong passphrase or corrupted file",
+            })?;
+
+        serde_json::from_slice(&plaintext).map_err(|err| format!("Decrypted settings are not valid JSON: {}", err))
+    }
+}
+
+fn decode(part: &str, encoded: &str) -> Result<Vec<u8>, String> {
+    STANDARD.decode(encoded).map_err(|err| format!("Encrypted settings are corrupted: invalid {}: {}", part, err))
+}
+
+// Secret for `EncryptionMode::Machine`
+pub fn machine_secret() -> Result<String, String> {
+    machine_uid::get().map_err(|err| format!("Failed to read the machine identifier: {}", err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_round_trip_and_wrong_passphrase() {
+        let settings = json!({ "api_endpoint": "https://api.example.com" });
+        let key = StoreKey::new(EncryptionMode::Passphrase, "correct horse").unwrap();
+        let envelope = key.encrypt(&settings).unwrap();
+        assert!(!envelope.ciphertext.contains("api.example.com"));
+
+        let reopened = StoreKey::for_envelope(&envelope, "correct horse").unwrap();
+        assert_eq!(reopened.decrypt(&envelope), Ok(settings));
+
+        let wrong = StoreKey::for_envelope(&envelope, "battery staple").unwrap();
+        assert!(wrong.decrypt(&envelope).unwrap_err().contains("wrong passphrase"));
+
+        let corrupted = Envelope { nonce: "not base64!".to_string(), ..envelope };
+        assert!(key.decrypt(&corrupted).unwrap_err().contains("corrupted"));
+    }
+}
@@ -0,0 +1,182 @@
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::breaks::{self, BreakBudget, BreakTypeSummary};
+use crate::clock;
+use crate::history::History;
+use crate::holidays::Holiday;
+use crate::overtime::OvertimePeriod;
+use crate::schedule::WorkBlock;
+
+// Worked time inside one scheduled block
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct BlockSummary {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+    pub worked_mins: i64,
+}
+
+// Worked time per scheduled block on a day, in the configured timezone
+pub fn block_report(
+    history: &History,
+    blocks: &[WorkBlock],
+    timezone: Option<&str>,
+    date: NaiveDate,
+    now: DateTime<Utc>,
+) -> Vec<BlockSummary> {
+    blocks
+        .iter()
+        .map(|block| {
+            let from = clock::resolve(timezone, date, block.start).with_timezone(&Utc);
+            let to = clock::resolve(timezone, date, block.end).with_timezone(&Utc);
+
+            BlockSummary {
+                start: block.start,
+                end: block.end,
+                worked_mins: history.worked_between(from, to, now).num_minutes(),
+            }
+        })
+        .collect()
+}
+
+// Worked time and breaks over a day or a week
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PeriodSummary {
+    pub period: OvertimePeriod,
+    pub start: NaiveDate,
+    pub worked_mins: i64,
+    pub break_count: usize,
+    pub break_mins: i64,
+    pub breaks_by_type: Vec<BreakTypeSummary>,
+    pub longest_stretch_mins: i64, // Longest session without a break or check-out
+    pub holidays: Vec<Holiday>, // Holidays in the period, which have no worked time to expect
+}
+
+// Summary of the day, or the week (Monday to Sunday), containing `date`
+pub fn period_summary(
+    history: &History,
+    timezone: Option<&str>,
+    holidays: &[Holiday],
+    period: OvertimePeriod,
+    date: NaiveDate,
+    now: DateTime<Utc>,
+) -> PeriodSummary {
+    let (start, last_day) = match period {
+        OvertimePeriod::Day => (date, date),
+        OvertimePeriod::Week => {
+            let start = clock::week_start(date);
+            (start, start + Duration::days(6))
+        }
+    };
+    let (from, _) = clock::day_bounds(timezone, start);
+    let (_, to) = clock::day_bounds(timezone, last_day);
+
+    let breaks_by_type = breaks::totals_by_type(&history.breaks, from, to, now);
+    let longest_stretch = history
+        .sessions
+        .iter()
+        .map(|session| session.overlap(from, to, now))
+        .max()
+        .unwrap_or_else(Duration::zero);
+
+    PeriodSummary {
+        period,
+        start,
+        worked_mins: history.worked_between(from, to, now).num_minutes(),
+        break_count: breaks_by_type.iter().map(|total| total.count).sum(),
+        break_mins: breaks_by_type.iter().map(|total| total.total_mins).sum(),
+        breaks_by_type,
+        longest_stretch_mins: longest_stretch.num_minutes(),
+        holidays: holidays.iter().filter(|holiday| holiday.date >= start && holiday.date <= last_day).cloned().collect(),
+    }
+}
+
+// Today's summary with the state of the daily break allowance
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TodaySummary {
+    #[serde(flatten)]
+    pub summary: PeriodSummary,
+    pub break_budget: Option<BreakBudget>,
+}
+
+pub fn today_summary(
+    history: &History,
+    timezone: Option<&str>,
+    holidays: &[Holiday],
+    break_budget_mins: Option<u64>,
+    today: NaiveDate,
+    now: DateTime<Utc>,
+) -> TodaySummary {
+    let summary = period_summary(history, timezone, holidays, OvertimePeriod::Day, today, now);
+    let break_budget = break_budget_mins.map(|allowance| BreakBudget::new(allowance, summary.break_mins));
+
+    TodaySummary { summary, break_budget }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::holidays::HolidaySource;
+    use crate::schedule::WeeklySchedule;
+    use chrono::Datelike;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_block_report_splits_worked_time() {
+        let time = |h| NaiveTime::from_hms_opt(h, 0, 0).unwrap();
+        let schedule = WeeklySchedule {
+            mon: vec![WorkBlock::new(time(8), time(12)), WorkBlock::new(time(16), time(20))],
+            ..WeeklySchedule::default()
+        };
+
+        let mut history = History::default();
+        history.start_session(at("2025-03-10T07:50:00Z"));
+        history.end_session(at("2025-03-10T12:10:00Z"));
+        history.start_session(at("2025-03-10T16:30:00Z"));
+
+        let date = NaiveDate::from_ymd_opt(2025, 3, 10).unwrap();
+        let blocks = schedule.blocks_for(date.weekday());
+        let report = block_report(&history, blocks, Some("UTC"), date, at("2025-03-10T18:00:00Z"));
+
+        assert_eq!(report.len(), 2);
+        assert_eq!(report[0].worked_mins, 240);
+        assert_eq!(report[1].worked_mins, 90);
+    }
+
+    #[test]
+    fn test_period_summary_includes_breaks() {
+        let mut history = History::default();
+        history.start_session(at("2025-03-10T09:00:00Z"));
+        history.start_break("coffee", at("2025-03-10T10:00:00Z"));
+        history.start_session(at("2025-03-10T10:15:00Z"));
+        history.start_break("lunch", at("2025-03-10T12:45:00Z"));
+        history.start_session(at("2025-03-10T13:30:00Z"));
+        history.end_session(at("2025-03-10T17:00:00Z"));
+        history.start_session(at("2025-03-12T09:00:00Z"));
+        history.end_session(at("2025-03-12T13:00:00Z"));
+
+        let date = NaiveDate::from_ymd_opt(2025, 3, 10).unwrap();
+        let now = at("2025-03-12T18:00:00Z");
+        let holidays = vec![
+            Holiday { date: NaiveDate::from_ymd_opt(2025, 3, 14).unwrap(), name: "Spring holiday".to_string(), source: HolidaySource::Manual },
+            Holiday { date: NaiveDate::from_ymd_opt(2025, 3, 17).unwrap(), name: "Next week".to_string(), source: HolidaySource::Manual },
+        ];
+
+        let day = period_summary(&history, Some("UTC"), &holidays, OvertimePeriod::Day, date, now);
+        assert_eq!(day.worked_mins, 60 + 150 + 210);
+        assert_eq!(day.break_count, 2);
+        assert_eq!(day.break_mins, 15 + 45);
+        assert_eq!(day.longest_stretch_mins, 210);
+        assert!(day.holidays.is_empty());
+
+        let week = period_summary(&history, Some("UTC"), &holidays, OvertimePeriod::Week, date.succ_opt().unwrap(), now);
+        assert_eq!(week.start, date);
+        assert_eq!(week.worked_mins, 60 + 150 + 210 + 240);
+        assert_eq!(week.longest_stretch_mins, 240);
+        assert_eq!(week.breaks_by_type.len(), 2);
+        assert_eq!(week.holidays, holidays[..1]);
+    }
+}
@@ -0,0 +1,230 @@
+use chrono::Utc;
+use log::{error, info};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::i18n::t;
+use crate::oauth::{self, DeviceCode, Provider, Token};
+use crate::secrets;
+use crate::{AppState, AttendanceStatus};
+
+// Keychain name of the Microsoft sign-in, see `secrets::load_token`
+pub const KEYCHAIN_NAME: &str = "teams";
+
+const PRESENCE_URL: &str = "https://graph.microsoft.com/v1.0/me/presence";
+const SCOPE: &str = "Presence.ReadWrite offline_access";
+
+// Microsoft Teams presence following the attendance status. The app
+// registration in `client_id` needs the delegated `Presence.ReadWrite`
+// permission and public client flows enabled.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+#[serde(default)]
+pub struct TeamsSettings {
+    pub enabled: bool,
+    pub client_id: Option<String>, // Application (client) ID of the organization's Entra ID app
+    pub tenant: String, // Directory (tenant) ID or domain, "organizations" for any work account
+    pub status_message: bool, // Also set a status message while on a break or paused
+}
+
+impl Default for TeamsSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            client_id: None,
+            tenant: "organizations".to_string(),
+            status_message: true,
+        }
+    }
+}
+
+// Whether Teams presence is set up, for the settings screen
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct TeamsStatus {
+    pub enabled: bool,
+    pub signed_in: bool,
+}
+
+// Code to show the user while they sign in on another device
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct SignInPrompt {
+    pub user_code: String,
+    pub verification_uri: String,
+    pub message: Option<String>,
+}
+
+impl From<&DeviceCode> for SignInPrompt {
+    fn from(code: &DeviceCode) -> Self {
+        Self {
+            user_code: code.user_code.clone(),
+            verification_uri: code.verification_uri.clone(),
+            message: code.message.clone(),
+        }
+    }
+}
+
+// Availability and activity as set with `setUserPreferredPresence`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Presence {
+    pub availability: &'static str,
+    pub activity: &'static str,
+}
+
+pub fn provider(settings: &TeamsSettings) -> Result<Provider, String> {
+    let client_id = settings.client_id.clone().ok_or("No Teams client ID is configured")?;
+    let base = format!("https://login.microsoftonline.com/{}/oauth2/v2.0", settings.tenant);
+    Ok(Provider {
+        device_code_url: format!("{}/devicecode", base),
+        authorize_url: format!("{}/authorize", base),
+        token_url: format!("{}/token", base),
+        client_id,
+        client_secret: None,
+        scope: SCOPE.to_string(),
+    })
+}
+
+// Presence for an attendance status. Checked out clears the preferred
+// presence, so Teams goes back to deriving it from activity.
+pub fn presence(status: &AttendanceStatus) -> Option<Presence> {
+    match status {
+        AttendanceStatus::CheckedIn => Some(Presence { availability: "Available", activity: "Available" }),
+        AttendanceStatus::OnBreak => Some(Presence { availability: "BeRightBack", activity: "BeRightBack" }),
+        AttendanceStatus::Paused => Some(Presence { availability: "Away", activity: "Away" }),
+        AttendanceStatus::CheckedOut => None,
+    }
+}
+
+// Status message for an attendance status, empty to clear it
+pub fn status_message(status: &AttendanceStatus) -> String {
+    match status {
+        AttendanceStatus::OnBreak => t("presence.on_break"),
+        AttendanceStatus::Paused => t("presence.paused"),
+        AttendanceStatus::CheckedIn | AttendanceStatus::CheckedOut => String::new(),
+    }
+}
+
+fn status_message_body(message: &str) -> Value {
+    json!({ "statusMessage": { "message": { "content": message, "contentType": "text" } } })
+}
+
+pub fn status(state: &AppState) -> TeamsStatus {
+    TeamsStatus {
+        enabled: state.settings.lock().unwrap().teams.enabled,
+        signed_in: secrets::load_token(KEYCHAIN_NAME).ok().flatten().is_some(),
+    }
+}
+
+// Start signing in. The returned code is entered at Microsoft's device login
+// page; the app waits for that in the background and emits `teams_signed_in`
+// or `teams_sign_in_failed`.
+pub async fn sign_in(app_handle: &AppHandle, state: &AppState) -> Result<SignInPrompt, String> {
+    let provider = provider(&state.settings.lock().unwrap().teams)?;
+    let code = oauth::request_device_code(&provider).await?;
+    let prompt = SignInPrompt::from(&code);
+
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        let result = oauth::poll(&provider, &code).await.and_then(|token| secrets::save_token(KEYCHAIN_NAME, Some(&token)));
+        match result {
+            Ok(()) => {
+                info!("Signed in to Microsoft Teams");
+                let _ = app_handle.emit("teams_signed_in", ());
+                update_presence(app_handle);
+            }
+            Err(err) => {
+                error!("Teams sign-in failed: {}", err);
+                let _ = app_handle.emit("teams_sign_in_failed", err);
+            }
+        }
+    });
+    Ok(prompt)
+}
+
+pub fn sign_out() -> Result<(), String> {
+    secrets::save_token(KEYCHAIN_NAME, None)?;
+    info!("Signed out of Microsoft Teams");
+    Ok(())
+}
+
+// A current access token, refreshed and stored again when it expired
+async fn access_token(settings: &TeamsSettings) -> Result<String, String> {
+    let token = secrets::load_token(KEYCHAIN_NAME)?.ok_or("Not signed in to Teams")?;
+    if !token.expired(Utc::now()) {
+        return Ok(token.access_token);
+    }
+
+    let refreshed: Token = oauth::refresh(&provider(settings)?, &token).await?;
+    secrets::save_token(KEYCHAIN_NAME, Some(&refreshed))?;
+    Ok(refreshed.access_token)
+}
+
+async fn post(token: &str, action: &str, body: &Value) -> Result<(), String> {
+    let response = reqwest::Client::new()
+        .post(format!("{}/{}", PRESENCE_URL, action))
+        .bearer_auth(token)
+        .json(body)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send request: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("Teams {} failed with status {}", action, response.status()));
+    }
+    Ok(())
+}
+
+// Set the presence, and the status message when enabled, for a status
+async fn set_presence(settings: &TeamsSettings, status: &AttendanceStatus) -> Result<(), String> {
+    let token = access_token(settings).await?;
+    match presence(status) {
+        Some(presence) => {
+            let body = json!({ "availability": presence.availability, "activity": presence.activity });
+            post(&token, "setUserPreferredPresence", &body).await?;
+        }
+        None => post(&token, "clearUserPreferredPresence", &json!({})).await?,
+    }
+    if settings.status_message {
+        post(&token, "setStatusMessage", &status_message_body(&status_message(status))).await?;
+    }
+    Ok(())
+}
+
+// Follow a status change in Teams, in the background. Does nothing until
+// enabled and signed in.
+pub fn update_presence(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let state: State<'_, std::sync::Arc<AppState>> = app_handle.state();
+        let settings = state.settings.lock().unwrap().teams.clone();
+        if !settings.enabled || !matches!(secrets::load_token(KEYCHAIN_NAME), Ok(Some(_))) {
+            return;
+        }
+
+        let status = state.status.lock().unwrap().clone();
+        if let Err(err) = set_presence(&settings, &status).await {
+            error!("Failed to update the Teams presence: {}", err);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presence_follows_status() {
+        assert_eq!(presence(&AttendanceStatus::CheckedIn).map(|presence| presence.availability), Some("Available"));
+        assert_eq!(presence(&AttendanceStatus::OnBreak).map(|presence| presence.activity), Some("BeRightBack"));
+        assert_eq!(presence(&AttendanceStatus::CheckedOut), None);
+        assert_eq!(status_message(&AttendanceStatus::CheckedIn), "");
+    }
+
+    #[test]
+    fn test_provider_uses_tenant() {
+        assert!(provider(&TeamsSettings::default()).is_err());
+
+        let settings = TeamsSettings { client_id: Some("app".to_string()), tenant: "contoso.com".to_string(), ..TeamsSettings::default() };
+        let provider = provider(&settings).unwrap();
+        assert_eq!(provider.token_url, "https://login.microsoftonline.com/contoso.com/oauth2/v2.0/token");
+        assert_eq!(status_message_body("Lunch")["statusMessage"]["message"]["content"], "Lunch");
+    }
+}
@@ -0,0 +1,243 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use log::{error, info, warn};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tauri::{AppHandle, Manager, State};
+
+use crate::i18n::{t, tf};
+use crate::{secrets, AppState, Settings};
+
+const API_URL: &str = "https://api.telegram.org";
+// How long `getUpdates` waits for new messages before returning empty
+const POLL_TIMEOUT_SECS: u64 = 30;
+// Commands sent while the app wasn't running are dropped rather than run late
+const STALE_SECS: i64 = 5 * 60;
+
+// A Telegram bot that reports auto check-outs and takes commands from the
+// allow-listed chats. The bot token from @BotFather is kept with the other
+// secrets, see `Secrets::telegram_bot_token`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+#[serde(default)]
+pub struct TelegramSettings {
+    pub enabled: bool,
+    pub chat_ids: Vec<i64>, // Only these chats may send commands and get alerts
+    pub notify_auto_check_out: bool,
+}
+
+impl Default for TelegramSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            chat_ids: Vec::new(),
+            notify_auto_check_out: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TelegramCommand {
+    Status,
+    CheckIn,
+    CheckOut,
+    Help,
+}
+
+// The command at the start of a message, such as `/checkin` or
+// `/checkin@RemodanceBot` in groups
+pub fn parse_command(text: &str) -> Option<TelegramCommand> {
+    let command = text.split_whitespace().next()?.strip_prefix('/')?;
+    let command = command.split('@').next().unwrap_or_default();
+    match command.to_lowercase().as_str() {
+        "status" => Some(TelegramCommand::Status),
+        "checkin" => Some(TelegramCommand::CheckIn),
+        "checkout" => Some(TelegramCommand::CheckOut),
+        "help" | "start" => Some(TelegramCommand::Help),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct UpdateList {
+    #[serde(default)]
+    result: Vec<RawUpdate>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawUpdate {
+    update_id: i64,
+    message: Option<RawMessage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawMessage {
+    date: i64,
+    chat: Chat,
+    text: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Chat {
+    id: i64,
+}
+
+// A text message sent to the bot
+#[derive(Debug, Clone, PartialEq)]
+pub struct Update {
+    pub update_id: i64,
+    pub chat_id: i64,
+    pub date: i64, // Unix time the message was sent
+    pub text: String,
+}
+
+pub fn parse_updates(body: &str) -> Result<Vec<Update>, String> {
+    let list: UpdateList = serde_json::from_str(body).map_err(|err| format!("Unexpected Telegram response: {}", err))?;
+    Ok(list
+        .result
+        .into_iter()
+        .map(|update| {
+            let message = update.message;
+            Update {
+                update_id: update.update_id,
+                chat_id: message.as_ref().map_or(0, |message| message.chat.id),
+                date: message.as_ref().map_or(0, |message| message.date),
+                text: message.and_then(|message| message.text).unwrap_or_default(),
+            }
+        })
+        .collect())
+}
+
+// The command to run for an update, if it comes from an allowed chat and isn't stale
+pub fn accepted_command(settings: &TelegramSettings, update: &Update, now: i64) -> Option<TelegramCommand> {
+    if !settings.chat_ids.contains(&update.chat_id) {
+        if !update.text.is_empty() {
+            warn!("Ignoring a Telegram message from chat {}, which isn't allowed", update.chat_id);
+        }
+        return None;
+    }
+    if now - update.date > STALE_SECS {
+        return None;
+    }
+    parse_command(&update.text)
+}
+
+async fn send_message(token: &str, chat_id: i64, text: &str) -> Result<(), String> {
+    let response = reqwest::Client::new()
+        .post(format!("{}/bot{}/sendMessage", API_URL, token))
+        .json(&json!({ "chat_id": chat_id, "text": text }))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send request: {}", e.without_url()))?;
+    if !response.status().is_success() {
+        return Err(format!("Telegram request failed with status {}", response.status()));
+    }
+    Ok(())
+}
+
+// Wait for messages after `offset` and run the accepted commands. Returns the
+// offset of the next poll.
+pub async fn poll(app_handle: &AppHandle, token: &str, offset: i64) -> Result<i64, String> {
+    let url = format!("{}/bot{}/getUpdates?offset={}&timeout={}", API_URL, token, offset, POLL_TIMEOUT_SECS);
+    let response = reqwest::Client::new()
+        .get(url)
+        .timeout(Duration::from_secs(POLL_TIMEOUT_SECS + 10))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send request: {}", e.without_url()))?;
+    if !response.status().is_success() {
+        return Err(format!("Telegram request failed with status {}", response.status()));
+    }
+    let body = response.text().await.map_err(|e| format!("Failed to read response: {}", e))?;
+
+    let mut next = offset;
+    for update in parse_updates(&body)? {
+        next = next.max(update.update_id + 1);
+        let settings = app_handle.state::<Arc<AppState>>().settings.lock().unwrap().telegram.clone();
+        if let Some(command) = accepted_command(&settings, &update, Utc::now().timestamp()) {
+            let reply = run(app_handle, command).await;
+            if let Err(err) = send_message(token, update.chat_id, &reply).await {
+                error!("Failed to answer on Telegram: {}", err);
+            }
+        }
+    }
+    Ok(next)
+}
+
+// Run a command through the same handlers as the UI, and describe the result
+async fn run(app_handle: &AppHandle, command: TelegramCommand) -> String {
+    info!("Telegram command: {:?}", command);
+    let state: State<'_, Arc<AppState>> = app_handle.state();
+    let result = match command {
+        TelegramCommand::Help => return t("telegram.help"),
+        TelegramCommand::Status => Ok(()),
+        TelegramCommand::CheckIn => crate::set_attendance_with_note(app_handle, &state, "check-in", None).await,
+        TelegramCommand::CheckOut => crate::set_attendance_with_note(app_handle, &state, "check-out", None).await,
+    };
+    crate::tray::refresh(app_handle, &state);
+
+    match result {
+        Ok(()) => crate::tray::current_status_line(&state),
+        Err(err) => tf("telegram.failed", &[("error", &err)]),
+    }
+}
+
+// Tell the allowed chats about an auto check-out, in the background
+pub fn notify_auto_check_out(state: &AppState, settings: &Settings, reason: &str) {
+    let telegram = settings.telegram.clone();
+    if !telegram.enabled || !telegram.notify_auto_check_out || telegram.chat_ids.is_empty() {
+        return;
+    }
+    let Some(token) = secrets::current(state).unwrap_or_default().telegram_bot_token else {
+        return;
+    };
+
+    let text = format!("{}\n{}", t("notify.auto_check_out.title"), reason);
+    tauri::async_runtime::spawn(async move {
+        for chat_id in telegram.chat_ids {
+            if let Err(err) = send_message(&token, chat_id, &text).await {
+                error!("Failed to send the Telegram alert: {}", err);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn update(chat_id: i64, date: i64, text: &str) -> Update {
+        Update { update_id: 1, chat_id, date, text: text.to_string() }
+    }
+
+    #[test]
+    fn test_parse_command() {
+        assert_eq!(parse_command("/checkin"), Some(TelegramCommand::CheckIn));
+        assert_eq!(parse_command("/CheckOut@RemodanceBot now"), Some(TelegramCommand::CheckOut));
+        assert_eq!(parse_command("/start"), Some(TelegramCommand::Help));
+        assert_eq!(parse_command("status"), None);
+        assert_eq!(parse_command("/delete"), None);
+    }
+
+    #[test]
+    fn test_parse_updates() {
+        let body = r#"{"ok": true, "result": [
+            {"update_id": 42, "message": {"message_id": 1, "date": 1715000000, "chat": {"id": 1234, "type": "private"}, "text": "/status"}},
+            {"update_id": 43, "edited_message": {"message_id": 1}}
+        ]}"#;
+        let updates = parse_updates(body).unwrap();
+        assert_eq!(updates[0], Update { update_id: 42, chat_id: 1234, date: 1715000000, text: "/status".to_string() });
+        assert_eq!(updates[1].text, "");
+    }
+
+    #[test]
+    fn test_only_recent_commands_from_allowed_chats_run() {
+        let settings = TelegramSettings { enabled: true, chat_ids: vec![1234], ..TelegramSettings::default() };
+        let now = 1715000000;
+        assert_eq!(accepted_command(&settings, &update(1234, now - 5, "/checkout"), now), Some(TelegramCommand::CheckOut));
+        assert_eq!(accepted_command(&settings, &update(999, now - 5, "/checkout"), now), None);
+        assert_eq!(accepted_command(&settings, &update(1234, now - STALE_SECS - 1, "/checkout"), now), None);
+    }
+}
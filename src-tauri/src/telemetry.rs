@@ -0,0 +1,315 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use log::{debug, Level, LevelFilter, Log, Metadata, Record};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+// Records kept between exports, the oldest are dropped beyond this
+const MAX_BUFFERED: usize = 1000;
+// Only the app's own log records are exported, not those of its libraries
+const LOG_TARGET: &str = "remodance_lib";
+
+// OTLP/HTTP export of the app's logs and of spans around API sends and status
+// changes, so client failures can be matched with server incidents. API
+// requests carry a W3C `traceparent` header with the span's trace ID.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+#[serde(default)]
+pub struct TelemetrySettings {
+    pub enabled: bool,
+    pub endpoint: Option<String>, // Collector base URL, e.g. http://localhost:4318
+    pub headers: BTreeMap<String, String>, // Sent with every export, e.g. an API key
+    pub service_name: String,
+}
+
+impl Default for TelemetrySettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint: None,
+            headers: BTreeMap::new(),
+            service_name: "remodance".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogRecord {
+    pub time_nanos: u128,
+    pub level: Level,
+    pub target: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FinishedSpan {
+    pub name: &'static str,
+    pub trace_id: String,
+    pub span_id: String,
+    pub client: bool, // Outgoing request rather than internal work
+    pub start_nanos: u128,
+    pub end_nanos: u128,
+    pub attributes: Vec<(&'static str, String)>,
+    pub error: Option<String>,
+}
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static LOGS: Mutex<VecDeque<LogRecord>> = Mutex::new(VecDeque::new());
+static SPANS: Mutex<VecDeque<FinishedSpan>> = Mutex::new(VecDeque::new());
+
+fn now_nanos() -> u128 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_nanos()).unwrap_or_default()
+}
+
+fn random_hex(bytes: usize) -> String {
+    uuid::Uuid::new_v4().simple().to_string()[..bytes * 2].to_string()
+}
+
+fn buffer<T>(queue: &Mutex<VecDeque<T>>, item: T) {
+    let mut queue = queue.lock().unwrap();
+    if queue.len() >= MAX_BUFFERED {
+        queue.pop_front();
+    }
+    queue.push_back(item);
+}
+
+// Start or stop collecting, as the settings change
+pub fn set_enabled(enabled: bool) {
+    let was_enabled = ENABLED.swap(enabled, Ordering::Relaxed);
+    if was_enabled && !enabled {
+        LOGS.lock().unwrap().clear();
+        SPANS.lock().unwrap().clear();
+    }
+}
+
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+// A span that is exported once it ends. Spans are only collected while the
+// export is enabled.
+pub struct Span {
+    name: &'static str,
+    trace_id: String,
+    span_id: String,
+    client: bool,
+    start_nanos: u128,
+    attributes: Vec<(&'static str, String)>,
+}
+
+impl Span {
+    pub fn start(name: &'static str, client: bool, attributes: &[(&'static str, &str)]) -> Self {
+        Self {
+            name,
+            trace_id: random_hex(16),
+            span_id: random_hex(8),
+            client,
+            start_nanos: now_nanos(),
+            attributes: attributes.iter().map(|(key, value)| (*key, value.to_string())).collect(),
+        }
+    }
+
+    // W3C trace context for the request this span covers, sampled
+    pub fn traceparent(&self) -> String {
+        format!("00-{}-{}-01", self.trace_id, self.span_id)
+    }
+
+    pub fn end(self, error: Option<&String>) {
+        if !enabled() {
+            return;
+        }
+        buffer(&SPANS, FinishedSpan {
+            name: self.name,
+            trace_id: self.trace_id,
+            span_id: self.span_id,
+            client: self.client,
+            start_nanos: self.start_nanos,
+            end_nanos: now_nanos(),
+            attributes: self.attributes,
+            error: error.cloned(),
+        });
+    }
+}
+
+// Passes records on to the log plugin's logger and keeps the app's own for export
+struct TelemetryLogger {
+    inner: Box<dyn Log>,
+}
+
+impl Log for TelemetryLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        self.inner.log(record);
+        // Records about the export itself would feed back into the next one
+        let own = record.target().starts_with(LOG_TARGET) && !record.target().starts_with(module_path!());
+        if enabled() && own && record.level() <= Level::Info {
+            buffer(&LOGS, LogRecord {
+                time_nanos: now_nanos(),
+                level: record.level(),
+                target: record.target().to_string(),
+                message: record.args().to_string(),
+            });
+        }
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+// Install the log plugin's logger, see `tauri_plugin_log::Builder::split`
+pub fn install_logger(inner: Box<dyn Log>, max_level: LevelFilter) -> Result<(), String> {
+    log::set_boxed_logger(Box::new(TelemetryLogger { inner })).map_err(|err| format!("Failed to install the logger: {}", err))?;
+    log::set_max_level(max_level);
+    Ok(())
+}
+
+fn attributes<'a>(pairs: impl IntoIterator<Item = (&'a str, &'a str)>) -> Value {
+    pairs.into_iter().map(|(key, value)| json!({ "key": key, "value": { "stringValue": value } })).collect()
+}
+
+fn severity_number(level: Level) -> u8 {
+    match level {
+        Level::Trace => 1,
+        Level::Debug => 5,
+        Level::Info => 9,
+        Level::Warn => 13,
+        Level::Error => 17,
+    }
+}
+
+// Who is sending, attached to every export
+pub fn resource(settings: &TelemetrySettings, username: &str, device_name: &str) -> Value {
+    json!({
+        "attributes": attributes([
+            ("service.name", settings.service_name.as_str()),
+            ("service.version", env!("CARGO_PKG_VERSION")),
+            ("host.name", device_name),
+            ("enduser.id", username),
+        ])
+    })
+}
+
+// Body of `/v1/logs` in the OTLP JSON encoding
+pub fn logs_body(resource: &Value, records: &[LogRecord]) -> Value {
+    let records: Vec<Value> = records
+        .iter()
+        .map(|record| {
+            json!({
+                "timeUnixNano": record.time_nanos.to_string(),
+                "severityNumber": severity_number(record.level),
+                "severityText": record.level.as_str(),
+                "body": { "stringValue": record.message },
+                "attributes": attributes([("log.target", record.target.as_str())]),
+            })
+        })
+        .collect();
+    json!({ "resourceLogs": [{ "resource": resource, "scopeLogs": [{ "scope": { "name": LOG_TARGET }, "logRecords": records }] }] })
+}
+
+// Body of `/v1/traces` in the OTLP JSON encoding
+pub fn traces_body(resource: &Value, spans: &[FinishedSpan]) -> Value {
+    let spans: Vec<Value> = spans
+        .iter()
+        .map(|span| {
+            let status = match &span.error {
+                Some(error) => json!({ "code": 2, "message": error }),
+                None => json!({ "code": 1 }),
+            };
+            json!({
+                "traceId": span.trace_id,
+                "spanId": span.span_id,
+                "name": span.name,
+                "kind": if span.client { 3 } else { 1 },
+                "startTimeUnixNano": span.start_nanos.to_string(),
+                "endTimeUnixNano": span.end_nanos.to_string(),
+                "attributes": attributes(span.attributes.iter().map(|(key, value)| (*key, value.as_str()))),
+                "status": status,
+            })
+        })
+        .collect();
+    json!({ "resourceSpans": [{ "resource": resource, "scopeSpans": [{ "scope": { "name": LOG_TARGET }, "spans": spans }] }] })
+}
+
+async fn post(settings: &TelemetrySettings, path: &str, body: &Value) -> Result<(), String> {
+    let endpoint = settings.endpoint.as_deref().ok_or("No OTLP endpoint is configured")?;
+    let mut request = reqwest::Client::new().post(format!("{}{}", endpoint.trim().trim_end_matches('/'), path)).json(body);
+    for (name, value) in &settings.headers {
+        request = request.header(name, value);
+    }
+
+    let response = request.send().await.map_err(|e| format!("Failed to send request: {}", e))?;
+    if !response.status().is_success() {
+        return Err(format!("OTLP export failed with status {}", response.status()));
+    }
+    Ok(())
+}
+
+// Send what was collected since the last export. Records that fail to export
+// are dropped rather than retried.
+pub async fn export(settings: &TelemetrySettings, username: &str, device_name: &str) -> Result<(), String> {
+    let logs: Vec<LogRecord> = LOGS.lock().unwrap().drain(..).collect();
+    let spans: Vec<FinishedSpan> = SPANS.lock().unwrap().drain(..).collect();
+    let resource = resource(settings, username, device_name);
+
+    if !logs.is_empty() {
+        post(settings, "/v1/logs", &logs_body(&resource, &logs)).await?;
+    }
+    if !spans.is_empty() {
+        post(settings, "/v1/traces", &traces_body(&resource, &spans)).await?;
+    }
+    debug!("Exported {} log record(s) and {} span(s)", logs.len(), spans.len());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(error: Option<&str>) -> FinishedSpan {
+        FinishedSpan {
+            name: "api.send",
+            trace_id: "4bf92f3577b34da6a3ce929d0e0e4736".to_string(),
+            span_id: "00f067aa0ba902b7".to_string(),
+            client: true,
+            start_nanos: 1_715_000_000_000_000_000,
+            end_nanos: 1_715_000_000_250_000_000,
+            attributes: vec![("event.type", "check-in".to_string())],
+            error: error.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_traceparent_matches_the_span() {
+        let span = Span::start("api.send", true, &[]);
+        let traceparent = span.traceparent();
+        assert_eq!(traceparent.len(), 55);
+        assert!(traceparent.starts_with(&format!("00-{}-{}", span.trace_id, span.span_id)));
+    }
+
+    #[test]
+    fn test_traces_body() {
+        let resource = resource(&TelemetrySettings::default(), "alice", "laptop");
+        let body = traces_body(&resource, &[span(None), span(Some("API request failed with status 502"))]);
+        let spans = &body["resourceSpans"][0]["scopeSpans"][0]["spans"];
+        assert_eq!(spans[0]["startTimeUnixNano"], json!("1715000000000000000"));
+        assert_eq!(spans[0]["kind"], json!(3));
+        assert_eq!(spans[1]["status"], json!({ "code": 2, "message": "API request failed with status 502" }));
+        assert_eq!(body["resourceSpans"][0]["resource"]["attributes"][0], json!({ "key": "service.name", "value": { "stringValue": "remodance" } }));
+    }
+
+    #[test]
+    fn test_logs_body() {
+        let record = LogRecord { time_nanos: 1, level: Level::Warn, target: "remodance_lib".to_string(), message: "Queued".to_string() };
+        let body = logs_body(&json!({}), &[record]);
+        let record = &body["resourceLogs"][0]["scopeLogs"][0]["logRecords"][0];
+        assert_eq!(record["severityNumber"], json!(13));
+        assert_eq!(record["body"], json!({ "stringValue": "Queued" }));
+    }
+}
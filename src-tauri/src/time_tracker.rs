@@ -0,0 +1,131 @@
+use std::future::Future;
+
+use chrono::{DateTime, Utc};
+use log::{error, info};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State};
+
+use crate::clockify::Clockify;
+use crate::harvest::Harvest;
+use crate::secrets;
+use crate::toggl::Toggl;
+use crate::{AppState, AttendanceStatus};
+
+// Tag or marker on the entries the app starts, so entries started in the
+// tracker itself are never stopped
+pub const TAG: &str = "remodance";
+
+// Time tracker sessions are mirrored into, see `Settings::time_tracker`
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum TimeTrackerKind {
+    Toggl,
+    Clockify,
+    Harvest,
+}
+
+// The entry running in a tracker
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimeEntry {
+    pub id: String,
+    pub workspace_id: String,
+    pub started_by_app: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    Start,
+    Stop(TimeEntry),
+}
+
+// A time tracking service. An entry runs while checked in and is stopped on
+// breaks and check-out, the same way for every service.
+pub trait TimeTracker {
+    fn name(&self) -> &'static str;
+    fn running(&self) -> impl Future<Output = Result<Option<TimeEntry>, String>> + Send;
+    fn start(&self, now: DateTime<Utc>) -> impl Future<Output = Result<(), String>> + Send;
+    fn stop(&self, entry: &TimeEntry, now: DateTime<Utc>) -> impl Future<Output = Result<(), String>> + Send;
+}
+
+// What to do in the tracker for an attendance status, given the running entry
+pub fn action(status: &AttendanceStatus, running: Option<&TimeEntry>) -> Option<Action> {
+    let ours = running.filter(|entry| entry.started_by_app);
+    match (status, ours) {
+        (AttendanceStatus::CheckedIn, None) => Some(Action::Start),
+        (AttendanceStatus::CheckedIn, Some(_)) => None,
+        (_, Some(entry)) => Some(Action::Stop(entry.clone())),
+        (_, None) => None,
+    }
+}
+
+async fn follow<T: TimeTracker + Sync>(tracker: &T, status: &AttendanceStatus) -> Result<(), String> {
+    let running = tracker.running().await?;
+    match action(status, running.as_ref()) {
+        Some(Action::Start) => {
+            tracker.start(Utc::now()).await?;
+            info!("Started a {} time entry", tracker.name());
+        }
+        Some(Action::Stop(entry)) => {
+            tracker.stop(&entry, Utc::now()).await?;
+            info!("Stopped the {} time entry", tracker.name());
+        }
+        None => {}
+    }
+    Ok(())
+}
+
+// Entries are started and stopped one status change at a time
+static FOLLOWING: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+// Follow a status change in the selected tracker, in the background. Does
+// nothing until a tracker is selected and its API key is set.
+pub fn follow_status(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let state: State<'_, std::sync::Arc<AppState>> = app_handle.state();
+        let settings = state.settings.lock().unwrap().clone();
+        let secrets = secrets::current(&state).unwrap_or_default();
+
+        let _following = FOLLOWING.lock().await;
+        let status = state.status.lock().unwrap().clone();
+        let result = match settings.time_tracker {
+            Some(TimeTrackerKind::Toggl) => match &secrets.toggl_api_token {
+                Some(token) => follow(&Toggl { settings: &settings.toggl, token }, &status).await,
+                None => Ok(()),
+            },
+            Some(TimeTrackerKind::Clockify) => match &secrets.clockify_api_key {
+                Some(api_key) => follow(&Clockify { settings: &settings.clockify, api_key }, &status).await,
+                None => Ok(()),
+            },
+            Some(TimeTrackerKind::Harvest) => match &secrets.harvest_token {
+                Some(token) => {
+                    let harvest = Harvest { settings: &settings.harvest, token, timezone: settings.timezone.as_deref() };
+                    follow(&harvest, &status).await
+                }
+                None => Ok(()),
+            },
+            None => Ok(()),
+        };
+        if let Err(err) = result {
+            error!("Failed to update the time tracker: {}", err);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(started_by_app: bool) -> TimeEntry {
+        TimeEntry { id: "7".to_string(), workspace_id: "1".to_string(), started_by_app }
+    }
+
+    #[test]
+    fn test_only_own_entries_are_stopped() {
+        assert_eq!(action(&AttendanceStatus::CheckedIn, None), Some(Action::Start));
+        assert_eq!(action(&AttendanceStatus::CheckedIn, Some(&entry(true))), None);
+        assert_eq!(action(&AttendanceStatus::CheckedIn, Some(&entry(false))), Some(Action::Start));
+        assert_eq!(action(&AttendanceStatus::OnBreak, Some(&entry(true))), Some(Action::Stop(entry(true))));
+        assert_eq!(action(&AttendanceStatus::CheckedOut, Some(&entry(false))), None);
+    }
+}
@@ -0,0 +1,124 @@
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::time_tracker::{TimeEntry, TimeTracker, TAG};
+
+const API_URL: &str = "https://api.track.toggl.com/api/v9";
+
+// Where Toggl Track entries go. The API token is kept with the other
+// secrets, see `Secrets::toggl_api_token`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, JsonSchema)]
+#[serde(default)]
+pub struct TogglSettings {
+    pub workspace_id: Option<u64>, // Workspace entries are created in
+    pub project_id: Option<u64>, // Project of the entries, none when unset
+    pub description: String,
+}
+
+impl Default for TogglSettings {
+    fn default() -> Self {
+        Self {
+            workspace_id: None,
+            project_id: None,
+            description: "Work".to_string(),
+        }
+    }
+}
+
+// A time entry as returned by the API. Running entries have a negative duration.
+#[derive(Debug, Deserialize)]
+struct Entry {
+    id: u64,
+    workspace_id: u64,
+    #[serde(default)]
+    tags: Option<Vec<String>>,
+}
+
+pub struct Toggl<'a> {
+    pub settings: &'a TogglSettings,
+    pub token: &'a str,
+}
+
+pub fn start_body(settings: &TogglSettings, workspace_id: u64, now: DateTime<Utc>) -> Value {
+    json!({
+        "created_with": TAG,
+        "description": settings.description,
+        "workspace_id": workspace_id,
+        "project_id": settings.project_id,
+        "start": now.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        "duration": -1,
+        "tags": [TAG],
+    })
+}
+
+// The running entry, from `GET /me/time_entries/current`, which answers
+// `null` when none is running. Entries the app started carry its tag.
+pub fn parse_current(body: &str) -> Result<Option<TimeEntry>, String> {
+    let entry: Option<Entry> = serde_json::from_str(body).map_err(|err| format!("Unexpected Toggl response: {}", err))?;
+    Ok(entry.map(|entry| TimeEntry {
+        id: entry.id.to_string(),
+        workspace_id: entry.workspace_id.to_string(),
+        started_by_app: entry.tags.is_some_and(|tags| tags.iter().any(|tag| tag == TAG)),
+    }))
+}
+
+impl Toggl<'_> {
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<String, String> {
+        let response = request
+            .basic_auth(self.token, Some("api_token"))
+            .send()
+            .await
+            .map_err(|e| format!("Failed to send request: {}", e))?;
+        if !response.status().is_success() {
+            return Err(format!("Toggl request failed with status {}", response.status()));
+        }
+        response.text().await.map_err(|e| format!("Failed to read response: {}", e))
+    }
+}
+
+impl TimeTracker for Toggl<'_> {
+    fn name(&self) -> &'static str {
+        "Toggl"
+    }
+
+    async fn running(&self) -> Result<Option<TimeEntry>, String> {
+        let body = self.send(reqwest::Client::new().get(format!("{}/me/time_entries/current", API_URL))).await?;
+        parse_current(&body)
+    }
+
+    async fn start(&self, now: DateTime<Utc>) -> Result<(), String> {
+        let workspace_id = self.settings.workspace_id.ok_or("No Toggl workspace is configured")?;
+        let url = format!("{}/workspaces/{}/time_entries", API_URL, workspace_id);
+        self.send(reqwest::Client::new().post(url).json(&start_body(self.settings, workspace_id, now))).await?;
+        Ok(())
+    }
+
+    async fn stop(&self, entry: &TimeEntry, _now: DateTime<Utc>) -> Result<(), String> {
+        let url = format!("{}/workspaces/{}/time_entries/{}/stop", API_URL, entry.workspace_id, entry.id);
+        self.send(reqwest::Client::new().patch(url)).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_current_and_start_body() {
+        assert_eq!(parse_current("null").unwrap(), None);
+        let running = parse_current(r#"{"id": 7, "workspace_id": 1, "duration": -1712400000, "tags": ["remodance"]}"#).unwrap().unwrap();
+        assert_eq!((running.id.as_str(), running.started_by_app), ("7", true));
+        let manual = parse_current(r#"{"id": 8, "workspace_id": 1, "duration": -1712400000, "tags": null}"#).unwrap().unwrap();
+        assert!(!manual.started_by_app);
+
+        let settings = TogglSettings { project_id: Some(42), ..TogglSettings::default() };
+        let now = DateTime::parse_from_rfc3339("2024-05-06T09:00:00Z").unwrap().with_timezone(&Utc);
+        let body = start_body(&settings, 1, now);
+        assert_eq!(body["project_id"], json!(42));
+        assert_eq!(body["start"], json!("2024-05-06T09:00:00Z"));
+        assert_eq!(body["duration"], json!(-1));
+    }
+}
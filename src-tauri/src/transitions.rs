@@ -0,0 +1,192 @@
+use chrono::{DateTime, Duration, FixedOffset, NaiveDate, NaiveTime};
+use serde::{Deserialize, Serialize};
+
+use crate::clock;
+use crate::holidays::Holiday;
+use crate::schedule::{self, DayOverride, WeeklySchedule};
+
+// How far ahead upcoming transitions are searched
+const LOOKAHEAD_DAYS: i64 = 31;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum TransitionKind {
+    CheckIn,
+    CheckOut,
+    EndOfDayReminder,
+    Holiday,
+    VacationStart,
+    VacationEnd,
+}
+
+// An automatic action the app will take, for display in the UI
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Transition {
+    pub at: DateTime<FixedOffset>,
+    pub kind: TransitionKind,
+    pub label: String,
+}
+
+// Everything that decides what the app does on a given day
+pub struct ScheduleContext<'a> {
+    pub schedule: &'a WeeklySchedule,
+    pub overrides: &'a [DayOverride],
+    pub holidays: &'a [Holiday],
+    pub vacation: Option<(NaiveDate, NaiveDate)>,
+    pub follow_schedule: bool,
+    pub end_of_day_reminder: Option<NaiveTime>,
+    pub timezone: Option<&'a str>,
+}
+
+impl ScheduleContext<'_> {
+    // Working blocks on a date, honouring one-off overrides
+    pub fn blocks_on(&self, date: NaiveDate) -> &[schedule::WorkBlock] {
+        schedule::blocks_on(self.schedule, self.overrides, date)
+    }
+
+    fn on_vacation(&self, date: NaiveDate) -> bool {
+        self.vacation.is_some_and(|(from, to)| date >= from && date <= to)
+    }
+
+    // Transitions on a single day, in chronological order
+    fn transitions_on(&self, date: NaiveDate) -> Vec<Transition> {
+        let at = |time: NaiveTime| clock::resolve(self.timezone, date, time);
+        let mut transitions = Vec::new();
+
+        if let Some((from, to)) = self.vacation {
+            if date == from {
+                transitions.push(Transition {
+                    at: at(NaiveTime::MIN),
+                    kind: TransitionKind::VacationStart,
+                    label: format!("Vacation until {}", to.format("%Y-%m-%d")),
+                });
+            }
+            if date == to + Duration::days(1) {
+                transitions.push(Transition {
+                    at: at(NaiveTime::MIN),
+                    kind: TransitionKind::VacationEnd,
+                    label: "Back from vacation".to_string(),
+                });
+            }
+        }
+
+        if self.on_vacation(date) {
+            return transitions;
+        }
+
+        if let Some(holiday) = self.holidays.iter().find(|h| h.date == date) {
+            transitions.push(Transition {
+                at: at(NaiveTime::MIN),
+                kind: TransitionKind::Holiday,
+                label: holiday.name.clone(),
+            });
+            return transitions;
+        }
+
+        for block in self.blocks_on(date) {
+            transitions.push(Transition {
+                at: at(block.start),
+                kind: TransitionKind::CheckIn,
+                label: format!("Work starts at {}", block.start.format("%H:%M")),
+            });
+            if self.follow_schedule {
+                transitions.push(Transition {
+                    at: at(block.end),
+                    kind: TransitionKind::CheckOut,
+                    label: format!("Automatic check-out at {}", block.end.format("%H:%M")),
+                });
+            }
+        }
+
+        if let Some(reminder) = self.end_of_day_reminder {
+            if !self.blocks_on(date).is_empty() {
+                transitions.push(Transition {
+                    at: at(reminder),
+                    kind: TransitionKind::EndOfDayReminder,
+                    label: format!("Check-out reminder at {}", reminder.format("%H:%M")),
+                });
+            }
+        }
+
+        transitions.sort_by_key(|transition| transition.at);
+        transitions
+    }
+
+    // The next `count` transitions after `now`
+    pub fn upcoming(&self, now: DateTime<FixedOffset>, count: usize) -> Vec<Transition> {
+        let today = clock::date_of(self.timezone, now.to_utc());
+
+        (0..LOOKAHEAD_DAYS)
+            .map(|offset| today + Duration::days(offset))
+            .flat_map(|date| self.transitions_on(date))
+            .filter(|transition| transition.at > now)
+            .take(count)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::holidays::HolidaySource;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn at(s: &str) -> DateTime<FixedOffset> {
+        DateTime::parse_from_rfc3339(s).unwrap()
+    }
+
+    #[test]
+    fn test_upcoming_skips_weekend_and_holidays() {
+        let schedule = WeeklySchedule::default();
+        let holidays = vec![Holiday {
+            date: date("2025-03-17"),
+            name: "Saint Patrick's Day".to_string(),
+            source: HolidaySource::Manual,
+        }];
+        let context = ScheduleContext {
+            schedule: &schedule,
+            overrides: &[],
+            holidays: &holidays,
+            vacation: None,
+            follow_schedule: true,
+            end_of_day_reminder: None,
+            timezone: Some("UTC"),
+        };
+
+        // Friday afternoon
+        let upcoming = context.upcoming(at("2025-03-14T15:00:00Z"), 4);
+        let kinds: Vec<TransitionKind> = upcoming.iter().map(|t| t.kind.clone()).collect();
+
+        assert_eq!(
+            kinds,
+            vec![TransitionKind::CheckOut, TransitionKind::Holiday, TransitionKind::CheckIn, TransitionKind::CheckOut]
+        );
+        assert_eq!(upcoming[0].at, at("2025-03-14T17:00:00Z"));
+        assert_eq!(upcoming[2].at, at("2025-03-18T09:00:00Z"));
+    }
+
+    #[test]
+    fn test_upcoming_reports_vacation_range() {
+        let schedule = WeeklySchedule::default();
+        let context = ScheduleContext {
+            schedule: &schedule,
+            overrides: &[],
+            holidays: &[],
+            vacation: Some((date("2025-03-11"), date("2025-03-12"))),
+            follow_schedule: false,
+            end_of_day_reminder: None,
+            timezone: Some("UTC"),
+        };
+
+        let upcoming = context.upcoming(at("2025-03-10T12:00:00Z"), 3);
+        let kinds: Vec<TransitionKind> = upcoming.iter().map(|t| t.kind.clone()).collect();
+
+        assert_eq!(
+            kinds,
+            vec![TransitionKind::VacationStart, TransitionKind::VacationEnd, TransitionKind::CheckIn]
+        );
+    }
+}
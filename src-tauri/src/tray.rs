@@ -0,0 +1,491 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+
+use log::{debug, error, info};
+use tauri::image::Image;
+use tauri::menu::{CheckMenuItem, IsMenuItem, Menu, MenuItem, PredefinedMenuItem, Submenu};
+use tauri::tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent};
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::i18n::{self, t, tf};
+use crate::{AppState, AttendanceStatus};
+
+const TRAY_ID: &str = "main";
+const ICON_SIZE: u32 = 32;
+const TOOLTIP_INTERVAL_SECS: u64 = 60;
+
+// Default for `Settings::status_line_format`, e.g. "● 4h 22m"
+pub const DEFAULT_STATUS_LINE_FORMAT: &str = "{icon} {elapsed}";
+
+// Menu item identifiers. Break items are `break:<kind>`, profile items `profile:<name>`.
+const MENU_CHECK_IN: &str = "check-in";
+const MENU_CHECK_OUT: &str = "check-out";
+const MENU_BREAK_PREFIX: &str = "break:";
+const MENU_PROFILE_PREFIX: &str = "profile:";
+const MENU_END_BREAK: &str = "end-break";
+const MENU_PAUSE_AUTO_MODE: &str = "pause-auto-mode";
+const MENU_OPEN_SETTINGS: &str = "open-settings";
+const MENU_QUIT: &str = "quit";
+
+// What the tray icon currently shows
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TrayState {
+    CheckedIn,
+    CheckedOut,
+    Paused,
+    Offline,
+}
+
+impl TrayState {
+    // Offline wins over the attendance status so a failing API is noticed
+    pub fn new(status: &AttendanceStatus, offline: bool) -> Self {
+        if offline {
+            return TrayState::Offline;
+        }
+
+        match status {
+            AttendanceStatus::CheckedIn => TrayState::CheckedIn,
+            AttendanceStatus::CheckedOut => TrayState::CheckedOut,
+            AttendanceStatus::OnBreak | AttendanceStatus::Paused => TrayState::Paused,
+        }
+    }
+
+    fn color(self) -> [u8; 3] {
+        match self {
+            TrayState::CheckedIn => [0x2e, 0xa0, 0x43],
+            TrayState::CheckedOut => [0x8c, 0x95, 0x9f],
+            TrayState::Paused => [0xf0, 0xa0, 0x20],
+            TrayState::Offline => [0xd0, 0x3a, 0x2f],
+        }
+    }
+}
+
+// Tooltip such as "Checked in — 3h 12m", with the time spent in the current status
+fn tooltip(status: &AttendanceStatus, offline: bool, elapsed: Option<chrono::Duration>) -> String {
+    let label = t(match status {
+        AttendanceStatus::CheckedIn => "status.checked_in",
+        AttendanceStatus::CheckedOut => "status.checked_out",
+        AttendanceStatus::OnBreak => "status.on_break",
+        AttendanceStatus::Paused => "status.paused",
+    });
+
+    let mut text = match elapsed {
+        Some(elapsed) => format!("{} — {}h {}m", label, elapsed.num_hours(), elapsed.num_minutes() % 60),
+        None => label,
+    };
+    if offline {
+        text.push_str(&format!(" ({})", t("tray.api_unreachable")));
+    }
+    text
+}
+
+// Short text for menu-bar titles and widgets. The template may use {icon},
+// {status}, {elapsed} (running session or break) and {today} (worked today).
+fn status_line(template: &str, status: &AttendanceStatus, elapsed: Option<chrono::Duration>, worked_today: chrono::Duration) -> String {
+    let icon = match status {
+        AttendanceStatus::CheckedIn => "●",
+        AttendanceStatus::CheckedOut => "○",
+        AttendanceStatus::OnBreak => "◐",
+        AttendanceStatus::Paused => "◑",
+    };
+    let duration = |duration: chrono::Duration| format!("{}h {}m", duration.num_hours(), duration.num_minutes() % 60);
+
+    template
+        .replace("{icon}", icon)
+        .replace("{status}", status.label())
+        .replace("{elapsed}", &elapsed.map(duration).unwrap_or_default())
+        .replace("{today}", &duration(worked_today))
+        .trim()
+        .to_string()
+}
+
+// Status line for the current state, using the configured template
+pub fn current_status_line(state: &AppState) -> String {
+    let (status, elapsed) = current_status(state);
+    let settings = state.settings.lock().unwrap().clone();
+    let timezone = settings.timezone.as_deref();
+    let worked_mins = {
+        let history = state.history.lock().unwrap();
+        crate::summary::today_summary(&history, timezone, &settings.holidays, None, crate::clock::today(timezone), Utc::now()).summary.worked_mins
+    };
+
+    status_line(&settings.status_line_format, &status, elapsed, chrono::Duration::minutes(worked_mins))
+}
+
+// The status and how long the running session or break has lasted
+fn current_status(state: &AppState) -> (AttendanceStatus, Option<chrono::Duration>) {
+    let status = state.status.lock().unwrap().clone();
+    let started = {
+        let history = state.history.lock().unwrap();
+        match status {
+            AttendanceStatus::CheckedIn => history.current_session().map(|session| session.start),
+            AttendanceStatus::OnBreak | AttendanceStatus::Paused => history.current_break().map(|period| period.start),
+            AttendanceStatus::CheckedOut => None,
+        }
+    };
+
+    (status, started.map(|start| Utc::now() - start))
+}
+
+// Shown in place of the tooltip, and as the menu-bar title, during the idle check-out warning
+fn countdown_text(secs_left: u64) -> String {
+    tf("tray.countdown", &[("secs", &secs_left)])
+}
+
+// Current tooltip text. Sessions and breaks show how long they have been running.
+fn current_tooltip(state: &AppState) -> String {
+    if let Some(secs_left) = *state.checkout_countdown.lock().unwrap() {
+        return countdown_text(secs_left);
+    }
+    let (status, elapsed) = current_status(state);
+    tooltip(&status, *state.api_offline.lock().unwrap(), elapsed)
+}
+
+// Dock badge text: time worked while checked in, a short word on breaks, nothing when checked out
+#[cfg_attr(not(any(target_os = "macos", target_os = "windows")), allow(dead_code))]
+fn badge_label(status: &AttendanceStatus, elapsed: Option<chrono::Duration>) -> Option<String> {
+    match status {
+        AttendanceStatus::CheckedIn => elapsed.map(|elapsed| format!("{}:{:02}", elapsed.num_hours(), elapsed.num_minutes() % 60)),
+        AttendanceStatus::OnBreak => Some("Break".to_string()),
+        AttendanceStatus::Paused => Some("Paused".to_string()),
+        AttendanceStatus::CheckedOut => None,
+    }
+}
+
+// Show the status on the window's dock or taskbar entry: a badge label on
+// macOS, the tray's status dot as an overlay on Windows and the worked hours
+// as a badge count where Linux desktops support it
+pub fn update_badge(app_handle: &AppHandle, state: &AppState) {
+    let Some(window) = app_handle.get_webview_window(crate::MAIN_WINDOW) else {
+        return;
+    };
+    let (status, elapsed) = current_status(state);
+
+    #[cfg(target_os = "macos")]
+    let result = window.set_badge_label(badge_label(&status, elapsed));
+    #[cfg(target_os = "windows")]
+    let result = {
+        let offline = *state.api_offline.lock().unwrap();
+        window.set_overlay_icon(badge_label(&status, elapsed).map(|_| icon(TrayState::new(&status, offline))))
+    };
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    let result = window.set_badge_count(match status {
+        AttendanceStatus::CheckedIn => elapsed.map(|elapsed| elapsed.num_hours()).filter(|hours| *hours > 0),
+        _ => None,
+    });
+
+    if let Err(err) = result {
+        debug!("Failed to update the taskbar badge: {}", err);
+    }
+}
+
+// Send the status line to widgets listening for `status_line`
+fn emit_status_line(app_handle: &AppHandle, state: &AppState) {
+    let _ = app_handle.emit("status_line", current_status_line(state));
+}
+
+fn update_tooltip(app_handle: &AppHandle, state: &AppState) {
+    if let Some(tray) = app_handle.tray_by_id(TRAY_ID) {
+        let _ = tray.set_tooltip(Some(current_tooltip(state)));
+    }
+}
+
+// Show or clear the idle check-out countdown. The title only appears next to
+// the icon in the macOS menu bar and on some Linux panels.
+pub fn update_countdown(app_handle: &AppHandle, state: &AppState) {
+    let Some(tray) = app_handle.tray_by_id(TRAY_ID) else {
+        return;
+    };
+    let countdown = *state.checkout_countdown.lock().unwrap();
+    if let Err(err) = tray.set_title(countdown.map(countdown_text)) {
+        debug!("Failed to update the tray title: {}", err);
+    }
+    update_tooltip(app_handle, state);
+}
+
+// Status dot drawn at runtime, so every state has its own artwork without extra assets
+fn icon(state: TrayState) -> Image<'static> {
+    let [red, green, blue] = state.color();
+    let center = (ICON_SIZE as f32 - 1.0) / 2.0;
+    let radius = ICON_SIZE as f32 / 2.0 - 2.0;
+
+    let mut rgba = Vec::with_capacity((ICON_SIZE * ICON_SIZE * 4) as usize);
+    for y in 0..ICON_SIZE {
+        for x in 0..ICON_SIZE {
+            let distance = ((x as f32 - center).powi(2) + (y as f32 - center).powi(2)).sqrt();
+            // One pixel of falloff smooths the edge
+            let alpha = (radius + 0.5 - distance).clamp(0.0, 1.0);
+            rgba.extend_from_slice(&[red, green, blue, (alpha * 255.0) as u8]);
+        }
+    }
+
+    Image::new_owned(rgba, ICON_SIZE, ICON_SIZE)
+}
+
+// Everything the tray icon and menu are drawn from
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrayView {
+    icon: TrayState,
+    status: AttendanceStatus,
+    auto_mode: bool,
+    break_types: Vec<String>,
+    profiles: Vec<String>,
+    active_profile: Option<String>,
+    locale: &'static str, // Menu labels are rebuilt in a new language
+}
+
+impl TrayView {
+    fn current(state: &AppState) -> Self {
+        let status = state.status.lock().unwrap().clone();
+        let offline = *state.api_offline.lock().unwrap();
+        let (auto_mode, break_types) = {
+            let settings = state.settings.lock().unwrap();
+            (settings.auto_mode, settings.break_types.iter().map(|t| t.name.clone()).collect())
+        };
+        let (profiles, active_profile) = {
+            let profiles = state.profiles.lock().unwrap();
+            (profiles.names(), profiles.active.clone())
+        };
+
+        Self {
+            icon: TrayState::new(&status, offline),
+            status,
+            auto_mode,
+            break_types,
+            profiles,
+            active_profile,
+            locale: i18n::current(),
+        }
+    }
+}
+
+fn build_menu(app_handle: &AppHandle, view: &TrayView) -> tauri::Result<Menu<tauri::Wry>> {
+    let checked_in = view.status == AttendanceStatus::CheckedIn;
+
+    let break_items = view
+        .break_types
+        .iter()
+        .map(|kind| MenuItem::with_id(app_handle, format!("{}{}", MENU_BREAK_PREFIX, kind), kind, true, None::<&str>))
+        .collect::<tauri::Result<Vec<_>>>()?;
+    let break_refs: Vec<&dyn IsMenuItem<tauri::Wry>> =
+        break_items.iter().map(|item| item as &dyn IsMenuItem<tauri::Wry>).collect();
+
+    let profile_items = view
+        .profiles
+        .iter()
+        .map(|name| {
+            let active = view.active_profile.as_ref() == Some(name);
+            CheckMenuItem::with_id(app_handle, format!("{}{}", MENU_PROFILE_PREFIX, name), name, true, active, None::<&str>)
+        })
+        .collect::<tauri::Result<Vec<_>>>()?;
+    let profile_refs: Vec<&dyn IsMenuItem<tauri::Wry>> =
+        profile_items.iter().map(|item| item as &dyn IsMenuItem<tauri::Wry>).collect();
+    let profile_menu = Submenu::with_items(app_handle, t("menu.profile"), true, &profile_refs)?;
+
+    let check_in = MenuItem::with_id(app_handle, MENU_CHECK_IN, t("menu.check_in"), !checked_in, None::<&str>)?;
+    let check_out = MenuItem::with_id(app_handle, MENU_CHECK_OUT, t("menu.check_out"), view.status != AttendanceStatus::CheckedOut, None::<&str>)?;
+    let start_break = Submenu::with_items(app_handle, t("menu.start_break"), checked_in && !break_refs.is_empty(), &break_refs)?;
+    let end_break = MenuItem::with_id(app_handle, MENU_END_BREAK, t("menu.end_break"), view.status == AttendanceStatus::OnBreak, None::<&str>)?;
+    let pause_auto_mode = CheckMenuItem::with_id(app_handle, MENU_PAUSE_AUTO_MODE, t("menu.pause_auto_mode"), true, !view.auto_mode, None::<&str>)?;
+    let open_settings = MenuItem::with_id(app_handle, MENU_OPEN_SETTINGS, t("menu.open_settings"), true, None::<&str>)?;
+    let quit = MenuItem::with_id(app_handle, MENU_QUIT, t("menu.quit"), true, None::<&str>)?;
+    let separators = [PredefinedMenuItem::separator(app_handle)?, PredefinedMenuItem::separator(app_handle)?];
+
+    let mut items: Vec<&dyn IsMenuItem<tauri::Wry>> = vec![&check_in, &check_out, &start_break, &end_break, &separators[0], &pause_auto_mode];
+    // The switcher only appears once there is something to switch between
+    if view.profiles.len() > 1 {
+        items.push(&profile_menu);
+    }
+    items.extend([&open_settings as &dyn IsMenuItem<tauri::Wry>, &separators[1], &quit]);
+
+    Menu::with_items(app_handle, &items)
+}
+
+// Run a menu action through the same functions as the matching commands
+fn handle_menu_event(app_handle: &AppHandle, id: &str) {
+    match id {
+        MENU_OPEN_SETTINGS => {
+            show_main_window(app_handle);
+            let _ = app_handle.emit("open_settings", ());
+            return;
+        }
+        MENU_QUIT => {
+            crate::request_exit(app_handle);
+            return;
+        }
+        _ => {}
+    }
+
+    let app_handle = app_handle.clone();
+    let id = id.to_string();
+    tauri::async_runtime::spawn(async move {
+        let state: State<'_, Arc<AppState>> = app_handle.state();
+        let result = match id.as_str() {
+            MENU_CHECK_IN => crate::set_attendance(&app_handle, &state, "check-in").await,
+            MENU_CHECK_OUT => crate::set_attendance(&app_handle, &state, "check-out").await,
+            MENU_END_BREAK => crate::return_from_break(&app_handle, &state).await,
+            MENU_PAUSE_AUTO_MODE => crate::update_settings(&app_handle, &state, crate::settings_audit::ChangeSource::Ui, |settings| {
+                settings.auto_mode = !settings.auto_mode;
+            })
+            .await
+            .map(|settings| info!("Auto mode {} from the tray", if settings.auto_mode { "resumed" } else { "paused" })),
+            _ => match (id.strip_prefix(MENU_BREAK_PREFIX), id.strip_prefix(MENU_PROFILE_PREFIX)) {
+                (Some(kind), _) => crate::take_break(&app_handle, &state, kind).await,
+                (_, Some(name)) => crate::change_profile(&app_handle, &state, name).await,
+                _ => Ok(()),
+            },
+        };
+
+        if let Err(err) = result {
+            error!("Tray action '{}' failed: {}", id, err);
+        }
+        refresh(&app_handle, &state);
+    });
+}
+
+// Create the tray icon. Clicking it brings up the main window, right-clicking opens the menu.
+pub fn create(app_handle: &AppHandle, state: &AppState) -> tauri::Result<()> {
+    let view = TrayView::current(state);
+
+    TrayIconBuilder::with_id(TRAY_ID)
+        .icon(icon(view.icon))
+        .tooltip(current_tooltip(state))
+        .menu(&build_menu(app_handle, &view)?)
+        .show_menu_on_left_click(false)
+        .on_menu_event(|app_handle, event| handle_menu_event(app_handle, event.id().as_ref()))
+        .on_tray_icon_event(|tray, event| {
+            if let TrayIconEvent::Click {
+                button: MouseButton::Left,
+                button_state: MouseButtonState::Up,
+                ..
+            } = event
+            {
+                show_main_window(tray.app_handle());
+            }
+        })
+        .build(app_handle)?;
+
+    *state.tray_view.lock().unwrap() = Some(view);
+    Ok(())
+}
+
+// Redraw the tray icon and menu when the status, API reachability or settings changed
+pub fn refresh(app_handle: &AppHandle, state: &AppState) {
+    let view = TrayView::current(state);
+    {
+        let mut shown = state.tray_view.lock().unwrap();
+        if shown.as_ref() == Some(&view) {
+            return;
+        }
+        *shown = Some(view.clone());
+    }
+
+    let Some(tray) = app_handle.tray_by_id(TRAY_ID) else {
+        return;
+    };
+    if let Err(err) = tray.set_icon(Some(icon(view.icon))) {
+        error!("Failed to update tray icon: {}", err);
+    }
+    update_countdown(app_handle, state);
+    update_badge(app_handle, state);
+    emit_status_line(app_handle, state);
+    match build_menu(app_handle, &view) {
+        Ok(menu) => {
+            let _ = tray.set_menu(Some(menu));
+        }
+        Err(err) => error!("Failed to update tray menu: {}", err),
+    }
+}
+
+// Keep the elapsed time in the tooltip, badge and status line current
+pub fn start_tooltip_timer(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let state: State<'_, Arc<AppState>> = app_handle.state();
+        let mut interval = tokio::time::interval(Duration::from_secs(TOOLTIP_INTERVAL_SECS));
+
+        loop {
+            interval.tick().await;
+            update_tooltip(&app_handle, &state);
+            update_badge(&app_handle, &state);
+            emit_status_line(&app_handle, &state);
+        }
+    });
+}
+
+pub fn show_main_window(app_handle: &AppHandle) {
+    // In headless mode the window doesn't exist until it is first asked for
+    let window = match app_handle.get_webview_window(crate::MAIN_WINDOW) {
+        Some(window) => window,
+        None => match crate::create_main_window(app_handle) {
+            Ok(window) => {
+                let state: State<'_, Arc<AppState>> = app_handle.state();
+                update_badge(app_handle, &state);
+                window
+            }
+            Err(err) => {
+                error!("Failed to create the main window: {}", err);
+                return;
+            }
+        },
+    };
+
+    let _ = window.show();
+    let _ = window.unminimize();
+    let _ = window.set_focus();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_offline_overrides_status() {
+        assert_eq!(TrayState::new(&AttendanceStatus::CheckedIn, false), TrayState::CheckedIn);
+        assert_eq!(TrayState::new(&AttendanceStatus::Paused, false), TrayState::Paused);
+        assert_eq!(TrayState::new(&AttendanceStatus::OnBreak, false), TrayState::Paused);
+        assert_eq!(TrayState::new(&AttendanceStatus::CheckedIn, true), TrayState::Offline);
+    }
+
+    #[test]
+    fn test_tooltip_shows_elapsed_time() {
+        let elapsed = chrono::Duration::minutes(3 * 60 + 12);
+        assert_eq!(tooltip(&AttendanceStatus::CheckedIn, false, Some(elapsed)), "Checked in — 3h 12m");
+        assert_eq!(tooltip(&AttendanceStatus::CheckedOut, true, None), "Checked out (API unreachable)");
+    }
+
+    #[test]
+    fn test_status_line_template() {
+        let elapsed = chrono::Duration::minutes(4 * 60 + 22);
+        let today = chrono::Duration::minutes(6 * 60);
+        assert_eq!(status_line(DEFAULT_STATUS_LINE_FORMAT, &AttendanceStatus::CheckedIn, Some(elapsed), today), "● 4h 22m");
+        assert_eq!(status_line(DEFAULT_STATUS_LINE_FORMAT, &AttendanceStatus::CheckedOut, None, today), "○");
+        assert_eq!(status_line("{status} ({today})", &AttendanceStatus::OnBreak, Some(elapsed), today), "on-break (6h 0m)");
+    }
+
+    #[test]
+    fn test_countdown_text() {
+        assert_eq!(countdown_text(45), "Checking out in 45s");
+    }
+
+    #[test]
+    fn test_badge_label_per_status() {
+        let elapsed = chrono::Duration::minutes(3 * 60 + 5);
+        assert_eq!(badge_label(&AttendanceStatus::CheckedIn, Some(elapsed)), Some("3:05".to_string()));
+        assert_eq!(badge_label(&AttendanceStatus::OnBreak, Some(elapsed)), Some("Break".to_string()));
+        assert_eq!(badge_label(&AttendanceStatus::CheckedOut, None), None);
+    }
+
+    #[test]
+    fn test_icon_is_a_dot() {
+        let image = icon(TrayState::CheckedIn);
+        let pixel = |x: u32, y: u32| {
+            let offset = ((y * ICON_SIZE + x) * 4) as usize;
+            image.rgba()[offset..offset + 4].to_vec()
+        };
+
+        assert_eq!(pixel(ICON_SIZE / 2, ICON_SIZE / 2), vec![0x2e, 0xa0, 0x43, 255]);
+        assert_eq!(pixel(0, 0)[3], 0);
+    }
+}
@@ -0,0 +1,105 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_updater::{Update, UpdaterExt};
+use url::Url;
+
+// Release channel to take updates from
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum UpdateChannel {
+    #[default]
+    Stable,
+    Beta, // Pre-releases, published under a rolling `beta` release
+}
+
+impl UpdateChannel {
+    // Update manifest for the channel
+    pub fn endpoint(self) -> &'static str {
+        match self {
+            UpdateChannel::Stable => "https://github.com/rashidpathiyil/remodance/releases/latest/download/latest.json",
+            UpdateChannel::Beta => "https://github.com/rashidpathiyil/remodance/releases/download/beta/latest.json",
+        }
+    }
+}
+
+// A newer version offered by the update endpoint
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct UpdateInfo {
+    pub version: String,
+    pub current_version: String,
+    pub notes: Option<String>,
+    pub date: Option<String>,
+}
+
+impl From<&Update> for UpdateInfo {
+    fn from(update: &Update) -> Self {
+        Self {
+            version: update.version.clone(),
+            current_version: update.current_version.clone(),
+            notes: update.body.clone(),
+            date: update.date.map(|date| date.to_string()),
+        }
+    }
+}
+
+// Download progress, emitted as `update_progress`
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct UpdateProgress {
+    pub downloaded: u64,
+    pub total: Option<u64>,
+}
+
+async fn find_update(app_handle: &AppHandle, channel: UpdateChannel) -> Result<Option<Update>, String> {
+    let endpoint = Url::parse(channel.endpoint()).map_err(|err| format!("Invalid update endpoint: {}", err))?;
+
+    app_handle
+        .updater_builder()
+        .endpoints(vec![endpoint])
+        .and_then(|builder| builder.build())
+        .map_err(|err| format!("Failed to set up the updater: {}", err))?
+        .check()
+        .await
+        .map_err(|err| format!("Failed to check for updates: {}", err))
+}
+
+// Look for a newer version on the channel
+pub async fn check(app_handle: &AppHandle, channel: UpdateChannel) -> Result<Option<UpdateInfo>, String> {
+    Ok(find_update(app_handle, channel).await?.as_ref().map(UpdateInfo::from))
+}
+
+// Download and install the newest version on the channel, then restart into it.
+// Returns without restarting when there is nothing to install.
+pub async fn install(app_handle: &AppHandle, channel: UpdateChannel) -> Result<(), String> {
+    let Some(update) = find_update(app_handle, channel).await? else {
+        return Ok(());
+    };
+
+    let mut downloaded = 0;
+    update
+        .download_and_install(
+            |chunk, total| {
+                downloaded += chunk as u64;
+                let _ = app_handle.emit("update_progress", UpdateProgress { downloaded, total });
+            },
+            || {
+                let _ = app_handle.emit("update_downloaded", ());
+            },
+        )
+        .await
+        .map_err(|err| format!("Failed to install update {}: {}", update.version, err))?;
+
+    app_handle.restart()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_channels_have_separate_endpoints() {
+        assert_ne!(UpdateChannel::Stable.endpoint(), UpdateChannel::Beta.endpoint());
+        assert!(Url::parse(UpdateChannel::Beta.endpoint()).is_ok());
+        assert_eq!(serde_json::to_string(&UpdateChannel::Beta).unwrap(), "\"beta\"");
+    }
+}
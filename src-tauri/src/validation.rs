@@ -0,0 +1,343 @@
+use std::net::IpAddr;
+
+use serde::Serialize;
+use url::Url;
+
+use crate::backend::BackendKind;
+use crate::clock;
+use crate::hotkeys::HotkeyAction;
+use crate::i18n;
+use crate::push::PushService;
+use crate::remote_config;
+use crate::schedule;
+use crate::time_tracker::TimeTrackerKind;
+use crate::Settings;
+
+// Error codes the settings screen can map to its own wording
+pub const REQUIRED: &str = "required";
+pub const INVALID_URL: &str = "invalid-url";
+pub const OUT_OF_RANGE: &str = "out-of-range";
+pub const INVALID_TIMEZONE: &str = "invalid-timezone";
+pub const UNKNOWN_TEMPLATE: &str = "unknown-template";
+pub const INVALID_SHORTCUT: &str = "invalid-shortcut";
+pub const INVALID_KEY: &str = "invalid-key";
+pub const UNSUPPORTED_LOCALE: &str = "unsupported-locale";
+pub const INVALID_ADDRESS: &str = "invalid-address";
+
+// Shortest idle timeout, below it normal pauses in typing would check the user out
+pub const MIN_IDLE_TIMEOUT_SECS: u64 = 10;
+
+// A problem with one settings field. `field` is the field's name as sent by
+// the frontend, nested fields are joined with dots.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct FieldError {
+    pub field: String,
+    pub code: &'static str,
+    pub message: String,
+}
+
+impl FieldError {
+    fn new(field: &str, code: &'static str, message: &str) -> Self {
+        Self {
+            field: field.to_string(),
+            code,
+            message: message.to_string(),
+        }
+    }
+}
+
+// Check settings before they are saved. An empty list means they are valid.
+pub fn validate(settings: &Settings) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+
+    match Url::parse(settings.api_endpoint.trim()) {
+        Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+        Ok(_) => errors.push(FieldError::new("api_endpoint", INVALID_URL, "The API endpoint must be an http or https URL")),
+        Err(err) => errors.push(FieldError::new("api_endpoint", INVALID_URL, &format!("The API endpoint is not a valid URL: {}", err))),
+    }
+    if settings.backend == BackendKind::Erpnext {
+        match Url::parse(settings.erpnext.url.trim()) {
+            Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+            Ok(_) => errors.push(FieldError::new("erpnext.url", INVALID_URL, "The ERPNext site must be an http or https URL")),
+            Err(err) => errors.push(FieldError::new("erpnext.url", INVALID_URL, &format!("The ERPNext site is not a valid URL: {}", err))),
+        }
+        if settings.erpnext.api_key.as_deref().is_none_or(|key| key.trim().is_empty()) {
+            errors.push(FieldError::new("erpnext.api_key", REQUIRED, "Enter the API key checkins are created with"));
+        }
+    }
+    if settings.backend == BackendKind::Odoo {
+        match Url::parse(settings.odoo.url.trim()) {
+            Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+            Ok(_) => errors.push(FieldError::new("odoo.url", INVALID_URL, "The Odoo URL must be an http or https URL")),
+            Err(err) => errors.push(FieldError::new("odoo.url", INVALID_URL, &format!("The Odoo URL is not valid: {}", err))),
+        }
+        if settings.odoo.database.trim().is_empty() {
+            errors.push(FieldError::new("odoo.database", REQUIRED, "Enter the Odoo database"));
+        }
+        if settings.odoo.login.trim().is_empty() {
+            errors.push(FieldError::new("odoo.login", REQUIRED, "Enter the login attendances are recorded as"));
+        }
+    }
+    if settings.backend == BackendKind::Bamboohr {
+        if settings.bamboohr.subdomain.trim().is_empty() {
+            errors.push(FieldError::new("bamboohr.subdomain", REQUIRED, "Enter the BambooHR subdomain"));
+        }
+        if settings.timezone.is_none() {
+            errors.push(FieldError::new("timezone", REQUIRED, "BambooHR needs the timezone of each entry, choose one"));
+        }
+    }
+    if settings.backend == BackendKind::Personio && settings.personio.client_id.trim().is_empty() {
+        errors.push(FieldError::new("personio.client_id", REQUIRED, "Enter the client ID of the Personio API credentials"));
+    }
+    if settings.username.trim().is_empty() {
+        errors.push(FieldError::new("username", REQUIRED, "Enter a username"));
+    }
+    if settings.device_name.trim().is_empty() {
+        errors.push(FieldError::new("device_name", REQUIRED, "Enter a device name"));
+    }
+    if let Some(timezone) = &settings.timezone {
+        if let Err(err) = clock::parse_timezone(timezone) {
+            errors.push(FieldError::new("timezone", INVALID_TIMEZONE, &err));
+        }
+    }
+    if let Some(locale) = &settings.locale {
+        if i18n::supported(locale).is_none() {
+            errors.push(FieldError::new("locale", UNSUPPORTED_LOCALE, &format!("There is no translation for '{}', use one of {}", locale, i18n::LOCALES.join(", "))));
+        }
+    }
+    if let Some(name) = &settings.active_template {
+        if schedule::find_template(&settings.schedule_templates, name).is_none() {
+            errors.push(FieldError::new("active_template", UNKNOWN_TEMPLATE, &format!("There is no schedule template named '{}'", name)));
+        }
+    }
+
+    if let Some(directory_url) = &settings.directory_url {
+        match Url::parse(directory_url.trim()) {
+            Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+            Ok(_) => errors.push(FieldError::new("directory_url", INVALID_URL, "The directory URL must be an http or https URL")),
+            Err(err) => errors.push(FieldError::new("directory_url", INVALID_URL, &format!("The directory URL is not a valid URL: {}", err))),
+        }
+    }
+    for (field, url) in settings.automation_triggers.urls() {
+        match Url::parse(url) {
+            Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+            Ok(_) => errors.push(FieldError::new(field, INVALID_URL, "Automation triggers must be http or https URLs")),
+            Err(err) => errors.push(FieldError::new(field, INVALID_URL, &format!("The automation trigger is not a valid URL: {}", err))),
+        }
+    }
+    for (i, hook) in settings.plugins.iter().enumerate() {
+        if hook.command.trim().is_empty() {
+            errors.push(FieldError::new(&format!("plugins.{}.command", i), REQUIRED, "Enter the executable to run"));
+        }
+        if hook.timeout_secs == 0 {
+            errors.push(FieldError::new(&format!("plugins.{}.timeout_secs", i), OUT_OF_RANGE, "Must be at least one second"));
+        }
+    }
+    if settings.local_api.enabled {
+        if settings.local_api.bind_address.trim().parse::<IpAddr>().is_err() {
+            errors.push(FieldError::new("local_api.bind_address", INVALID_ADDRESS, "Enter an IP address to listen on, e.g. 127.0.0.1"));
+        }
+        if settings.local_api.port == 0 {
+            errors.push(FieldError::new("local_api.port", OUT_OF_RANGE, "Choose a port between 1 and 65535"));
+        }
+    }
+    if settings.matrix.enabled {
+        match Url::parse(settings.matrix.homeserver.trim()) {
+            Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+            Ok(_) => errors.push(FieldError::new("matrix.homeserver", INVALID_URL, "The homeserver must be an http or https URL")),
+            Err(err) => errors.push(FieldError::new("matrix.homeserver", INVALID_URL, &format!("The homeserver is not a valid URL: {}", err))),
+        }
+        if !settings.matrix.room_id.trim().starts_with('!') {
+            errors.push(FieldError::new("matrix.room_id", REQUIRED, "Enter the room ID, e.g. !abcdef:example.org, not its alias"));
+        }
+    }
+    if settings.caldav.enabled {
+        match Url::parse(settings.caldav.calendar_url.trim()) {
+            Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+            Ok(_) => errors.push(FieldError::new("caldav.calendar_url", INVALID_URL, "The calendar URL must be an http or https URL")),
+            Err(err) => errors.push(FieldError::new("caldav.calendar_url", INVALID_URL, &format!("The calendar URL is not a valid URL: {}", err))),
+        }
+        if settings.caldav.username.trim().is_empty() {
+            errors.push(FieldError::new("caldav.username", REQUIRED, "Enter the user name of the calendar account"));
+        }
+    }
+    if settings.code_activity.enabled {
+        if let Some(api_url) = settings.code_activity.api_url.as_deref().filter(|api_url| !api_url.trim().is_empty()) {
+            match Url::parse(api_url.trim()) {
+                Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+                Ok(_) => errors.push(FieldError::new("code_activity.api_url", INVALID_URL, "The API URL must be an http or https URL")),
+                Err(err) => errors.push(FieldError::new("code_activity.api_url", INVALID_URL, &format!("The API URL is not a valid URL: {}", err))),
+            }
+        }
+        if settings.code_activity.username.trim().is_empty() {
+            errors.push(FieldError::new("code_activity.username", REQUIRED, "Enter your login on the code host"));
+        }
+    }
+    if settings.telemetry.enabled {
+        match settings.telemetry.endpoint.as_deref().map(|endpoint| Url::parse(endpoint.trim())) {
+            Some(Ok(url)) if matches!(url.scheme(), "http" | "https") => {}
+            Some(Ok(_)) => errors.push(FieldError::new("telemetry.endpoint", INVALID_URL, "The OTLP endpoint must be an http or https URL")),
+            Some(Err(err)) => errors.push(FieldError::new("telemetry.endpoint", INVALID_URL, &format!("The OTLP endpoint is not a valid URL: {}", err))),
+            None => errors.push(FieldError::new("telemetry.endpoint", REQUIRED, "Enter the URL of the OTLP collector")),
+        }
+    }
+    if settings.org_id.as_deref().is_some_and(|org_id| org_id.trim().is_empty()) {
+        errors.push(FieldError::new("org_id", REQUIRED, "Enter an organization ID or leave it unset"));
+    }
+    if settings.employee_id.as_deref().is_some_and(|employee_id| employee_id.trim().is_empty()) {
+        errors.push(FieldError::new("employee_id", REQUIRED, "Enter an employee ID or leave it unset"));
+    }
+    if settings.teams.enabled && settings.teams.client_id.as_deref().is_none_or(|client_id| client_id.trim().is_empty()) {
+        errors.push(FieldError::new("teams.client_id", REQUIRED, "Enter the client ID of the app registration used for Teams"));
+    }
+    if settings.google_calendar.enabled && settings.google_calendar.client_id.as_deref().is_none_or(|client_id| client_id.trim().is_empty()) {
+        errors.push(FieldError::new("google_calendar.client_id", REQUIRED, "Enter the client ID of the Google OAuth client"));
+    }
+    if settings.outlook_calendar.enabled && settings.outlook_calendar.client_id.as_deref().is_none_or(|client_id| client_id.trim().is_empty()) {
+        errors.push(FieldError::new("outlook_calendar.client_id", REQUIRED, "Enter the client ID of the app registration used for the Outlook calendar"));
+    }
+    if settings.google_sheets.enabled && settings.google_sheets.client_id.as_deref().is_none_or(|client_id| client_id.trim().is_empty()) {
+        errors.push(FieldError::new("google_sheets.client_id", REQUIRED, "Enter the client ID of the Google OAuth client"));
+    }
+    if settings.google_sheets.enabled && settings.google_sheets.spreadsheet_id.as_deref().is_none_or(|id| id.trim().is_empty()) {
+        errors.push(FieldError::new("google_sheets.spreadsheet_id", REQUIRED, "Enter the ID of the sheet attendance is appended to"));
+    }
+    if settings.email_report.enabled {
+        let email_report = &settings.email_report;
+        if email_report.smtp_host.trim().is_empty() {
+            errors.push(FieldError::new("email_report.smtp_host", REQUIRED, "Enter the mail server summaries are sent through"));
+        }
+        if email_report.from.trim().is_empty() {
+            errors.push(FieldError::new("email_report.from", REQUIRED, "Enter the address summaries are sent from"));
+        }
+        if email_report.recipients.iter().all(|recipient| recipient.trim().is_empty()) {
+            errors.push(FieldError::new("email_report.recipients", REQUIRED, "Enter at least one address to send summaries to"));
+        }
+    }
+    if settings.discord.enabled && settings.discord.application_id.as_deref().is_none_or(|id| id.trim().is_empty()) {
+        errors.push(FieldError::new("discord.application_id", REQUIRED, "Enter the ID of the Discord application the activity is shown as"));
+    }
+    if settings.telegram.enabled && settings.telegram.chat_ids.is_empty() {
+        errors.push(FieldError::new("telegram.chat_ids", REQUIRED, "Enter at least one chat ID the bot answers"));
+    }
+    if settings.home_assistant.enabled && settings.mqtt.host.trim().is_empty() {
+        errors.push(FieldError::new("mqtt.host", REQUIRED, "Enter the MQTT broker Home Assistant listens to"));
+    }
+    match settings.push.service {
+        Some(PushService::Ntfy | PushService::Pushover) if settings.push.topic.as_deref().is_none_or(|topic| topic.trim().is_empty()) => {
+            errors.push(FieldError::new("push.topic", REQUIRED, "Enter the ntfy topic or Pushover user key pushes go to"));
+        }
+        Some(PushService::Gotify) if settings.push.server_url.as_deref().is_none_or(|url| url.trim().is_empty()) => {
+            errors.push(FieldError::new("push.server_url", REQUIRED, "Enter the URL of the Gotify server"));
+        }
+        _ => {}
+    }
+    match settings.time_tracker {
+        Some(TimeTrackerKind::Toggl) if settings.toggl.workspace_id.is_none() => {
+            errors.push(FieldError::new("toggl.workspace_id", REQUIRED, "Enter the ID of the Toggl workspace entries are created in"));
+        }
+        Some(TimeTrackerKind::Clockify) if settings.clockify.workspace_id.as_deref().is_none_or(|id| id.trim().is_empty()) => {
+            errors.push(FieldError::new("clockify.workspace_id", REQUIRED, "Enter the ID of the Clockify workspace entries are created in"));
+        }
+        Some(TimeTrackerKind::Harvest) if settings.harvest.account_id.is_none() || settings.harvest.project_id.is_none() || settings.harvest.task_id.is_none() => {
+            errors.push(FieldError::new("harvest", REQUIRED, "Enter the Harvest account, project and task timers are booked on"));
+        }
+        _ => {}
+    }
+    if let Some(config_url) = &settings.config_url {
+        match Url::parse(config_url.trim()) {
+            Ok(url) if url.scheme() == "https" => {}
+            Ok(_) => errors.push(FieldError::new("config_url", INVALID_URL, "The settings bundle URL must be an https URL")),
+            Err(err) => errors.push(FieldError::new("config_url", INVALID_URL, &format!("The settings bundle URL is not a valid URL: {}", err))),
+        }
+        match &settings.config_public_key {
+            Some(key) => {
+                if let Err(err) = remote_config::parse_public_key(key) {
+                    errors.push(FieldError::new("config_public_key", INVALID_KEY, &err));
+                }
+            }
+            None => errors.push(FieldError::new("config_public_key", REQUIRED, "Enter the public key the settings bundle is signed with")),
+        }
+    }
+
+    if settings.idle_timeout_secs < MIN_IDLE_TIMEOUT_SECS {
+        errors.push(FieldError::new("idle_timeout_secs", OUT_OF_RANGE, &format!("Must be at least {} seconds", MIN_IDLE_TIMEOUT_SECS)));
+    }
+    for (field, value) in minutes(settings) {
+        if value == Some(0) {
+            errors.push(FieldError::new(field, OUT_OF_RANGE, "Must be at least one minute"));
+        }
+    }
+
+    let (_, conflicts) = settings.hotkeys.resolve();
+    for conflict in conflicts {
+        let field = match conflict.action {
+            HotkeyAction::CheckIn => "hotkeys.check_in",
+            HotkeyAction::CheckOut => "hotkeys.check_out",
+            HotkeyAction::ToggleBreak => "hotkeys.toggle_break",
+        };
+        errors.push(FieldError::new(field, INVALID_SHORTCUT, &conflict.reason));
+    }
+
+    errors
+}
+
+// Fields that must be at least one minute when set
+pub fn minutes(settings: &Settings) -> [(&'static str, Option<u64>); 7] {
+    [
+        ("break_reminder_snooze_mins", Some(settings.break_reminder_snooze_mins)),
+        ("overtime_daily_mins", settings.overtime_daily_mins),
+        ("overtime_weekly_mins", settings.overtime_weekly_mins),
+        ("max_daily_mins", settings.max_daily_mins),
+        ("break_reminder_after_mins", settings.break_reminder_after_mins),
+        ("break_reminder_interval_mins", settings.break_reminder_interval_mins),
+        ("micro_break_interval_mins", settings.micro_break_interval_mins),
+    ]
+}
+
+// All errors in one line, for callers that can only report a string
+pub fn summary(errors: &[FieldError]) -> String {
+    errors.iter().map(|error| format!("{}: {}", error.field, error.message)).collect::<Vec<_>>().join("; ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reports_each_invalid_field() {
+        assert!(validate(&Settings::default()).is_empty());
+
+        let settings = Settings {
+            api_endpoint: "example.com/attendance".to_string(),
+            username: " ".to_string(),
+            idle_timeout_secs: 5,
+            timezone: Some("Mars/Olympus".to_string()),
+            ..Settings::default()
+        };
+        let errors = validate(&settings);
+        let fields: Vec<_> = errors.iter().map(|error| (error.field.as_str(), error.code)).collect();
+
+        assert_eq!(
+            fields,
+            vec![
+                ("api_endpoint", INVALID_URL),
+                ("username", REQUIRED),
+                ("timezone", INVALID_TIMEZONE),
+                ("idle_timeout_secs", OUT_OF_RANGE),
+            ]
+        );
+        assert!(summary(&errors).starts_with("api_endpoint: "));
+    }
+
+    #[test]
+    fn test_automation_triggers_need_web_urls() {
+        let mut settings = Settings::default();
+        settings.automation_triggers.check_in = Some("https://hooks.zapier.com/hooks/catch/1/abc".to_string());
+        settings.automation_triggers.api_failure = Some("ftp://example.com/hook".to_string());
+
+        let errors = validate(&settings);
+        let fields: Vec<_> = errors.iter().map(|error| (error.field.as_str(), error.code)).collect();
+        assert_eq!(fields, vec![("automation_triggers.api_failure", INVALID_URL)]);
+    }
+}
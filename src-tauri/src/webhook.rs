@@ -0,0 +1,91 @@
+use log::{debug, error};
+use serde_json::Value;
+
+use crate::backend::{self, BackendAdapter, Reply, SendError};
+use crate::secrets;
+
+// Header telling a shared API which organization an event belongs to
+pub const ORG_ID_HEADER: &str = "X-Remodance-Org-Id";
+
+// The app's own API: every event is posted to `Settings::api_endpoint` as is,
+// with the bearer token and body signature from the secrets
+pub struct Webhook<'a> {
+    pub endpoint: &'a str,
+    pub org_id: Option<&'a str>,
+    pub api_token: Option<&'a str>,
+    pub hmac_key: Option<&'a str>,
+    pub traceparent: Option<String>, // Trace context of the send's span, when exported
+}
+
+impl<'a> BackendAdapter for Webhook<'a> {
+    // The JSON body, serialized once so the signature matches what is sent
+    type Event = String;
+    type Session = Option<&'a str>;
+
+    fn name(&self) -> &'static str {
+        "API"
+    }
+
+    fn build(&self, _event_type: &str, payload: &Value) -> Result<Option<String>, String> {
+        serde_json::to_string(payload).map(Some).map_err(|e| format!("Failed to serialize payload: {}", e))
+    }
+
+    async fn authenticate(&self, _client: &reqwest::Client) -> Result<Option<&'a str>, SendError> {
+        Ok(self.api_token)
+    }
+
+    async fn submit(&self, client: &reqwest::Client, api_token: &Option<&'a str>, body: String) -> Result<Option<Reply>, SendError> {
+        debug!("Payload: {}", body);
+        let mut request = client.post(self.endpoint).header("Content-Type", "application/json");
+        if let Some(token) = api_token {
+            request = request.bearer_auth(token);
+        }
+        if let Some(key) = self.hmac_key {
+            request = request.header("X-Remodance-Signature", secrets::sign(key, &body));
+        }
+        if let Some(org_id) = self.org_id {
+            request = request.header(ORG_ID_HEADER, org_id);
+        }
+        if let Some(traceparent) = &self.traceparent {
+            request = request.header("traceparent", traceparent);
+        }
+        backend::request(request.body(body)).await.map(Some)
+    }
+
+    // Any answer means the API is reachable, error statuses reject the event
+    fn interpret(&self, reply: &Reply) -> Result<(), SendError> {
+        if reply.status.is_success() {
+            return Ok(());
+        }
+        error!("API request failed with status {}: {}", reply.status, reply.body);
+        Err(SendError::Rejected(format!("API request failed with status {}", reply.status)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderMap;
+    use reqwest::StatusCode;
+    use serde_json::json;
+
+    fn webhook() -> Webhook<'static> {
+        Webhook { endpoint: "https://api.example.com/events", org_id: None, api_token: None, hmac_key: None, traceparent: None }
+    }
+
+    #[test]
+    fn test_every_event_is_sent_as_is() {
+        let payload = json!({ "event_type": "pause", "user_id": "alice" });
+        assert_eq!(webhook().build("pause", &payload), Ok(Some(payload.to_string())));
+    }
+
+    #[test]
+    fn test_error_statuses_reject_the_event() {
+        let reply = |status| Reply { status, headers: HeaderMap::new(), body: "nope".to_string() };
+        assert_eq!(webhook().interpret(&reply(StatusCode::CREATED)), Ok(()));
+        assert_eq!(
+            webhook().interpret(&reply(StatusCode::UNPROCESSABLE_ENTITY)),
+            Err(SendError::Rejected("API request failed with status 422 Unprocessable Entity".to_string()))
+        );
+    }
+}